@@ -1,27 +1,147 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
 use std::path::Path;
+use khmer_rs::algorithm::Segmenter;
 use khmer_rs::dictionary::Dictionary;
+#[cfg(feature = "external")]
+use khmer_rs::external::{ExternalSegmenter, ExternalTool};
+use khmer_rs::maxmatch::MaxMatchSegmenter;
 use khmer_rs::segmenter::KhmerSegmenter;
 
-fn benchmark_segmentation(c: &mut Criterion) {
+const SHORT_TEXT: &str = "កងកម្លាំងរក្សាសន្តិសុខនិងសណ្តាប់ធ្នាប់សាធារណៈ"; // "Security and public order forces"
+const MIXED_TEXT: &str = "ក្រុមហ៊ុន Google បានបើកការិយាល័យថ្មីនៅភ្នំពេញ ដើម្បីគាំទ្រ Android developers និងសហគ្រាស startup ក្នុងស្រុក។";
+const DIGIT_TEXT: &str = "តម្លៃ $1,250.50 ថ្ងៃទី១៥ ខែ០១ ឆ្នាំ២០២៤ លេខ 012 345 678 បរិមាណ ៣៥០០០ គីឡូក្រាម និង ១២.៥ ភាគរយ។";
+
+const CORPUS_PATH: &str = "../data/khmer_folktales_extracted.txt";
+const CORPUS_SAMPLE_LINES: usize = 200;
+
+fn load_segmenter() -> Option<KhmerSegmenter> {
     let dict_path = Path::new("../data/khmer_dictionary_words.txt");
     let freq_path = Path::new("../data/khmer_word_frequencies.json");
 
     if !dict_path.exists() {
         eprintln!("Skipping benchmark: Data files not found.");
-        return;
+        return None;
     }
 
     let dictionary = Dictionary::new(dict_path, freq_path).expect("Failed to load dictionary");
-    let segmenter = KhmerSegmenter::new(dictionary);
+    Some(KhmerSegmenter::new(dictionary))
+}
+
+/// Every Nth non-empty line of the corpus, so the sample spans the whole file.
+fn sample_corpus(path: &Path, count: usize) -> Vec<String> {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(_) => return Vec::new(),
+    };
+    let lines: Vec<String> = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    let step = (lines.len() / count).max(1);
+    lines.into_iter().step_by(step).take(count).collect()
+}
+
+fn char_count(lines: &[String]) -> u64 {
+    lines.iter().map(|l| l.chars().count() as u64).sum()
+}
+
+fn benchmark_line_length(c: &mut Criterion, algo: &str, segmenter: &dyn Segmenter) {
+    let mut group = c.benchmark_group("line_length");
 
-    let text = "កងកម្លាំងរក្សាសន្តិសុខនិងសណ្តាប់ធ្នាប់សាធារណៈ"; // "Security and public order forces"
+    let inputs = [
+        ("short", SHORT_TEXT.to_string()),
+        ("medium", SHORT_TEXT.repeat(8)),
+        ("long", SHORT_TEXT.repeat(64)),
+    ];
+
+    for (name, text) in &inputs {
+        group.throughput(Throughput::Elements(text.chars().count() as u64));
+        group.bench_with_input(BenchmarkId::new(algo, name), text, |b, text| {
+            b.iter(|| segmenter.segment(black_box(text)))
+        });
+    }
+
+    group.finish();
+}
+
+fn benchmark_content_mix(c: &mut Criterion, algo: &str, segmenter: &dyn Segmenter) {
+    let mut group = c.benchmark_group("content_mix");
+
+    let inputs = [
+        ("khmer", SHORT_TEXT),
+        ("mixed_latin", MIXED_TEXT),
+        ("digit_heavy", DIGIT_TEXT),
+    ];
+
+    for (name, text) in inputs {
+        group.throughput(Throughput::Elements(text.chars().count() as u64));
+        group.bench_with_input(BenchmarkId::new(algo, name), text, |b, text| {
+            b.iter(|| segmenter.segment(black_box(text)))
+        });
+    }
+
+    group.finish();
+}
+
+fn benchmark_corpus(c: &mut Criterion, algo: &str, segmenter: &dyn Segmenter) {
+    let lines = sample_corpus(Path::new(CORPUS_PATH), CORPUS_SAMPLE_LINES);
+    if lines.is_empty() {
+        eprintln!("Skipping corpus benchmark: {} not found.", CORPUS_PATH);
+        return;
+    }
+
+    let mut group = c.benchmark_group("corpus");
+    group.sample_size(20);
+    group.throughput(Throughput::Elements(char_count(&lines)));
+    group.bench_function(BenchmarkId::new(algo, format!("folktales/{}", lines.len())), |b| {
+        b.iter(|| {
+            for line in &lines {
+                black_box(segmenter.segment(black_box(line)));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn benchmark_segmentation(c: &mut Criterion) {
+    let segmenter = match load_segmenter() {
+        Some(s) => s,
+        None => return,
+    };
 
     c.bench_function("segment_short_sentence", |b| {
         b.iter(|| {
-            segmenter.segment(black_box(text));
+            segmenter.segment(black_box(SHORT_TEXT));
         })
     });
+
+    // Every algorithm runs the same inputs, so results compare side by side.
+    // Throughput is reported in codepoints (Elements) so results read as chars/sec.
+    let maxmatch = MaxMatchSegmenter::new(segmenter.dictionary());
+    let algorithms: [(&str, &dyn Segmenter); 2] = [("viterbi", &segmenter), ("maxmatch", &maxmatch)];
+    for (algo, segmenter) in algorithms {
+        benchmark_line_length(c, algo, segmenter);
+        benchmark_content_mix(c, algo, segmenter);
+        benchmark_corpus(c, algo, segmenter);
+    }
+
+    // Other tokenizers on the same inputs, when installed (`--features external`)
+    #[cfg(feature = "external")]
+    for tool in ExternalTool::ALL {
+        match ExternalSegmenter::tool(tool).and_then(|external| external.try_segment(SHORT_TEXT).map(|_| external)) {
+            Ok(external) => {
+                benchmark_line_length(c, tool.name(), &external);
+                benchmark_content_mix(c, tool.name(), &external);
+                benchmark_corpus(c, tool.name(), &external);
+            }
+            Err(e) => eprintln!("Skipping {}: {}", tool, e),
+        }
+    }
 }
 
 criterion_group!(benches, benchmark_segmentation);
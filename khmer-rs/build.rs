@@ -0,0 +1,44 @@
+//! With the `embedded` feature, compress the default dictionary and
+//! frequency data into `OUT_DIR` for `src/embedded.rs` to include.
+//! `KHMER_RS_EMBED_DICT` and `KHMER_RS_EMBED_FREQ` point at other files.
+
+fn main() {
+    #[cfg(feature = "embedded")]
+    embed::run();
+}
+
+#[cfg(feature = "embedded")]
+mod embed {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    const DEFAULT_DICT: &str = "../data/khmer_dictionary_words.txt";
+    const DEFAULT_FREQ: &str = "../data/khmer_word_frequencies.json";
+
+    pub fn run() {
+        let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by cargo"));
+        let dict = source("KHMER_RS_EMBED_DICT", DEFAULT_DICT);
+        let freq = source("KHMER_RS_EMBED_FREQ", DEFAULT_FREQ);
+        compress(&dict, &out_dir.join("dictionary.deflate"));
+        compress(&freq, &out_dir.join("frequencies.deflate"));
+        // The frequency format follows the file name, as for `--freq`
+        let name = freq.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        println!("cargo:rustc-env=KHMER_RS_EMBEDDED_FREQ_NAME={}", name);
+    }
+
+    fn source(var: &str, default: &str) -> PathBuf {
+        println!("cargo:rerun-if-env-changed={}", var);
+        let path = env::var_os(var).map_or_else(|| PathBuf::from(default), PathBuf::from);
+        println!("cargo:rerun-if-changed={}", path.display());
+        path
+    }
+
+    fn compress(source: &Path, target: &Path) {
+        let data = fs::read(source).unwrap_or_else(|e| {
+            panic!("embedded feature: cannot read {}: {} (set KHMER_RS_EMBED_DICT/KHMER_RS_EMBED_FREQ)", source.display(), e)
+        });
+        let compressed = miniz_oxide::deflate::compress_to_vec(&data, 10);
+        fs::write(target, compressed).unwrap_or_else(|e| panic!("cannot write {}: {}", target.display(), e));
+    }
+}
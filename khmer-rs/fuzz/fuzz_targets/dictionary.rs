@@ -0,0 +1,26 @@
+//! Arbitrary word lists and JSON frequency data through `Dictionary`: bad
+//! input must be an error, never a panic, and any dictionary that loads
+//! must segment its own words.
+
+#![no_main]
+
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::segmenter::KhmerSegmenter;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Word list, then frequency data after the first NUL byte if any
+    let (words, frequencies) = match data.iter().position(|&b| b == 0) {
+        Some(i) => (&data[..i], Some(&data[i + 1..])),
+        None => (data, None),
+    };
+    let Ok(dictionary) = Dictionary::from_bytes(words, frequencies) else {
+        return;
+    };
+
+    let text = String::from_utf8_lossy(words);
+    let segmenter = KhmerSegmenter::new(dictionary);
+    for line in text.lines().take(64) {
+        assert_eq!(segmenter.segment(line).concat(), line.replace('\u{200b}', ""));
+    }
+});
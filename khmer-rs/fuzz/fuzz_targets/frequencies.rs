@@ -0,0 +1,15 @@
+//! Arbitrary bytes through every frequency file format: errors are fine,
+//! panics are not.
+
+#![no_main]
+
+use khmer_rs::frequency::{read_frequencies, FrequencyFormat};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for format in [FrequencyFormat::Json, FrequencyFormat::Tsv, FrequencyFormat::Csv, FrequencyFormat::Counts] {
+        if let Ok(counts) = read_frequencies(data, format) {
+            assert!(counts.keys().all(|word| !word.is_empty()) || format == FrequencyFormat::Json);
+        }
+    }
+});
@@ -0,0 +1,39 @@
+//! Arbitrary text through `KhmerSegmenter`: no panics, and the output
+//! still covers the input.
+
+#![no_main]
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::segmenter::KhmerSegmenter;
+use libfuzzer_sys::fuzz_target;
+
+fn segmenter() -> &'static KhmerSegmenter {
+    static SEGMENTER: OnceLock<KhmerSegmenter> = OnceLock::new();
+    SEGMENTER.get_or_init(|| {
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../data");
+        let dictionary = Dictionary::new(
+            &data.join("khmer_dictionary_words.txt"),
+            &data.join("khmer_word_frequencies.json"),
+        )
+        .expect("Failed to load dictionary");
+        KhmerSegmenter::new(dictionary)
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    // Invalid UTF-8 becomes U+FFFD, as when a corpus is read lossily
+    let text = String::from_utf8_lossy(data);
+    let segmenter = segmenter();
+
+    let segments = segmenter.segment(&text);
+    assert_eq!(segments.concat(), text.replace('\u{200b}', ""));
+    assert!(segments.iter().all(|seg| !seg.is_empty()));
+
+    for span in segmenter.segment_spans(&text) {
+        assert!(span.start < span.end && text.is_char_boundary(span.start) && text.is_char_boundary(span.end));
+    }
+    assert_eq!(segmenter.segment_lossless(&text).concat(), text);
+});
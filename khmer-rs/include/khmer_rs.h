@@ -0,0 +1,63 @@
+/*
+ * khmer_rs.h - C ABI for the khmer-rs word segmenter.
+ *
+ * Build the shared library with:
+ *   cargo rustc --profile cdylib --lib --features cdylib --crate-type cdylib
+ * and link against target/cdylib/libkhmer_rs.{so,dylib} (khmer_rs.dll on Windows).
+ *
+ * This header is the stable interface: fields and signatures only change
+ * with a major version bump.
+ */
+#ifndef KHMER_RS_H
+#define KHMER_RS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define KHMER_OK 0
+#define KHMER_ERR_NULL_POINTER (-1)
+#define KHMER_ERR_INVALID_UTF8 (-2)
+/* A Rust panic was caught at the boundary; `out` is left untouched. */
+#define KHMER_ERR_PANIC (-3)
+
+/* Opaque segmenter handle. Safe to share between threads. */
+typedef struct KhmerSegmenter KhmerSegmenter;
+
+/* UTF-8 slice, NOT NUL-terminated. */
+typedef struct {
+    const uint8_t *ptr;
+    size_t len;
+} KhmerStr;
+
+/* Output of khmer_segment. `data` and `data_len` are private storage. */
+typedef struct {
+    KhmerStr *segments;
+    size_t count;
+    uint8_t *data;
+    size_t data_len;
+} KhmerSegments;
+
+/* Load dictionary + frequency files. Returns NULL on failure, including a panic. */
+KhmerSegmenter *khmer_segmenter_new(const char *dict_path, const char *freq_path);
+
+/* Release a handle from khmer_segmenter_new. NULL is ignored. */
+void khmer_segmenter_free(KhmerSegmenter *handle);
+
+/*
+ * Segment `len` bytes of UTF-8 text. On KHMER_OK, `out` must later be
+ * released with khmer_segments_free.
+ */
+int32_t khmer_segment(const KhmerSegmenter *handle, const uint8_t *text, size_t len, KhmerSegments *out);
+
+/* Release the contents of `segments` and reset it to empty. NULL is ignored. */
+void khmer_segments_free(KhmerSegments *segments);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KHMER_RS_H */
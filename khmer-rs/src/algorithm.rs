@@ -0,0 +1,121 @@
+//! The `Segmenter` trait: what the CLI, eval and benchmarks need from a
+//! segmentation algorithm, so different algorithms can run through the same
+//! harness and be compared side by side.
+
+use std::sync::Arc;
+
+use crate::cache::CacheStats;
+use crate::dictionary::Cost;
+use crate::maxmatch::MaxMatchSegmenter;
+use crate::segmenter::KhmerSegmenter;
+use crate::token::TokenKind;
+
+/// A segmentation algorithm. Implementations are shared across rayon worker
+/// threads, hence `Send + Sync`.
+pub trait Segmenter: Send + Sync {
+    fn segment(&self, text: &str) -> Vec<String>;
+
+    /// Hits and misses of the algorithm's line cache, if it has one.
+    fn cache_stats(&self) -> Option<CacheStats> {
+        None
+    }
+
+    /// The kind of one of the algorithm's segments, for algorithms with a
+    /// dictionary to tell words from unknown text.
+    fn token_kind(&self, _segment: &str) -> Option<TokenKind> {
+        None
+    }
+
+    /// Dictionary cost of one of the algorithm's segments, if it is a
+    /// dictionary word of an algorithm with a dictionary.
+    fn word_cost(&self, _segment: &str) -> Option<Cost> {
+        None
+    }
+}
+
+impl Segmenter for KhmerSegmenter {
+    fn segment(&self, text: &str) -> Vec<String> {
+        KhmerSegmenter::segment(self, text)
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        KhmerSegmenter::cache_stats(self)
+    }
+
+    fn token_kind(&self, segment: &str) -> Option<TokenKind> {
+        Some(KhmerSegmenter::token_kind(self, segment))
+    }
+
+    fn word_cost(&self, segment: &str) -> Option<Cost> {
+        KhmerSegmenter::word_cost(self, segment)
+    }
+}
+
+impl Segmenter for MaxMatchSegmenter {
+    fn segment(&self, text: &str) -> Vec<String> {
+        MaxMatchSegmenter::segment(self, text)
+    }
+
+    fn token_kind(&self, segment: &str) -> Option<TokenKind> {
+        Some(TokenKind::classify(segment, self.dictionary()))
+    }
+
+    fn word_cost(&self, segment: &str) -> Option<Cost> {
+        let dictionary = self.dictionary();
+        dictionary.contains(segment).then(|| dictionary.get_word_cost(segment))
+    }
+}
+
+impl<S: Segmenter + ?Sized> Segmenter for &S {
+    fn segment(&self, text: &str) -> Vec<String> {
+        (**self).segment(text)
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        (**self).cache_stats()
+    }
+
+    fn token_kind(&self, segment: &str) -> Option<TokenKind> {
+        (**self).token_kind(segment)
+    }
+
+    fn word_cost(&self, segment: &str) -> Option<Cost> {
+        (**self).word_cost(segment)
+    }
+}
+
+impl<S: Segmenter + ?Sized> Segmenter for Box<S> {
+    fn segment(&self, text: &str) -> Vec<String> {
+        (**self).segment(text)
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        (**self).cache_stats()
+    }
+
+    fn token_kind(&self, segment: &str) -> Option<TokenKind> {
+        (**self).token_kind(segment)
+    }
+
+    fn word_cost(&self, segment: &str) -> Option<Cost> {
+        (**self).word_cost(segment)
+    }
+}
+
+impl<S: Segmenter + ?Sized> Segmenter for Arc<S> {
+    fn segment(&self, text: &str) -> Vec<String> {
+        (**self).segment(text)
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        (**self).cache_stats()
+    }
+
+    fn token_kind(&self, segment: &str) -> Option<TokenKind> {
+        (**self).token_kind(segment)
+    }
+
+    fn word_cost(&self, segment: &str) -> Option<Cost> {
+        (**self).word_cost(segment)
+    }
+}
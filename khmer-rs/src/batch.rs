@@ -0,0 +1,551 @@
+//! Helpers for segmenting a whole corpus at once (the CLI's batch mode).
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::sync_channel;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use fxhash::{FxHashMap, FxHashSet, FxHasher};
+
+const INTERNER_SHARDS: usize = 16;
+const DEFAULT_BATCH_LINES: usize = 256;
+/// Batches in flight per worker
+const DEFAULT_BATCHES_PER_WORKER: usize = 4;
+
+/// The distinct lines of a batch, in order of first appearance, and for each
+/// line the index of its distinct line. Segmenting `unique` and fanning the
+/// results back out with `index` does the work once per distinct line while
+/// keeping the output in input order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dedup<'a> {
+    pub unique: Vec<&'a str>,
+    pub index: Vec<usize>,
+}
+
+impl<'a> Dedup<'a> {
+    pub fn new<S: AsRef<str>>(lines: &'a [S]) -> Self {
+        let mut first: FxHashMap<&'a str, usize> = FxHashMap::default();
+        let mut unique = Vec::new();
+        let index = lines
+            .iter()
+            .map(|line| {
+                let line = line.as_ref();
+                *first.entry(line).or_insert_with(|| {
+                    unique.push(line);
+                    unique.len() - 1
+                })
+            })
+            .collect();
+        Dedup { unique, index }
+    }
+
+    /// Lines that repeat an earlier one.
+    pub fn duplicates(&self) -> usize {
+        self.index.len() - self.unique.len()
+    }
+}
+
+/// A pool of shared token strings: every interned copy of a token is the same
+/// `Arc<str>`, so results held in memory store each distinct token once
+/// (`"។"`, `" "` and common words repeat millions of times in a corpus).
+/// Safe to use from parallel workers; the pool is sharded to keep them from
+/// waiting on one lock.
+pub struct Interner {
+    shards: Vec<Mutex<FxHashSet<Arc<str>>>>,
+}
+
+impl Default for Interner {
+    fn default() -> Self {
+        Interner::new()
+    }
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner { shards: (0..INTERNER_SHARDS).map(|_| Mutex::new(FxHashSet::default())).collect() }
+    }
+
+    /// The pooled copy of `token`, added on first use.
+    pub fn intern(&self, token: &str) -> Arc<str> {
+        let mut hasher = FxHasher::default();
+        token.hash(&mut hasher);
+        let mut shard = self.shards[hasher.finish() as usize % INTERNER_SHARDS].lock().unwrap();
+        match shard.get(token) {
+            Some(pooled) => Arc::clone(pooled),
+            None => {
+                let pooled: Arc<str> = Arc::from(token);
+                shard.insert(Arc::clone(&pooled));
+                pooled
+            }
+        }
+    }
+
+    /// `segments` with each one replaced by its pooled copy.
+    pub fn intern_all(&self, segments: Vec<String>) -> Vec<Arc<str>> {
+        segments.iter().map(|segment| self.intern(segment)).collect()
+    }
+
+    /// Number of distinct tokens in the pool.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Bytes of input read but not yet written, capped (`Pipeline::max_bytes`).
+struct ByteBudget {
+    limit: usize,
+    /// Bytes in flight, and whether the writer has stopped
+    state: Mutex<(usize, bool)>,
+    freed: Condvar,
+}
+
+impl ByteBudget {
+    fn new(limit: usize) -> Self {
+        ByteBudget { limit, state: Mutex::new((0, false)), freed: Condvar::new() }
+    }
+
+    /// Take `bytes` if they fit (or nothing else is in flight); with `wait`,
+    /// wait until they do. False if they do not fit, or the writer stopped.
+    fn acquire(&self, bytes: usize, wait: bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let (used, closed) = *state;
+            if closed {
+                return false;
+            }
+            if used == 0 || used + bytes <= self.limit {
+                state.0 += bytes;
+                return true;
+            }
+            if !wait {
+                return false;
+            }
+            state = self.freed.wait(state).unwrap();
+        }
+    }
+
+    fn release(&self, bytes: usize) {
+        self.state.lock().unwrap().0 -= bytes;
+        self.freed.notify_all();
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().1 = true;
+        self.freed.notify_all();
+    }
+}
+
+/// Closes the budget when the writer stops, however it stops.
+struct CloseOnDrop<'a>(Option<&'a ByteBudget>);
+
+impl Drop for CloseOnDrop<'_> {
+    fn drop(&mut self) {
+        if let Some(budget) = self.0 {
+            budget.close();
+        }
+    }
+}
+
+/// What a worker made of one line: its result, or the id of the earlier line
+/// it repeats (with `Pipeline::dedup`).
+enum Outcome<T> {
+    Done(T),
+    Repeat(usize),
+}
+
+/// A streaming producer -> workers -> ordered writer pipeline.
+///
+/// One thread reads lines and hands them out in batches, the workers process
+/// the batches in parallel, and the calling thread writes the results in
+/// input order. At most a fixed number of batches are in flight at once, read
+/// but not yet written, so memory stays flat whatever the corpus size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pipeline {
+    workers: usize,
+    batch_lines: usize,
+    max_batches: Option<usize>,
+    max_bytes: Option<usize>,
+    dedup: bool,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Pipeline::new()
+    }
+}
+
+impl Pipeline {
+    /// One worker per available core, batches of 256 lines.
+    pub fn new() -> Self {
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        Pipeline { workers, batch_lines: DEFAULT_BATCH_LINES, max_batches: None, max_bytes: None, dedup: false }
+    }
+
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Lines handed to a worker at a time.
+    pub fn batch_lines(mut self, lines: usize) -> Self {
+        self.batch_lines = lines.max(1);
+        self
+    }
+
+    /// Batches read but not yet written, at most; 4 per worker by default.
+    pub fn max_batches(mut self, batches: usize) -> Self {
+        self.max_batches = Some(batches.max(1));
+        self
+    }
+
+    /// Input bytes read but not yet written, at most; a line longer than
+    /// this still goes through, on its own. What the results take on top
+    /// depends on `process`: the caller sizes this to its memory budget.
+    pub fn max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = Some(bytes.max(1));
+        self
+    }
+
+    /// Process each distinct line once and pass its result again for every
+    /// line that repeats it. The distinct lines and their results are kept
+    /// until the end of the run.
+    pub fn dedup(mut self, enabled: bool) -> Self {
+        self.dedup = enabled;
+        self
+    }
+
+    /// Run `process` over `lines` on the workers and call `write` with each
+    /// line's 0-based id, text and result, in input order. Returns the number
+    /// of lines written. A read error stops the run after the lines before
+    /// it are written; a write error stops it at once.
+    pub fn run<I, T, F, W>(&self, lines: I, process: F, mut write: W) -> io::Result<usize>
+    where
+        I: IntoIterator<Item = io::Result<String>>,
+        I::IntoIter: Send,
+        T: Send,
+        F: Fn(&str) -> T + Sync,
+        W: FnMut(usize, &str, &T) -> io::Result<()>,
+    {
+        let max_batches = self.max_batches.unwrap_or(self.workers * DEFAULT_BATCHES_PER_WORKER);
+        let (batch_lines, dedup) = (self.batch_lines, self.dedup);
+        let lines = lines.into_iter();
+
+        let (job_tx, job_rx) = sync_channel::<(usize, Vec<(usize, String, Option<usize>)>)>(max_batches);
+        let job_rx = Mutex::new(job_rx);
+        let (result_tx, result_rx) = sync_channel::<(usize, thread::Result<Vec<(usize, String, Outcome<T>)>>)>(max_batches);
+        // One token per batch in flight: the producer takes one per batch and
+        // the writer gives it back once the batch is written
+        let (token_tx, token_rx) = sync_channel::<()>(max_batches);
+        for _ in 0..max_batches {
+            token_tx.send(()).unwrap();
+        }
+        let budget = self.max_bytes.map(ByteBudget::new);
+        let budget = budget.as_ref();
+
+        thread::scope(|scope| {
+            let producer = scope.spawn(move || -> io::Result<()> {
+                let mut first_ids: FxHashMap<String, usize> = FxHashMap::default();
+                let mut batch = Vec::with_capacity(batch_lines);
+                let mut batch_id = 0;
+                let mut send = |batch: Vec<_>| {
+                    let sent = token_rx.recv().is_ok() && job_tx.send((batch_id, batch)).is_ok();
+                    batch_id += 1;
+                    sent
+                };
+                for (id, line) in lines.enumerate() {
+                    let line = line?;
+                    if let Some(budget) = budget {
+                        // Hand out what is waiting before waiting for room
+                        if !budget.acquire(line.len(), false) {
+                            if !batch.is_empty() && !send(std::mem::replace(&mut batch, Vec::with_capacity(batch_lines))) {
+                                return Ok(());
+                            }
+                            if !budget.acquire(line.len(), true) {
+                                return Ok(());
+                            }
+                        }
+                    }
+                    let repeat = if dedup {
+                        match first_ids.get(&line) {
+                            Some(&first) => Some(first),
+                            None => {
+                                first_ids.insert(line.clone(), id);
+                                None
+                            }
+                        }
+                    } else {
+                        None
+                    };
+                    batch.push((id, line, repeat));
+                    if batch.len() == batch_lines && !send(std::mem::replace(&mut batch, Vec::with_capacity(batch_lines))) {
+                        // The writer stopped
+                        return Ok(());
+                    }
+                }
+                if !batch.is_empty() {
+                    send(batch);
+                }
+                Ok(())
+            });
+
+            for _ in 0..self.workers {
+                let (job_rx, result_tx, process) = (&job_rx, result_tx.clone(), &process);
+                scope.spawn(move || loop {
+                    let Ok((batch_id, batch)) = job_rx.lock().unwrap().recv() else { break };
+                    // A panic is passed on to the writer, which would otherwise
+                    // wait for this batch forever
+                    let results = panic::catch_unwind(AssertUnwindSafe(|| {
+                        batch
+                            .into_iter()
+                            .map(|(id, line, repeat)| {
+                                let outcome = match repeat {
+                                    Some(first) => Outcome::Repeat(first),
+                                    None => Outcome::Done(process(&line)),
+                                };
+                                (id, line, outcome)
+                            })
+                            .collect()
+                    }));
+                    if result_tx.send((batch_id, results)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(result_tx);
+
+            // Ordered writer: hold batches that arrive early until their turn.
+            // Returning drops the token sender and the result receiver, which
+            // stops the producer and the workers
+            let token_tx = token_tx;
+            let _close = CloseOnDrop(budget);
+            let mut pending = BTreeMap::new();
+            let mut kept: FxHashMap<usize, T> = FxHashMap::default();
+            let (mut next, mut written) = (0, 0);
+            for (batch_id, batch) in result_rx {
+                pending.insert(batch_id, batch.unwrap_or_else(|payload| panic::resume_unwind(payload)));
+                while let Some(batch) = pending.remove(&next) {
+                    let mut bytes = 0;
+                    for (id, line, outcome) in batch {
+                        bytes += line.len();
+                        match outcome {
+                            Outcome::Done(result) => {
+                                write(id, &line, &result)?;
+                                if dedup {
+                                    kept.insert(id, result);
+                                }
+                            }
+                            Outcome::Repeat(first) => write(id, &line, &kept[&first])?,
+                        }
+                        written += 1;
+                    }
+                    next += 1;
+                    let _ = token_tx.send(());
+                    if let Some(budget) = budget {
+                        budget.release(bytes);
+                    }
+                }
+            }
+            producer.join().unwrap()?;
+            Ok(written)
+        })
+    }
+}
+
+/// Progress of a batch run, saved next to its output so `--resume` can pick
+/// up after a crash: the number of input lines whose records are fully
+/// written, and the length of the output holding exactly those records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub lines: usize,
+    pub bytes: u64,
+}
+
+impl Checkpoint {
+    /// Where the checkpoint of `output` is kept: `<output>.checkpoint`.
+    pub fn path_for(output: &Path) -> PathBuf {
+        let mut path = output.as_os_str().to_owned();
+        path.push(".checkpoint");
+        PathBuf::from(path)
+    }
+
+    /// The checkpoint saved at `path`, or `None` if there is none.
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid checkpoint {}", path.display()));
+        let (mut lines, mut bytes) = (None, None);
+        for line in text.lines() {
+            match line.split_once(' ') {
+                Some(("lines", value)) => lines = Some(value.parse().map_err(|_| invalid())?),
+                Some(("bytes", value)) => bytes = Some(value.parse().map_err(|_| invalid())?),
+                _ => return Err(invalid()),
+            }
+        }
+        match (lines, bytes) {
+            (Some(lines), Some(bytes)) => Ok(Some(Checkpoint { lines, bytes })),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Save to `path`, replacing the previous checkpoint in one step so a
+    /// crash leaves either the old one or the new one.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, format!("lines {}\nbytes {}\n", self.lines, self.bytes))?;
+        fs::rename(&tmp, path)
+    }
+}
+
+/// Output of a batch run, one file or numbered shards of a fixed number of
+/// records (`out.jsonl` -> `out-00000.jsonl`, `out-00001.jsonl`, ...),
+/// each opened when its first record arrives. Tracks what a `Checkpoint` of
+/// the run records.
+pub struct RecordWriter {
+    path: PathBuf,
+    shard_lines: Option<usize>,
+    writer: Option<BufWriter<File>>,
+    shards: usize,
+    lines: usize,
+    /// Bytes in the current file
+    bytes: u64,
+}
+
+impl RecordWriter {
+    const BUFFER: usize = 262144;
+
+    /// Start the output at `path`, split into shards of `shard_lines` records
+    /// if given.
+    pub fn create(path: &Path, shard_lines: Option<usize>) -> io::Result<Self> {
+        let mut writer = RecordWriter::create_empty(path, shard_lines);
+        if writer.shard_lines.is_none() {
+            writer.open(File::create(path)?);
+        }
+        Ok(writer)
+    }
+
+    /// Continue the output at `path` after `checkpoint`: the file it was
+    /// writing is cut back to the checkpoint's length, dropping any records
+    /// written after it.
+    pub fn resume(path: &Path, shard_lines: Option<usize>, checkpoint: Checkpoint) -> io::Result<Self> {
+        if checkpoint.lines == 0 {
+            return RecordWriter::create(path, shard_lines);
+        }
+        let mut writer = RecordWriter::create_empty(path, shard_lines);
+        writer.lines = checkpoint.lines;
+        let current = match writer.shard_lines {
+            Some(shard_lines) => {
+                let shard = checkpoint.lines.saturating_sub(1) / shard_lines;
+                writer.shards = shard + 1;
+                shard_path(path, shard)
+            }
+            None => path.to_path_buf(),
+        };
+        let mut file = OpenOptions::new().write(true).create(true).truncate(false).open(&current)?;
+        if file.metadata()?.len() < checkpoint.bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is shorter than its checkpoint says", current.display()),
+            ));
+        }
+        file.set_len(checkpoint.bytes)?;
+        file.seek(SeekFrom::End(0))?;
+        writer.open(file);
+        writer.bytes = checkpoint.bytes;
+        Ok(writer)
+    }
+
+    fn create_empty(path: &Path, shard_lines: Option<usize>) -> Self {
+        RecordWriter {
+            path: path.to_path_buf(),
+            shard_lines: shard_lines.map(|lines| lines.max(1)),
+            writer: None,
+            shards: 0,
+            lines: 0,
+            bytes: 0,
+        }
+    }
+
+    fn open(&mut self, file: File) {
+        self.writer = Some(BufWriter::with_capacity(Self::BUFFER, file));
+        self.bytes = 0;
+    }
+
+    /// Write one record and its newline, starting a new shard first if the
+    /// current one is full.
+    pub fn write_record(&mut self, record: &str) -> io::Result<()> {
+        self.write_parts(record.as_bytes(), b"\n")
+    }
+
+    /// Write one record already encoded with its terminator, if its format
+    /// has one (see `output::OutputFormatter`).
+    pub fn write_encoded(&mut self, record: &[u8]) -> io::Result<()> {
+        self.write_parts(record, b"")
+    }
+
+    fn write_parts(&mut self, record: &[u8], terminator: &[u8]) -> io::Result<()> {
+        if let Some(shard_lines) = self.shard_lines {
+            if self.lines.is_multiple_of(shard_lines) {
+                self.flush()?;
+                let file = File::create(shard_path(&self.path, self.shards))?;
+                self.open(file);
+                self.shards += 1;
+            }
+        }
+        let writer = self.writer.as_mut().expect("output is open");
+        writer.write_all(record)?;
+        writer.write_all(terminator)?;
+        self.lines += 1;
+        self.bytes += (record.len() + terminator.len()) as u64;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Flush and return the progress to save: valid once this returns.
+    pub fn checkpoint(&mut self) -> io::Result<Checkpoint> {
+        self.flush()?;
+        Ok(Checkpoint { lines: self.lines, bytes: self.bytes })
+    }
+
+    /// Records written in total, resumed ones included.
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// Files written, from `shard_path(path, 0)` on; 1 when not sharding.
+    pub fn files(&self) -> usize {
+        if self.shard_lines.is_some() {
+            self.shards
+        } else {
+            1
+        }
+    }
+}
+
+/// The `index`th shard of `path`: `out.jsonl` -> `out-00003.jsonl`.
+pub fn shard_path(path: &Path, index: usize) -> PathBuf {
+    let stem = path.file_stem().map_or_else(Default::default, |stem| stem.to_string_lossy());
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{:05}.{}", stem, index, extension.to_string_lossy()),
+        None => format!("{}-{:05}", stem, index),
+    };
+    path.with_file_name(name)
+}
@@ -0,0 +1,95 @@
+//! Bigram transition costs for the Viterbi DP.
+//!
+//! A table file has one `previous<TAB>word<TAB>cost` entry per line, where
+//! `cost` is `-log10 P(word | previous)`. When a dictionary word follows a
+//! word with an entry, the bigram cost replaces the word's unigram cost;
+//! otherwise the unigram cost is used unchanged.
+//!
+//! This is a greedy approximation, not bigram Viterbi: the DP keeps one
+//! best path per position, not one per (position, previous word), so the
+//! previous word is the one on the best unigram-or-bigram path to that
+//! position. The search stays linear, but the result is not always the
+//! cheapest path under the bigram costs: a path that is worse up to a
+//! position is dropped there even when its last word would have made the
+//! next bigram cheaper.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use fxhash::FxHashMap;
+
+use crate::dictionary::Cost;
+
+/// Next word -> cost, keyed by codepoints so the DP can look up slices.
+pub(crate) type Successors = FxHashMap<Box<[char]>, Cost>;
+
+#[derive(Debug, Clone, Default)]
+pub struct BigramTable {
+    costs: FxHashMap<Box<[char]>, Successors>,
+    len: usize,
+}
+
+impl BigramTable {
+    pub fn new() -> Self {
+        BigramTable::default()
+    }
+
+    pub fn open(path: &Path) -> io::Result<Self> {
+        BigramTable::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut table = BigramTable::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields = line.split('\t');
+            let parsed = match (fields.next(), fields.next(), fields.next().map(|c| c.trim().parse::<Cost>())) {
+                (Some(prev), Some(word), Some(Ok(cost))) => Some((prev, word, cost)),
+                _ => None,
+            };
+            match parsed {
+                Some((prev, word, cost)) => table.insert(prev, word, cost),
+                None if i == 0 => continue, // header
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: expected previous<TAB>word<TAB>cost", i + 1),
+                    ))
+                }
+            }
+        }
+        Ok(table)
+    }
+
+    pub fn insert(&mut self, previous: &str, word: &str, cost: Cost) {
+        let next = self.costs.entry(previous.chars().collect()).or_default();
+        if next.insert(word.chars().collect(), cost).is_none() {
+            self.len += 1;
+        }
+    }
+
+    pub fn get(&self, previous: &str, word: &str) -> Option<Cost> {
+        let previous: Vec<char> = previous.chars().collect();
+        let word: Vec<char> = word.chars().collect();
+        self.successors(&previous)?.get(word.as_slice()).copied()
+    }
+
+    /// Costs of every word that has an entry after `previous`.
+    #[inline]
+    pub(crate) fn successors(&self, previous: &[char]) -> Option<&Successors> {
+        self.costs.get(previous)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
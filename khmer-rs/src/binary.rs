@@ -0,0 +1,334 @@
+//! Binary dictionary cache (`DictionaryBuilder::binary_cache`).
+//!
+//! Building a `Dictionary` from text means parsing the frequency file,
+//! generating spelling variants and deriving costs. The first load writes
+//! the result (word table, variants and trie) under the user cache
+//! directory, never next to the word list; later loads read it back when it
+//! is still fresh.
+//!
+//! The cache is fresh when it was built with the same options (variant
+//! rules, cost overrides, frequency format, cost width) from the same
+//! sources: each file has the size it had and either the same modification
+//! time or the same content hash. Overlays and Latin word lists are applied
+//! after loading and are not cached. A missing, stale or unreadable cache is
+//! rebuilt; a cache that cannot be written only costs the next startup.
+
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use fxhash::{FxHashMap, FxHasher64};
+
+use crate::dictionary::{Cost, Dictionary, NodeId, Trie, TrieNode};
+
+const MAGIC: &[u8; 8] = b"KHDICT\0\0";
+/// Bumped whenever the layout changes, so older caches are rebuilt.
+const VERSION: u32 = 1;
+const EXTENSION: &str = "dictcache";
+
+/// Size, modification time and content hash of a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fingerprint {
+    len: u64,
+    modified: u64,
+    hash: u64,
+}
+
+impl Fingerprint {
+    /// `None` when the file does not exist.
+    fn of(path: &Path) -> io::Result<Option<Fingerprint>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let (len, modified) = metadata(path)?;
+        Ok(Some(Fingerprint { len, modified, hash: hash_bytes(&fs::read(path)?) }))
+    }
+
+    /// Whether `path` still has this content, hashing it only when the size
+    /// matches but the modification time does not.
+    fn matches(stored: Option<Fingerprint>, path: &Path) -> bool {
+        match stored {
+            None => !path.exists(),
+            Some(stored) => match metadata(path) {
+                Ok((len, _)) if len != stored.len => false,
+                Ok((_, modified)) if modified == stored.modified => true,
+                Ok(_) => fs::read(path).is_ok_and(|bytes| hash_bytes(&bytes) == stored.hash),
+                Err(_) => false,
+            },
+        }
+    }
+}
+
+fn metadata(path: &Path) -> io::Result<(u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+    Ok((metadata.len(), modified))
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = FxHasher64::default();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Key of the options a cache was built with; `options` is their `Debug` text.
+pub(crate) fn options_key(options: &str) -> u64 {
+    let mut hasher = FxHasher64::default();
+    (VERSION, std::mem::size_of::<Cost>(), options).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Where the cache of the dictionary built from `dict_path` and `freq_path`
+/// with options `key` is: under the user cache directory
+/// (`$XDG_CACHE_HOME/khmer-rs`, `~/.cache/khmer-rs`, or the temporary
+/// directory without one), named after the hash of the absolute paths and
+/// the options, so each combination keeps its own file.
+pub(crate) fn cache_path(dict_path: &Path, freq_path: &Path, key: u64) -> PathBuf {
+    let absolute = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut hasher = FxHasher64::default();
+    (absolute(dict_path), absolute(freq_path), key).hash(&mut hasher);
+    let dir = user_cache_dir().unwrap_or_else(std::env::temp_dir);
+    dir.join("khmer-rs").join(format!("{:016x}.{}", hasher.finish(), EXTENSION))
+}
+
+fn user_cache_dir() -> Option<PathBuf> {
+    let non_empty = |var| std::env::var_os(var).filter(|value| !value.is_empty()).map(PathBuf::from);
+    non_empty("XDG_CACHE_HOME")
+        .or_else(|| non_empty("HOME").map(|home| home.join(".cache")))
+        .or_else(|| non_empty("LOCALAPPDATA"))
+}
+
+/// The cached dictionary of `dict_path` and `freq_path`, if a fresh one
+/// built with options `key` exists.
+pub(crate) fn load(dict_path: &Path, freq_path: &Path, key: u64) -> Option<Dictionary> {
+    let path = cache_path(dict_path, freq_path, key);
+    let bytes = fs::read(&path).ok()?;
+    let mut reader = Reader { bytes: &bytes, pos: 0 };
+    let (stored_key, dict, freq) = read_header(&mut reader).ok()?;
+    if stored_key != key || !Fingerprint::matches(dict, dict_path) || !Fingerprint::matches(freq, freq_path) {
+        return None;
+    }
+    let dictionary = read_dictionary(&mut reader).ok()?;
+    #[cfg(feature = "tracing")]
+    tracing::debug!(cache = %path.display(), "dictionary cache hit");
+    Some(dictionary)
+}
+
+/// Write the cache of `dictionary`, built from `dict_path` and `freq_path`
+/// with options `key`. Returns its path.
+pub(crate) fn store(dictionary: &Dictionary, dict_path: &Path, freq_path: &Path, key: u64) -> io::Result<PathBuf> {
+    let mut bytes = Vec::new();
+    write_header(&mut bytes, key, Fingerprint::of(dict_path)?, Fingerprint::of(freq_path)?);
+    write_dictionary(&mut bytes, dictionary);
+
+    let path = cache_path(dict_path, freq_path, key);
+    write_atomically(&path, &bytes)?;
+    #[cfg(feature = "tracing")]
+    tracing::debug!(cache = %path.display(), bytes = bytes.len(), "dictionary cache written");
+    Ok(path)
+}
+
+/// Write to a temporary file and rename it, so concurrent loads never see a
+/// partial cache.
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut temp = path.as_os_str().to_os_string();
+    temp.push(format!(".tmp{}", std::process::id()));
+    let temp = PathBuf::from(temp);
+    let result = fs::File::create(&temp).and_then(|mut file| file.write_all(bytes)).and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+fn write_header(out: &mut Vec<u8>, key: u64, dict: Option<Fingerprint>, freq: Option<Fingerprint>) {
+    out.extend_from_slice(MAGIC);
+    put_u32(out, VERSION);
+    put_u64(out, key);
+    for fingerprint in [dict, freq] {
+        match fingerprint {
+            Some(fingerprint) => {
+                out.push(1);
+                put_u64(out, fingerprint.len);
+                put_u64(out, fingerprint.modified);
+                put_u64(out, fingerprint.hash);
+            }
+            None => out.push(0),
+        }
+    }
+}
+
+fn read_header(reader: &mut Reader) -> io::Result<(u64, Option<Fingerprint>, Option<Fingerprint>)> {
+    if reader.take(MAGIC.len())? != MAGIC || reader.u32()? != VERSION {
+        return Err(invalid("not a dictionary cache of this version"));
+    }
+    let key = reader.u64()?;
+    let mut fingerprint = || -> io::Result<Option<Fingerprint>> {
+        Ok(match reader.u8()? {
+            0 => None,
+            _ => Some(Fingerprint { len: reader.u64()?, modified: reader.u64()?, hash: reader.u64()? }),
+        })
+    };
+    Ok((key, fingerprint()?, fingerprint()?))
+}
+
+fn write_dictionary(out: &mut Vec<u8>, dictionary: &Dictionary) {
+    put_u64(out, dictionary.max_word_length as u64);
+    put_cost(out, dictionary.default_cost);
+    put_cost(out, dictionary.unknown_cost);
+
+    // Words in index order, so the cost table lines up on reading
+    let mut words: Vec<(&str, usize)> = dictionary.words.iter().map(|(word, &i)| (word.as_str(), i)).collect();
+    words.sort_unstable_by_key(|&(_, i)| i);
+    put_u64(out, words.len() as u64);
+    for (word, i) in words {
+        put_str(out, word);
+        put_cost(out, dictionary.costs[i]);
+    }
+
+    put_u64(out, dictionary.variant_of.len() as u64);
+    for (variant, canonical) in &dictionary.variant_of {
+        put_str(out, variant);
+        put_str(out, canonical);
+    }
+
+    put_u64(out, dictionary.trie.nodes.len() as u64);
+    for node in &dictionary.trie.nodes {
+        out.push(u8::from(node.is_word));
+        put_cost(out, node.cost);
+        put_u32(out, node.children.len() as u32);
+        for &(c, child) in &node.children {
+            put_u32(out, c as u32);
+            put_u32(out, child);
+        }
+    }
+}
+
+fn read_dictionary(reader: &mut Reader) -> io::Result<Dictionary> {
+    let max_word_length = reader.u64()? as usize;
+    let default_cost = reader.cost()?;
+    let unknown_cost = reader.cost()?;
+
+    let count = reader.len()?;
+    let mut words = FxHashMap::default();
+    words.reserve(count);
+    let mut costs = Vec::with_capacity(count);
+    for i in 0..count {
+        words.insert(reader.str()?.to_string(), i);
+        costs.push(reader.cost()?);
+    }
+
+    let count = reader.len()?;
+    let mut variant_of = FxHashMap::default();
+    variant_of.reserve(count);
+    for _ in 0..count {
+        variant_of.insert(reader.str()?.to_string(), reader.str()?.to_string());
+    }
+
+    let count = reader.len()?;
+    let mut nodes = Vec::with_capacity(count);
+    for _ in 0..count {
+        let is_word = reader.u8()? != 0;
+        let cost = reader.cost()?;
+        let children = (0..reader.u32()?)
+            .map(|_| {
+                let c = char::from_u32(reader.u32()?).ok_or_else(|| invalid("bad character"))?;
+                let child: NodeId = reader.u32()?;
+                if child as usize >= count {
+                    return Err(invalid("bad trie node"));
+                }
+                Ok((c, child))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        nodes.push(TrieNode { children, is_word, cost });
+    }
+    if nodes.is_empty() || reader.pos != reader.bytes.len() {
+        return Err(invalid("bad trie"));
+    }
+
+    Ok(Dictionary {
+        words,
+        costs,
+        trie: Trie { nodes },
+        max_word_length,
+        default_cost,
+        unknown_cost,
+        variant_of,
+        latin_words: FxHashMap::default(),
+    })
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_cost(out: &mut Vec<u8>, cost: Cost) {
+    out.extend_from_slice(&cost.to_le_bytes());
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    put_u32(out, s.len() as u32);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("dictionary cache: {}", message))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        let end = self.pos.checked_add(n).filter(|&end| end <= self.bytes.len()).ok_or_else(|| invalid("truncated"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("slice of N bytes"))
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    /// A count, checked against the bytes left so a corrupt file cannot
+    /// trigger a huge allocation.
+    fn len(&mut self) -> io::Result<usize> {
+        let len = self.u64()?;
+        if len > (self.bytes.len() - self.pos) as u64 {
+            return Err(invalid("bad count"));
+        }
+        Ok(len as usize)
+    }
+
+    fn cost(&mut self) -> io::Result<Cost> {
+        Ok(Cost::from_le_bytes(self.array()?))
+    }
+
+    fn str(&mut self) -> io::Result<&'a str> {
+        let len = self.u32()? as usize;
+        std::str::from_utf8(self.take(len)?).map_err(|_| invalid("bad UTF-8"))
+    }
+}
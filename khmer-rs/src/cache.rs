@@ -0,0 +1,208 @@
+//! Bounded LRU cache of segmentations keyed by the input line (see
+//! `SegmenterConfig::cache_size`).
+//!
+//! News corpora repeat headlines and boilerplate; a hit skips the DP and the
+//! post-processing entirely. Large caches are split into shards, each an LRU
+//! list behind its own mutex, so parallel workers rarely wait on each other.
+
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use fxhash::{FxHashMap, FxHasher};
+use serde::{Deserialize, Serialize};
+
+const MAX_SHARDS: usize = 16;
+const MIN_SHARD_SIZE: usize = 1024;
+const NIL: usize = usize::MAX;
+
+/// Hit and miss counts of a `SegmentCache`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Lines cached now
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+impl CacheStats {
+    pub fn lookups(&self) -> u64 {
+        self.hits + self.misses
+    }
+
+    /// Share of lookups that were hits, 0 before the first one.
+    pub fn hit_rate(&self) -> f64 {
+        self.hits as f64 / self.lookups().max(1) as f64
+    }
+}
+
+struct Entry {
+    key: String,
+    value: Vec<String>,
+    prev: usize,
+    next: usize,
+}
+
+/// One LRU list: `entries` linked from `head` (most recent) to `tail`.
+struct Shard {
+    map: FxHashMap<String, usize>,
+    entries: Vec<Entry>,
+    head: usize,
+    tail: usize,
+    capacity: usize,
+}
+
+impl Shard {
+    fn new(capacity: usize) -> Self {
+        Shard { map: FxHashMap::default(), entries: Vec::new(), head: NIL, tail: NIL, capacity }
+    }
+
+    fn unlink(&mut self, idx: usize) {
+        let (prev, next) = (self.entries[idx].prev, self.entries[idx].next);
+        match prev {
+            NIL => self.head = next,
+            prev => self.entries[prev].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => self.entries[next].prev = prev,
+        }
+    }
+
+    fn push_front(&mut self, idx: usize) {
+        self.entries[idx].prev = NIL;
+        self.entries[idx].next = self.head;
+        match self.head {
+            NIL => self.tail = idx,
+            head => self.entries[head].prev = idx,
+        }
+        self.head = idx;
+    }
+
+    fn get(&mut self, key: &str) -> Option<Vec<String>> {
+        let idx = *self.map.get(key)?;
+        if idx != self.head {
+            self.unlink(idx);
+            self.push_front(idx);
+        }
+        Some(self.entries[idx].value.clone())
+    }
+
+    fn insert(&mut self, key: &str, value: Vec<String>) {
+        if let Some(&idx) = self.map.get(key) {
+            self.entries[idx].value = value;
+            self.unlink(idx);
+            self.push_front(idx);
+            return;
+        }
+        let idx = if self.entries.len() < self.capacity {
+            self.entries.push(Entry { key: key.to_string(), value, prev: NIL, next: NIL });
+            self.entries.len() - 1
+        } else {
+            // Reuse the least recently used slot
+            let idx = self.tail;
+            self.unlink(idx);
+            let entry = &mut self.entries[idx];
+            self.map.remove(&entry.key);
+            entry.key = key.to_string();
+            entry.value = value;
+            idx
+        };
+        self.push_front(idx);
+        self.map.insert(key.to_string(), idx);
+    }
+
+    fn clear(&mut self) {
+        self.map.clear();
+        self.entries.clear();
+        self.head = NIL;
+        self.tail = NIL;
+    }
+}
+
+/// Concurrent LRU cache from input line to its segments, holding at most
+/// about `capacity` lines (rounded up to a multiple of the shard count).
+pub struct SegmentCache {
+    shards: Vec<Mutex<Shard>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    /// Bumped by `clear`, so segments computed before it are not inserted
+    /// after it (`insert_if_current`)
+    generation: AtomicU64,
+}
+
+impl SegmentCache {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        // Small caches are one exact LRU list
+        let shards = (capacity / MIN_SHARD_SIZE).clamp(1, MAX_SHARDS);
+        let per_shard = capacity.div_ceil(shards);
+        SegmentCache {
+            shards: (0..shards).map(|_| Mutex::new(Shard::new(per_shard))).collect(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    fn shard(&self, key: &str) -> &Mutex<Shard> {
+        let mut hasher = FxHasher::default();
+        key.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+
+    /// The cached segments of `key`, marking it most recently used.
+    pub fn get(&self, key: &str) -> Option<Vec<String>> {
+        let value = self.shard(key).lock().unwrap().get(key);
+        let counter = if value.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    /// Cache `value` for `key`, evicting the least recently used line of its
+    /// shard if full.
+    pub fn insert(&self, key: &str, value: Vec<String>) {
+        self.shard(key).lock().unwrap().insert(key, value);
+    }
+
+    /// Number of `clear` calls so far. Read it before computing a value for
+    /// `insert_if_current`.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// `insert`, unless the cache was cleared since `generation` was read:
+    /// the value may have been computed from what the clear invalidated.
+    pub fn insert_if_current(&self, key: &str, value: Vec<String>, generation: u64) {
+        let mut shard = self.shard(key).lock().unwrap();
+        // Checked under the shard lock, which `clear` takes after bumping the
+        // generation, so an entry is either skipped here or cleared there
+        if self.generation.load(Ordering::SeqCst) == generation {
+            shard.insert(key, value);
+        }
+    }
+
+    /// Drop every entry; the hit and miss counts are kept.
+    pub fn clear(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        for shard in &self.shards {
+            shard.lock().unwrap().clear();
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let (mut entries, mut capacity) = (0, 0);
+        for shard in &self.shards {
+            let shard = shard.lock().unwrap();
+            entries += shard.entries.len();
+            capacity += shard.capacity;
+        }
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries,
+            capacity,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Dictionary-independent orthographic units of Khmer text.
+
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+use crate::constants::{is_coeng, is_consonant, is_dependent_vowel, is_sign};
+use crate::span::Span;
+
+/// Split `text` into orthographic syllables (KCV clusters): a base consonant
+/// or independent vowel with its coeng subscripts, dependent vowels and signs.
+///
+/// No dictionary is consulted. Every non-Khmer character, and any Khmer mark
+/// that cannot attach to a base, is returned on its own. Zero-width spaces are
+/// dropped, as in `KhmerSegmenter::segment`.
+pub fn segment_syllables(text: &str) -> Vec<String> {
+    let cleaned = text.replace('\u{200b}', "");
+    khmer_clusters(&cleaned)
+        .map(|span| span.as_str(&cleaned).to_string())
+        .collect()
+}
+
+/// Iterate over the cluster spans of `text`: base consonant or independent
+/// vowel, followed by any coeng+consonant stacks, dependent vowels and signs.
+///
+/// Spans are contiguous and cover the whole input. Characters that cannot
+/// start a cluster (non-Khmer text, ZWSP, a leading coeng, a dependent vowel
+/// without a base) each form a one-character span, and a coeng that is not
+/// followed by a consonant ends the cluster before it.
+pub fn khmer_clusters(text: &str) -> KhmerClusters<'_> {
+    KhmerClusters { cps: text.chars().collect(), pos: 0, offset: 0, _text: PhantomData }
+}
+
+pub struct KhmerClusters<'a> {
+    cps: Vec<char>,
+    /// Next cluster's start, in codepoints and in bytes
+    pos: usize,
+    offset: usize,
+    _text: PhantomData<&'a str>,
+}
+
+impl Iterator for KhmerClusters<'_> {
+    type Item = Span;
+
+    fn next(&mut self) -> Option<Span> {
+        let len = get_khmer_cluster_length_cps(&self.cps, self.pos);
+        if len == 0 {
+            return None;
+        }
+        let start = self.offset;
+        self.offset += self.cps[self.pos..self.pos + len].iter().map(|c| c.len_utf8()).sum::<usize>();
+        self.pos += len;
+        Some(Span::new(start, self.offset))
+    }
+}
+
+impl FusedIterator for KhmerClusters<'_> {}
+
+/// Length in codepoints of the cluster starting at `cps[start]`.
+///
+/// Returns 1 when `cps[start]` cannot start a cluster (non-Khmer characters,
+/// dangling vowels/signs, a leading coeng), and 0 when `start` is out of range.
+#[inline]
+pub fn get_khmer_cluster_length_cps(cps: &[char], start: usize) -> usize {
+    if start >= cps.len() {
+        return 0;
+    }
+
+    let first_char = cps[start];
+    let code = first_char as u32;
+
+    // Must start with Base Consonant (1780-17A2) or Indep Vowel (17A3-17B3)
+    if !(0x1780..=0x17B3).contains(&code) {
+        return 1;
+    }
+
+    let mut len = 1;
+    let mut i = start + 1;
+
+    while i < cps.len() {
+        let c = cps[i];
+
+        // Coeng
+        if is_coeng(c) {
+            // Check if next is consonant
+            if i + 1 < cps.len() && is_consonant(cps[i + 1]) {
+                len += 2;
+                i += 2;
+                continue;
+            }
+            break;
+        }
+
+        if is_dependent_vowel(c) || is_sign(c) {
+            len += 1;
+            i += 1;
+            continue;
+        }
+
+        break;
+    }
+
+    len
+}
+
+/// Number of clusters in `cps`, as `get_khmer_cluster_length_cps` splits them.
+pub(crate) fn count_khmer_clusters_cps(cps: &[char]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < cps.len() {
+        i += get_khmer_cluster_length_cps(cps, i);
+        count += 1;
+    }
+    count
+}
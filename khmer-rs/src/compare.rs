@@ -0,0 +1,123 @@
+//! A/B comparison of two segmenters on the same unlabeled corpus.
+//!
+//! No reference is needed: A's output stands in for the reference when
+//! scoring B, so `Scores` reads as agreement rather than accuracy. Lines
+//! agree when both sides produce the same words, ignoring whitespace and
+//! zero-width spaces as `eval` does.
+
+use std::ops::Range;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::algorithm::Segmenter;
+use crate::eval::Scores;
+
+/// A line the two segmenters split differently.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Disagreement {
+    /// 0-based index into the compared lines
+    pub line: usize,
+    pub a: Vec<String>,
+    pub b: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Comparison {
+    /// A as reference, B as prediction: `exact_lines` counts agreeing lines,
+    /// `words.reference`/`words.predicted` are A's and B's token counts.
+    pub scores: Scores,
+    /// The first disagreements, in line order
+    pub samples: Vec<Disagreement>,
+}
+
+impl Disagreement {
+    /// Token ranges `(a_range, b_range)` of each stretch the two sides split
+    /// differently. Both ranges of a hunk cover the same text.
+    pub fn hunks(&self) -> Vec<(Range<usize>, Range<usize>)> {
+        let len = |t: &String| t.chars().filter(|&c| c != '\u{200B}').count();
+        let mut hunks = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.a.len() && j < self.b.len() {
+            if self.a[i] == self.b[j] {
+                i += 1;
+                j += 1;
+                continue;
+            }
+            // Advance whichever side is behind until both end at the same offset
+            let (start_i, start_j) = (i, j);
+            let (mut end_a, mut end_b) = (len(&self.a[i]), len(&self.b[j]));
+            i += 1;
+            j += 1;
+            while end_a != end_b {
+                if end_a < end_b && i < self.a.len() {
+                    end_a += len(&self.a[i]);
+                    i += 1;
+                } else if end_b < end_a && j < self.b.len() {
+                    end_b += len(&self.b[j]);
+                    j += 1;
+                } else {
+                    break;
+                }
+            }
+            hunks.push((start_i..i, start_j..j));
+        }
+        if i < self.a.len() || j < self.b.len() {
+            hunks.push((i..self.a.len(), j..self.b.len()));
+        }
+        hunks
+    }
+}
+
+impl Comparison {
+    pub fn agreement_rate(&self) -> f64 {
+        self.scores.exact_rate()
+    }
+
+    pub fn tokens_a(&self) -> usize {
+        self.scores.words.reference
+    }
+
+    pub fn tokens_b(&self) -> usize {
+        self.scores.words.predicted
+    }
+
+    /// B's token count minus A's.
+    pub fn token_delta(&self) -> isize {
+        self.tokens_b() as isize - self.tokens_a() as isize
+    }
+}
+
+/// Segment every line with both `a` and `b` (in parallel) and keep up to
+/// `max_samples` disagreements.
+pub fn compare<A, B, S>(a: &A, b: &B, lines: &[S], max_samples: usize) -> Comparison
+where
+    A: Segmenter + ?Sized,
+    B: Segmenter + ?Sized,
+    S: AsRef<str> + Sync,
+{
+    // Per line: its scores, plus both segmentations when they differ
+    type LineResult = (Scores, Option<(Vec<String>, Vec<String>)>);
+    let per_line: Vec<LineResult> = lines
+        .par_iter()
+        .map(|line| {
+            let (seg_a, seg_b) = (a.segment(line.as_ref()), b.segment(line.as_ref()));
+            let mut scores = Scores::default();
+            scores.add(&seg_a, &seg_b);
+            let differs = scores.exact_lines == 0;
+            (scores, differs.then_some((seg_a, seg_b)))
+        })
+        .collect();
+
+    let mut comparison = Comparison::default();
+    for (line, (scores, segments)) in per_line.into_iter().enumerate() {
+        comparison.scores.merge(&scores);
+        if let Some((a, b)) = segments {
+            if comparison.samples.len() < max_samples {
+                comparison.samples.push(Disagreement { line, a, b });
+            }
+        }
+    }
+    comparison
+}
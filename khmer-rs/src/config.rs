@@ -0,0 +1,379 @@
+//! Segmenter options set through `KhmerSegmenterBuilder`, dictionary cost
+//! overrides set through `DictionaryBuilder`, and the TOML file holding both:
+//!
+//! ```toml
+//! [dictionary]
+//! default_cost = 7.5
+//! unknown_cost = 12.5
+//!
+//! [segmenter]
+//! repair_penalty = 50.0
+//! invalid_single_penalty = 10.0
+//! length_normalization = 0.2
+//! disabled_passes = ["unknown-merge"]
+//! ```
+//!
+//! Every key is optional; field names below are the keys.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::is_separator;
+use crate::dictionary::Cost;
+
+/// Cambodian phone numbers: `0` and a two-digit prefix followed by six or
+/// seven digits, with spaces, hyphens or dots, or with the `+855` country
+/// code. See `SegmenterConfig::phone_patterns` for the syntax.
+pub const DEFAULT_PHONE_PATTERNS: &[&str] = &[
+    "0## ### ###",
+    "0## ### ####",
+    "0##-###-###",
+    "0##-###-####",
+    "0##.###.###",
+    "0##.###.####",
+    "0########",
+    "0#########",
+    "+855 ## ### ###",
+    "+855 ## ### ####",
+    "+855-##-###-###",
+    "+855-##-###-####",
+    "+855########",
+    "+855#########",
+];
+
+/// Currency symbols and codes recognized next to an amount (`$100`,
+/// `100៛`, `USD 50`). See `SegmenterConfig::currencies`.
+pub const DEFAULT_CURRENCIES: &[&str] =
+    &["$", "៛", "€", "£", "¥", "฿", "₫", "USD", "KHR", "EUR", "GBP", "JPY", "THB", "VND"];
+
+/// Extra cost of consuming one character in repair mode (after a coeng, or
+/// at a dependent vowel), on top of the unknown cost.
+pub const DEFAULT_REPAIR_PENALTY: Cost = 50.0;
+
+/// Extra cost of an unknown single consonant that cannot stand alone as a word.
+pub const DEFAULT_INVALID_SINGLE_PENALTY: Cost = 10.0;
+
+/// A post-processing pass run after the DP, listed in the order they run.
+/// All are enabled by default; disabling one shows how it changes the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Pass {
+    /// Attach unknown single consonants that cannot stand alone to the
+    /// previous segment, unless separators surround them.
+    SnapInvalidSingles,
+    /// Merge a consonant carrying Bantoc, Kakabat, Ahsdja or I + Toe into the
+    /// previous segment, and one carrying Samyok Sannya into the next.
+    SignMerge,
+    /// Join runs of consecutive unknown segments into one.
+    UnknownMerge,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 3] = [Pass::SnapInvalidSingles, Pass::SignMerge, Pass::UnknownMerge];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pass::SnapInvalidSingles => "snap-invalid-singles",
+            Pass::SignMerge => "sign-merge",
+            Pass::UnknownMerge => "unknown-merge",
+        }
+    }
+}
+
+impl fmt::Display for Pass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Pass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Pass::ALL.into_iter().find(|pass| pass.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = Pass::ALL.iter().map(|pass| pass.name()).collect();
+            format!("unknown pass {:?} (expected one of {})", s, names.join(", "))
+        })
+    }
+}
+
+/// Which separators may appear between the digits of one number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NumberFormat {
+    /// `,`, `.` and space anywhere between digits, as in the Python reference.
+    #[default]
+    Any,
+    /// `1,234,567.89`: `,` or space before each group of three digits, `.`
+    /// before the decimals.
+    DecimalPoint,
+    /// `1.234.567,89`: `.` or space before each group of three digits, `,`
+    /// before the decimals.
+    DecimalComma,
+}
+
+impl NumberFormat {
+    pub const ALL: [NumberFormat; 3] = [NumberFormat::Any, NumberFormat::DecimalPoint, NumberFormat::DecimalComma];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NumberFormat::Any => "any",
+            NumberFormat::DecimalPoint => "decimal-point",
+            NumberFormat::DecimalComma => "decimal-comma",
+        }
+    }
+}
+
+impl fmt::Display for NumberFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for NumberFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NumberFormat::ALL.into_iter().find(|format| format.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = NumberFormat::ALL.iter().map(|format| format.name()).collect();
+            format!("unknown number format {:?} (expected one of {})", s, names.join(", "))
+        })
+    }
+}
+
+/// What becomes of whitespace between tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WhitespacePolicy {
+    /// Each space is its own `Separator` token and other whitespace (tabs)
+    /// is an unknown character, as in the Python reference.
+    #[default]
+    Split,
+    /// A run of whitespace is one token, kept verbatim.
+    Keep,
+    /// A run of whitespace is one `" "` token.
+    Collapse,
+    /// Whitespace produces no tokens.
+    Drop,
+}
+
+impl WhitespacePolicy {
+    pub const ALL: [WhitespacePolicy; 4] =
+        [WhitespacePolicy::Split, WhitespacePolicy::Keep, WhitespacePolicy::Collapse, WhitespacePolicy::Drop];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WhitespacePolicy::Split => "split",
+            WhitespacePolicy::Keep => "keep",
+            WhitespacePolicy::Collapse => "collapse",
+            WhitespacePolicy::Drop => "drop",
+        }
+    }
+}
+
+impl fmt::Display for WhitespacePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for WhitespacePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WhitespacePolicy::ALL.into_iter().find(|policy| policy.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = WhitespacePolicy::ALL.iter().map(|policy| policy.name()).collect();
+            format!("unknown whitespace policy {:?} (expected one of {})", s, names.join(", "))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SegmenterConfig {
+    /// Map emitted tokens that are generated spelling variants (Ta/Da,
+    /// coeng-Ro order) back to the spelling listed in the dictionary.
+    pub canonicalize_variants: bool,
+
+    /// Also match dictionary words that differ from the input by one dependent
+    /// vowel or sign, at the word's cost plus this penalty. `None` disables it.
+    pub fuzzy_match_cost: Option<Cost>,
+
+    /// Multiply each dictionary word's cost by `clusters ^ exponent`. Long
+    /// words cover more text with a single cost, so they beat splits that are
+    /// individually more likely; a positive exponent counters that. `None`
+    /// leaves costs unchanged.
+    pub length_normalization: Option<Cost>,
+
+    /// Beam pruning margin. A position is not expanded when its path cost
+    /// exceeds, by more than this margin, the cheapest path already reaching
+    /// it or a position within one word length ahead. Bounds the work on very
+    /// long lines at the price of guaranteed optimality. `None` runs the exact DP.
+    pub beam_margin: Option<Cost>,
+
+    /// `None` uses `DEFAULT_REPAIR_PENALTY`.
+    pub repair_penalty: Option<Cost>,
+
+    /// `None` uses `DEFAULT_INVALID_SINGLE_PENALTY`.
+    pub invalid_single_penalty: Option<Cost>,
+
+    /// Keep `#hashtags` (Latin or Khmer) and `@mentions` as single tokens,
+    /// for social media text.
+    pub social_media: bool,
+
+    /// Keep numeric dates (`15/01/2024`) and Khmer date expressions
+    /// (`ថ្ងៃទី១៥ ខែមករា ឆ្នាំ២០២៤`) as single tokens.
+    pub dates: bool,
+
+    /// Phone number patterns kept whole as one `Phone` token: `#` is any
+    /// digit, any other character (including a digit) matches itself, with
+    /// ASCII digits also matching Khmer ones. `None` uses
+    /// `DEFAULT_PHONE_PATTERNS`; an empty list recognizes none.
+    pub phone_patterns: Option<Vec<String>>,
+
+    /// Currency symbols and codes kept with an adjacent amount as one
+    /// `Currency` token, before (`$100`) or after it (`100៛`). Codes made of
+    /// letters (`USD`, `រៀល`) may be separated from the amount by one space.
+    /// `None` uses `DEFAULT_CURRENCIES`; an empty list recognizes none.
+    pub currencies: Option<Vec<String>>,
+
+    /// Grouping and decimal separators accepted inside numbers.
+    pub number_format: NumberFormat,
+
+    /// Keep ranges written with a hyphen or en dash and no spaces
+    /// (`10-20`, `២០–៣០`) as one number.
+    pub number_ranges: bool,
+
+    /// Characters split off as single `Separator` tokens, replacing the
+    /// built-in set (Khmer punctuation, common ASCII punctuation, `«»“”`,
+    /// `$`, `%` and space; see `constants::is_separator`). `None` uses it.
+    pub separators: Option<Vec<char>>,
+
+    /// Characters treated as separators on top of `separators` (`|`, `…`).
+    pub extra_separators: Vec<char>,
+
+    /// Treat zero-width spaces in the input as forced word boundaries: the
+    /// text between two of them is segmented (post-processing included) on
+    /// its own, so no segment spans one. Otherwise they are deleted before
+    /// segmentation, as in the Python reference.
+    pub zwsp_boundaries: bool,
+
+    /// Emit each zero-width space (U+200B) as its own token instead of
+    /// deleting it, so that the segments concatenate to the input. A ZWSP
+    /// inside a segment (when the segmenter joined across it) stays in that
+    /// segment's text.
+    pub keep_zwsp: bool,
+
+    /// Make `segment`, `segment_spans` and `segment_tokens` lossless: the
+    /// segments concatenate to the input exactly, zero-width spaces and
+    /// whitespace included, in the input's spelling. Overrides `keep_zwsp`,
+    /// `whitespace` (other than `Split`) and `canonicalize_variants`; see
+    /// `KhmerSegmenter::segment_lossless`.
+    pub lossless: bool,
+
+    /// Except under `WhitespacePolicy::Split`, every whitespace character is
+    /// a separator and runs of them are merged into one token.
+    pub whitespace: WhitespacePolicy,
+
+    /// Post-processing passes to skip.
+    pub disabled_passes: Vec<Pass>,
+
+    /// Remember the segments of up to this many recent distinct lines, so a
+    /// repeated line skips segmentation (see `cache`). `None` caches nothing.
+    pub cache_size: Option<usize>,
+}
+
+impl SegmenterConfig {
+    pub fn is_enabled(&self, pass: Pass) -> bool {
+        !self.disabled_passes.contains(&pass)
+    }
+
+    /// Whether `c` is a separator under `separators` and `extra_separators`.
+    pub fn is_separator(&self, c: char) -> bool {
+        let in_set = match &self.separators {
+            Some(separators) => separators.contains(&c),
+            None => is_separator(c),
+        };
+        in_set
+            || self.extra_separators.contains(&c)
+            || (self.whitespace != WhitespacePolicy::Split && c.is_whitespace())
+    }
+
+    /// Whether the built-in separator set is in effect, unchanged.
+    pub fn has_default_separators(&self) -> bool {
+        self.separators.is_none() && self.extra_separators.is_empty() && self.whitespace == WhitespacePolicy::Split
+    }
+}
+
+/// Overrides for the costs `Dictionary` derives from the frequency data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DictionaryConfig {
+    /// Cost of listed words without a frequency. Derived from the frequency
+    /// floor (`-log10(5 / total)`) when `None`.
+    pub default_cost: Option<Cost>,
+
+    /// Cost of an unknown cluster or character. Derived from the frequency
+    /// data (5 above the derived default cost) when `None`.
+    pub unknown_cost: Option<Cost>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub dictionary: DictionaryConfig,
+    pub segmenter: SegmenterConfig,
+}
+
+impl ConfigFile {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        ConfigFile::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(text: &str) -> io::Result<Self> {
+        toml::from_str(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    /// Config file text that `from_toml` reads back as `self`; unset
+    /// options are left out.
+    pub fn to_toml(&self) -> io::Result<String> {
+        toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    /// Names accepted by `set`.
+    pub const NUMERIC_KEYS: &'static [&'static str] = &[
+        "default_cost",
+        "unknown_cost",
+        "repair_penalty",
+        "invalid_single_penalty",
+        "fuzzy_match_cost",
+        "length_normalization",
+        "beam_margin",
+    ];
+
+    /// Set a numeric parameter by its key (section names are not needed;
+    /// keys are unique across sections).
+    pub fn set(&mut self, key: &str, value: Cost) -> io::Result<()> {
+        let field = match key {
+            "default_cost" => &mut self.dictionary.default_cost,
+            "unknown_cost" => &mut self.dictionary.unknown_cost,
+            "repair_penalty" => &mut self.segmenter.repair_penalty,
+            "invalid_single_penalty" => &mut self.segmenter.invalid_single_penalty,
+            "fuzzy_match_cost" => &mut self.segmenter.fuzzy_match_cost,
+            "length_normalization" => &mut self.segmenter.length_normalization,
+            "beam_margin" => &mut self.segmenter.beam_margin,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown parameter {:?} (expected one of {})", key, ConfigFile::NUMERIC_KEYS.join(", ")),
+                ))
+            }
+        };
+        *field = Some(value);
+        Ok(())
+    }
+}
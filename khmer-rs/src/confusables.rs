@@ -0,0 +1,146 @@
+//! Folding of lookalike codepoints before dictionary lookup
+//! (`--confusables`).
+//!
+//! Text copied from old fonts, PDFs or other scripts' keyboards carries
+//! codepoints that look like the ones the dictionary uses but are distinct,
+//! such as the deprecated independent vowel U+17A3 `ឣ` for U+17A2 `អ`, or
+//! fullwidth digits. The DP sees each codepoint through the mapping, so
+//! words and numbers written with them are found; the segments still come
+//! back spelled as in the input (folded with `canonicalize_variants`).
+//!
+//! A mapping file has one `from<TAB>to` pair per line, each a single
+//! character written as itself or as `U+17A3`; empty lines and lines
+//! starting with `#` are skipped. Mappings are one codepoint to one
+//! codepoint, so segments keep their positions in the input; deprecated
+//! characters that stand for several (U+17A4, U+17D8) are `--normalize`'s.
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use fxhash::FxHashMap;
+
+/// The built-in mapping: the deprecated U+17A3, fullwidth digits, the
+/// no-break space and the dash lookalikes of the hyphen-minus.
+const BUILTIN: &[(char, char)] = &[
+    ('\u{17A3}', '\u{17A2}'),
+    ('\u{FF10}', '0'),
+    ('\u{FF11}', '1'),
+    ('\u{FF12}', '2'),
+    ('\u{FF13}', '3'),
+    ('\u{FF14}', '4'),
+    ('\u{FF15}', '5'),
+    ('\u{FF16}', '6'),
+    ('\u{FF17}', '7'),
+    ('\u{FF18}', '8'),
+    ('\u{FF19}', '9'),
+    ('\u{00A0}', ' '),
+    ('\u{2010}', '-'),
+    ('\u{2011}', '-'),
+    ('\u{2212}', '-'),
+];
+
+/// A mapping of codepoints to the ones the dictionary is written with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Confusables {
+    map: FxHashMap<char, char>,
+}
+
+impl Confusables {
+    /// An empty mapping.
+    pub fn new() -> Self {
+        Confusables::default()
+    }
+
+    /// The built-in mapping (see `BUILTIN`).
+    pub fn builtin() -> Self {
+        let mut confusables = Confusables::new();
+        for &(from, to) in BUILTIN {
+            confusables.insert(from, to);
+        }
+        confusables
+    }
+
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Confusables::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut confusables = Confusables::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split('\t');
+            match (fields.next().and_then(parse_char), fields.next().and_then(parse_char), fields.next()) {
+                (Some(from), Some(to), None) => confusables.insert(from, to),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: expected from<TAB>to, each one character or U+XXXX", i + 1),
+                    ))
+                }
+            }
+        }
+        Ok(confusables)
+    }
+
+    /// Read `from` as `to`. A later mapping of `from` replaces this one.
+    pub fn insert(&mut self, from: char, to: char) {
+        if from == to {
+            self.map.remove(&from);
+        } else {
+            self.map.insert(from, to);
+        }
+    }
+
+    /// `c` as the dictionary spells it.
+    #[inline]
+    pub fn fold(&self, c: char) -> char {
+        self.map.get(&c).copied().unwrap_or(c)
+    }
+
+    /// Fold `chars` in place; whether any changed.
+    pub fn fold_chars(&self, chars: &mut [char]) -> bool {
+        let mut changed = false;
+        for c in chars {
+            if let Some(&to) = self.map.get(c) {
+                *c = to;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// `text` folded; borrowed when nothing changes.
+    pub fn fold_str<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if text.chars().any(|c| self.map.contains_key(&c)) {
+            Cow::Owned(text.chars().map(|c| self.fold(c)).collect())
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// One character, as itself or in `U+XXXX` notation.
+fn parse_char(field: &str) -> Option<char> {
+    let field = field.trim_matches(|c: char| c == ' ' || c == '\r');
+    if let Some(hex) = field.strip_prefix("U+").or_else(|| field.strip_prefix("u+")) {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+    }
+    let mut chars = field.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
@@ -7,27 +7,27 @@ pub const KHMER_SYMBOLS_END: char = '\u{19FF}';
 
 pub fn is_khmer_char(c: char) -> bool {
     let code = c as u32;
-    (code >= 0x1780 && code <= 0x17FF) || (code >= 0x19E0 && code <= 0x19FF)
+    (0x1780..=0x17FF).contains(&code) || (0x19E0..=0x19FF).contains(&code)
 }
 
 pub fn is_consonant(c: char) -> bool {
     let code = c as u32;
-    code >= 0x1780 && code <= 0x17A2
+    (0x1780..=0x17A2).contains(&code)
 }
 
 pub fn is_independent_vowel(c: char) -> bool {
     let code = c as u32;
-    code >= 0x17A3 && code <= 0x17B3
+    (0x17A3..=0x17B3).contains(&code)
 }
 
 pub fn is_dependent_vowel(c: char) -> bool {
     let code = c as u32;
-    code >= 0x17B6 && code <= 0x17C5
+    (0x17B6..=0x17C5).contains(&code)
 }
 
 pub fn is_sign(c: char) -> bool {
     let code = c as u32;
-    (code >= 0x17C6 && code <= 0x17D1) || c == '\u{17D3}' || c == '\u{17DD}'
+    (0x17C6..=0x17D1).contains(&code) || c == '\u{17D3}' || c == '\u{17DD}'
 }
 
 pub fn is_coeng(c: char) -> bool {
@@ -37,7 +37,7 @@ pub fn is_coeng(c: char) -> bool {
 pub fn is_digit(c: char) -> bool {
     let code = c as u32;
     // ASCII 0-9 or Khmer 0-9
-    (code >= 0x30 && code <= 0x39) || (code >= 0x17E0 && code <= 0x17E9)
+    (0x30..=0x39).contains(&code) || (0x17E0..=0x17E9).contains(&code)
 }
 
 pub fn is_currency_symbol(c: char) -> bool {
@@ -47,7 +47,7 @@ pub fn is_currency_symbol(c: char) -> bool {
 pub fn is_separator(c: char) -> bool {
     let code = c as u32;
     // Khmer Punctuation 0x17D4 - 0x17DA
-    if code >= 0x17D4 && code <= 0x17DA {
+    if (0x17D4..=0x17DA).contains(&code) {
         return true;
     }
     // Currency Reil (U+17DB) is NOT a separator for splitting purposes in our logic (it's currency)
@@ -74,3 +74,35 @@ pub fn is_valid_single_word(c: char) -> bool {
     // Independent Vowels
     matches!(c, 'ឬ' | 'ឮ' | 'ឪ' | 'ឯ' | 'ឱ' | 'ឦ' | 'ឧ' | 'ឳ')
 }
+
+// Emoji (UTS #51). Extended_Pictographic, approximated by the blocks that
+// hold emoji plus the scattered emoji outside them.
+pub fn is_pictographic(c: char) -> bool {
+    let code = c as u32;
+    matches!(
+        code,
+        0x00A9 | 0x00AE | 0x203C | 0x2049 | 0x2122 | 0x2139 | 0x2194..=0x2199 | 0x21A9..=0x21AA
+            | 0x231A..=0x231B | 0x2328 | 0x23CF | 0x23E9..=0x23F3 | 0x23F8..=0x23FA | 0x24C2
+            | 0x25AA..=0x25AB | 0x25B6 | 0x25C0 | 0x25FB..=0x25FE | 0x2600..=0x27BF | 0x2934..=0x2935
+            | 0x2B05..=0x2B07 | 0x2B1B..=0x2B1C | 0x2B50 | 0x2B55 | 0x3030 | 0x303D | 0x3297 | 0x3299
+            | 0x1F000..=0x1FAFF
+    ) && !is_regional_indicator(c)
+}
+
+/// Skin tone modifiers
+pub fn is_emoji_modifier(c: char) -> bool {
+    ('\u{1F3FB}'..='\u{1F3FF}').contains(&c)
+}
+
+/// Flag letters; two in a row form a country flag
+pub fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// Letters of the Latin script (Basic Latin through Latin Extended-B, and
+/// Latin Extended Additional for Vietnamese)
+pub fn is_latin_letter(c: char) -> bool {
+    let code = c as u32;
+    c.is_ascii_alphabetic()
+        || (((0xC0..=0x24F).contains(&code) || (0x1E00..=0x1EFF).contains(&code)) && c.is_alphabetic())
+}
@@ -0,0 +1,146 @@
+//! Unix domain socket daemon that keeps a loaded segmenter warm.
+//!
+//! Wire protocol (all integers are big-endian `u32`):
+//! - Request:  `len` + `len` bytes of UTF-8 text
+//! - Response: `count` + `count` x (`len` + `len` bytes of UTF-8 segment)
+//!
+//! A connection may carry any number of requests; the server closes it on EOF.
+
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+use crate::segmenter::KhmerSegmenter;
+
+/// Upper bound on a single frame, guards against garbage length prefixes.
+pub const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+fn write_frame<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
+    w.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    w.write_all(bytes)
+}
+
+/// Returns `Ok(None)` on a clean EOF before the length prefix.
+fn read_len<R: Read>(r: &mut R) -> io::Result<Option<usize>> {
+    let mut len_buf = [0u8; 4];
+    match r.read_exact(&mut len_buf) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("frame too large: {} bytes", len)));
+    }
+    Ok(Some(len))
+}
+
+fn read_string<R: Read>(r: &mut R, len: usize) -> io::Result<String> {
+    let mut buf = vec![0u8; len];
+    r.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+fn handle_connection(segmenter: &KhmerSegmenter, stream: UnixStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    while let Some(len) = read_len(&mut reader)? {
+        let text = read_string(&mut reader, len)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(monotonic_counter.daemon_requests = 1u64, bytes = len);
+        let segments = segmenter.segment(&text);
+
+        writer.write_all(&(segments.len() as u32).to_be_bytes())?;
+        for seg in &segments {
+            write_frame(&mut writer, seg.as_bytes())?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Remove a socket left behind by a daemon that is no longer running. Fails
+/// if a daemon still answers on it, or if the path is not a socket.
+fn remove_stale_socket(socket_path: &Path) -> io::Result<()> {
+    let metadata = match std::fs::symlink_metadata(socket_path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", socket_path.display()),
+        ));
+    }
+    if UnixStream::connect(socket_path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("a daemon is already running on {}", socket_path.display()),
+        ));
+    }
+    std::fs::remove_file(socket_path)
+}
+
+/// Bind `socket_path` and serve requests forever, one thread per connection.
+///
+/// A stale socket left behind by a previous run is removed first; a socket
+/// another daemon is listening on, or any other file, is left alone.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "info", skip_all, fields(socket = %socket_path.display())))]
+pub fn serve(segmenter: Arc<KhmerSegmenter>, socket_path: &Path) -> io::Result<()> {
+    remove_stale_socket(socket_path)?;
+    let listener = UnixListener::bind(socket_path)?;
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let segmenter = Arc::clone(&segmenter);
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(&segmenter, stream) {
+                eprintln!("Connection error: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Client side of the daemon protocol.
+pub struct Client {
+    reader: BufReader<UnixStream>,
+    writer: BufWriter<UnixStream>,
+}
+
+impl Client {
+    pub fn connect(socket_path: &Path) -> io::Result<Self> {
+        let stream = UnixStream::connect(socket_path)?;
+        Ok(Client {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: BufWriter::new(stream),
+        })
+    }
+
+    pub fn segment(&mut self, text: &str) -> io::Result<Vec<String>> {
+        write_frame(&mut self.writer, text.as_bytes())?;
+        self.writer.flush()?;
+
+        let count = read_len(&mut self.reader)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "daemon closed connection"))?;
+        let mut segments = Vec::with_capacity(count.min(4096));
+        for _ in 0..count {
+            let len = read_len(&mut self.reader)?
+                .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "truncated response"))?;
+            segments.push(read_string(&mut self.reader, len)?);
+        }
+        Ok(segments)
+    }
+}
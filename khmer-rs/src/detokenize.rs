@@ -0,0 +1,94 @@
+//! Rejoining segmented text (`detokenize`), the inverse of segmentation for
+//! post-editing pipelines: segment, edit the tokens, write the text back.
+//!
+//! Khmer writes no spaces between words, so Khmer tokens (Khmer digits
+//! included) are joined directly. Latin words, ASCII numbers and other
+//! tokens get a space on each side. Punctuation follows the usual
+//! conventions: no space before `។`, `,`, `)` and the like, none after `(`,
+//! `«` or `$`, none around `-`, `/` and `ៗ`. Whitespace and zero-width space
+//! tokens are kept as they are, and no space is added next to them, so
+//! segments that keep their whitespace rejoin to the original line.
+
+use crate::constants::{is_khmer_char, is_separator};
+
+/// How a token joins its neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Glue {
+    /// Khmer text: no space next to other Khmer text
+    Khmer,
+    /// Latin words, ASCII numbers, symbols: a space on each side
+    Spaced,
+    /// Whitespace kept from the input: no space added next to it
+    Space,
+    /// Opening punctuation: no space after it
+    Open,
+    /// Closing punctuation: no space before it
+    Close,
+    /// Joiners such as `-` and `/`: no space on either side
+    Join,
+}
+
+fn is_closing(c: char) -> bool {
+    matches!(
+        c,
+        '។' | '៕' | '៖' | '៚' | '៛' | '!' | '?' | '.' | ',' | ';' | ':' | ')' | ']' | '}' | '»' | '”' | '%'
+    )
+}
+
+fn is_opening(c: char) -> bool {
+    matches!(c, '(' | '[' | '{' | '«' | '“' | '$' | '€' | '£' | '¥')
+}
+
+fn glue(token: &str, quote_open: &mut bool) -> Glue {
+    if token.is_empty() || token.chars().all(|c| c.is_whitespace() || c == '\u{200b}') {
+        return Glue::Space;
+    }
+    if token == "\"" {
+        // Straight quotes alternate between opening and closing
+        *quote_open = !*quote_open;
+        return if *quote_open { Glue::Open } else { Glue::Close };
+    }
+    if token.chars().all(is_closing) {
+        return Glue::Close;
+    }
+    if token.chars().all(is_opening) {
+        return Glue::Open;
+    }
+    if token.chars().all(is_separator) {
+        return Glue::Join;
+    }
+    // Khmer numbers may hold spaces and separators: `៩៣ ៩៧០`, `១,០០០`
+    if token.chars().any(is_khmer_char)
+        && token.chars().all(|c| is_khmer_char(c) || is_separator(c) || c.is_whitespace())
+    {
+        return Glue::Khmer;
+    }
+    Glue::Spaced
+}
+
+fn needs_space(previous: Glue, next: Glue) -> bool {
+    !matches!(
+        (previous, next),
+        (Glue::Space | Glue::Open | Glue::Join, _)
+            | (_, Glue::Space | Glue::Close | Glue::Join)
+            | (Glue::Khmer, Glue::Khmer)
+    )
+}
+
+/// Join `tokens` back into text with Khmer spacing rules:
+/// `["ខ្ញុំ", "ប្រើ", "iPhone", "។"]` becomes `ខ្ញុំប្រើ iPhone។`.
+pub fn detokenize<S: AsRef<str>>(tokens: &[S]) -> String {
+    let mut text = String::with_capacity(tokens.iter().map(|t| t.as_ref().len() + 1).sum());
+    let mut quote_open = false;
+    let mut previous = None;
+    for token in tokens {
+        let token = token.as_ref();
+        let next = glue(token, &mut quote_open);
+        if previous.is_some_and(|previous| needs_space(previous, next)) {
+            text.push(' ');
+        }
+        text.push_str(token);
+        previous = Some(next);
+    }
+    text
+}
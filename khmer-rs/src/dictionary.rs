@@ -1,69 +1,353 @@
 use std::collections::{HashSet, HashMap};
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use fxhash::FxHashMap;
 
+use crate::binary;
+use crate::config::DictionaryConfig;
 use crate::constants::is_valid_single_word;
+#[cfg(feature = "embedded")]
+use crate::embedded;
+use crate::frequency::{read_frequencies, Frequencies, FrequencyFormat};
+use crate::overlay::Overlay;
+use crate::variants::VariantGenerator;
+
+/// Word and path cost (`-log10` probability).
+///
+/// `f32` by default. The `f64-costs` feature switches every cost, including
+/// the DP's running totals, to `f64` as in the Python reference, so ties that
+/// `f32` rounding would break differently are resolved the same way.
+#[cfg(not(feature = "f64-costs"))]
+pub type Cost = f32;
+#[cfg(feature = "f64-costs")]
+pub type Cost = f64;
+
+/// Index of a node in its `Trie`.
+pub type NodeId = u32;
 
 #[derive(Default)]
 pub struct TrieNode {
-    children: FxHashMap<char, Box<TrieNode>>,
-    is_word: bool,
-    cost: f32,
+    /// Sorted by character. Most nodes have one or two children, so a small
+    /// array searched in place beats a hash map on both size and speed.
+    pub(crate) children: Vec<(char, NodeId)>,
+    pub(crate) is_word: bool,
+    pub(crate) cost: Cost,
 }
 
-impl TrieNode {
+/// Codepoint trie of the dictionary words. Nodes live in one array, the root
+/// first, and refer to their children by index.
+pub struct Trie {
+    pub(crate) nodes: Vec<TrieNode>,
+}
+
+impl Default for Trie {
+    fn default() -> Self {
+        Trie { nodes: vec![TrieNode::default()] }
+    }
+}
+
+impl Trie {
+    pub fn new() -> Self {
+        Trie::default()
+    }
+
+    /// Number of nodes, the root included.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the trie holds no word.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1 && !self.nodes[0].is_word
+    }
+
+    #[inline]
+    pub(crate) fn root(&self) -> &TrieNode {
+        &self.nodes[0]
+    }
+
     #[inline]
-    fn get_child(&self, c: char) -> Option<&TrieNode> {
-        self.children.get(&c).map(|b| b.as_ref())
+    pub(crate) fn child(&self, node: &TrieNode, c: char) -> Option<&TrieNode> {
+        let i = node.children.binary_search_by_key(&c, |&(child, _)| child).ok()?;
+        Some(&self.nodes[node.children[i].1 as usize])
+    }
+
+    /// Children of `node` in character order.
+    pub(crate) fn children<'a>(&'a self, node: &'a TrieNode) -> impl Iterator<Item = (char, &'a TrieNode)> + 'a {
+        node.children.iter().map(|&(c, id)| (c, &self.nodes[id as usize]))
     }
 
-    fn get_or_create_child(&mut self, c: char) -> &mut TrieNode {
-        self.children.entry(c).or_insert_with(|| Box::new(TrieNode::default()))
+    /// Add `word` with `cost`, or set the cost of a word already there.
+    pub(crate) fn insert(&mut self, word: &str, cost: Cost) {
+        let mut id = 0;
+        for c in word.chars() {
+            id = match self.nodes[id].children.binary_search_by_key(&c, |&(child, _)| child) {
+                Ok(i) => self.nodes[id].children[i].1 as usize,
+                Err(i) => {
+                    let child = NodeId::try_from(self.nodes.len()).expect("trie node count exceeds u32");
+                    self.nodes.push(TrieNode::default());
+                    self.nodes[id].children.insert(i, (c, child));
+                    child as usize
+                }
+            };
+        }
+        self.nodes[id].is_word = true;
+        self.nodes[id].cost = cost;
+    }
+
+    /// Release the spare capacity left by building.
+    pub(crate) fn shrink_to_fit(&mut self) {
+        for node in &mut self.nodes {
+            node.children.shrink_to_fit();
+        }
+        self.nodes.shrink_to_fit();
+    }
+
+    /// Estimated heap bytes: the node array and every child array.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<TrieNode>()
+            + self.nodes.iter().map(|node| node.children.capacity() * std::mem::size_of::<(char, NodeId)>()).sum::<usize>()
     }
 }
 
 pub struct Dictionary {
     pub words: FxHashMap<String, usize>, // Maps word -> index
-    pub costs: Vec<f32>,                 // Maps index -> cost
-    pub trie: TrieNode,                  // Trie for fast codepoint-based lookups
+    pub costs: Vec<Cost>,                // Maps index -> cost
+    pub trie: Trie,                      // Trie for fast codepoint-based lookups
     pub max_word_length: usize,          // Max word length in codepoints
-    pub default_cost: f32,
-    pub unknown_cost: f32,
+    pub default_cost: Cost,
+    pub unknown_cost: Cost,
+    pub variant_of: FxHashMap<String, String>, // Generated variant -> listed (canonical) spelling
+    pub latin_words: FxHashMap<String, Cost>,  // Lowercased Latin-script word -> cost
+}
+
+/// Options for loading a `Dictionary`. `Dictionary::new` and
+/// `Dictionary::from_bytes` use the defaults.
+#[derive(Debug, Clone, Default)]
+pub struct DictionaryBuilder {
+    variants: VariantGenerator,
+    overlays: Vec<Overlay>,
+    latin_words: Vec<Overlay>,
+    frequency_format: Option<FrequencyFormat>,
+    costs: DictionaryConfig,
+    binary_cache: bool,
+}
+
+impl DictionaryBuilder {
+    pub fn new() -> Self {
+        DictionaryBuilder::default()
+    }
+
+    /// Rules used to add spelling variants of every listed word.
+    pub fn variants(mut self, generator: VariantGenerator) -> Self {
+        self.variants = generator;
+        self
+    }
+
+    /// Format of the frequency data. By default `load` picks it from the file
+    /// extension and `load_bytes` expects JSON.
+    pub fn frequency_format(mut self, format: FrequencyFormat) -> Self {
+        self.frequency_format = Some(format);
+        self
+    }
+
+    /// Fixed default/unknown costs instead of the ones derived from the
+    /// frequency data (see `DictionaryConfig`).
+    pub fn config(mut self, config: DictionaryConfig) -> Self {
+        self.costs = config;
+        self
+    }
+
+    /// See `DictionaryConfig::default_cost`.
+    pub fn default_cost(mut self, cost: Cost) -> Self {
+        self.costs.default_cost = Some(cost);
+        self
+    }
+
+    /// See `DictionaryConfig::unknown_cost`.
+    pub fn unknown_cost(mut self, cost: Cost) -> Self {
+        self.costs.unknown_cost = Some(cost);
+        self
+    }
+
+    /// Apply `overlay` after the base word list. Overlays are applied in the
+    /// order they are added; see `overlay` for the merge rules.
+    pub fn overlay(mut self, overlay: Overlay) -> Self {
+        self.overlays.push(overlay);
+        self
+    }
+
+    /// Latin-script words (e.g. common English words in Khmer news text)
+    /// matched case-insensitively as whole Latin words instead of costing
+    /// an unknown token. Same file format as an overlay; entries without
+    /// their own cost use the list's cost, or the default cost.
+    pub fn latin_words(mut self, words: Overlay) -> Self {
+        self.latin_words.push(words);
+        self
+    }
+
+    /// Keep a compiled copy of the dictionary built by `load` in a binary
+    /// cache file, and load that instead while the source files are
+    /// unchanged (see `binary`). Off by default.
+    pub fn binary_cache(mut self, enabled: bool) -> Self {
+        self.binary_cache = enabled;
+        self
+    }
+
+    /// The binary cache file `load` uses for `dict_path` and `freq_path`
+    /// with these options.
+    pub fn cache_path(&self, dict_path: &Path, freq_path: &Path) -> PathBuf {
+        binary::cache_path(dict_path, freq_path, self.cache_key(freq_path))
+    }
+
+    fn format_of(&self, freq_path: &Path) -> FrequencyFormat {
+        self.frequency_format.unwrap_or_else(|| FrequencyFormat::from_path(freq_path))
+    }
+
+    fn cache_key(&self, freq_path: &Path) -> u64 {
+        let format = self.format_of(freq_path);
+        binary::options_key(&format!("{:?} {:?} {:?}", self.variants, self.costs, format))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "info", skip_all, fields(dict = %dict_path.display(), freq = %freq_path.display()))
+    )]
+    pub fn load(self, dict_path: &Path, freq_path: &Path) -> io::Result<Dictionary> {
+        let format = self.format_of(freq_path);
+        let cache_key = self.cache_key(freq_path);
+        if self.binary_cache {
+            if let Some(dictionary) = binary::load(dict_path, freq_path, cache_key) {
+                return Ok(self.finish(dictionary));
+            }
+        }
+
+        let dict_reader = BufReader::new(File::open(dict_path)?);
+        let freq_data = if freq_path.exists() {
+            Some(read_frequencies(BufReader::new(File::open(freq_path)?), format)?)
+        } else {
+            println!("Frequency file not found. Using defaults.");
+            None
+        };
+
+        let dictionary = Dictionary::build(dict_reader, freq_data, &self.variants, self.costs)?;
+        if self.binary_cache {
+            // Not fatal: the next load builds from text again
+            let _ = binary::store(&dictionary, dict_path, freq_path, cache_key);
+        }
+        Ok(self.finish(dictionary))
+    }
+
+    /// Load the word list from `dict_path` with word counts already in memory,
+    /// e.g. counts from `train::count_tokens`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "info", skip_all, fields(dict = %dict_path.display())))]
+    pub fn load_with_frequencies(self, dict_path: &Path, freq_data: Frequencies) -> io::Result<Dictionary> {
+        let dict_reader = BufReader::new(File::open(dict_path)?);
+        Ok(self.finish(Dictionary::build(dict_reader, Some(freq_data), &self.variants, self.costs)?))
+    }
+
+    /// Build from in-memory data: a newline-separated word list and optional
+    /// frequency data (JSON unless `frequency_format` says otherwise). Used
+    /// where there is no file system (e.g. WebAssembly).
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "info", skip_all, fields(bytes = dict_bytes.len())))]
+    pub fn load_bytes(self, dict_bytes: &[u8], freq_bytes: Option<&[u8]>) -> io::Result<Dictionary> {
+        let format = self.frequency_format.unwrap_or_default();
+        let freq_data = match freq_bytes {
+            Some(bytes) => Some(read_frequencies(bytes, format)?),
+            None => None,
+        };
+        Ok(self.finish(Dictionary::build(dict_bytes, freq_data, &self.variants, self.costs)?))
+    }
+
+    /// Build from the dictionary and frequency data compiled into the binary
+    /// (`embedded` feature). The embedded frequency format is used whatever
+    /// `frequency_format` says.
+    #[cfg(feature = "embedded")]
+    pub fn load_embedded(self) -> io::Result<Dictionary> {
+        let format = embedded::frequency_format();
+        self.frequency_format(format).load_bytes(&embedded::dictionary()?, Some(&embedded::frequencies()?))
+    }
+
+    /// The embedded word list with word counts already in memory, as
+    /// `load_with_frequencies`.
+    #[cfg(feature = "embedded")]
+    pub fn load_embedded_with_frequencies(self, freq_data: Frequencies) -> io::Result<Dictionary> {
+        let words = embedded::dictionary()?;
+        Ok(self.finish(Dictionary::build(words.as_slice(), Some(freq_data), &self.variants, self.costs)?))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(overlays = self.overlays.len(), latin_words = self.latin_words.len()))
+    )]
+    fn finish(&self, mut dictionary: Dictionary) -> Dictionary {
+        for overlay in &self.overlays {
+            dictionary.apply_overlay(overlay, &self.variants);
+        }
+        for words in &self.latin_words {
+            dictionary.apply_latin_words(words);
+        }
+        dictionary
+    }
 }
 
 impl Dictionary {
     pub fn new(dict_path: &Path, freq_path: &Path) -> io::Result<Self> {
+        DictionaryBuilder::new().load(dict_path, freq_path)
+    }
+
+    /// See `DictionaryBuilder::load_bytes`.
+    pub fn from_bytes(dict_bytes: &[u8], freq_bytes: Option<&[u8]>) -> io::Result<Self> {
+        DictionaryBuilder::new().load_bytes(dict_bytes, freq_bytes)
+    }
+
+    /// The default dictionary compiled into the binary (`embedded` feature).
+    #[cfg(feature = "embedded")]
+    pub fn embedded() -> io::Result<Self> {
+        DictionaryBuilder::new().load_embedded()
+    }
+
+    pub fn builder() -> DictionaryBuilder {
+        DictionaryBuilder::new()
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    fn build<R: BufRead>(
+        dict_reader: R,
+        freq_data: Option<Frequencies>,
+        generator: &VariantGenerator,
+        overrides: DictionaryConfig,
+    ) -> io::Result<Self> {
         // Temporary storage for building
         let mut temp_words: HashSet<String> = HashSet::new();
         let mut max_word_length = 0;
+        let mut variant_of = FxHashMap::default();
 
         // 1. Load Words
-        Dictionary::load_words(dict_path, &mut temp_words, &mut max_word_length)?;
+        Dictionary::load_words(dict_reader, generator, &mut temp_words, &mut max_word_length, &mut variant_of)?;
 
         // 2. Load Frequencies & Calculate Costs
-        let (word_costs_map, default_cost, unknown_cost) = Dictionary::calculate_costs(freq_path, &temp_words)?;
+        let (word_costs_map, default_cost, unknown_cost) = Dictionary::calculate_costs(freq_data, &temp_words, generator);
+        let default_cost = overrides.default_cost.unwrap_or(default_cost);
+        let unknown_cost = overrides.unknown_cost.unwrap_or(unknown_cost);
 
         // 3. Build HashMap and Trie
         let mut words_map = FxHashMap::default();
         let mut costs_vec = Vec::with_capacity(temp_words.len());
-        let mut trie = TrieNode::default();
+        let mut trie = Trie::new();
 
         for (i, word) in temp_words.into_iter().enumerate() {
             let cost = *word_costs_map.get(&word).unwrap_or(&default_cost);
             words_map.insert(word.clone(), i);
             costs_vec.push(cost);
 
-            // Build trie
-            let chars: Vec<char> = word.chars().collect();
-            let mut node = &mut trie;
-            for &c in &chars {
-                node = node.get_or_create_child(c);
-            }
-            node.is_word = true;
-            node.cost = cost;
+            trie.insert(&word, cost);
         }
+        trie.shrink_to_fit();
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(words = words_map.len(), max_word_length, default_cost, unknown_cost, "dictionary built");
 
         Ok(Dictionary {
             words: words_map,
@@ -72,14 +356,20 @@ impl Dictionary {
             max_word_length,
             default_cost,
             unknown_cost,
+            variant_of,
+            latin_words: FxHashMap::default(),
         })
     }
 
-    fn load_words(path: &Path, words_set: &mut HashSet<String>, max_len: &mut usize) -> io::Result<()> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-
+    fn load_words<R: BufRead>(
+        reader: R,
+        generator: &VariantGenerator,
+        words_set: &mut HashSet<String>,
+        max_len: &mut usize,
+        variant_of: &mut FxHashMap<String, String>,
+    ) -> io::Result<()> {
         let mut words_to_remove = HashSet::new();
+        let mut listed_words = HashSet::new();
 
         for line in reader.lines() {
             let word = line?.trim().to_string();
@@ -92,13 +382,15 @@ impl Dictionary {
             }
 
             words_set.insert(word.clone());
+            listed_words.insert(word.clone());
             let word_char_len = word.chars().count();
             if word_char_len > *max_len { *max_len = word_char_len; }
 
             // Generate variants
-            let variants = Dictionary::generate_variants(&word);
+            let variants = generator.generate(&word);
             for v in variants {
                 words_set.insert(v.clone());
+                variant_of.entry(v.clone()).or_insert_with(|| word.clone());
                 let v_char_len = v.chars().count();
                 if v_char_len > *max_len { *max_len = v_char_len; }
             }
@@ -131,6 +423,11 @@ impl Dictionary {
         }
         if words_set.contains("ៗ") { words_set.remove("ៗ"); }
 
+        // Words listed in the file are canonical even if another entry generates them
+        variant_of.retain(|v, canonical| {
+            words_set.contains(v) && !listed_words.contains(v) && words_set.contains(canonical)
+        });
+
         // Recalculate max length in codepoints
         *max_len = 0;
         for word in words_set.iter() {
@@ -141,28 +438,30 @@ impl Dictionary {
         Ok(())
     }
 
-    fn calculate_costs(path: &Path, words_set: &HashSet<String>) -> io::Result<(HashMap<String, f32>, f32, f32)> {
+    fn calculate_costs(
+        freq_data: Option<Frequencies>,
+        words_set: &HashSet<String>,
+        generator: &VariantGenerator,
+    ) -> (HashMap<String, Cost>, Cost, Cost) {
         let mut word_costs = HashMap::new();
         let mut default_cost = 10.0;
         let mut unknown_cost = 20.0;
 
-        if !path.exists() {
-            println!("Frequency file not found. Using defaults.");
-            return Ok((word_costs, default_cost, unknown_cost));
-        }
-
-        let file = File::open(path)?;
-        let data: HashMap<String, f32> = serde_json::from_reader(file)?;
+        let data = match freq_data {
+            Some(data) => data,
+            None => return (word_costs, default_cost, unknown_cost),
+        };
 
-        let min_freq_floor = 5.0;
+        let min_freq_floor: Cost = 5.0;
         let mut total_tokens = 0.0;
-        let mut effective_counts: HashMap<String, f32> = HashMap::new();
+        let mut effective_counts: HashMap<String, Cost> = HashMap::new();
 
-        for (word, &count) in &data {
+        // Summed in file order, as the reference implementation does
+        for (word, count) in data.iter() {
             let eff = count.max(min_freq_floor);
-            effective_counts.insert(word.clone(), eff);
+            effective_counts.insert(word.to_string(), eff);
 
-            let variants = Dictionary::generate_variants(word);
+            let variants = generator.generate(word);
             for v in variants {
                 effective_counts.entry(v).or_insert(eff);
             }
@@ -187,100 +486,10 @@ impl Dictionary {
             }
         }
 
-        Ok((word_costs, default_cost, unknown_cost))
+        (word_costs, default_cost, unknown_cost)
     }
 
-    fn generate_variants(word: &str) -> HashSet<String> {
-        let mut variants = HashSet::new();
-        let coeng_ta = "\u{17D2}\u{178F}";
-        let coeng_da = "\u{17D2}\u{178D}";
-
-        // 1. Ta/Da Swapping
-        if word.contains(coeng_ta) {
-            variants.insert(word.replace(coeng_ta, coeng_da));
-        }
-        if word.contains(coeng_da) {
-            variants.insert(word.replace(coeng_da, coeng_ta));
-        }
-
-        // 2. Coeng Ro Ordering
-        // Pattern: (Coeng Ro)(Other Coeng) <-> (Other Coeng)(Coeng Ro)
-        // Coeng Ro: \u17D2\u179A
-        // Other Coeng: \u17D2 followed by NOT \u179A
-
-        // Base set for Ro swapping includes original and Ta/Da variants
-        let mut base_set = variants.clone();
-        base_set.insert(word.to_string());
-
-        let coeng = '\u{17D2}';
-        let ro = '\u{179A}';
-
-        for w in base_set {
-            let chars: Vec<char> = w.chars().collect();
-            let n = chars.len();
-            if n < 4 {
-                 continue;
-            }
-
-            // Pass 1: Ro + Other -> Other + Ro
-            // Pattern: [Coeng, Ro, Coeng, NotRo]
-            let mut new_chars = chars.clone();
-            let mut modified = false;
-            let mut i = 0;
-            while i + 3 < new_chars.len() {
-                let c0 = new_chars[i];
-                let c1 = new_chars[i+1];
-                let c2 = new_chars[i+2];
-                let c3 = new_chars[i+3];
-
-                if c0 == coeng && c1 == ro && c2 == coeng && c3 != ro {
-                    // Swap (0,1) with (2,3)
-                    new_chars[i] = c2;
-                    new_chars[i+1] = c3;
-                    new_chars[i+2] = c0;
-                    new_chars[i+3] = c1;
-                    modified = true;
-                    i += 4;
-                } else {
-                    i += 1;
-                }
-            }
-            if modified {
-                variants.insert(new_chars.iter().collect());
-            }
-
-            // Pass 2: Other + Ro -> Ro + Other
-            // Pattern: [Coeng, NotRo, Coeng, Ro]
-            let mut new_chars_2 = chars.clone();
-            let mut modified_2 = false;
-            let mut i = 0;
-            while i + 3 < new_chars_2.len() {
-                let c0 = new_chars_2[i];
-                let c1 = new_chars_2[i+1];
-                let c2 = new_chars_2[i+2];
-                let c3 = new_chars_2[i+3];
-
-                if c0 == coeng && c1 != ro && c2 == coeng && c3 == ro {
-                    // Swap (0,1) with (2,3)
-                    new_chars_2[i] = c2;
-                    new_chars_2[i+1] = c3;
-                    new_chars_2[i+2] = c0;
-                    new_chars_2[i+3] = c1;
-                    modified_2 = true;
-                    i += 4;
-                } else {
-                    i += 1;
-                }
-            }
-            if modified_2 {
-                variants.insert(new_chars_2.iter().collect());
-            }
-        }
-
-        variants
-    }
-
-    pub fn get_word_cost(&self, word: &str) -> f32 {
+    pub fn get_word_cost(&self, word: &str) -> Cost {
         if let Some(&idx) = self.words.get(word) {
             if let Some(&cost) = self.costs.get(idx) {
                 return cost;
@@ -290,19 +499,75 @@ impl Dictionary {
         self.unknown_cost
     }
 
+    /// Spelling of `word` as listed in the dictionary file. Generated Ta/Da and
+    /// coeng-Ro variants map back to the entry they were generated from; any
+    /// other input is returned unchanged.
+    pub fn canonical_form<'a>(&'a self, word: &'a str) -> &'a str {
+        self.variant_of.get(word).map_or(word, String::as_str)
+    }
+
     pub fn contains(&self, word: &str) -> bool {
         self.words.contains_key(word)
     }
 
+    /// Cost of a Latin-script word from the `DictionaryBuilder::latin_words`
+    /// lists, ignoring case.
+    pub fn latin_word_cost(&self, word: &str) -> Option<Cost> {
+        if self.latin_words.is_empty() {
+            return None;
+        }
+        self.latin_words.get(&normalize_latin(word)).copied()
+    }
+
     #[inline]
-    pub fn lookup_codepoints(&self, cps: &[char], start: usize, end: usize) -> Option<f32> {
-        let mut node = &self.trie;
-        for i in start..end {
-            match node.get_child(cps[i]) {
+    pub fn lookup_codepoints(&self, cps: &[char], start: usize, end: usize) -> Option<Cost> {
+        let mut node = self.trie.root();
+        for &c in &cps[start..end] {
+            match self.trie.child(node, c) {
                 Some(child) => node = child,
                 None => return None,
             }
         }
         if node.is_word { Some(node.cost) } else { None }
     }
+
+    /// Every dictionary word starting at `cps[start]`, as `(end, cost)` pairs in
+    /// increasing `end` order, found in a single trie walk.
+    #[inline]
+    pub fn common_prefixes<'a>(&'a self, cps: &'a [char], start: usize) -> CommonPrefixes<'a> {
+        CommonPrefixes { trie: &self.trie, node: Some(self.trie.root()), cps, pos: start }
+    }
+}
+
+/// Key of a Latin word in `Dictionary::latin_words`: lowercase, with the
+/// typographic apostrophe folded into `'`.
+pub(crate) fn normalize_latin(word: &str) -> String {
+    word.chars().map(|c| if c == '’' { '\'' } else { c }).flat_map(char::to_lowercase).collect()
+}
+
+/// Iterator returned by `Dictionary::common_prefixes`.
+pub struct CommonPrefixes<'a> {
+    trie: &'a Trie,
+    node: Option<&'a TrieNode>,
+    cps: &'a [char],
+    pos: usize,
+}
+
+impl Iterator for CommonPrefixes<'_> {
+    type Item = (usize, Cost);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, Cost)> {
+        while let Some(node) = self.node {
+            let child = self.cps.get(self.pos).and_then(|&c| self.trie.child(node, c));
+            self.node = child;
+            if let Some(child) = child {
+                self.pos += 1;
+                if child.is_word {
+                    return Some((self.pos, child.cost));
+                }
+            }
+        }
+        None
+    }
 }
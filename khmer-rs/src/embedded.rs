@@ -0,0 +1,42 @@
+//! Default dictionary and frequency data compiled into the binary
+//! (`embedded` feature), so the tool ships as one self-contained executable.
+//!
+//! `build.rs` deflates `../data/khmer_dictionary_words.txt` and
+//! `../data/khmer_word_frequencies.json` (or the files named by
+//! `KHMER_RS_EMBED_DICT` and `KHMER_RS_EMBED_FREQ`) at build time; they are
+//! inflated on each load. Use `Dictionary::embedded` or
+//! `DictionaryBuilder::load_embedded`.
+
+use std::io;
+use std::path::Path;
+
+use crate::frequency::FrequencyFormat;
+
+const DICTIONARY: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/dictionary.deflate"));
+const FREQUENCIES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/frequencies.deflate"));
+const FREQUENCY_FILE: &str = env!("KHMER_RS_EMBEDDED_FREQ_NAME");
+
+/// The embedded word list, one word per line.
+pub fn dictionary() -> io::Result<Vec<u8>> {
+    inflate(DICTIONARY)
+}
+
+/// The embedded frequency data, in `frequency_format`.
+pub fn frequencies() -> io::Result<Vec<u8>> {
+    inflate(FREQUENCIES)
+}
+
+/// Format of the embedded frequency data, from its file name.
+pub fn frequency_format() -> FrequencyFormat {
+    FrequencyFormat::from_path(Path::new(FREQUENCY_FILE))
+}
+
+/// Compressed size of the embedded data in bytes.
+pub fn compressed_len() -> usize {
+    DICTIONARY.len() + FREQUENCIES.len()
+}
+
+fn inflate(data: &[u8]) -> io::Result<Vec<u8>> {
+    miniz_oxide::inflate::decompress_to_vec(data)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("embedded data: {:?}", e.status)))
+}
@@ -0,0 +1,342 @@
+//! Accuracy evaluation against reference segmentations.
+//!
+//! Two views of the same comparison:
+//! - word scores: a predicted word is correct when a reference word covers
+//!   exactly the same characters
+//! - boundary scores: precision/recall of the positions between words
+//!
+//! Offsets are counted in characters of the concatenated tokens with
+//! whitespace and zero-width spaces removed, so both sides line up even when
+//! one keeps spaces as tokens and the other does not.
+
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::algorithm::Segmenter;
+use crate::sampling::SplitMix64;
+
+/// One reference line: the raw input and its gold segmentation.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Reference {
+    pub input: String,
+    pub segments: Vec<String>,
+}
+
+/// Read JSONL records with `input` and `segments` fields, the format of
+/// `data/golden_master.jsonl`.
+pub fn read_reference_jsonl<R: BufRead>(reader: R) -> io::Result<Vec<Reference>> {
+    let mut references = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        references.push(serde_json::from_str(&line)?);
+    }
+    Ok(references)
+}
+
+/// Encodings of reference segmentations understood by `read_references`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ReferenceFormat {
+    /// JSONL records with `input` and `segments` fields (`read_reference_jsonl`)
+    #[default]
+    Jsonl,
+    /// khPOS: one sentence per line, `word/TAG` tokens separated by spaces
+    KhPos,
+    /// ALT (Asian Language Treebank): `SNT.<id><TAB>` then the tokens
+    /// separated by spaces, with or without `/TAG`
+    Alt,
+    /// One line per sentence, tokens separated by spaces
+    Space,
+    /// One line per sentence, tokens separated by zero-width spaces (U+200B);
+    /// other spaces are part of the text
+    Zwsp,
+}
+
+impl ReferenceFormat {
+    pub const ALL: [ReferenceFormat; 5] = [
+        ReferenceFormat::Jsonl,
+        ReferenceFormat::KhPos,
+        ReferenceFormat::Alt,
+        ReferenceFormat::Space,
+        ReferenceFormat::Zwsp,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ReferenceFormat::Jsonl => "jsonl",
+            ReferenceFormat::KhPos => "khpos",
+            ReferenceFormat::Alt => "alt",
+            ReferenceFormat::Space => "space",
+            ReferenceFormat::Zwsp => "zwsp",
+        }
+    }
+}
+
+impl fmt::Display for ReferenceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ReferenceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ReferenceFormat::ALL.into_iter().find(|format| format.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = ReferenceFormat::ALL.iter().map(|format| format.name()).collect();
+            format!("unknown reference format {:?} (expected one of {})", s, names.join(", "))
+        })
+    }
+}
+
+/// Read reference segmentations in `format`. For the token formats the
+/// input is the tokens joined without spaces, as Khmer is written.
+pub fn read_references<R: BufRead>(reader: R, format: ReferenceFormat) -> io::Result<Vec<Reference>> {
+    if format == ReferenceFormat::Jsonl {
+        return read_reference_jsonl(reader);
+    }
+    let mut references = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let reference = match format {
+            ReferenceFormat::Alt => {
+                let tokens = line.split_once('\t').map_or(line.as_str(), |(_, tokens)| tokens);
+                parse_tagged_tokens(tokens)
+            }
+            ReferenceFormat::Space => parse_space_tokens(&line),
+            ReferenceFormat::Zwsp => parse_zwsp_tokens(&line),
+            _ => parse_tagged_tokens(&line),
+        };
+        references.extend(reference);
+    }
+    Ok(references)
+}
+
+/// A reference from tokens separated by spaces; `None` for a blank line.
+pub fn parse_space_tokens(line: &str) -> Option<Reference> {
+    let segments: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+    if segments.is_empty() {
+        return None;
+    }
+    Some(Reference { input: segments.concat(), segments })
+}
+
+/// A reference from tokens separated by zero-width spaces. The input is the
+/// line without them, its spaces kept; `None` for a blank line.
+pub fn parse_zwsp_tokens(line: &str) -> Option<Reference> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    let segments: Vec<String> =
+        line.trim().split('\u{200b}').filter(|token| !token.is_empty()).map(str::to_string).collect();
+    Some(Reference { input: segments.concat(), segments })
+}
+
+/// A reference from space-separated tokens, dropping any `/TAG` suffix;
+/// `None` for a line without tokens.
+pub fn parse_tagged_tokens(line: &str) -> Option<Reference> {
+    let segments: Vec<String> = line.split_whitespace().map(|token| strip_tag(token).to_string()).collect();
+    if segments.is_empty() {
+        return None;
+    }
+    Some(Reference { input: segments.concat(), segments })
+}
+
+/// `word` of `word/TAG`, where the tag is ASCII letters, digits, `-` or `_`.
+fn strip_tag(token: &str) -> &str {
+    match token.rsplit_once('/') {
+        Some((word, tag))
+            if !word.is_empty()
+                && !tag.is_empty()
+                && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            word
+        }
+        _ => token,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counts {
+    pub reference: usize,
+    pub predicted: usize,
+    pub matched: usize,
+}
+
+impl Counts {
+    pub fn precision(&self) -> f64 {
+        ratio(self.matched, self.predicted)
+    }
+
+    pub fn recall(&self) -> f64 {
+        ratio(self.matched, self.reference)
+    }
+
+    pub fn f1(&self) -> f64 {
+        let (p, r) = (self.precision(), self.recall());
+        if p + r == 0.0 { 0.0 } else { 2.0 * p * r / (p + r) }
+    }
+
+    fn add(&mut self, other: Counts) {
+        self.reference += other.reference;
+        self.predicted += other.predicted;
+        self.matched += other.matched;
+    }
+}
+
+fn ratio(num: usize, den: usize) -> f64 {
+    if den == 0 { 1.0 } else { num as f64 / den as f64 }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scores {
+    pub lines: usize,
+    /// Lines whose words all match the reference
+    pub exact_lines: usize,
+    pub words: Counts,
+    pub boundaries: Counts,
+}
+
+impl Scores {
+    /// Score one line.
+    pub fn add<S: AsRef<str>, T: AsRef<str>>(&mut self, reference: &[S], predicted: &[T]) {
+        let reference = word_spans(reference);
+        let predicted = word_spans(predicted);
+
+        self.lines += 1;
+        if reference == predicted {
+            self.exact_lines += 1;
+        }
+        self.words.add(Counts {
+            reference: reference.len(),
+            predicted: predicted.len(),
+            matched: count_common(&reference, &predicted),
+        });
+
+        let reference: Vec<usize> = internal_boundaries(&reference);
+        let predicted: Vec<usize> = internal_boundaries(&predicted);
+        self.boundaries.add(Counts {
+            reference: reference.len(),
+            predicted: predicted.len(),
+            matched: count_common(&reference, &predicted),
+        });
+    }
+
+    pub fn merge(&mut self, other: &Scores) {
+        self.lines += other.lines;
+        self.exact_lines += other.exact_lines;
+        self.words.add(other.words);
+        self.boundaries.add(other.boundaries);
+    }
+
+    pub fn exact_rate(&self) -> f64 {
+        ratio(self.exact_lines, self.lines)
+    }
+}
+
+/// Segment every reference input and score it.
+pub fn evaluate<S: Segmenter + ?Sized>(segmenter: &S, references: &[Reference]) -> Scores {
+    let mut scores = Scores::default();
+    for reference in references {
+        scores.add(&reference.segments, &segmenter.segment(&reference.input));
+    }
+    scores
+}
+
+/// Split `references` into `k` folds of nearly equal size for
+/// cross-validation. Lines are dealt out in an order shuffled with `seed`,
+/// so folds mix the parts of a corpus; each fold keeps the input order.
+pub fn k_folds(references: &[Reference], k: usize, seed: u64) -> Vec<Vec<Reference>> {
+    let k = k.max(1);
+    let mut order: Vec<usize> = (0..references.len()).collect();
+    let mut rng = SplitMix64::new(seed);
+    for i in (1..order.len()).rev() {
+        order.swap(i, rng.below(i as u64 + 1) as usize);
+    }
+    let mut fold_of = vec![0; references.len()];
+    for (position, &index) in order.iter().enumerate() {
+        fold_of[index] = position % k;
+    }
+    let mut folds = vec![Vec::new(); k];
+    for (reference, &fold) in references.iter().zip(&fold_of) {
+        folds[fold].push(reference.clone());
+    }
+    folds
+}
+
+/// One round of cross-validation: the candidate that scored best on the
+/// other folds, and how it does on the held-out one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fold {
+    /// Index of the chosen candidate
+    pub best: usize,
+    /// Its scores on the other folds, merged
+    pub train: Scores,
+    /// Its scores on the held-out fold
+    pub test: Scores,
+}
+
+/// Cross-validate a choice among candidates (e.g. the points of a parameter
+/// grid): `scores[c][f]` are candidate `c`'s scores on fold `f`. For each
+/// fold, the candidate with the best word F1 on the other folds is chosen
+/// (ties keep the earliest), then scored on that fold.
+pub fn cross_validate(scores: &[Vec<Scores>]) -> Vec<Fold> {
+    let folds = scores.first().map_or(0, Vec::len);
+    (0..folds)
+        .map(|held_out| {
+            let mut chosen: Option<(usize, Scores)> = None;
+            for (candidate, fold_scores) in scores.iter().enumerate() {
+                let mut train = Scores::default();
+                for (_, fold) in fold_scores.iter().enumerate().filter(|&(f, _)| f != held_out) {
+                    train.merge(fold);
+                }
+                if chosen.as_ref().is_none_or(|(_, best)| train.words.f1() > best.words.f1()) {
+                    chosen = Some((candidate, train));
+                }
+            }
+            let (best, train) = chosen.unwrap_or_default();
+            let test = scores.get(best).map(|fold_scores| fold_scores[held_out].clone()).unwrap_or_default();
+            Fold { best, train, test }
+        })
+        .collect()
+}
+
+/// `(start, end)` character offsets of the non-whitespace tokens.
+fn word_spans<S: AsRef<str>>(tokens: &[S]) -> Vec<(usize, usize)> {
+    let mut spans = Vec::with_capacity(tokens.len());
+    let mut pos = 0;
+    for token in tokens {
+        let len = token.as_ref().chars().filter(|&c| !c.is_whitespace() && c != '\u{200B}').count();
+        if len > 0 {
+            spans.push((pos, pos + len));
+            pos += len;
+        }
+    }
+    spans
+}
+
+fn internal_boundaries(spans: &[(usize, usize)]) -> Vec<usize> {
+    spans.iter().skip(1).map(|&(start, _)| start).collect()
+}
+
+/// Number of items in both sorted lists.
+fn count_common<T: Ord>(a: &[T], b: &[T]) -> usize {
+    let (mut i, mut j, mut common) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                common += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    common
+}
@@ -0,0 +1,153 @@
+//! Other Khmer tokenizers as comparison backends (`--features external`).
+//!
+//! The tool runs as a child process for the whole run: each line of text
+//! goes to its stdin, and it answers with one line of tokens separated by a
+//! delimiter. Any tool that reads and answers line by line, flushing after
+//! each line, fits; `ExternalTool` holds the commands of known ones. Lines
+//! are sent one at a time, so parallel workers take turns.
+//!
+//! Tokens are split on the delimiter, so spaces in the text are lost when
+//! the delimiter is a space. `eval` and `ab-eval` ignore whitespace, so
+//! scores are not affected.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::algorithm::Segmenter;
+
+/// Reads lines on stdin and prints `word_tokenize`'s tokens tab-separated.
+const KHMER_NLTK_SCRIPT: &str = "import sys\n\
+from khmernltk import word_tokenize\n\
+for line in sys.stdin:\n    \
+print('\\t'.join(word_tokenize(line.rstrip('\\n'))), flush=True)\n";
+
+/// Known tokenizers and how to run them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExternalTool {
+    /// `khmercut` (Rust, CRF), reading stdin, tokens separated by spaces
+    KhmerCut,
+    /// `khmer-nltk` (Python, CRF) through `python3`
+    KhmerNltk,
+}
+
+impl ExternalTool {
+    pub const ALL: [ExternalTool; 2] = [ExternalTool::KhmerCut, ExternalTool::KhmerNltk];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExternalTool::KhmerCut => "khmercut",
+            ExternalTool::KhmerNltk => "khmer-nltk",
+        }
+    }
+
+    /// Program, arguments and the delimiter between the tokens it prints.
+    pub fn command(self) -> (&'static str, Vec<&'static str>, &'static str) {
+        match self {
+            ExternalTool::KhmerCut => ("khmercut", Vec::new(), " "),
+            ExternalTool::KhmerNltk => ("python3", vec!["-u", "-c", KHMER_NLTK_SCRIPT], "\t"),
+        }
+    }
+}
+
+impl fmt::Display for ExternalTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ExternalTool {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ExternalTool::ALL.into_iter().find(|tool| tool.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = ExternalTool::ALL.iter().map(|tool| tool.name()).collect();
+            format!("unknown external tool {:?} (expected one of {})", s, names.join(", "))
+        })
+    }
+}
+
+/// How long a tool may take to exit once its stdin is closed.
+const EXIT_TIMEOUT: Duration = Duration::from_millis(500);
+
+struct Process {
+    child: Child,
+    /// `None` once closed, on drop
+    stdin: Option<BufWriter<ChildStdin>>,
+    stdout: BufReader<ChildStdout>,
+}
+
+/// A tokenizer running as a child process, answering one line at a time.
+pub struct ExternalSegmenter {
+    process: Mutex<Process>,
+    delimiter: String,
+}
+
+impl ExternalSegmenter {
+    /// Start `program` with `args`; it prints each line's tokens separated
+    /// by `delimiter`.
+    pub fn spawn<S: AsRef<str>>(program: &str, args: &[S], delimiter: &str) -> io::Result<Self> {
+        if delimiter.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty token delimiter"));
+        }
+        let mut child = Command::new(program)
+            .args(args.iter().map(AsRef::as_ref))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", program, e)))?;
+        let stdin = Some(BufWriter::new(child.stdin.take().expect("piped stdin")));
+        let stdout = BufReader::new(child.stdout.take().expect("piped stdout"));
+        Ok(ExternalSegmenter { process: Mutex::new(Process { child, stdin, stdout }), delimiter: delimiter.to_string() })
+    }
+
+    /// Start a known tool.
+    pub fn tool(tool: ExternalTool) -> io::Result<Self> {
+        let (program, args, delimiter) = tool.command();
+        ExternalSegmenter::spawn(program, &args, delimiter)
+    }
+
+    pub fn try_segment(&self, text: &str) -> io::Result<Vec<String>> {
+        // One line in, one line out: line breaks inside the text would desync
+        let line = text.replace(['\n', '\r'], " ");
+        let mut process = self.process.lock().unwrap_or_else(|e| e.into_inner());
+        let stdin = process.stdin.as_mut().expect("stdin is open until drop");
+        writeln!(stdin, "{}", line)?;
+        stdin.flush()?;
+        let mut answer = String::new();
+        if process.stdout.read_line(&mut answer)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the tokenizer exited"));
+        }
+        let answer = answer.strip_suffix('\n').unwrap_or(&answer);
+        let answer = answer.strip_suffix('\r').unwrap_or(answer);
+        Ok(answer.split(self.delimiter.as_str()).filter(|token| !token.is_empty()).map(str::to_string).collect())
+    }
+}
+
+impl Segmenter for ExternalSegmenter {
+    /// Panics if the tool fails; use `try_segment` to handle the error.
+    fn segment(&self, text: &str) -> Vec<String> {
+        self.try_segment(text).expect("external tokenizer failed")
+    }
+}
+
+impl Drop for ExternalSegmenter {
+    fn drop(&mut self) {
+        let process = self.process.get_mut().unwrap_or_else(|e| e.into_inner());
+        // Closing stdin ends the tool's input loop; kill it if that is not enough
+        drop(process.stdin.take());
+        let deadline = Instant::now() + EXIT_TIMEOUT;
+        while Instant::now() < deadline {
+            match process.child.try_wait() {
+                Ok(None) => thread::sleep(Duration::from_millis(5)),
+                Ok(Some(_)) | Err(_) => return,
+            }
+        }
+        let _ = process.child.kill();
+        let _ = process.child.wait();
+    }
+}
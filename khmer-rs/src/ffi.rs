@@ -0,0 +1,147 @@
+//! C ABI (`--features cdylib`). The matching header is `include/khmer_rs.h`.
+//!
+//! Ownership rules:
+//! - `khmer_segmenter_new` returns a handle owned by the caller, released with
+//!   `khmer_segmenter_free`.
+//! - `khmer_segment` fills a caller-provided `KhmerSegments`; its contents are
+//!   owned by the library until released with `khmer_segments_free`.
+//! - A handle may be shared between threads for concurrent `khmer_segment` calls.
+//!
+//! A panic unwinding into the caller's frames aborts the host process, so
+//! every function catches it and returns NULL or `KHMER_ERR_PANIC` instead.
+
+use std::ffi::{c_char, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+use crate::dictionary::Dictionary;
+use crate::segmenter::KhmerSegmenter;
+
+pub const KHMER_OK: i32 = 0;
+pub const KHMER_ERR_NULL_POINTER: i32 = -1;
+pub const KHMER_ERR_INVALID_UTF8: i32 = -2;
+pub const KHMER_ERR_PANIC: i32 = -3;
+
+/// Borrowed UTF-8 slice (not NUL-terminated).
+#[repr(C)]
+pub struct KhmerStr {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+/// Result of `khmer_segment`. `data`/`data_len` are backing storage and must
+/// not be touched by callers.
+#[repr(C)]
+pub struct KhmerSegments {
+    pub segments: *mut KhmerStr,
+    pub count: usize,
+    pub data: *mut u8,
+    pub data_len: usize,
+}
+
+/// Load a segmenter from a dictionary and frequency file. Returns NULL on failure.
+///
+/// # Safety
+/// `dict_path` and `freq_path` must be valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn khmer_segmenter_new(dict_path: *const c_char, freq_path: *const c_char) -> *mut KhmerSegmenter {
+    panic::catch_unwind(|| segmenter_new(dict_path, freq_path)).unwrap_or(ptr::null_mut())
+}
+
+unsafe fn segmenter_new(dict_path: *const c_char, freq_path: *const c_char) -> *mut KhmerSegmenter {
+    if dict_path.is_null() || freq_path.is_null() {
+        return ptr::null_mut();
+    }
+    let (dict_path, freq_path) = match (CStr::from_ptr(dict_path).to_str(), CStr::from_ptr(freq_path).to_str()) {
+        (Ok(d), Ok(f)) => (d, f),
+        _ => return ptr::null_mut(),
+    };
+    match Dictionary::new(Path::new(dict_path), Path::new(freq_path)) {
+        Ok(dictionary) => Box::into_raw(Box::new(KhmerSegmenter::new(dictionary))),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `handle` must come from `khmer_segmenter_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn khmer_segmenter_free(handle: *mut KhmerSegmenter) {
+    if !handle.is_null() {
+        // A panicking drop leaks what is left of the handle
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(handle))));
+    }
+}
+
+/// Segment `len` bytes of UTF-8 at `text` into `out`.
+///
+/// # Safety
+/// `handle` must be a live segmenter, `text` must point to `len` readable
+/// bytes, and `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn khmer_segment(
+    handle: *const KhmerSegmenter,
+    text: *const u8,
+    len: usize,
+    out: *mut KhmerSegments,
+) -> i32 {
+    panic::catch_unwind(AssertUnwindSafe(|| segment_into(handle, text, len, out))).unwrap_or(KHMER_ERR_PANIC)
+}
+
+unsafe fn segment_into(handle: *const KhmerSegmenter, text: *const u8, len: usize, out: *mut KhmerSegments) -> i32 {
+    if handle.is_null() || out.is_null() || (text.is_null() && len > 0) {
+        return KHMER_ERR_NULL_POINTER;
+    }
+    let bytes = if len == 0 { &[][..] } else { std::slice::from_raw_parts(text, len) };
+    let text = match std::str::from_utf8(bytes) {
+        Ok(t) => t,
+        Err(_) => return KHMER_ERR_INVALID_UTF8,
+    };
+
+    let segments = (*handle).segment(text);
+
+    // One contiguous buffer for all segment bytes, plus the slice table
+    let data: Box<[u8]> = segments.concat().into_bytes().into_boxed_slice();
+    let data_len = data.len();
+    let data = Box::into_raw(data) as *mut u8;
+
+    let mut offset = 0;
+    let table: Box<[KhmerStr]> = segments
+        .iter()
+        .map(|s| {
+            let entry = KhmerStr { ptr: data.add(offset), len: s.len() };
+            offset += s.len();
+            entry
+        })
+        .collect();
+    let count = table.len();
+
+    *out = KhmerSegments {
+        segments: Box::into_raw(table) as *mut KhmerStr,
+        count,
+        data,
+        data_len,
+    };
+    KHMER_OK
+}
+
+/// # Safety
+/// `segments` must have been filled by `khmer_segment` and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn khmer_segments_free(segments: *mut KhmerSegments) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| segments_free(segments)));
+}
+
+unsafe fn segments_free(segments: *mut KhmerSegments) {
+    if segments.is_null() {
+        return;
+    }
+    let s = &mut *segments;
+    if !s.segments.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(s.segments, s.count)));
+    }
+    if !s.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(s.data, s.data_len)));
+    }
+    *s = KhmerSegments { segments: ptr::null_mut(), count: 0, data: ptr::null_mut(), data_len: 0 };
+}
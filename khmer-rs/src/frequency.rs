@@ -0,0 +1,230 @@
+//! Word frequency file formats.
+//!
+//! Besides the JSON map used by the reference data, the formats most corpus
+//! counting tools emit are accepted:
+//! - TSV: `word<TAB>count`, extra columns ignored
+//! - CSV: `word,count`, the word optionally double-quoted
+//! - Counts: `count word` as printed by `sort | uniq -c`, or `word count`
+//!
+//! In the line-based formats a first line whose count does not parse is
+//! treated as a header, and repeated words have their counts summed.
+//!
+//! Counts are read as `Cost` and kept in file order (`Frequencies`): the
+//! reference implementation sums them in that order, so with `f64-costs` the
+//! total, and every cost derived from it, comes out bit for bit the same.
+
+use std::fmt;
+use std::io::{self, BufRead};
+use std::ops::Index;
+use std::path::Path;
+
+use fxhash::FxHashMap;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::dictionary::Cost;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrequencyFormat {
+    #[default]
+    Json,
+    Tsv,
+    Csv,
+    Counts,
+}
+
+impl FrequencyFormat {
+    /// Guess the format from the file extension: `.tsv`, `.csv`, `.txt` /
+    /// `.counts`; anything else is read as JSON.
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+        match ext.as_deref() {
+            Some("tsv") => FrequencyFormat::Tsv,
+            Some("csv") => FrequencyFormat::Csv,
+            Some("txt") | Some("counts") => FrequencyFormat::Counts,
+            _ => FrequencyFormat::Json,
+        }
+    }
+}
+
+/// Word counts in the order the words were first read.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frequencies {
+    entries: Vec<(String, Cost)>,
+    index: FxHashMap<String, usize>,
+}
+
+impl Frequencies {
+    pub fn new() -> Self {
+        Frequencies::default()
+    }
+
+    /// Add `count` to `word`'s count, appending the word if it is new.
+    pub fn add(&mut self, word: String, count: Cost) {
+        match self.index.get(&word) {
+            Some(&i) => self.entries[i].1 += count,
+            None => self.push(word, count),
+        }
+    }
+
+    /// Set `word`'s count, keeping its position if it is already there.
+    pub fn set(&mut self, word: String, count: Cost) {
+        match self.index.get(&word) {
+            Some(&i) => self.entries[i].1 = count,
+            None => self.push(word, count),
+        }
+    }
+
+    fn push(&mut self, word: String, count: Cost) {
+        self.index.insert(word.clone(), self.entries.len());
+        self.entries.push((word, count));
+    }
+
+    pub fn get(&self, word: &str) -> Option<Cost> {
+        self.index.get(word).map(|&i| self.entries[i].1)
+    }
+
+    /// Words and counts in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Cost)> {
+        self.entries.iter().map(|(word, count)| (word.as_str(), *count))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Index<&str> for Frequencies {
+    type Output = Cost;
+
+    fn index(&self, word: &str) -> &Cost {
+        &self.entries[self.index[word]].1
+    }
+}
+
+/// Repeated words have their counts summed.
+impl FromIterator<(String, Cost)> for Frequencies {
+    fn from_iter<I: IntoIterator<Item = (String, Cost)>>(iter: I) -> Self {
+        let mut frequencies = Frequencies::new();
+        for (word, count) in iter {
+            frequencies.add(word, count);
+        }
+        frequencies
+    }
+}
+
+/// A JSON object read in document order; a repeated key takes the last
+/// value, at the position of the first, as a Python `dict` does.
+impl<'de> Deserialize<'de> for Frequencies {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FrequenciesVisitor;
+
+        impl<'de> Visitor<'de> for FrequenciesVisitor {
+            type Value = Frequencies;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of words to counts")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Frequencies, A::Error> {
+                let mut frequencies = Frequencies::new();
+                while let Some((word, count)) = map.next_entry()? {
+                    frequencies.set(word, count);
+                }
+                Ok(frequencies)
+            }
+        }
+
+        deserializer.deserialize_map(FrequenciesVisitor)
+    }
+}
+
+/// A JSON object in order, as read.
+impl Serialize for Frequencies {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (word, count) in self.iter() {
+            map.serialize_entry(word, &count)?;
+        }
+        map.end()
+    }
+}
+
+/// Read word counts in the given format.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(reader)))]
+pub fn read_frequencies<R: BufRead>(reader: R, format: FrequencyFormat) -> io::Result<Frequencies> {
+    if format == FrequencyFormat::Json {
+        return Ok(serde_json::from_reader(reader)?);
+    }
+
+    let mut counts = Frequencies::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parsed = match format {
+            FrequencyFormat::Tsv => parse_delimited(line, '\t').map(|(w, c)| (w.to_string(), c)),
+            FrequencyFormat::Csv => parse_csv(line),
+            _ => parse_counts(line).map(|(w, c)| (w.to_string(), c)),
+        };
+        match parsed {
+            Some((word, count)) if !word.is_empty() => {
+                counts.add(word, count);
+            }
+            _ if i == 0 => continue, // header
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: expected a word and a count, got {:?}", i + 1, line),
+                ))
+            }
+        }
+    }
+    Ok(counts)
+}
+
+fn parse_delimited(line: &str, delimiter: char) -> Option<(&str, Cost)> {
+    let mut fields = line.split(delimiter);
+    let word = fields.next()?.trim();
+    let count = fields.next()?.trim().parse().ok()?;
+    Some((word, count))
+}
+
+fn parse_csv(line: &str) -> Option<(String, Cost)> {
+    let Some(quoted) = line.strip_prefix('"') else {
+        return parse_delimited(line, ',').map(|(w, c)| (w.to_string(), c));
+    };
+    // Quoted word: `""` is an escaped quote, a lone `"` ends the field
+    let mut word = String::new();
+    let mut chars = quoted.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c != '"' {
+            word.push(c);
+        } else if chars.next_if(|&(_, n)| n == '"').is_some() {
+            word.push('"');
+        } else {
+            let rest = quoted[i + 1..].trim_start().strip_prefix(',')?;
+            let count = rest.split(',').next()?.trim().parse().ok()?;
+            return Some((word, count));
+        }
+    }
+    None
+}
+
+fn parse_counts(line: &str) -> Option<(&str, Cost)> {
+    let mut fields = line.split_whitespace();
+    let (first, second) = (fields.next()?, fields.next()?);
+    if fields.next().is_some() {
+        return None;
+    }
+    match first.parse() {
+        Ok(count) if second.parse::<Cost>().is_err() => Some((second, count)),
+        _ => Some((first, second.parse().ok()?)),
+    }
+}
@@ -1,5 +1,7 @@
 use std::borrow::Cow;
 use crate::dictionary::Dictionary;
+use crate::patterns::{is_date, is_email, is_emoji, is_hashtag, is_latin_word, is_mention, is_url};
+use crate::script::is_bypassed_run;
 use crate::constants::{is_valid_single_word, is_separator, is_digit};
 
 // ============================================================================
@@ -51,6 +53,20 @@ fn first_char(s: &str) -> char {
     s.chars().next().unwrap_or(' ')
 }
 
+/// Get first char and codepoint length without allocating Vec<char>
+#[inline]
+fn first_char_and_len(s: &str) -> (char, usize) {
+    let mut first = ' ';
+    let mut count = 0;
+    for c in s.chars() {
+        if count == 0 {
+            first = c;
+        }
+        count += 1;
+    }
+    (first, count)
+}
+
 pub fn apply_heuristics<'a>(segments: Vec<Cow<'a, str>>, dictionary: &Dictionary) -> Vec<Cow<'a, str>> {
     // Pass 1: Rule 1 & 2 (Consonants + Signs)
     let mut merged: Vec<Cow<'a, str>> = Vec::with_capacity(segments.len());
@@ -117,6 +133,77 @@ pub fn apply_heuristics<'a>(segments: Vec<Cow<'a, str>>, dictionary: &Dictionary
     merged
 }
 
+// 1BRC: Optimized snap_invalid_single_consonants with inline char extraction
+pub fn snap_invalid_singles_string(segments: Vec<String>, dictionary: &Dictionary) -> Vec<String> {
+    snap_invalid_singles_with(segments, dictionary, is_separator)
+}
+
+/// `snap_invalid_singles_string` with `is_separator` deciding what counts as a separator.
+pub fn snap_invalid_singles_with(
+    segments: Vec<String>,
+    dictionary: &Dictionary,
+    is_separator: impl Fn(char) -> bool,
+) -> Vec<String> {
+    let mut pass1_segments: Vec<String> = Vec::with_capacity(segments.len());
+
+    for (j, seg) in segments.iter().enumerate() {
+        // 1BRC: Use fast inline first char + length extraction
+        let (first, seg_len) = first_char_and_len(seg);
+
+        let is_invalid_single = seg_len == 1
+            && !is_valid_single_word(first)
+            && !dictionary.contains(seg)
+            && !is_digit(first)
+            && !is_separator(first);
+
+        if is_invalid_single {
+            // Check Valid Context (surrounded by separators?)
+            let mut prev_is_sep = false;
+            if !pass1_segments.is_empty() {
+                let prev_seg = pass1_segments.last().unwrap();
+                let p_char = first_char(prev_seg);
+                if is_separator(p_char) || prev_seg == " " || prev_seg == "\u{200b}" {
+                    prev_is_sep = true;
+                }
+            } else if j == 0 {
+                prev_is_sep = true;
+            }
+
+            let mut next_is_sep = false;
+            if j + 1 < segments.len() {
+                let next_seg = &segments[j + 1];
+                let n_char = first_char(next_seg);
+                if is_separator(n_char) || next_seg == " " || next_seg == "\u{200b}" {
+                    next_is_sep = true;
+                }
+            } else {
+                next_is_sep = true;
+            }
+
+            if prev_is_sep && next_is_sep {
+                pass1_segments.push(seg.clone());
+                continue;
+            }
+
+            if !pass1_segments.is_empty() {
+                let p_char = first_char(pass1_segments.last().unwrap());
+                if !is_separator(p_char) {
+                    let prev = pass1_segments.pop().unwrap();
+                    pass1_segments.push(prev + seg);
+                } else {
+                    pass1_segments.push(seg.clone());
+                }
+            } else {
+                pass1_segments.push(seg.clone());
+            }
+        } else {
+            pass1_segments.push(seg.clone());
+        }
+    }
+
+    pass1_segments
+}
+
 pub fn apply_heuristics_string(segments: Vec<String>, dictionary: &Dictionary) -> Vec<String> {
     // Pass 1: Rule 1 & 2 (Consonants + Signs)
     let mut merged: Vec<String> = Vec::with_capacity(segments.len());
@@ -188,20 +275,12 @@ pub fn post_process_unknowns<'a>(pass2_segments: Vec<Cow<'a, str>>, dictionary:
         let first = first_char(&seg);
         let count = char_count(&seg);
 
-        let is_known = if is_digit(first) {
-            true
-        } else if dictionary.contains(&seg) {
-            true
-        } else if count == 1 && is_valid_single_word(first) {
-            true
-        } else if count == 1 && is_separator(first) {
-            true
-        } else if seg.contains('.') && count >= 2 {
+        let is_known = is_digit(first)
+            || dictionary.contains(&seg)
+            || (count == 1 && is_valid_single_word(first))
+            || (count == 1 && is_separator(first))
             // Rudimentary acronym check
-            true
-        } else {
-            false
-        };
+            || (seg.contains('.') && count >= 2);
 
         if is_known {
             if !unknown_buffer.is_empty() {
@@ -226,6 +305,15 @@ pub fn post_process_unknowns<'a>(pass2_segments: Vec<Cow<'a, str>>, dictionary:
 }
 
 pub fn post_process_unknowns_string(pass2_segments: Vec<String>, dictionary: &Dictionary) -> Vec<String> {
+    post_process_unknowns_with(pass2_segments, dictionary, is_separator)
+}
+
+/// `post_process_unknowns_string` with `is_separator` deciding what counts as a separator.
+pub fn post_process_unknowns_with(
+    pass2_segments: Vec<String>,
+    dictionary: &Dictionary,
+    is_separator: impl Fn(char) -> bool,
+) -> Vec<String> {
     let mut final_segments = Vec::with_capacity(pass2_segments.len());
     let mut unknown_buffer = Vec::new();
 
@@ -234,20 +322,22 @@ pub fn post_process_unknowns_string(pass2_segments: Vec<String>, dictionary: &Di
         let first = first_char(&seg);
         let count = char_count(&seg);
 
-        let is_known = if is_digit(first) {
-            true
-        } else if dictionary.contains(&seg) {
-            true
-        } else if count == 1 && is_valid_single_word(first) {
-            true
-        } else if count == 1 && is_separator(first) {
-            true
-        } else if seg.contains('.') && count >= 2 {
+        let is_known = is_digit(first)
+            || dictionary.contains(&seg)
+            || (count == 1 && is_valid_single_word(first))
+            || (count == 1 && is_separator(first))
             // Rudimentary acronym check
-            true
-        } else {
-            false
-        };
+            || (seg.contains('.') && count >= 2)
+            || is_url(&seg)
+            || is_email(&seg)
+            || is_hashtag(&seg)
+            || is_mention(&seg)
+            || is_emoji(&seg)
+            || is_latin_word(&seg)
+            || is_date(&seg)
+            // Numbers behind a prefix (`+855 12 345 678`, `$100`, `USD 50`)
+            || seg.ends_with(is_digit)
+            || is_bypassed_run(&seg);
 
         if is_known {
             if !unknown_buffer.is_empty() {
@@ -0,0 +1,57 @@
+//! HuggingFace `tokenizers` integration (`--features hf-tokenizers`).
+//!
+//! `KhmerPreTokenizer` splits each normalized string into Khmer words before
+//! the model (e.g. BPE) runs. Offsets are reported by `tokenizers` itself via
+//! `NormalizedString::slice`, so they stay aligned with the original input.
+
+use std::sync::Arc;
+
+use tokenizers::normalizer::Range;
+use tokenizers::tokenizer::{NormalizedString, OffsetReferential, OffsetType, PreTokenizedString, PreTokenizer, Result};
+
+use crate::segmenter::KhmerSegmenter;
+
+pub struct KhmerPreTokenizer {
+    segmenter: Arc<KhmerSegmenter>,
+    keep_whitespace: bool,
+}
+
+impl KhmerPreTokenizer {
+    /// Whitespace-only segments are dropped, like the `Whitespace` pre-tokenizer.
+    pub fn new(segmenter: Arc<KhmerSegmenter>) -> Self {
+        KhmerPreTokenizer { segmenter, keep_whitespace: false }
+    }
+
+    /// Keep whitespace segments as their own pre-tokens.
+    pub fn keep_whitespace(mut self, keep: bool) -> Self {
+        self.keep_whitespace = keep;
+        self
+    }
+}
+
+impl PreTokenizer for KhmerPreTokenizer {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized.split(|_, normalized: NormalizedString| {
+            let text = normalized.get();
+            let spans = self.segmenter.segment_spans(text);
+
+            Ok(spans
+                .into_iter()
+                .filter(|span| self.keep_whitespace || !span.as_str(text).trim().is_empty())
+                .filter_map(|span| normalized.slice(Range::Normalized(span.start..span.end)))
+                .collect::<Vec<_>>())
+        })
+    }
+}
+
+/// Pre-tokenize `text` and return `(token, (start, end))` pairs with byte
+/// offsets into the original string. Convenience for inspection and tests.
+pub fn pre_tokenize_str(pre_tokenizer: &KhmerPreTokenizer, text: &str) -> Result<Vec<(String, (usize, usize))>> {
+    let mut pretokenized = PreTokenizedString::from(text);
+    pre_tokenizer.pre_tokenize(&mut pretokenized)?;
+    Ok(pretokenized
+        .get_splits(OffsetReferential::Original, OffsetType::Byte)
+        .into_iter()
+        .map(|(s, offsets, _)| (s.to_string(), offsets))
+        .collect())
+}
@@ -0,0 +1,45 @@
+//! ICU4X word segmentation as a comparison backend (`--features icu`).
+//!
+//! ICU segments Khmer with either a compiled-in dictionary or an LSTM model,
+//! both shipped as baked data in `icu_segmenter`. Neither uses this crate's
+//! dictionary or post-processing, so scores show how the two approaches
+//! compare on the same references.
+
+use icu_segmenter::options::WordBreakInvariantOptions;
+use icu_segmenter::{WordSegmenter, WordSegmenterBorrowed};
+
+use crate::algorithm::Segmenter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcuModel {
+    Dictionary,
+    Lstm,
+}
+
+pub struct IcuSegmenter {
+    segmenter: WordSegmenterBorrowed<'static>,
+}
+
+impl IcuSegmenter {
+    pub fn new(model: IcuModel) -> Self {
+        let options = WordBreakInvariantOptions::default();
+        let segmenter = match model {
+            IcuModel::Dictionary => WordSegmenter::new_dictionary(options),
+            IcuModel::Lstm => WordSegmenter::new_lstm(options),
+        };
+        IcuSegmenter { segmenter }
+    }
+
+    /// Text between consecutive ICU word breaks. Whitespace and punctuation
+    /// come back as their own segments, as in `KhmerSegmenter`.
+    pub fn segment(&self, text: &str) -> Vec<String> {
+        let breaks: Vec<usize> = self.segmenter.segment_str(text).collect();
+        breaks.windows(2).map(|w| text[w[0]..w[1]].to_string()).collect()
+    }
+}
+
+impl Segmenter for IcuSegmenter {
+    fn segment(&self, text: &str) -> Vec<String> {
+        IcuSegmenter::segment(self, text)
+    }
+}
@@ -0,0 +1,220 @@
+//! Decoding of input lines (`LineDecoder`): line endings, the byte order
+//! mark, bytes that are not valid UTF-8 and surrounding whitespace, in that
+//! order.
+//!
+//! By default a line ends with `\n` or `\r\n`, so Windows-produced corpora
+//! leave no carriage return in the text, and the UTF-8 byte order mark some
+//! editors put at the start of a file is dropped from the first line.
+//!
+//! A line with invalid UTF-8 fails the read, as `BufRead::lines` does. With
+//! `Utf8Policy::Lossy` (`--utf8 lossy`) each invalid sequence is replaced by
+//! U+FFFD and the run goes on; the decoder counts them and keeps the numbers
+//! of the lines they were on, for the report at the end.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// What to do with input that is not valid UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Utf8Policy {
+    /// Fail the read
+    #[default]
+    Strict,
+    /// Replace each invalid sequence with U+FFFD and count it
+    Lossy,
+}
+
+impl Utf8Policy {
+    pub const ALL: [Utf8Policy; 2] = [Utf8Policy::Strict, Utf8Policy::Lossy];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Utf8Policy::Strict => "strict",
+            Utf8Policy::Lossy => "lossy",
+        }
+    }
+}
+
+impl fmt::Display for Utf8Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Utf8Policy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Utf8Policy::ALL.into_iter().find(|policy| policy.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = Utf8Policy::ALL.iter().map(|policy| policy.name()).collect();
+            format!("unknown UTF-8 policy {:?} (expected one of {})", s, names.join(", "))
+        })
+    }
+}
+
+/// What ends a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LineEndings {
+    /// `\n` or `\r\n`: carriage returns at the end of a line are dropped
+    #[default]
+    Crlf,
+    /// `\n` only: a carriage return before it stays in the text
+    Lf,
+}
+
+impl LineEndings {
+    pub const ALL: [LineEndings; 2] = [LineEndings::Crlf, LineEndings::Lf];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LineEndings::Crlf => "crlf",
+            LineEndings::Lf => "lf",
+        }
+    }
+}
+
+impl fmt::Display for LineEndings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LineEndings {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LineEndings::ALL.into_iter().find(|endings| endings.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = LineEndings::ALL.iter().map(|endings| endings.name()).collect();
+            format!("unknown line endings {:?} (expected one of {})", s, names.join(", "))
+        })
+    }
+}
+
+/// The UTF-8 encoding of U+FEFF, the byte order mark.
+const BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Invalid UTF-8 a lossy decoder replaced.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InvalidUtf8 {
+    /// Invalid sequences replaced, each by one U+FFFD
+    pub sequences: usize,
+    /// Lines with at least one of them
+    pub lines: usize,
+    /// 1-based numbers of the first `InvalidUtf8::MAX_LINE_NUMBERS` of those
+    /// lines, counting every line of the input
+    pub line_numbers: Vec<usize>,
+}
+
+impl InvalidUtf8 {
+    pub const MAX_LINE_NUMBERS: usize = 1000;
+
+    pub fn is_empty(&self) -> bool {
+        self.sequences == 0
+    }
+}
+
+/// Turns the bytes of input lines into text. Clones share their
+/// `InvalidUtf8` report, so a decoder moved into a reader thread can be
+/// asked for it afterwards through a clone.
+#[derive(Debug, Clone, Default)]
+pub struct LineDecoder {
+    line_endings: LineEndings,
+    keep_bom: bool,
+    utf8: Utf8Policy,
+    trim: bool,
+    invalid: Arc<Mutex<InvalidUtf8>>,
+}
+
+impl LineDecoder {
+    pub fn new() -> Self {
+        LineDecoder::default()
+    }
+
+    /// What ends a line (Default: `LineEndings::Crlf`).
+    pub fn line_endings(mut self, endings: LineEndings) -> Self {
+        self.line_endings = endings;
+        self
+    }
+
+    /// Keep a byte order mark at the start of the first line as text
+    /// (Default: dropped).
+    pub fn keep_bom(mut self, keep: bool) -> Self {
+        self.keep_bom = keep;
+        self
+    }
+
+    /// What to do with invalid UTF-8 (Default: `Utf8Policy::Strict`).
+    pub fn utf8(mut self, policy: Utf8Policy) -> Self {
+        self.utf8 = policy;
+        self
+    }
+
+    /// Remove whitespace at both ends of each line (Default: off).
+    pub fn trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Text of the line numbered `number` (1-based), read with its line
+    /// ending, if any.
+    pub fn decode(&self, number: usize, mut bytes: Vec<u8>) -> io::Result<String> {
+        if bytes.last() == Some(&b'\n') {
+            bytes.pop();
+        }
+        if self.line_endings == LineEndings::Crlf {
+            // Also a last line without a newline
+            while bytes.last() == Some(&b'\r') {
+                bytes.pop();
+            }
+        }
+        if number == 1 && !self.keep_bom && bytes.starts_with(BOM) {
+            bytes.drain(..BOM.len());
+        }
+        let line = self.decode_utf8(number, bytes)?;
+        if self.trim && line.trim().len() < line.len() {
+            return Ok(line.trim().to_string());
+        }
+        Ok(line)
+    }
+
+    fn decode_utf8(&self, number: usize, bytes: Vec<u8>) -> io::Result<String> {
+        match (String::from_utf8(bytes), self.utf8) {
+            (Ok(line), _) => Ok(line),
+            (Err(_), Utf8Policy::Strict) => {
+                Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid UTF-8 on line {}", number)))
+            }
+            (Err(err), Utf8Policy::Lossy) => {
+                let (line, sequences) = decode_lossy(err.as_bytes());
+                let mut invalid = self.invalid.lock().unwrap();
+                invalid.sequences += sequences;
+                invalid.lines += 1;
+                if invalid.line_numbers.len() < InvalidUtf8::MAX_LINE_NUMBERS {
+                    invalid.line_numbers.push(number);
+                }
+                Ok(line)
+            }
+        }
+    }
+
+    /// Invalid UTF-8 replaced so far by this decoder and its clones.
+    pub fn invalid_utf8(&self) -> InvalidUtf8 {
+        self.invalid.lock().unwrap().clone()
+    }
+}
+
+/// `bytes` with each invalid sequence replaced by U+FFFD, as
+/// `String::from_utf8_lossy` does, and the number of sequences replaced.
+pub fn decode_lossy(bytes: &[u8]) -> (String, usize) {
+    let mut text = String::with_capacity(bytes.len());
+    let mut sequences = 0;
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        if !chunk.invalid().is_empty() {
+            text.push(char::REPLACEMENT_CHARACTER);
+            sequences += 1;
+        }
+    }
+    (text, sequences)
+}
@@ -0,0 +1,127 @@
+//! Keyword extraction by TF-IDF over segmented documents (`keywords`).
+//!
+//! A document is a line or a group of lines. Terms are the Khmer words of
+//! its segmentation, as counted by `train` (no numbers, punctuation or Latin
+//! words). A word scores high in a document when it is frequent there and
+//! rare across the corpus:
+//!
+//! `score = tf * idf`, `tf = count / words in the document`,
+//! `idf = ln((1 + documents) / (1 + documents containing it)) + 1`
+//!
+//! Document frequencies are only known once the whole corpus has been read,
+//! so every document's term counts are kept until then, as word ids.
+
+use fxhash::FxHashMap;
+use serde::Serialize;
+
+use crate::train::is_countable;
+
+/// One keyword of a document.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Keyword<'a> {
+    pub word: &'a str,
+    /// Occurrences in the document
+    pub count: u32,
+    pub score: f64,
+}
+
+/// Term counts of a corpus of documents, with each word's document
+/// frequency.
+#[derive(Debug, Clone, Default)]
+pub struct TfIdf {
+    ids: FxHashMap<String, u32>,
+    words: Vec<String>,
+    /// Documents containing each word, by id
+    df: Vec<u32>,
+    /// Per document, `(word id, count)` in order of first occurrence
+    documents: Vec<Vec<(u32, u32)>>,
+}
+
+impl TfIdf {
+    pub fn new() -> Self {
+        TfIdf::default()
+    }
+
+    /// Add a document from the segments of its lines; returns its index.
+    pub fn add_document<I, S>(&mut self, segments: I) -> usize
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut counts: Vec<(u32, u32)> = Vec::new();
+        let mut slots: FxHashMap<u32, usize> = FxHashMap::default();
+        for segment in segments {
+            let token = segment.as_ref();
+            if !is_countable(token) {
+                continue;
+            }
+            let id = match self.ids.get(token) {
+                Some(&id) => id,
+                None => {
+                    let id = self.words.len() as u32;
+                    self.ids.insert(token.to_string(), id);
+                    self.words.push(token.to_string());
+                    self.df.push(0);
+                    id
+                }
+            };
+            match slots.get(&id) {
+                Some(&slot) => counts[slot].1 += 1,
+                None => {
+                    slots.insert(id, counts.len());
+                    counts.push((id, 1));
+                    self.df[id as usize] += 1;
+                }
+            }
+        }
+        self.documents.push(counts);
+        self.documents.len() - 1
+    }
+
+    /// Documents added.
+    pub fn documents(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Distinct words over all documents.
+    pub fn vocabulary(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Documents containing `word`.
+    pub fn document_frequency(&self, word: &str) -> u32 {
+        self.ids.get(word).map_or(0, |&id| self.df[id as usize])
+    }
+
+    /// Inverse document frequency of `word`, smoothed as if one extra
+    /// document contained every word.
+    pub fn idf(&self, word: &str) -> f64 {
+        self.idf_of(self.document_frequency(word))
+    }
+
+    fn idf_of(&self, df: u32) -> f64 {
+        ((1 + self.documents.len()) as f64 / (1 + df) as f64).ln() + 1.0
+    }
+
+    /// The `top` highest-scoring words of document `index`, best first (ties
+    /// in word order). Words found in more than `max_df` of the documents
+    /// (a share, 1.0 keeps all) are left out, as too common to say anything
+    /// about one document.
+    pub fn keywords(&self, index: usize, top: usize, max_df: f64) -> Vec<Keyword<'_>> {
+        let counts = &self.documents[index];
+        let length: u32 = counts.iter().map(|&(_, count)| count).sum();
+        let max_df = max_df * self.documents.len() as f64;
+        let mut keywords: Vec<Keyword> = counts
+            .iter()
+            .filter(|&&(id, _)| self.df[id as usize] as f64 <= max_df)
+            .map(|&(id, count)| Keyword {
+                word: &self.words[id as usize],
+                count,
+                score: count as f64 / length as f64 * self.idf_of(self.df[id as usize]),
+            })
+            .collect();
+        keywords.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.word.cmp(b.word)));
+        keywords.truncate(top);
+        keywords
+    }
+}
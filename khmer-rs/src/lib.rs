@@ -1,4 +1,66 @@
 pub mod constants;
 pub mod dictionary;
+pub mod binary;
+pub mod frequency;
+pub mod variants;
+pub mod overlay;
+pub mod suggest;
+pub mod bigram;
+pub mod confusables;
+pub mod cache;
+pub mod stats;
+pub mod report;
+pub mod validate;
 pub mod segmenter;
+pub mod maxmatch;
+pub mod algorithm;
+pub mod batch;
+pub mod schema;
+pub mod output;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod nonblocking;
+pub mod sampling;
+pub mod input;
+pub mod config;
 pub mod heuristics;
+pub mod patterns;
+pub mod rules;
+pub mod postprocess;
+pub mod trace;
+pub mod visualize;
+pub mod span;
+pub mod script;
+pub mod token;
+pub mod sentences;
+pub mod stream;
+pub mod detokenize;
+pub mod clusters;
+pub mod orthography;
+pub mod normalize;
+pub mod sanitize;
+pub mod memory;
+pub mod train;
+pub mod ngrams;
+pub mod keywords;
+pub mod stopwords;
+pub mod eval;
+pub mod compare;
+#[cfg(unix)]
+pub mod daemon;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "cdylib")]
+pub mod ffi;
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(feature = "hf-tokenizers")]
+pub mod hf;
+#[cfg(feature = "icu")]
+pub mod icu;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+#[cfg(feature = "external")]
+pub mod external;
+#[cfg(feature = "embedded")]
+pub mod embedded;
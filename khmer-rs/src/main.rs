@@ -1,180 +1,2463 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 use std::time::Instant;
-use rayon::prelude::*;
-use std::cell::RefCell;
+use std::sync::Arc;
+#[cfg(unix)]
+use std::time::{Duration, SystemTime};
 
-use khmer_rs::dictionary::Dictionary;
-use khmer_rs::segmenter::KhmerSegmenter;
+use khmer_rs::algorithm::Segmenter;
+use khmer_rs::batch::{shard_path, Checkpoint, Interner, Pipeline, RecordWriter};
+use khmer_rs::bigram::BigramTable;
+use khmer_rs::compare::compare;
+use khmer_rs::confusables::Confusables;
+use khmer_rs::config::{ConfigFile, DictionaryConfig, NumberFormat, Pass, SegmenterConfig, WhitespacePolicy};
+use khmer_rs::constants::is_separator;
+#[cfg(unix)]
+use khmer_rs::daemon;
+use khmer_rs::detokenize::detokenize;
+use khmer_rs::dictionary::{Cost, Dictionary, DictionaryBuilder};
+use khmer_rs::eval::{cross_validate, evaluate, k_folds, read_references, Reference, ReferenceFormat, Scores};
+#[cfg(feature = "external")]
+use khmer_rs::external::{ExternalSegmenter, ExternalTool};
+use khmer_rs::frequency::FrequencyFormat;
+use khmer_rs::input::{InvalidUtf8, LineDecoder, LineEndings, Utf8Policy};
+#[cfg(feature = "icu")]
+use khmer_rs::icu::{IcuModel, IcuSegmenter};
+#[cfg(feature = "onnx")]
+use khmer_rs::onnx::OnnxSegmenter;
+use khmer_rs::keywords::TfIdf;
+use khmer_rs::maxmatch::MaxMatchSegmenter;
+use khmer_rs::memory::{format_bytes, parse_bytes, MemorySample};
+use khmer_rs::ngrams::NgramCounter;
+use khmer_rs::normalize::normalize;
+use khmer_rs::output::{Jsonl, OutputFormat, OutputFormatter, Record};
+use khmer_rs::overlay::Overlay;
+use khmer_rs::report::{CoverageReport, UnknownReport};
+use khmer_rs::rules::RuleSet;
+use khmer_rs::sampling::{LineSelection, Shuffle};
+use khmer_rs::sanitize::{InvisibleClass, SanitizeAction, SanitizeCounts, Sanitizer};
+use khmer_rs::schema;
+use khmer_rs::segmenter::{KhmerSegmenter, Stage};
+use khmer_rs::sentences::segment_by_sentence;
+#[cfg(feature = "sqlite")]
+use khmer_rs::sqlite::SqliteWriter;
+use khmer_rs::stopwords::{FilterMode, StopWords, TokenFilter};
+use khmer_rs::train::{reestimate, write_frequencies, TokenCounter};
+use khmer_rs::validate::validate_words;
+use khmer_rs::token::TokenKind;
+use khmer_rs::trace::Trace;
+use khmer_rs::visualize::render_html;
+use khmer_rs::variants::VariantGenerator;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
-struct Args {
-    /// Path to dictionary file
-    #[arg(short, long, default_value = "../data/khmer_dictionary_words.txt")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+// Parsed once per run; boxing the larger variants would buy nothing.
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Keep the model loaded and serve segmentation requests over a Unix socket
+    #[cfg(unix)]
+    Serve {
+        #[command(flatten)]
+        model: ModelArgs,
+
+        /// Path of the Unix domain socket to listen on
+        #[arg(short, long)]
+        socket: String,
+
+        /// Check the model files every SECS seconds and swap in a reloaded dictionary when they change
+        #[arg(long, value_name = "SECS")]
+        reload_interval: Option<u64>,
+    },
+
+    /// Send lines to a running daemon and write JSONL results
+    #[cfg(unix)]
+    Client {
+        /// Path of the daemon's Unix domain socket
+        #[arg(short, long)]
+        socket: String,
+
+        /// Input text file (defaults to stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Output file (JSONL, defaults to stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Segment a raw corpus and write the word counts as a new frequency file
+    TrainFreq {
+        #[command(flatten)]
+        model: ModelArgs,
+
+        /// Raw corpus, one line of text per line
+        #[arg(short, long)]
+        input: String,
+
+        /// Frequency file to write; the format follows the extension (.json, .tsv, .csv, .txt)
+        #[arg(short, long)]
+        output: String,
+
+        /// Limit number of corpus lines to read
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// Drop words seen fewer than N times
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        min_count: u64,
+
+        /// Rounds of segment -> count -> rebuild; each round segments with the previous round's counts
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        iterations: usize,
+
+        /// Held-out lines to re-segment after each round, reporting how many change
+        #[arg(long, value_name = "FILE")]
+        dev: Option<String>,
+    },
+
+    /// Score segmentation against reference segmentations (word and boundary precision/recall/F1)
+    Eval {
+        #[command(flatten)]
+        model: ModelArgs,
+
+        #[command(flatten)]
+        options: SegmenterArgs,
+
+        /// Algorithms to score, side by side (repeat or comma-separate)
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "viterbi")]
+        algo: Vec<Algo>,
+
+        /// Reference segmentations, by default JSONL with `input` and `segments` fields (e.g. ../data/golden_master.jsonl); repeat to score several files
+        #[arg(short, long, required = true)]
+        reference: Vec<String>,
+
+        /// Encoding of --reference: jsonl, khpos (`word/TAG` tokens), alt (`SNT.<id><TAB>` then tokens), space or zwsp (tokens separated by spaces or U+200B)
+        #[arg(long, value_name = "FORMAT", default_value_t = ReferenceFormat::Jsonl)]
+        ref_format: ReferenceFormat,
+
+        /// Limit number of reference lines of each file
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// Score viterbi at every combination of these parameter values instead (repeat per parameter)
+        #[arg(long, value_name = "KEY=V1,V2,...")]
+        grid: Vec<String>,
+
+        /// Cross-validate --grid: tune on K-1 folds of the references, score on the held-out one, K times
+        #[arg(long, value_name = "K", requires = "grid")]
+        folds: Option<usize>,
+
+        /// Seed of the random assignment of reference lines to --folds
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        seed: u64,
+    },
+
+    /// Segment the same corpus with two configurations and report where they disagree
+    AbEval {
+        /// Options of configuration A, as given to the main command (e.g. "--length-norm 0.2")
+        #[arg(long, value_name = "ARGS", default_value = "", allow_hyphen_values = true)]
+        a: String,
+
+        /// Options of configuration B (e.g. "--algo maxmatch" or "--dict other_words.txt")
+        #[arg(long, value_name = "ARGS", default_value = "", allow_hyphen_values = true)]
+        b: String,
+
+        /// Raw corpus, one line of text per line
+        #[arg(short, long)]
+        input: String,
+
+        /// Limit number of corpus lines to read
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// Number of disagreeing lines to print
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        samples: usize,
+    },
+
+    /// Segment a corpus with several algorithms and report each one's throughput and agreement with the first
+    Bench {
+        #[command(flatten)]
+        model: ModelArgs,
+
+        #[command(flatten)]
+        options: SegmenterArgs,
+
+        /// Algorithms to run (repeat or comma-separate); the first is the baseline for agreement
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "viterbi,maxmatch")]
+        algo: Vec<Algo>,
+
+        /// Raw corpus, one line of text per line
+        #[arg(short, long)]
+        input: String,
+
+        /// Limit number of corpus lines to read
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+
+    /// Render a sample of a corpus as an HTML page: token boundaries, kinds by color and costs on hover
+    Visualize {
+        #[command(flatten)]
+        model: ModelArgs,
+
+        #[command(flatten)]
+        options: SegmenterArgs,
+
+        /// Raw corpus, one line of text per line
+        #[arg(short, long)]
+        input: String,
+
+        /// HTML file to write
+        #[arg(short, long)]
+        output: String,
+
+        /// Limit number of corpus lines to read
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// Number of lines to render, spread evenly over the corpus
+        #[arg(long, value_name = "N", default_value_t = 200)]
+        sample: usize,
+    },
+
+    /// Segment a corpus and write its word frequency table
+    Count {
+        #[command(flatten)]
+        model: ModelArgs,
+
+        #[command(flatten)]
+        options: SegmenterArgs,
+
+        /// Segmentation algorithm
+        #[arg(long, value_enum, default_value_t = Algo::Viterbi)]
+        algo: Algo,
+
+        /// Raw corpus, one line of text per line
+        #[arg(short, long)]
+        input: String,
+
+        /// Frequency table to write; the format follows the extension (.json, .tsv, .csv, .txt)
+        #[arg(short, long)]
+        output: String,
+
+        /// Limit number of corpus lines to read
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// Drop words seen fewer than N times
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        min_count: u64,
+
+        /// Count every token but whitespace (numbers, punctuation, Latin words), not only Khmer words
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Segment a corpus and count the n-grams of its tokens
+    Ngrams {
+        #[command(flatten)]
+        model: ModelArgs,
+
+        #[command(flatten)]
+        options: SegmenterArgs,
+
+        /// Segmentation algorithm
+        #[arg(long, value_enum, default_value_t = Algo::Viterbi)]
+        algo: Algo,
+
+        /// Raw corpus, one line of text per line
+        #[arg(short, long)]
+        input: String,
+
+        /// N-gram table to write: the tokens of each n-gram, then its count, tab-separated
+        #[arg(short, long)]
+        output: String,
+
+        /// Limit number of corpus lines to read
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// N-gram orders to count, comma-separated
+        #[arg(long, value_name = "N,..", value_delimiter = ',', default_value = "2")]
+        n: Vec<usize>,
+
+        /// Leave separators (punctuation, symbols) out; no n-gram spans one
+        #[arg(long)]
+        skip_separators: bool,
+
+        /// Drop n-grams seen fewer than N times
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        min_count: u64,
+
+        /// Also write the bigram costs -log10 P(word | previous) as a --bigrams table to FILE
+        #[arg(long, value_name = "FILE")]
+        bigram_costs: Option<String>,
+    },
+
+    /// Segment a corpus and write the top TF-IDF keywords of each document
+    Keywords {
+        #[command(flatten)]
+        model: ModelArgs,
+
+        #[command(flatten)]
+        options: SegmenterArgs,
+
+        /// Segmentation algorithm
+        #[arg(long, value_enum, default_value_t = Algo::Viterbi)]
+        algo: Algo,
+
+        /// Raw corpus, one line of text per line
+        #[arg(short, long)]
+        input: String,
+
+        /// JSONL keywords to write, one document per line
+        #[arg(short, long)]
+        output: String,
+
+        /// Limit number of corpus lines to read
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// Consecutive lines that make up one document
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        doc_lines: usize,
+
+        /// Keywords to keep per document
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+
+        /// Leave out words found in more than this share of the documents
+        #[arg(long, value_name = "RATE", value_parser = parse_rate, default_value_t = 1.0)]
+        max_df: f64,
+
+        /// Leave out stop words: the words of FILE, one per line, or the built-in Khmer list without FILE
+        #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "")]
+        stop_words: Option<String>,
+    },
+
+    /// Report how much of a corpus the dictionary covers, overall and by token length
+    Coverage {
+        #[command(flatten)]
+        model: ModelArgs,
+
+        #[command(flatten)]
+        options: SegmenterArgs,
+
+        /// Segmentation algorithm (viterbi or maxmatch)
+        #[arg(long, value_enum, default_value_t = Algo::Viterbi)]
+        algo: Algo,
+
+        /// Raw corpus, one line of text per line
+        #[arg(short, long)]
+        input: String,
+
+        /// Limit number of corpus lines to read
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// Also write the report as JSON to FILE
+        #[arg(long, value_name = "FILE")]
+        json: Option<String>,
+    },
+
+    /// Shuffle the lines of a corpus with a fixed seed, even one larger than memory
+    Shuffle {
+        /// Corpus to shuffle, one record per line
+        #[arg(short, long)]
+        input: String,
+
+        /// Shuffled corpus to write
+        #[arg(short, long)]
+        output: String,
+
+        /// Seed: the same seed, input and --max-memory give the same order
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        seed: u64,
+
+        /// Memory for the lines held at once (e.g. 1G); larger inputs are shuffled through temporary files
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_memory: Option<u64>,
+
+        /// Directory for the temporary files (default: the output's directory)
+        #[arg(long, value_name = "DIR")]
+        temp_dir: Option<String>,
+    },
+
+    /// Join segmented text back into lines with Khmer spacing rules
+    Detokenize {
+        /// Segmented text: JSONL records with a "segments" field (.jsonl, .json), or one line of tokens per line
+        #[arg(short, long)]
+        input: String,
+
+        /// Text to write, one line per record (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// What separates the tokens of a text input line
+        #[arg(long, value_name = "STR", default_value = " ")]
+        delimiter: String,
+    },
+
+    /// Dictionary maintenance tools
+    Dict {
+        #[command(subcommand)]
+        command: DictCommand,
+    },
+}
+
+// Parsed once per run, like `Command`
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand, Debug)]
+enum DictCommand {
+    /// Print entry counts, length distribution, trie size and estimated memory
+    Stats {
+        #[command(flatten)]
+        model: ModelArgs,
+    },
+
+    /// Report malformed, duplicate and redundant entries in a word list
+    Validate {
+        /// Path to dictionary file
+        #[arg(short, long, default_value = "../data/khmer_dictionary_words.txt")]
+        dict: String,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct ModelArgs {
+    /// Path to dictionary file, or `embedded` for the data compiled into the binary (`embedded` feature)
+    #[cfg_attr(not(feature = "embedded"), arg(short, long, default_value = "../data/khmer_dictionary_words.txt"))]
+    #[cfg_attr(feature = "embedded", arg(short, long, default_value = EMBEDDED))]
     dict: String,
 
-    /// Path to frequency file
+    /// Path to frequency file (not read with `--dict embedded`)
     #[arg(short, long, default_value = "../data/khmer_word_frequencies.json")]
     freq: String,
 
+    /// Extra word list applied over the dictionary, in order; `:cost=C` sets the cost of its words
+    #[arg(long = "overlay", value_name = "FILE[:cost=C]")]
+    overlays: Vec<String>,
+
+    /// Latin-script word list (e.g. English words) matched case-insensitively; `:cost=C` sets the cost of its words
+    #[arg(long = "latin-words", value_name = "FILE[:cost=C]")]
+    latin_words: Vec<String>,
+
+    /// Bigram cost table (previous<TAB>word<TAB>cost) used on top of the unigram costs
+    #[arg(long, value_name = "FILE")]
+    bigrams: Option<String>,
+
+    /// Look words up with lookalike codepoints folded (from<TAB>to per line), or the built-in mapping without FILE
+    #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "")]
+    confusables: Option<String>,
+
+    /// Sign-merge rules (TOML, see rules/sign_merge.toml) used instead of the built-in ones
+    #[arg(long, value_name = "FILE")]
+    rules: Option<String>,
+
+    /// TOML file with `[dictionary]` and `[segmenter]` parameters; command-line options take precedence
+    #[arg(long, value_name = "FILE")]
+    config: Option<String>,
+
+    /// Cost of listed words without a frequency (derived from the frequency data by default)
+    #[arg(long, value_name = "COST")]
+    default_cost: Option<Cost>,
+
+    /// Cost of an unknown cluster or character (derived from the frequency data by default)
+    #[arg(long, value_name = "COST")]
+    unknown_cost: Option<Cost>,
+
+    /// Always build the dictionary from the text files; do not read or write the binary cache in the user cache directory
+    #[arg(long)]
+    no_dict_cache: bool,
+}
+
+/// `--dict` value selecting the dictionary and frequencies compiled into the
+/// binary, the default with the `embedded` feature.
+#[cfg(feature = "embedded")]
+const EMBEDDED: &str = "embedded";
+
+#[derive(clap::Args, Debug)]
+struct RunArgs {
+    #[command(flatten)]
+    model: ModelArgs,
+
     /// Input text file
-    #[arg(short, long)]
-    input: String,
+    #[arg(short, long, required_unless_present = "print_schema")]
+    input: Option<String>,
 
     /// Output file (JSONL) - optional, skip to benchmark only
     #[arg(short, long)]
     output: Option<String>,
 
+    /// Encoding of the output records: jsonl, msgpack (`msgpack` feature), cbor (`cbor` feature), protobuf
+    /// (`protobuf` feature: length-delimited messages of proto/segmentation.proto) or sqlite (`sqlite` feature: a
+    /// database with lines and tokens tables)
+    #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::Jsonl)]
+    format: OutputFormat,
+
+    /// Print the JSON Schema of the output records and exit
+    #[arg(long)]
+    print_schema: bool,
+
     /// Limit number of lines to process
     #[arg(short, long)]
     limit: Option<usize>,
+
+    /// Skip this many lines first
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    skip: usize,
+
+    /// Process a random share of the lines, e.g. 0.01 for 1% (reproducible with --seed)
+    #[arg(long, value_name = "RATE", value_parser = parse_rate)]
+    sample: Option<f64>,
+
+    /// Seed of --sample
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    seed: u64,
+
+    /// Only read the lines within the first SIZE bytes of the input (e.g. 100M)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    head_bytes: Option<u64>,
+
+    /// Invalid UTF-8 in the input: strict (fail the run) or lossy (replace it with U+FFFD and report the lines)
+    #[arg(long, value_name = "POLICY", default_value_t = Utf8Policy::Strict)]
+    utf8: Utf8Policy,
+
+    /// What ends an input line: crlf (\n or \r\n, carriage returns dropped) or lf (\n only, carriage returns kept)
+    #[arg(long, value_name = "ENDINGS", default_value_t = LineEndings::Crlf)]
+    line_endings: LineEndings,
+
+    /// Keep a UTF-8 byte order mark at the start of the input as text instead of dropping it
+    #[arg(long)]
+    keep_bom: bool,
+
+    /// Report resident memory after each phase (Linux only)
+    #[arg(long)]
+    memory: bool,
+
+    /// Sample the processing phase with pprof and write a flamegraph to SVG (`profile` feature)
+    #[cfg(feature = "profile")]
+    #[arg(long, value_name = "SVG")]
+    profile: Option<String>,
+
+    /// Write every unknown token of the run, ranked by frequency with examples, to FILE (TSV)
+    #[arg(long, value_name = "FILE")]
+    unknown_report: Option<String>,
+
+    /// Examples of each unknown token in --unknown-report
+    #[arg(long, value_name = "N", default_value_t = 3)]
+    unknown_examples: usize,
+
+    /// Filter stop words out of the output: the words of FILE, one per line, or the built-in Khmer list without FILE
+    #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "")]
+    stop_words: Option<String>,
+
+    /// Filter separators (punctuation and whitespace) out of the output
+    #[arg(long)]
+    filter_separators: bool,
+
+    /// What becomes of filtered tokens: drop (left out) or tag (kept, their indexes listed in "filtered")
+    #[arg(long, value_name = "MODE", default_value_t = FilterMode::Drop)]
+    filter_mode: FilterMode,
+
+    /// Split lines into sentences and segment each sentence separately
+    #[arg(long)]
+    split_sentences: bool,
+
+    /// Normalize text (NFC, Khmer mark order, deprecated chars, joiners) before segmenting
+    #[arg(long)]
+    normalize: bool,
+
+    /// Remove control, bidi and other invisible characters before segmenting: strip (default) or space (controls
+    /// become spaces)
+    #[arg(long, value_name = "ACTION", num_args = 0..=1, default_missing_value = "strip")]
+    sanitize: Option<SanitizeAction>,
+
+    /// Segment each distinct line once and copy its segments to the lines that repeat it
+    #[arg(long)]
+    dedup: bool,
+
+    /// Hold the segments kept by --dedup as pooled tokens (each distinct token stored once)
+    #[arg(long)]
+    intern: bool,
+
+    /// Save progress to <OUTPUT>.checkpoint every LINES lines, so --resume can continue after a crash
+    #[arg(long, value_name = "LINES")]
+    checkpoint_every: Option<usize>,
+
+    /// Continue a run from its checkpoint, skipping the input lines already written to --output
+    #[arg(long)]
+    resume: bool,
+
+    /// Split the output into numbered files of LINES records each (out-00000.jsonl, out-00001.jsonl, ...)
+    #[arg(long, value_name = "LINES")]
+    shard_size: Option<usize>,
+
+    /// Split the output into N numbered files of about the same size
+    #[arg(long, value_name = "N", conflicts_with = "shard_size")]
+    shards: Option<usize>,
+
+    /// Keep the process within SIZE of memory (e.g. 512M, 2G) by bounding the lines in flight
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, conflicts_with = "dedup")]
+    max_memory: Option<u64>,
+
+    /// Worker threads segmenting lines (default: one per core)
+    #[arg(long, value_name = "N")]
+    workers: Option<usize>,
+
+    /// Lines handed to a worker at a time
+    #[arg(long, value_name = "LINES")]
+    batch_lines: Option<usize>,
+
+    /// Show each line's segments after the DP and after every post-processing stage (JSONL with --output)
+    #[arg(long)]
+    explain: bool,
+
+    /// Show every arc the DP considered for each line, its cost and whether it won (JSONL with --output)
+    #[arg(long, conflicts_with = "explain")]
+    trace: bool,
+
+    /// Segmentation algorithm
+    #[arg(long, value_enum, default_value_t = Algo::Viterbi)]
+    algo: Algo,
+
+    #[command(flatten)]
+    options: SegmenterArgs,
 }
 
-// ============================================================================
-// 1BRC Optimization: Fast JSON builder with thread-local buffers
-// Avoids serde_json overhead and allocation per record
-// ============================================================================
-
-/// Pre-computed hex digits table (avoids snprintf overhead)
-const HEX_DIGITS: &[u8] = b"0123456789abcdef";
-
-thread_local! {
-    static JSON_BUFFER: RefCell<String> = RefCell::new(String::with_capacity(1024));
-}
-
-/// Fast JSON string escaper - appends directly to buffer
-#[inline]
-fn escape_json_to(out: &mut String, s: &str) {
-    for c in s.chars() {
-        match c {
-            '"' => out.push_str("\\\""),
-            '\\' => out.push_str("\\\\"),
-            '\n' => out.push_str("\\n"),
-            '\r' => out.push_str("\\r"),
-            '\t' => out.push_str("\\t"),
-            c if (c as u32) < 0x20 => {
-                // Fast hex encoding using lookup table
-                let code = c as u8;
-                out.push_str("\\u00");
-                out.push(HEX_DIGITS[(code >> 4) as usize] as char);
-                out.push(HEX_DIGITS[(code & 0xF) as usize] as char);
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Algo {
+    /// Minimum-cost segmentation (default)
+    Viterbi,
+    /// Greedy longest dictionary match, no cost model or post-processing
+    #[value(name = "maxmatch")]
+    MaxMatch,
+    /// ICU4X dictionary-based word breaking (`icu` feature)
+    #[cfg(feature = "icu")]
+    IcuDictionary,
+    /// ICU4X LSTM word breaking (`icu` feature)
+    #[cfg(feature = "icu")]
+    IcuLstm,
+    /// ONNX character-tagging model given by --onnx-model/--onnx-vocab (`onnx` feature)
+    #[cfg(feature = "onnx")]
+    Onnx,
+    /// Another tokenizer run as a child process, given by --external-tool or --external-cmd (`external` feature)
+    #[cfg(feature = "external")]
+    External,
+}
+
+/// One side of `ab-eval`: the model and segmentation options of the main command.
+#[derive(Parser, Debug)]
+#[command(no_binary_name = true)]
+struct AbConfig {
+    #[command(flatten)]
+    model: ModelArgs,
+
+    #[arg(long, value_enum, default_value_t = Algo::Viterbi)]
+    algo: Algo,
+
+    #[command(flatten)]
+    options: SegmenterArgs,
+}
+
+/// Segmentation options shared by every command that segments text.
+#[derive(clap::Args, Debug, Clone)]
+struct SegmenterArgs {
+    /// Emit generated spelling variants (Ta/Da, coeng-Ro order) in their dictionary spelling
+    #[arg(long)]
+    canonicalize: bool,
+
+    /// Keep #hashtags (Latin or Khmer) and @mentions as single tokens
+    #[arg(long)]
+    social_media: bool,
+
+    /// Keep dates (15/01/2024, ថ្ងៃទី១៥ ខែមករា ឆ្នាំ២០២៤) as single tokens
+    #[arg(long)]
+    dates: bool,
+
+    /// Phone number pattern (`#` = any digit), repeatable; replaces the built-in Cambodian patterns
+    #[arg(long = "phone-pattern", value_name = "PATTERN")]
+    phone_patterns: Vec<String>,
+
+    /// Separators inside numbers: any (`,` `.` space anywhere), decimal-point (1,234.5) or decimal-comma (1.234,5)
+    #[arg(long, value_name = "FORMAT")]
+    number_format: Option<NumberFormat>,
+
+    /// Currency symbol or code kept with an adjacent amount, repeatable; replaces the built-in set ($ ៛ € USD KHR ...)
+    #[arg(long = "currency", value_name = "SYMBOL")]
+    currencies: Vec<String>,
+
+    /// Keep numeric ranges without spaces (10-20, ២០–៣០) as one number
+    #[arg(long)]
+    number_ranges: bool,
+
+    /// Characters split off as separators, replacing the built-in punctuation set
+    #[arg(long, value_name = "CHARS")]
+    separators: Option<String>,
+
+    /// Characters split off as separators in addition to the built-in (or --separators) set, e.g. '|…'
+    #[arg(long, value_name = "CHARS")]
+    extra_separators: Option<String>,
+
+    /// Treat zero-width spaces in the input as forced word boundaries instead of deleting them
+    #[arg(long)]
+    zwsp_boundaries: bool,
+
+    /// Emit zero-width spaces as their own tokens instead of deleting them
+    #[arg(long)]
+    keep_zwsp: bool,
+
+    /// Output segments that concatenate to the input line exactly (whitespace, ZWSP and spelling kept)
+    #[arg(long)]
+    lossless: bool,
+
+    /// Whitespace between tokens: split (one token per space, as in Python), keep (each run verbatim, lines not stripped), collapse (each run as one space) or drop
+    #[arg(long, value_name = "POLICY")]
+    whitespace: Option<WhitespacePolicy>,
+
+    /// Match words differing by one vowel/sign from the input, at word cost + this penalty
+    #[arg(long, value_name = "COST")]
+    fuzzy_cost: Option<Cost>,
+
+    /// Scale dictionary word costs by (cluster count)^EXP; > 0 counters the bias toward long words
+    #[arg(long, value_name = "EXP", allow_negative_numbers = true)]
+    length_norm: Option<Cost>,
+
+    /// Prune DP paths costing more than MARGIN above the best one nearby (faster on very long lines, not exact)
+    #[arg(long, value_name = "MARGIN")]
+    beam: Option<Cost>,
+
+    /// Extra cost per character consumed in repair mode (after a coeng or at a dependent vowel) [default: 50]
+    #[arg(long, value_name = "COST")]
+    repair_penalty: Option<Cost>,
+
+    /// Extra cost of an unknown consonant that cannot stand alone as a word [default: 10]
+    #[arg(long, value_name = "COST")]
+    invalid_single_penalty: Option<Cost>,
+
+    /// Cache the segments of up to LINES recent distinct lines, so repeated lines skip segmentation
+    #[arg(long, value_name = "LINES")]
+    cache: Option<usize>,
+
+    /// Skip a post-processing pass: snap-invalid-singles, sign-merge or unknown-merge (repeat or comma-separate)
+    #[arg(long = "disable-pass", value_name = "PASS", value_delimiter = ',')]
+    disabled_passes: Vec<Pass>,
+
+    /// ONNX character-tagging model for `--algo onnx`
+    #[cfg(feature = "onnx")]
+    #[arg(long, value_name = "FILE")]
+    onnx_model: Option<String>,
+
+    /// Character vocabulary of the ONNX model, one entry per line
+    #[cfg(feature = "onnx")]
+    #[arg(long, value_name = "FILE")]
+    onnx_vocab: Option<String>,
+
+    /// Known tokenizer for `--algo external`: khmercut or khmer-nltk
+    #[cfg(feature = "external")]
+    #[arg(long, value_name = "TOOL")]
+    external_tool: Option<ExternalTool>,
+
+    /// Command line of the tokenizer for `--algo external`, instead of --external-tool (split on whitespace)
+    #[cfg(feature = "external")]
+    #[arg(long, value_name = "CMD", conflicts_with = "external_tool")]
+    external_cmd: Option<String>,
+
+    /// What separates the tokens the external tokenizer prints [default: the tool's, or a space]
+    #[cfg(feature = "external")]
+    #[arg(long, value_name = "STR")]
+    external_delimiter: Option<String>,
+}
+
+impl ModelArgs {
+    /// Whether `--dict embedded` selects the compiled-in data.
+    fn is_embedded(&self) -> bool {
+        #[cfg(feature = "embedded")]
+        return self.dict == EMBEDDED;
+        #[cfg(not(feature = "embedded"))]
+        false
+    }
+}
+
+impl RunArgs {
+    /// The input lines picked by `--head-bytes`, `--skip`, `--sample` and `--limit`.
+    fn selection(&self) -> LineSelection {
+        LineSelection {
+            head_bytes: self.head_bytes,
+            skip: self.skip,
+            sample: self.sample,
+            seed: self.seed,
+            limit: self.limit,
+        }
+    }
+
+    /// `--sanitize`, then `--normalize`: the text of `line` that is segmented.
+    fn prepare<'a>(&self, sanitizer: Option<&Sanitizer>, line: &'a str) -> Cow<'a, str> {
+        let text = match sanitizer {
+            Some(sanitizer) => sanitizer.sanitize(line),
+            None => Cow::Borrowed(line),
+        };
+        if self.normalize {
+            Cow::Owned(normalize(&text))
+        } else {
+            text
+        }
+    }
+
+    /// Decoder of the input lines, for `--utf8`, `--line-endings` and `--keep-bom`.
+    fn decoder(&self) -> LineDecoder {
+        LineDecoder::new().line_endings(self.line_endings).keep_bom(self.keep_bom).utf8(self.utf8)
+    }
+}
+
+impl SegmenterArgs {
+    /// `base` (from `--config`) with the options given on the command line applied over it.
+    fn config(&self, base: SegmenterConfig) -> SegmenterConfig {
+        let mut disabled_passes = base.disabled_passes;
+        for &pass in &self.disabled_passes {
+            if !disabled_passes.contains(&pass) {
+                disabled_passes.push(pass);
             }
-            c => out.push(c),
+        }
+        let mut extra_separators = base.extra_separators;
+        extra_separators.extend(self.extra_separators.iter().flat_map(|chars| chars.chars()));
+        SegmenterConfig {
+            canonicalize_variants: base.canonicalize_variants || self.canonicalize,
+            social_media: base.social_media || self.social_media,
+            dates: base.dates || self.dates,
+            phone_patterns: if self.phone_patterns.is_empty() {
+                base.phone_patterns
+            } else {
+                Some(self.phone_patterns.clone())
+            },
+            currencies: if self.currencies.is_empty() { base.currencies } else { Some(self.currencies.clone()) },
+            number_format: self.number_format.unwrap_or(base.number_format),
+            number_ranges: base.number_ranges || self.number_ranges,
+            separators: self.separators.as_ref().map(|chars| chars.chars().collect()).or(base.separators),
+            extra_separators,
+            zwsp_boundaries: base.zwsp_boundaries || self.zwsp_boundaries,
+            keep_zwsp: base.keep_zwsp || self.keep_zwsp,
+            lossless: base.lossless || self.lossless,
+            whitespace: self.whitespace.unwrap_or(base.whitespace),
+            fuzzy_match_cost: self.fuzzy_cost.or(base.fuzzy_match_cost),
+            length_normalization: self.length_norm.or(base.length_normalization),
+            beam_margin: self.beam.or(base.beam_margin),
+            repair_penalty: self.repair_penalty.or(base.repair_penalty),
+            invalid_single_penalty: self.invalid_single_penalty.or(base.invalid_single_penalty),
+            disabled_passes,
+            cache_size: self.cache.or(base.cache_size),
         }
     }
 }
 
-/// Fast integer to string - appends directly to buffer
-#[inline]
-fn append_int(out: &mut String, val: usize) {
-    if val == 0 {
-        out.push('0');
-        return;
+/// The output record of a line, with the segments `filter` matches dropped or
+/// tagged.
+fn filtered_record<'a, S: AsRef<str>>(
+    filter: &TokenFilter,
+    mode: FilterMode,
+    id: usize,
+    input: &'a str,
+    segments: &'a [S],
+) -> Record<'a> {
+    let mut record = Record::new(id, input, segments);
+    if !filter.is_active() {
+        return record;
     }
-    let mut buf = [0u8; 20];
-    let mut i = 20;
-    let mut v = val;
-    while v > 0 {
-        i -= 1;
-        buf[i] = b'0' + (v % 10) as u8;
-        v /= 10;
+    match mode {
+        FilterMode::Drop => {
+            record.segments.retain(|segment| !filter.matches(segment));
+            record
+        }
+        FilterMode::Tag => record.filtered(filter.positions(segments)),
     }
-    for j in i..20 {
-        out.push(buf[j] as char);
+}
+
+/// `--stop-words FILE`, or the built-in list for an empty path.
+fn load_stop_words(path: &str) -> anyhow::Result<StopWords> {
+    if path.is_empty() {
+        return Ok(StopWords::khmer());
     }
+    StopWords::open(Path::new(path)).map_err(|e| anyhow::anyhow!("stop words {}: {}", path, e))
 }
 
-/// Build JSON record directly using thread-local buffer
-#[inline]
-fn build_json_record(id: usize, input: &str, segments: &[String]) -> String {
-    JSON_BUFFER.with(|buf| {
-        let mut buffer = buf.borrow_mut();
-        buffer.clear();
+fn report_memory(phase: &str) {
+    match MemorySample::current() {
+        Some(m) => println!(
+            "Memory ({}): RSS {}, peak RSS {}",
+            phase,
+            format_bytes(m.rss_bytes),
+            format_bytes(m.peak_rss_bytes)
+        ),
+        None => println!("Memory ({}): unavailable on this platform", phase),
+    }
+}
 
-        // Build: {"id":N,"input":"...","segments":["...", ...]}
-        buffer.push_str("{\"id\":");
-        append_int(&mut buffer, id);
-        buffer.push_str(",\"input\":\"");
-        escape_json_to(&mut buffer, input);
-        buffer.push_str("\",\"segments\":[");
+/// Split `FILE[:cost=C]` into the path and the cost, if any.
+fn split_overlay_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.rsplit_once(":cost=") {
+        Some((path, cost)) => (path, Some(cost)),
+        None => (spec, None),
+    }
+}
 
-        for (i, seg) in segments.iter().enumerate() {
-            if i > 0 {
-                buffer.push(',');
-            }
-            buffer.push('"');
-            escape_json_to(&mut buffer, seg);
-            buffer.push('"');
+fn parse_overlay(spec: &str) -> anyhow::Result<Overlay> {
+    let (path, cost) = split_overlay_spec(spec);
+    let cost = cost.map(str::parse::<Cost>).transpose()?;
+    let overlay = Overlay::open(Path::new(path)).map_err(|e| anyhow::anyhow!("overlay {}: {}", path, e))?;
+    Ok(match cost {
+        Some(cost) => overlay.cost(cost),
+        None => overlay,
+    })
+}
+
+/// The `--config` file (all defaults without one) with `--default-cost` and
+/// `--unknown-cost` applied over it.
+fn config_file(model: &ModelArgs) -> anyhow::Result<ConfigFile> {
+    let mut file = match &model.config {
+        Some(path) => ConfigFile::open(Path::new(path)).map_err(|e| anyhow::anyhow!("config {}: {}", path, e))?,
+        None => ConfigFile::default(),
+    };
+    if let Some(cost) = model.default_cost {
+        file.dictionary.default_cost = Some(cost);
+    }
+    if let Some(cost) = model.unknown_cost {
+        file.dictionary.unknown_cost = Some(cost);
+    }
+    Ok(file)
+}
+
+fn dictionary_builder(model: &ModelArgs) -> anyhow::Result<DictionaryBuilder> {
+    let mut builder = Dictionary::builder().config(config_file(model)?.dictionary).binary_cache(!model.no_dict_cache);
+    for spec in &model.overlays {
+        builder = builder.overlay(parse_overlay(spec)?);
+    }
+    for spec in &model.latin_words {
+        builder = builder.latin_words(parse_overlay(spec)?);
+    }
+    Ok(builder)
+}
+
+fn load_dictionary(model: &ModelArgs) -> anyhow::Result<Dictionary> {
+    load_model_files(dictionary_builder(model)?, model)
+}
+
+/// Build with `builder` from the `--dict` and `--freq` files, or from the
+/// compiled-in data for `--dict embedded`.
+fn load_model_files(builder: DictionaryBuilder, model: &ModelArgs) -> anyhow::Result<Dictionary> {
+    #[cfg(feature = "embedded")]
+    if model.dict == EMBEDDED {
+        return Ok(builder.load_embedded()?);
+    }
+    Ok(builder.load(Path::new(&model.dict), Path::new(&model.freq))?)
+}
+
+impl Algo {
+    fn name(self) -> &'static str {
+        match self {
+            Algo::Viterbi => "viterbi",
+            Algo::MaxMatch => "maxmatch",
+            #[cfg(feature = "icu")]
+            Algo::IcuDictionary => "icu-dictionary",
+            #[cfg(feature = "icu")]
+            Algo::IcuLstm => "icu-lstm",
+            #[cfg(feature = "onnx")]
+            Algo::Onnx => "onnx",
+            #[cfg(feature = "external")]
+            Algo::External => "external",
         }
+    }
+}
 
-        buffer.push_str("]}");
-        buffer.clone()
-    })
+/// Load the model once and build every requested algorithm over it.
+fn load_algorithms(model: &ModelArgs, options: &SegmenterArgs, algos: &[Algo]) -> anyhow::Result<Vec<Box<dyn Segmenter>>> {
+    let viterbi = Arc::new(load_segmenter_with(model, options.config(config_file(model)?.segmenter))?);
+    algos
+        .iter()
+        .map(|algo| -> anyhow::Result<Box<dyn Segmenter>> {
+            Ok(match algo {
+                Algo::Viterbi => Box::new(Arc::clone(&viterbi)),
+                Algo::MaxMatch => Box::new(MaxMatchSegmenter::new(viterbi.dictionary())),
+                #[cfg(feature = "icu")]
+                Algo::IcuDictionary => Box::new(IcuSegmenter::new(IcuModel::Dictionary)),
+                #[cfg(feature = "icu")]
+                Algo::IcuLstm => Box::new(IcuSegmenter::new(IcuModel::Lstm)),
+                #[cfg(feature = "onnx")]
+                Algo::Onnx => {
+                    let (Some(model), Some(vocab)) = (&options.onnx_model, &options.onnx_vocab) else {
+                        anyhow::bail!("--algo onnx needs --onnx-model and --onnx-vocab");
+                    };
+                    println!("ONNX model: {}", model);
+                    Box::new(OnnxSegmenter::open(Path::new(model), Path::new(vocab))?)
+                }
+                #[cfg(feature = "external")]
+                Algo::External => Box::new(spawn_external(options)?),
+            })
+        })
+        .collect()
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+/// Start the tokenizer of `--external-cmd` or `--external-tool`.
+#[cfg(feature = "external")]
+fn spawn_external(options: &SegmenterArgs) -> anyhow::Result<ExternalSegmenter> {
+    let (program, args, delimiter) = match (&options.external_cmd, options.external_tool) {
+        (Some(cmd), _) => {
+            let mut words = cmd.split_whitespace();
+            let program = words.next().ok_or_else(|| anyhow::anyhow!("--external-cmd is empty"))?;
+            (program, words.collect(), " ")
+        }
+        (None, Some(tool)) => tool.command(),
+        (None, None) => anyhow::bail!("--algo external needs --external-tool or --external-cmd"),
+    };
+    let delimiter = options.external_delimiter.as_deref().unwrap_or(delimiter);
+    println!("External tokenizer: {}", options.external_cmd.as_deref().unwrap_or(program));
+    let segmenter = ExternalSegmenter::spawn(program, &args, delimiter)?;
+    // Fail here rather than mid-run when the tool cannot start or answer
+    segmenter.try_segment("ខ្ញុំ").map_err(|e| anyhow::anyhow!("external tokenizer {}: {}", program, e))?;
+    Ok(segmenter)
+}
+
+/// `--confusables`: the mapping of FILE, or the built-in one without it.
+fn load_confusables(model: &ModelArgs) -> anyhow::Result<Option<Confusables>> {
+    match model.confusables.as_deref() {
+        None => Ok(None),
+        Some("") => Ok(Some(Confusables::builtin())),
+        Some(path) => {
+            let confusables =
+                Confusables::open(Path::new(path)).map_err(|e| anyhow::anyhow!("confusables {}: {}", path, e))?;
+            Ok(Some(confusables))
+        }
+    }
+}
+
+fn load_segmenter(model: &ModelArgs) -> anyhow::Result<KhmerSegmenter> {
+    load_segmenter_with(model, config_file(model)?.segmenter)
+}
 
+fn load_segmenter_with(model: &ModelArgs, config: SegmenterConfig) -> anyhow::Result<KhmerSegmenter> {
     println!("Initializing Segmenter...");
-    println!("Dictionary: {}", args.dict);
-    println!("Frequencies: {}", args.freq);
+    println!("Dictionary: {}", model.dict);
+    if !model.is_embedded() {
+        println!("Frequencies: {}", model.freq);
+    }
+    for spec in &model.overlays {
+        println!("Overlay: {}", spec);
+    }
+    for spec in &model.latin_words {
+        println!("Latin words: {}", spec);
+    }
 
     let start_load = Instant::now();
-    let dictionary = Dictionary::new(Path::new(&args.dict), Path::new(&args.freq))?;
-    let segmenter = KhmerSegmenter::new(dictionary);
+    let dictionary = load_dictionary(model)?;
+    let mut builder = KhmerSegmenter::builder(dictionary).config(config);
+    if let Some(path) = &model.bigrams {
+        let table = BigramTable::open(Path::new(path)).map_err(|e| anyhow::anyhow!("bigrams {}: {}", path, e))?;
+        println!("Bigrams: {} ({} pairs)", path, table.len());
+        builder = builder.bigrams(table);
+    }
+    if let Some(confusables) = load_confusables(model)? {
+        println!("Confusables: {} mappings", confusables.len());
+        builder = builder.confusables(confusables);
+    }
+    if let Some(path) = &model.rules {
+        let rules = RuleSet::open(Path::new(path)).map_err(|e| anyhow::anyhow!("rules {}: {}", path, e))?;
+        println!("Rules: {} ({} rules)", path, rules.len());
+        builder = builder.rules(rules);
+    }
+    let segmenter = builder.build();
     println!("Model loaded in {:.2}s", start_load.elapsed().as_secs_f32());
+    Ok(segmenter)
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    // Spans are logged to stderr when they close, with their duration, at the levels `RUST_LOG` enables
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .init();
+
+    match cli.command {
+        None => run_batch(cli.run),
+        #[cfg(unix)]
+        Some(Command::Serve { model, socket, reload_interval }) => run_serve(model, &socket, reload_interval),
+        #[cfg(unix)]
+        Some(Command::Client { socket, input, output }) => run_client(&socket, input.as_deref(), output.as_deref()),
+        Some(Command::TrainFreq { model, input, output, limit, min_count, iterations, dev }) => {
+            run_train_freq(&model, &input, &output, limit, min_count, iterations, dev.as_deref())
+        }
+        Some(Command::Eval { model, options, algo, reference, ref_format, limit, grid, folds, seed }) => {
+            let folds = folds.map(|k| (k, seed));
+            run_eval(&model, &options, &algo, &reference, ref_format, limit, &grid, folds)
+        }
+        Some(Command::AbEval { a, b, input, limit, samples }) => run_ab_eval(&a, &b, &input, limit, samples),
+        Some(Command::Bench { model, options, algo, input, limit }) => run_bench(&model, &options, &algo, &input, limit),
+        Some(Command::Visualize { model, options, input, output, limit, sample }) => {
+            run_visualize(&model, &options, &input, &output, limit, sample)
+        }
+        Some(Command::Count { model, options, algo, input, output, limit, min_count, all }) => {
+            run_count(&model, &options, algo, &input, &output, limit, min_count, all)
+        }
+        Some(Command::Ngrams {
+            model,
+            options,
+            algo,
+            input,
+            output,
+            limit,
+            n,
+            skip_separators,
+            min_count,
+            bigram_costs,
+        }) => {
+            let bigram_costs = bigram_costs.as_deref();
+            run_ngrams(&model, &options, algo, &input, &output, limit, &n, skip_separators, min_count, bigram_costs)
+        }
+        Some(Command::Keywords { model, options, algo, input, output, limit, doc_lines, top, max_df, stop_words }) => {
+            let stop_words = stop_words.as_deref();
+            run_keywords(&model, &options, algo, &input, &output, limit, doc_lines, top, max_df, stop_words)
+        }
+        Some(Command::Coverage { model, options, algo, input, limit, json }) => {
+            run_coverage(&model, &options, algo, &input, limit, json.as_deref())
+        }
+        Some(Command::Shuffle { input, output, seed, max_memory, temp_dir }) => {
+            run_shuffle(&input, &output, seed, max_memory, temp_dir.as_deref())
+        }
+        Some(Command::Detokenize { input, output, delimiter }) => run_detokenize(&input, output.as_deref(), &delimiter),
+        Some(Command::Dict { command: DictCommand::Stats { model } }) => run_dict_stats(&model),
+        Some(Command::Dict { command: DictCommand::Validate { dict } }) => run_dict_validate(&dict),
+    }
+}
+
+#[cfg(unix)]
+fn run_serve(model: ModelArgs, socket: &str, reload_interval: Option<u64>) -> anyhow::Result<()> {
+    let segmenter = Arc::new(load_segmenter(&model)?);
+    if let Some(secs) = reload_interval {
+        let segmenter = Arc::clone(&segmenter);
+        std::thread::spawn(move || watch_model(&model, &segmenter, Duration::from_secs(secs)));
+    }
+    println!("Listening on {}", socket);
+    daemon::serve(segmenter, Path::new(socket))?;
+    Ok(())
+}
 
-    println!("Reading source: {}", args.input);
-    let file = File::open(&args.input)?;
-    let reader = BufReader::new(file);
-    // Read and trim lines - must match Python's line.strip() behavior
-    let mut lines: Vec<String> = reader
-        .lines()
-        .collect::<Result<Vec<String>, _>>()?
+/// Latest modification time among the model files, if any can be read.
+#[cfg(unix)]
+fn model_mtime(model: &ModelArgs) -> Option<SystemTime> {
+    let overlays = model.overlays.iter().chain(&model.latin_words).map(|spec| split_overlay_spec(spec).0);
+    [model.dict.as_str(), model.freq.as_str()]
         .into_iter()
-        .map(|l| l.trim().to_string())
-        .filter(|l| !l.is_empty())
-        .collect();
+        .chain(overlays)
+        .filter_map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .max()
+}
+
+/// Reload the dictionary in the background whenever the model files change,
+/// then swap it in. Requests keep being served from the old one meanwhile.
+#[cfg(unix)]
+fn watch_model(model: &ModelArgs, segmenter: &KhmerSegmenter, interval: Duration) {
+    let mut last_mtime = model_mtime(model);
+    loop {
+        std::thread::sleep(interval);
+        let mtime = model_mtime(model);
+        if mtime == last_mtime {
+            continue;
+        }
+        last_mtime = mtime;
 
-    if let Some(limit) = args.limit {
-        if limit < lines.len() {
-            lines.truncate(limit);
+        let start = Instant::now();
+        match load_dictionary(model) {
+            Ok(dictionary) => {
+                segmenter.swap_dictionary(Arc::new(dictionary));
+                println!("Dictionary reloaded in {:.2}s", start.elapsed().as_secs_f32());
+            }
+            Err(e) => eprintln!("Dictionary reload failed, keeping the current one: {}", e),
         }
     }
+}
 
-    println!("Processing {} lines...", lines.len());
-    let start_process = Instant::now();
+#[cfg(unix)]
+fn run_client(socket: &str, input: Option<&str>, output: Option<&str>) -> anyhow::Result<()> {
+    let mut client = daemon::Client::connect(Path::new(socket))?;
+
+    let reader: Box<dyn BufRead> = match input {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+    let writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    let mut writer = BufWriter::with_capacity(262144, writer);
+
+    let mut id = 0;
+    let mut record = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let segments = client.segment(line)?;
+        record.clear();
+        Jsonl.encode(&Record::new(id, line, &segments), &mut record)?;
+        writer.write_all(&record)?;
+        id += 1;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn run_dict_stats(model: &ModelArgs) -> anyhow::Result<()> {
+    let dictionary = load_dictionary(model)?;
+    let stats = dictionary.stats();
+
+    println!("Dictionary: {}", model.dict);
+    println!("Entries:        {}", stats.total_words());
+    println!("  base words:   {}", stats.base_words);
+    println!("  variants:     {}", stats.variant_words);
+    println!("Trie nodes:     {}", stats.trie_nodes);
+    println!("Est. memory:    {} (trie {}, tables {})",
+        format_bytes(stats.estimated_bytes() as u64),
+        format_bytes(stats.trie_bytes as u64),
+        format_bytes(stats.table_bytes as u64));
+    println!("Mean length:    {:.2} codepoints", stats.mean_length());
+    println!("Length distribution (codepoints: entries):");
+    for (len, &count) in stats.length_histogram.iter().enumerate() {
+        if count > 0 {
+            println!("  {:>3}: {}", len, count);
+        }
+    }
+    Ok(())
+}
+
+fn run_dict_validate(dict: &str) -> anyhow::Result<()> {
+    let reader = BufReader::new(File::open(dict)?);
+    let issues = validate_words(reader, &VariantGenerator::new())?;
+
+    for issue in &issues {
+        println!("{}:{}: {}: {}", dict, issue.line, issue.word, issue.kind);
+    }
+    if !issues.is_empty() {
+        anyhow::bail!("{} issue(s) found in {}", issues.len(), dict);
+    }
+    println!("No issues found in {}", dict);
+    Ok(())
+}
+
+/// `--explain`: the stages of each line, printed or written as JSONL to `--output`.
+fn run_explain(args: RunArgs) -> anyhow::Result<()> {
+    run_per_line(args, "--explain", |segmenter, i, line, text, writer| {
+        let stages = segmenter.explain(text);
+        match writer {
+            Some(writer) => {
+                let stages: Vec<_> = stages
+                    .iter()
+                    .map(|stage| serde_json::json!({ "name": stage.name, "segments": stage.segments }))
+                    .collect();
+                writeln!(writer, "{}", serde_json::json!({ "id": i, "input": line, "stages": stages }))
+            }
+            None => {
+                print_stages(i, line, &stages);
+                Ok(())
+            }
+        }
+    })
+}
 
-    // Parallel processing using Rayon with 1BRC fast JSON builder
-    let results: Vec<String> = lines.par_iter()
-        .enumerate()
-        .map(|(i, line)| {
+/// `--trace`: the DP arcs of each line, printed or written as JSONL to `--output`.
+fn run_trace(args: RunArgs) -> anyhow::Result<()> {
+    run_per_line(args, "--trace", |segmenter, i, line, text, writer| {
+        let trace = segmenter.segment_trace(text);
+        match writer {
+            Some(writer) => writeln!(writer, "{}", serde_json::json!({ "id": i, "input": line, "trace": trace })),
+            None => {
+                print_trace(i, line, &trace);
+                Ok(())
+            }
+        }
+    })
+}
+
+/// The loop shared by `--explain` and `--trace`: `report` gets each line's
+/// id, the input line and the text to segment, and writes its JSONL record
+/// to the `--output` writer, or prints it without one.
+fn run_per_line<F>(args: RunArgs, flag: &str, mut report: F) -> anyhow::Result<()>
+where
+    F: FnMut(&KhmerSegmenter, usize, &str, &str, Option<&mut BufWriter<File>>) -> io::Result<()>,
+{
+    anyhow::ensure!(args.algo == Algo::Viterbi, "{} only supports --algo viterbi", flag);
+    anyhow::ensure!(!args.split_sentences, "{} works on whole lines, not with --split-sentences", flag);
+    let config = args.options.config(config_file(&args.model)?.segmenter);
+    let trim = !config.lossless && config.whitespace != WhitespacePolicy::Keep;
+    let segmenter = load_segmenter_with(&args.model, config)?;
+
+    let input = args.input.as_deref().expect("--input is required");
+    let lines: Vec<String> = stream_lines(input, args.selection(), args.decoder(), trim)?.collect::<io::Result<_>>()?;
+    let mut writer = match &args.output {
+        Some(path) => Some(BufWriter::new(File::create(path)?)),
+        None => None,
+    };
+    let sanitizer = args.sanitize.map(Sanitizer::new);
+    for (i, line) in lines.iter().enumerate() {
+        let text = args.prepare(sanitizer.as_ref(), line);
+        report(&segmenter, i, line, &text, writer.as_mut())?;
+    }
+    if let Some(mut writer) = writer {
+        writer.flush()?;
+        println!("Done. Saved to {}", args.output.as_deref().unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// One line's arcs grouped by start position; `*` marks the arcs that won.
+fn print_trace(id: usize, line: &str, trace: &Trace) {
+    println!("[{}] {}", id, line);
+    for arc in &trace.arcs {
+        println!(
+            "  {:>4}..{:<4} {} {:15} {:8.3} {:9.3}  {}",
+            arc.start,
+            arc.end,
+            if arc.won { '*' } else { ' ' },
+            arc.kind.name(),
+            arc.cost,
+            arc.total,
+            arc.text
+        );
+    }
+    println!("  => {}", trace.segments.join("|"));
+}
+
+/// One line's stages, segments separated by `|`; `=` marks a stage that changed nothing.
+fn print_stages(id: usize, line: &str, stages: &[Stage]) {
+    println!("[{}] {}", id, line);
+    let width = stages.iter().map(|stage| stage.name.chars().count()).max().unwrap_or(0);
+    let mut previous: Option<&[String]> = None;
+    for stage in stages {
+        let segments = if previous == Some(stage.segments.as_slice()) { "=".to_string() } else { stage.segments.join("|") };
+        println!("  {:width$}  {}", stage.name, segments, width = width);
+        previous = Some(&stage.segments);
+    }
+}
+
+/// Non-empty lines of `path`, trimmed if `trim`, at most `limit` of them.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "info", skip(trim)))]
+fn read_lines(path: &str, limit: Option<usize>, trim: bool) -> anyhow::Result<Vec<String>> {
+    // Trimmed, the lines match Python's line.strip()
+    let lines = stream_lines(path, LineSelection { limit, ..Default::default() }, LineDecoder::new(), trim)?;
+    Ok(lines.collect::<io::Result<_>>()?)
+}
+
+/// Checkpoint interval of `--resume` without `--checkpoint-every`
+const DEFAULT_CHECKPOINT_LINES: usize = 10_000;
+
+/// Memory a byte of input takes in the batch pipeline, with its segments,
+/// their allocations and its JSON record (measured on the wiki corpus, with
+/// headroom), for `--max-memory`
+const IN_FLIGHT_BYTES_PER_INPUT_BYTE: u64 = 8;
+
+/// `--sample` values: a share of lines in `(0, 1]`.
+fn parse_rate(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate <= 1.0 => Ok(rate),
+        _ => Err(format!("invalid rate {:?} (expected a number in (0, 1], e.g. 0.01)", text)),
+    }
+}
+
+/// `--max-memory` and `--head-bytes` values: `512M`, `2G`, ...
+fn parse_size(text: &str) -> Result<u64, String> {
+    parse_bytes(text).ok_or_else(|| format!("invalid size {:?} (expected e.g. 512M or 2G)", text))
+}
+
+/// `--utf8 lossy`: what was replaced, and on which lines.
+fn report_invalid_utf8(invalid: &InvalidUtf8) {
+    const SHOWN: usize = 20;
+    if invalid.is_empty() {
+        return;
+    }
+    let numbers: Vec<String> = invalid.line_numbers.iter().take(SHOWN).map(ToString::to_string).collect();
+    let more = if invalid.lines > SHOWN { ", ..." } else { "" };
+    println!(
+        "Invalid UTF-8: {} sequence(s) replaced with U+FFFD on {} line(s) (lines {}{})",
+        invalid.sequences,
+        invalid.lines,
+        numbers.join(", "),
+        more
+    );
+}
+
+/// `--sanitize`: the characters removed, by class.
+fn report_sanitized(counts: &SanitizeCounts) {
+    let classes: Vec<String> = InvisibleClass::ALL
+        .iter()
+        .filter(|&&class| counts.get(class) > 0)
+        .map(|&class| format!("{} {}", counts.get(class), class.name()))
+        .collect();
+    if classes.is_empty() {
+        println!("Sanitized: no control or invisible characters found");
+    } else {
+        println!("Sanitized: {} character(s) removed or replaced ({})", counts.total(), classes.join(", "));
+    }
+}
+
+/// Number of lines `stream_lines` yields for `path`.
+fn count_lines(path: &str, selection: LineSelection, decoder: LineDecoder) -> io::Result<usize> {
+    selection
+        .decoded_lines(BufReader::new(File::open(path)?), decoder)
+        .try_fold(0, |count, line| line.map(|_| count + 1))
+}
+
+/// The lines of `path` picked by `selection` and decoded by `decoder`,
+/// trimmed if `trim`, read lazily.
+fn stream_lines(
+    path: &str,
+    selection: LineSelection,
+    decoder: LineDecoder,
+    trim: bool,
+) -> anyhow::Result<impl Iterator<Item = io::Result<String>> + Send> {
+    println!("Reading source: {}", path);
+    let lines = selection
+        .decoded_lines(BufReader::new(File::open(path)?), decoder.trim(trim));
+    Ok(lines)
+}
+
+/// Write `counts` to `output` in the format of its extension.
+fn save_frequencies(output: &str, counts: &HashMap<String, u64>) -> anyhow::Result<()> {
+    let mut writer = BufWriter::new(File::create(output)?);
+    let skipped = write_frequencies(&mut writer, counts, FrequencyFormat::from_path(Path::new(output)))?;
+    writer.flush()?;
+    if skipped > 0 {
+        eprintln!(
+            "Left out {} tokens containing whitespace, which this format cannot hold; write .json to keep them",
+            skipped
+        );
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_count(
+    model: &ModelArgs,
+    options: &SegmenterArgs,
+    algo: Algo,
+    input: &str,
+    output: &str,
+    limit: Option<usize>,
+    min_count: u64,
+    all: bool,
+) -> anyhow::Result<()> {
+    let config = options.config(config_file(model)?.segmenter);
+    let trim = !config.lossless && config.whitespace != WhitespacePolicy::Keep;
+    let segmenter = load_algorithms(model, options, &[algo])?.remove(0);
+
+    let start = Instant::now();
+    let lines = stream_lines(input, LineSelection { limit, ..Default::default() }, LineDecoder::new(), trim)?;
+    // Each worker counts into its own map; they are merged at the end
+    let counter = TokenCounter::new(all);
+    let count = Pipeline::new().run(lines, |line| counter.add(&segmenter.segment(line)), |_, _, _| Ok(()))?;
+    let mut counts = counter.finish();
+    let tokens: u64 = counts.values().sum();
+    counts.retain(|_, n| *n >= min_count);
+    println!(
+        "Counted {} tokens, {} distinct, in {} lines in {:.2}s",
+        tokens,
+        counts.len(),
+        count,
+        start.elapsed().as_secs_f32()
+    );
+
+    save_frequencies(output, &counts)?;
+    println!("Saved to {}", output);
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_ngrams(
+    model: &ModelArgs,
+    options: &SegmenterArgs,
+    algo: Algo,
+    input: &str,
+    output: &str,
+    limit: Option<usize>,
+    orders: &[usize],
+    skip_separators: bool,
+    min_count: u64,
+    bigram_costs: Option<&str>,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(orders.iter().all(|&n| n > 0), "--n orders must be at least 1");
+    anyhow::ensure!(
+        bigram_costs.is_none() || orders.contains(&2),
+        "--bigram-costs needs bigrams: add 2 to --n"
+    );
+    let config = options.config(config_file(model)?.segmenter);
+    let trim = !config.lossless && config.whitespace != WhitespacePolicy::Keep;
+    let segmenter = load_algorithms(model, options, &[algo])?.remove(0);
+    // Algorithms without a dictionary classify nothing; fall back to the characters
+    let separator = |token: &str| match segmenter.token_kind(token) {
+        Some(kind) => kind == TokenKind::Separator,
+        None => token.chars().all(is_separator),
+    };
+
+    let start = Instant::now();
+    let lines = stream_lines(input, LineSelection { limit, ..Default::default() }, LineDecoder::new(), trim)?;
+    let counter = NgramCounter::new(orders, skip_separators);
+    let count = Pipeline::new()
+        .run(lines, |line| counter.add(&segmenter.segment(line), separator), |_, _, _| Ok(()))?;
+    let ngrams = counter.finish();
+    let mut distinct = orders.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    let summary: Vec<String> = distinct.iter().map(|&n| format!("{} {}-grams", ngrams.distinct(n), n)).collect();
+    println!("Counted {} in {} lines in {:.2}s", summary.join(", "), count, start.elapsed().as_secs_f32());
+
+    let mut writer = BufWriter::new(File::create(output)?);
+    ngrams.write_tsv(&mut writer, min_count)?;
+    writer.flush()?;
+    println!("Saved to {}", output);
+    if let Some(path) = bigram_costs {
+        let mut writer = BufWriter::new(File::create(path)?);
+        ngrams.write_bigram_costs(&mut writer, min_count)?;
+        writer.flush()?;
+        println!("Bigram costs saved to {}", path);
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_keywords(
+    model: &ModelArgs,
+    options: &SegmenterArgs,
+    algo: Algo,
+    input: &str,
+    output: &str,
+    limit: Option<usize>,
+    doc_lines: usize,
+    top: usize,
+    max_df: f64,
+    stop_words: Option<&str>,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(doc_lines > 0, "--doc-lines must be at least 1");
+    let filter = match stop_words {
+        Some(path) => TokenFilter::new().stop_words(load_stop_words(path)?),
+        None => TokenFilter::new(),
+    };
+    let config = options.config(config_file(model)?.segmenter);
+    let trim = !config.lossless && config.whitespace != WhitespacePolicy::Keep;
+    let segmenter = load_algorithms(model, options, &[algo])?.remove(0);
+
+    let start = Instant::now();
+    let lines = stream_lines(input, LineSelection { limit, ..Default::default() }, LineDecoder::new(), trim)?;
+    // Document frequencies need the whole corpus: keep each document's counts
+    let mut tfidf = TfIdf::new();
+    let mut document: Vec<String> = Vec::new();
+    let count = Pipeline::new().run(
+        lines,
+        |line| segmenter.segment(line),
+        |id, _, segments| {
+            document.extend(segments.iter().filter(|segment| !filter.matches(segment)).cloned());
+            if (id + 1).is_multiple_of(doc_lines) {
+                tfidf.add_document(document.drain(..));
+            }
+            Ok(())
+        },
+    )?;
+    if !document.is_empty() {
+        tfidf.add_document(document.drain(..));
+    }
+    println!(
+        "Indexed {} documents ({} lines, {} distinct words) in {:.2}s",
+        tfidf.documents(),
+        count,
+        tfidf.vocabulary(),
+        start.elapsed().as_secs_f32()
+    );
+
+    let mut writer = BufWriter::new(File::create(output)?);
+    for index in 0..tfidf.documents() {
+        let first = index * doc_lines;
+        let last = (first + doc_lines).min(count) - 1;
+        let keywords = tfidf.keywords(index, top, max_df);
+        writeln!(writer, "{}", serde_json::json!({ "id": index, "lines": [first, last], "keywords": keywords }))?;
+    }
+    writer.flush()?;
+    println!("Saved to {}", output);
+    Ok(())
+}
+
+fn run_coverage(
+    model: &ModelArgs,
+    options: &SegmenterArgs,
+    algo: Algo,
+    input: &str,
+    limit: Option<usize>,
+    json: Option<&str>,
+) -> anyhow::Result<()> {
+    let config = options.config(config_file(model)?.segmenter);
+    let trim = !config.lossless && config.whitespace != WhitespacePolicy::Keep;
+    let segmenter = load_algorithms(model, options, &[algo])?.remove(0);
+    anyhow::ensure!(
+        segmenter.token_kind("").is_some(),
+        "coverage needs an algorithm with a dictionary (viterbi or maxmatch)"
+    );
+
+    let lines = stream_lines(input, LineSelection { limit, ..Default::default() }, LineDecoder::new(), trim)?;
+    let mut report = CoverageReport::new();
+    Pipeline::new().run(
+        lines,
+        |line| {
             let segments = segmenter.segment(line);
-            // 1BRC: Use fast inline JSON builder instead of serde_json
-            build_json_record(i, line, &segments)
+            let kinds: Vec<TokenKind> =
+                segments.iter().map(|segment| segmenter.token_kind(segment).unwrap_or(TokenKind::Unknown)).collect();
+            (segments, kinds)
+        },
+        |_, _, (segments, kinds)| {
+            report.add(segments, kinds);
+            Ok(())
+        },
+    )?;
+
+    let row = &report.overall;
+    let total = row.total();
+    let percent = |part: u64, whole: u64| part as f64 * 100.0 / whole.max(1) as f64;
+    println!("Coverage of {} lines ({} tokens, {} characters, whitespace aside):", report.lines, total.tokens, total.chars);
+    println!("  {:<8} {:>10} {:>8} {:>12} {:>8}", "", "tokens", "share", "characters", "share");
+    for (name, counts) in [("word", row.word), ("unknown", row.unknown), ("other", row.other)] {
+        println!(
+            "  {:<8} {:>10} {:>7.2}% {:>12} {:>7.2}%",
+            name,
+            counts.tokens,
+            percent(counts.tokens, total.tokens),
+            counts.chars,
+            percent(counts.chars, total.chars)
+        );
+    }
+    let (tokens, chars) = row.coverage();
+    println!(
+        "Dictionary coverage: {:.2}% of word and unknown tokens, {:.2}% of their characters",
+        tokens * 100.0,
+        chars * 100.0
+    );
+
+    println!("By token length (codepoints):");
+    println!("  {:>6} {:>10} {:>8} {:>8} {:>8} {:>9}", "length", "tokens", "word", "unknown", "other", "coverage");
+    for (i, row) in report.by_length.iter().enumerate() {
+        let total = row.total();
+        if total.tokens == 0 {
+            continue;
+        }
+        let length = if i + 1 == report.by_length.len() { format!("{}+", i + 1) } else { (i + 1).to_string() };
+        println!(
+            "  {:>6} {:>10} {:>7.2}% {:>7.2}% {:>7.2}% {:>8.2}%",
+            length,
+            total.tokens,
+            percent(row.word.tokens, total.tokens),
+            percent(row.unknown.tokens, total.tokens),
+            percent(row.other.tokens, total.tokens),
+            row.coverage().1 * 100.0
+        );
+    }
+
+    if let Some(path) = json {
+        let mut file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut file, &report)?;
+        writeln!(file)?;
+        file.flush()?;
+        println!("Saved to {}", path);
+    }
+    Ok(())
+}
+
+/// Record of `detokenize` JSONL input, as written by the main command.
+#[derive(serde::Deserialize)]
+struct SegmentsRecord {
+    segments: Vec<String>,
+}
+
+fn run_detokenize(input: &str, output: Option<&str>, delimiter: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(!delimiter.is_empty(), "--delimiter must not be empty");
+    let extension = Path::new(input).extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+    let jsonl = matches!(extension.as_deref(), Some("jsonl") | Some("json"));
+    let reader = BufReader::new(File::open(input).map_err(|e| anyhow::anyhow!("{}: {}", input, e))?);
+    let writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    let mut writer = BufWriter::new(writer);
+
+    let mut count = 0;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let text = if jsonl {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: SegmentsRecord =
+                serde_json::from_str(&line).map_err(|e| anyhow::anyhow!("{} line {}: {}", input, i + 1, e))?;
+            detokenize(&record.segments)
+        } else {
+            let tokens: Vec<&str> = line.split(delimiter).filter(|token| !token.is_empty()).collect();
+            detokenize(&tokens)
+        };
+        writeln!(writer, "{}", text)?;
+        count += 1;
+    }
+    writer.flush()?;
+    if let Some(path) = output {
+        println!("Detokenized {} lines. Saved to {}", count, path);
+    }
+    Ok(())
+}
+
+fn run_shuffle(
+    input: &str,
+    output: &str,
+    seed: u64,
+    max_memory: Option<u64>,
+    temp_dir: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut shuffle = Shuffle::new(seed);
+    if let Some(bytes) = max_memory {
+        shuffle = shuffle.max_memory(bytes);
+    }
+    if let Some(dir) = temp_dir {
+        shuffle = shuffle.temp_dir(Path::new(dir));
+    }
+    let start = Instant::now();
+    let stats = shuffle.run(Path::new(input), Path::new(output))?;
+    println!(
+        "Shuffled {} lines in {:.2}s ({} bucket{}). Saved to {}",
+        stats.lines,
+        start.elapsed().as_secs_f32(),
+        stats.buckets,
+        if stats.buckets == 1 { "" } else { "s" },
+        output
+    );
+    Ok(())
+}
+
+fn run_train_freq(
+    model: &ModelArgs,
+    input: &str,
+    output: &str,
+    limit: Option<usize>,
+    min_count: u64,
+    iterations: usize,
+    dev: Option<&str>,
+) -> anyhow::Result<()> {
+    let segmenter = load_segmenter(model)?;
+    let lines = read_lines(input, limit, true)?;
+    let dev_lines = match dev {
+        Some(path) => read_lines(path, None, true)?,
+        None => Vec::new(),
+    };
+
+    println!("Counting words in {} lines ({} iteration(s))...", lines.len(), iterations);
+    let start = Instant::now();
+    let builder = dictionary_builder(model)?;
+    let mut counts = reestimate(
+        &segmenter,
+        &lines,
+        &dev_lines,
+        iterations,
+        |freq_data| {
+            #[cfg(feature = "embedded")]
+            if model.dict == EMBEDDED {
+                return builder.clone().load_embedded_with_frequencies(freq_data);
+            }
+            builder.clone().load_with_frequencies(Path::new(&model.dict), freq_data)
+        },
+        |it| {
+            print!("Iteration {}: {} distinct words", it.iteration, it.vocabulary);
+            if dev.is_some() {
+                print!(
+                    ", dev: {}/{} lines changed, {} boundaries moved",
+                    it.dev_changed_lines,
+                    dev_lines.len(),
+                    it.dev_boundary_changes
+                );
+            }
+            println!();
+        },
+    )?;
+    counts.retain(|_, n| *n >= min_count);
+
+    save_frequencies(output, &counts)?;
+
+    println!("Wrote {} words to {} in {:.2}s", counts.len(), output, start.elapsed().as_secs_f32());
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_eval(
+    model: &ModelArgs,
+    options: &SegmenterArgs,
+    algos: &[Algo],
+    reference_paths: &[String],
+    ref_format: ReferenceFormat,
+    limit: Option<usize>,
+    grid: &[String],
+    folds: Option<(usize, u64)>,
+) -> anyhow::Result<()> {
+    let mut files = Vec::with_capacity(reference_paths.len());
+    for path in reference_paths {
+        let file = File::open(path).map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+        let mut references =
+            read_references(BufReader::new(file), ref_format).map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+        if let Some(limit) = limit {
+            references.truncate(limit);
+        }
+        files.push((path.as_str(), references));
+    }
+    if !grid.is_empty() {
+        if algos != [Algo::Viterbi] {
+            anyhow::bail!("--grid only tunes the viterbi segmenter");
+        }
+        // Tune on every file at once
+        let references: Vec<Reference> = files.into_iter().flat_map(|(_, references)| references).collect();
+        let name = reference_paths.join(", ");
+        return match folds {
+            Some((k, seed)) => run_cross_validation(model, options, &parse_grid(grid)?, &name, &references, k, seed),
+            None => run_grid(model, options, &parse_grid(grid)?, &name, references),
+        };
+    }
+
+    let segmenters = load_algorithms(model, options, algos)?;
+
+    for (algo, segmenter) in algos.iter().zip(&segmenters) {
+        println!();
+        println!("Algorithm:  {}", algo.name());
+        let mut overall = Scores::default();
+        let mut elapsed = 0.0;
+        for (path, references) in &files {
+            let start = Instant::now();
+            let scores = evaluate(segmenter, references);
+            elapsed += start.elapsed().as_secs_f64();
+            print_scores(path, &scores);
+            overall.merge(&scores);
+        }
+        if files.len() > 1 {
+            print_scores("overall", &overall);
+        }
+        println!("Speed:      {:.2} lines/sec", overall.lines as f64 / elapsed);
+    }
+    Ok(())
+}
+
+/// Parse `--grid KEY=V1,V2,...` specs; keys are those of `ConfigFile::set`.
+fn parse_grid(specs: &[String]) -> anyhow::Result<Vec<(String, Vec<Cost>)>> {
+    specs
+        .iter()
+        .map(|spec| {
+            let (key, values) = spec
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("--grid {}: expected KEY=V1,V2,...", spec))?;
+            ConfigFile::default().set(key, 0.0)?;
+            let values = values
+                .split(',')
+                .map(|v| v.trim().parse::<Cost>().map_err(|e| anyhow::anyhow!("--grid {}: {}", spec, e)))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok((key.to_string(), values))
         })
+        .collect()
+}
+
+/// Parameter values of a grid point, and its scores on each part of the
+/// references.
+type GridRow = (Vec<Cost>, Vec<Scores>);
+
+/// Score the viterbi segmenter at every point of `grid` (on top of the
+/// `--config` file and command-line options), on each of `parts`: one row
+/// of parameter values and per-part scores per point.
+fn grid_scores(
+    model: &ModelArgs,
+    options: &SegmenterArgs,
+    grid: &[(String, Vec<Cost>)],
+    parts: &[Vec<Reference>],
+) -> anyhow::Result<Vec<GridRow>> {
+    let base = config_file(model)?;
+    let base = ConfigFile { segmenter: options.config(base.segmenter), ..base };
+    let bigrams = match &model.bigrams {
+        Some(path) => Some(BigramTable::open(Path::new(path)).map_err(|e| anyhow::anyhow!("bigrams {}: {}", path, e))?),
+        None => None,
+    };
+    let rules = match &model.rules {
+        Some(path) => Some(RuleSet::open(Path::new(path)).map_err(|e| anyhow::anyhow!("rules {}: {}", path, e))?),
+        None => None,
+    };
+    let confusables = load_confusables(model)?;
+    let points: usize = grid.iter().map(|(_, values)| values.len()).product();
+    let lines: usize = parts.iter().map(Vec::len).sum();
+    println!("Grid: {} points, {} reference lines", points, lines);
+
+    // Only the dictionary parameters need a reload
+    let mut dictionaries: Vec<(DictionaryConfig, Arc<Dictionary>)> = Vec::new();
+    let mut rows = Vec::with_capacity(points);
+    for n in 0..points {
+        let mut point = base.clone();
+        let mut values = vec![0.0; grid.len()];
+        // Mixed-radix index: the last parameter varies fastest
+        let mut rest = n;
+        for (i, (key, choices)) in grid.iter().enumerate().rev() {
+            values[i] = choices[rest % choices.len()];
+            rest /= choices.len();
+            point.set(key, values[i])?;
+        }
+
+        let dictionary = match dictionaries.iter().find(|(config, _)| *config == point.dictionary) {
+            Some((_, dictionary)) => Arc::clone(dictionary),
+            None => {
+                let dictionary = load_model_files(dictionary_builder(model)?.config(point.dictionary), model)?;
+                let dictionary = Arc::new(dictionary);
+                dictionaries.push((point.dictionary, Arc::clone(&dictionary)));
+                dictionary
+            }
+        };
+        let mut builder = KhmerSegmenter::builder(dictionary).config(point.segmenter);
+        if let Some(table) = &bigrams {
+            builder = builder.bigrams(table.clone());
+        }
+        if let Some(confusables) = &confusables {
+            builder = builder.confusables(confusables.clone());
+        }
+        if let Some(rules) = &rules {
+            builder = builder.rules(rules.clone());
+        }
+        let segmenter = builder.build();
+        rows.push((values, parts.iter().map(|part| evaluate(&segmenter, part)).collect()));
+    }
+    Ok(rows)
+}
+
+/// Score the grid on `references` and print one row per point.
+fn run_grid(
+    model: &ModelArgs,
+    options: &SegmenterArgs,
+    grid: &[(String, Vec<Cost>)],
+    reference: &str,
+    references: Vec<Reference>,
+) -> anyhow::Result<()> {
+    let rows: Vec<(Vec<Cost>, Scores)> = grid_scores(model, options, grid, &[references])?
+        .into_iter()
+        .map(|(values, mut scores)| (values, scores.remove(0)))
         .collect();
 
-    // Write results to file only if output is specified
-    if let Some(ref output_path) = args.output {
-        let output_file = File::create(output_path)?;
-        // 1BRC: Use buffered writer with large buffer for better I/O
-        let mut writer = BufWriter::with_capacity(262144, output_file);
-        for result in &results {
-            writeln!(writer, "{}", result)?;
+    // Ties keep the earliest point
+    let mut best = 0;
+    for (i, (_, scores)) in rows.iter().enumerate() {
+        if scores.words.f1() > rows[best].1.words.f1() {
+            best = i;
+        }
+    }
+    let widths: Vec<usize> = grid.iter().map(|(key, _)| key.len().max(8)).collect();
+
+    println!();
+    println!("Reference: {}", reference);
+    for ((key, _), width) in grid.iter().zip(&widths) {
+        print!("{:>width$}  ", key, width = width);
+    }
+    println!("{:>7}  {:>7}  {:>8}", "Exact%", "Word F1", "Bound F1");
+    for (i, (values, scores)) in rows.iter().enumerate() {
+        for (value, width) in values.iter().zip(&widths) {
+            print!("{:>width$}  ", value, width = width);
+        }
+        println!(
+            "{:>7.2}  {:>7.4}  {:>8.4}{}",
+            scores.exact_rate() * 100.0,
+            scores.words.f1(),
+            scores.boundaries.f1(),
+            if i == best { "  *" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+/// k-fold cross-validation of the grid: for each fold, the point with the
+/// best word F1 on the other folds is scored on it. The spread of the
+/// held-out scores shows how far tuned parameters carry to unseen text.
+fn run_cross_validation(
+    model: &ModelArgs,
+    options: &SegmenterArgs,
+    grid: &[(String, Vec<Cost>)],
+    reference: &str,
+    references: &[Reference],
+    k: usize,
+    seed: u64,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(k >= 2, "--folds must be at least 2");
+    anyhow::ensure!(references.len() >= k, "--folds {} needs at least {} reference lines", k, k);
+    let parts = k_folds(references, k, seed);
+    let rows = grid_scores(model, options, grid, &parts)?;
+    let point_scores: Vec<Vec<Scores>> = rows.iter().map(|(_, scores)| scores.clone()).collect();
+    let folds = cross_validate(&point_scores);
+
+    let format_point = |values: &[Cost]| -> String {
+        grid.iter().zip(values).map(|((key, _), value)| format!("{}={}", key, value)).collect::<Vec<_>>().join(" ")
+    };
+    println!();
+    println!("Reference: {} ({} folds, seed {})", reference, k, seed);
+    println!(
+        "{:>4}  {:>6}  {:>8}  {:>7}  {:>8}  {:>7}  Best on the other folds",
+        "Fold", "Lines", "Train F1", "Exact%", "Bound F1", "Word F1"
+    );
+    for (i, fold) in folds.iter().enumerate() {
+        println!(
+            "{:>4}  {:>6}  {:>8.4}  {:>7.2}  {:>8.4}  {:>7.4}  {}",
+            i + 1,
+            fold.test.lines,
+            fold.train.words.f1(),
+            fold.test.exact_rate() * 100.0,
+            fold.test.boundaries.f1(),
+            fold.test.words.f1(),
+            format_point(&rows[fold.best].0)
+        );
+    }
+    println!();
+    println!("Held-out scores over {} folds:", k);
+    let held_out = |metric: fn(&Scores) -> f64| -> Vec<f64> { folds.iter().map(|fold| metric(&fold.test)).collect() };
+    for (name, values) in [
+        ("Exact lines", held_out(|scores| scores.exact_rate())),
+        ("Word F1", held_out(|scores| scores.words.f1())),
+        ("Boundary F1", held_out(|scores| scores.boundaries.f1())),
+    ] {
+        let mean = values.iter().sum::<f64>() / k as f64;
+        // Sample variance: the folds are a sample of possible held-out sets
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (k - 1) as f64;
+        let sd = variance.sqrt();
+        println!("  {:<12} mean {:.4}  variance {:.2e}  sd {:.4}", format!("{}:", name), mean, variance, sd);
+    }
+
+    // The choice to ship: the best point on all the references
+    let mut all: Vec<Scores> = Vec::with_capacity(rows.len());
+    for (_, scores) in &rows {
+        let mut merged = Scores::default();
+        scores.iter().for_each(|fold| merged.merge(fold));
+        all.push(merged);
+    }
+    let mut best = 0;
+    for (i, scores) in all.iter().enumerate() {
+        if scores.words.f1() > all[best].words.f1() {
+            best = i;
+        }
+    }
+    println!("Best on all folds: {} (word F1 {:.4})", format_point(&rows[best].0), all[best].words.f1());
+    Ok(())
+}
+
+fn run_ab_eval(a: &str, b: &str, input: &str, limit: Option<usize>, samples: usize) -> anyhow::Result<()> {
+    let mut segmenters = Vec::new();
+    for (name, args) in [("A", a), ("B", b)] {
+        // Whitespace-separated, no quoting: paths with spaces are not supported
+        let config = AbConfig::try_parse_from(args.split_whitespace())
+            .map_err(|e| anyhow::anyhow!("configuration {}: {}", name, e))?;
+        println!("Configuration {}: {}", name, if args.trim().is_empty() { "(defaults)" } else { args });
+        segmenters.push(load_algorithms(&config.model, &config.options, &[config.algo])?.remove(0));
+    }
+    let lines = read_lines(input, limit, true)?;
+
+    let comparison = compare(&segmenters[0], &segmenters[1], &lines, samples);
+    let (tokens_a, tokens_b) = (comparison.tokens_a(), comparison.tokens_b());
+    let boundaries = &comparison.scores.boundaries;
+    println!();
+    println!("Lines:      {}", comparison.scores.lines);
+    println!("Agreement:  {:.2}% of lines identical", comparison.agreement_rate() * 100.0);
+    println!(
+        "Tokens:     A {}  B {}  delta {:+} ({:+.2}%)",
+        tokens_a,
+        tokens_b,
+        comparison.token_delta(),
+        comparison.token_delta() as f64 * 100.0 / tokens_a.max(1) as f64
+    );
+    println!(
+        "Boundaries: shared {}  A only {}  B only {}",
+        boundaries.matched,
+        boundaries.reference - boundaries.matched,
+        boundaries.predicted - boundaries.matched
+    );
+
+    if !comparison.samples.is_empty() {
+        println!();
+        println!("Sample disagreements:");
+        for sample in &comparison.samples {
+            let hunks = sample.hunks();
+            println!("  line {} ({} differences)", sample.line + 1, hunks.len());
+            for (a, b) in hunks {
+                // Show the preceding token (shared by both sides) as context
+                let context = match a.start {
+                    0 => String::new(),
+                    i => format!("{} | ", sample.a[i - 1]),
+                };
+                println!("    A: {}[{}]", context, sample.a[a].join(" | "));
+                println!("    B: {}[{}]", context, sample.b[b].join(" | "));
+            }
         }
+    }
+    Ok(())
+}
+
+/// Segment `input` with each algorithm in turn, on one thread so the speeds
+/// compare per core, and score every output against the first algorithm's.
+fn run_bench(
+    model: &ModelArgs,
+    options: &SegmenterArgs,
+    algos: &[Algo],
+    input: &str,
+    limit: Option<usize>,
+) -> anyhow::Result<()> {
+    let segmenters = load_algorithms(model, options, algos)?;
+    let lines = read_lines(input, limit, true)?;
+    let chars: usize = lines.iter().map(|line| line.chars().count()).sum();
+
+    let mut baseline: Option<Vec<Vec<String>>> = None;
+    println!();
+    println!("Lines:      {}  ({} characters)", lines.len(), chars);
+    println!(
+        "{:<16} {:>12} {:>14} {:>10} {:>10} {:>12}",
+        "algorithm", "lines/sec", "chars/sec", "tokens", "agreement", "boundary F1"
+    );
+    for (algo, segmenter) in algos.iter().zip(&segmenters) {
+        let start = Instant::now();
+        let outputs: Vec<Vec<String>> = lines.iter().map(|line| segmenter.segment(line)).collect();
+        let elapsed = start.elapsed().as_secs_f64().max(f64::EPSILON);
+
+        let mut scores = Scores::default();
+        for (reference, predicted) in baseline.as_ref().unwrap_or(&outputs).iter().zip(&outputs) {
+            scores.add(reference, predicted);
+        }
+        println!(
+            "{:<16} {:>12.1} {:>14.1} {:>10} {:>9.2}% {:>11.2}%",
+            algo.name(),
+            lines.len() as f64 / elapsed,
+            chars as f64 / elapsed,
+            scores.words.predicted,
+            scores.exact_rate() * 100.0,
+            scores.boundaries.f1() * 100.0
+        );
+        baseline.get_or_insert(outputs);
+    }
+    Ok(())
+}
+
+fn run_visualize(
+    model: &ModelArgs,
+    options: &SegmenterArgs,
+    input: &str,
+    output: &str,
+    limit: Option<usize>,
+    sample: usize,
+) -> anyhow::Result<()> {
+    let config = options.config(config_file(model)?.segmenter);
+    let trim = !config.lossless && config.whitespace != WhitespacePolicy::Keep;
+    let segmenter = load_segmenter_with(model, config)?;
+    let lines = read_lines(input, limit, trim)?;
+
+    // Every step-th line, so the sample covers the whole corpus
+    let step = lines.len().div_ceil(sample.max(1)).max(1);
+    let sampled: Vec<(usize, String)> = lines.into_iter().enumerate().step_by(step).take(sample).collect();
+    let title = format!("Segmentation of {}", input);
+    std::fs::write(output, render_html(&segmenter, &sampled, &title))?;
+    println!("Rendered {} lines to {}", sampled.len(), output);
+    Ok(())
+}
+
+fn print_scores(name: &str, scores: &Scores) {
+    println!("Reference:  {} ({} lines)", name, scores.lines);
+    println!("Exact lines: {:.2}%", scores.exact_rate() * 100.0);
+    for (label, counts) in [("Words", &scores.words), ("Boundaries", &scores.boundaries)] {
+        println!(
+            "{:<11} P {:.4}  R {:.4}  F1 {:.4}",
+            format!("{}:", label),
+            counts.precision(),
+            counts.recall(),
+            counts.f1()
+        );
+    }
+}
+
+/// Samples per second of `--profile`, off a round number so sampling does
+/// not run in lockstep with periodic work.
+#[cfg(feature = "profile")]
+const PROFILE_FREQUENCY: i32 = 997;
+
+#[cfg(feature = "profile")]
+fn start_profiler() -> anyhow::Result<pprof::ProfilerGuard<'static>> {
+    pprof::ProfilerGuardBuilder::default()
+        .frequency(PROFILE_FREQUENCY)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(|e| anyhow::anyhow!("--profile: {}", e))
+}
+
+/// Stop sampling and write what `profiler` saw as a flamegraph to `path`.
+#[cfg(feature = "profile")]
+fn write_flamegraph(profiler: &pprof::ProfilerGuard<'_>, path: &str) -> anyhow::Result<()> {
+    let report = profiler.report().build().map_err(|e| anyhow::anyhow!("--profile: {}", e))?;
+    let samples: isize = report.data.values().sum();
+    let file = File::create(path).map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+    report.flamegraph(BufWriter::new(file)).map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+    println!("Flamegraph: {} ({} samples)", path, samples);
+    Ok(())
+}
+
+fn run_batch(args: RunArgs) -> anyhow::Result<()> {
+    if args.print_schema {
+        println!("{}", serde_json::to_string_pretty(&schema::json_schema())?);
+        return Ok(());
+    }
+    if args.explain {
+        return run_explain(args);
+    }
+    if args.trace {
+        return run_trace(args);
+    }
+    let segmenter = load_algorithms(&args.model, &args.options, &[args.algo])?.remove(0);
+    if args.memory {
+        report_memory("after model load");
+    }
+
+    let input = args.input.as_deref().expect("--input is required");
+    let config = args.options.config(config_file(&args.model)?.segmenter);
+    let trim = !config.lossless && config.whitespace != WhitespacePolicy::Keep;
+    let decoder = args.decoder();
+    let mut lines = stream_lines(input, args.selection(), decoder.clone(), trim)?;
+
+    // --resume: drop what was written after the last checkpoint and skip the
+    // input lines before it
+    let checkpoint_path = args.output.as_deref().map(|path| Checkpoint::path_for(Path::new(path)));
+    let checkpoint_every = args.checkpoint_every.map(|lines| lines.max(1)).or(args.resume.then_some(DEFAULT_CHECKPOINT_LINES));
+    anyhow::ensure!(checkpoint_every.is_none() || args.output.is_some(), "--checkpoint-every and --resume need --output");
+    // --format sqlite writes a database rather than a stream of records
+    let formatter = args.format.formatter();
+    anyhow::ensure!(
+        formatter.is_some() || (checkpoint_every.is_none() && args.shards.is_none() && args.shard_size.is_none()),
+        "--format {} does not support --checkpoint-every, --resume, --shards or --shard-size",
+        args.format
+    );
+    let resumed = match (&checkpoint_path, args.resume) {
+        (Some(path), true) => {
+            let checkpoint = Checkpoint::load(path)?
+                .ok_or_else(|| anyhow::anyhow!("no checkpoint at {} to resume from", path.display()))?;
+            for line in lines.by_ref().take(checkpoint.lines) {
+                line?;
+            }
+            println!("Resuming after line {}", checkpoint.lines);
+            Some(checkpoint)
+        }
+        _ => None,
+    };
+    let skipped = resumed.map_or(0, |checkpoint| checkpoint.lines);
+
+    println!("Processing lines...");
+    #[cfg(feature = "profile")]
+    let profiler = args.profile.as_ref().map(|_| start_profiler()).transpose()?;
+    let start_process = Instant::now();
+
+    // Producer -> workers -> ordered writer over bounded channels, so memory
+    // stays flat whatever the corpus size
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("pipeline", output = ?args.output).entered();
+    let sanitizer = args.sanitize.map(Sanitizer::new);
+    let segment_line = |line: &str| {
+        let text = args.prepare(sanitizer.as_ref(), line);
+        if args.split_sentences {
+            segment_by_sentence(&segmenter, &text)
+        } else {
+            segmenter.segment(&text)
+        }
+    };
+    let mut pipeline = Pipeline::new().dedup(args.dedup);
+    if let Some(workers) = args.workers {
+        pipeline = pipeline.workers(workers);
+    }
+    if let Some(batch_lines) = args.batch_lines {
+        pipeline = pipeline.batch_lines(batch_lines);
+    }
+    // --max-memory: what the model does not take goes to lines in flight, each
+    // byte of input taking about IN_FLIGHT_BYTES_PER_INPUT_BYTE with its segments
+    if let Some(max_memory) = args.max_memory {
+        let loaded = MemorySample::current().map_or(0, |sample| sample.rss_bytes);
+        anyhow::ensure!(
+            max_memory > loaded,
+            "--max-memory {} is less than the {} taken after loading the model",
+            format_bytes(max_memory),
+            format_bytes(loaded)
+        );
+        let in_flight = (max_memory - loaded) / IN_FLIGHT_BYTES_PER_INPUT_BYTE;
+        println!("Memory budget: {} of input in flight", format_bytes(in_flight));
+        pipeline = pipeline.max_bytes(in_flight as usize);
+    }
+    // --shards: spread the lines evenly, which takes counting them first
+    let shard_lines = match args.shards {
+        Some(shards) => Some(count_lines(input, args.selection(), args.decoder())?.div_ceil(shards.max(1)).max(1)),
+        None => args.shard_size,
+    };
+    #[cfg(feature = "sqlite")]
+    let mut database = match (&args.output, args.format) {
+        (Some(output_path), OutputFormat::Sqlite) => Some(SqliteWriter::create(Path::new(output_path))?),
+        _ => None,
+    };
+    let mut writer = match (&args.output, resumed) {
+        _ if formatter.is_none() => None,
+        (Some(output_path), Some(checkpoint)) => Some(RecordWriter::resume(Path::new(output_path), shard_lines, checkpoint)?),
+        (Some(output_path), None) => Some(RecordWriter::create(Path::new(output_path), shard_lines)?),
+        (None, _) => None,
+    };
+    // One buffer for every record, encoded in --format
+    let mut encoded = Vec::with_capacity(1024);
+    let mut write_record = |record: Record| -> io::Result<()> {
+        #[cfg(feature = "sqlite")]
+        if let Some(database) = &mut database {
+            return database.write_record(&record, &segmenter);
+        }
+        let (Some(writer), Some(formatter)) = (&mut writer, &formatter) else { return Ok(()) };
+        encoded.clear();
+        formatter.encode(&record, &mut encoded)?;
+        writer.write_encoded(&encoded)?;
+        if let (Some(every), Some(path)) = (checkpoint_every, &checkpoint_path) {
+            if writer.lines().is_multiple_of(every) {
+                writer.checkpoint()?.save(path)?;
+            }
+        }
+        Ok(())
+    };
+
+    // --unknown-report: the workers classify the segments, the writer
+    // aggregates them in line order
+    let mut unknown_report = args.unknown_report.as_ref().map(|_| UnknownReport::new(args.unknown_examples));
+    anyhow::ensure!(
+        unknown_report.is_none() || segmenter.token_kind("").is_some(),
+        "--unknown-report needs an algorithm with a dictionary (viterbi or maxmatch)"
+    );
+    let classify = unknown_report.is_some();
+    let kinds = |segments: &[String]| -> Vec<TokenKind> {
+        if classify {
+            segments.iter().map(|segment| segmenter.token_kind(segment).unwrap_or(TokenKind::Unknown)).collect()
+        } else {
+            Vec::new()
+        }
+    };
+
+    // --stop-words, --filter-separators: applied as records are written
+    let mut filter = TokenFilter::new().separators(args.filter_separators);
+    if let Some(path) = &args.stop_words {
+        filter = filter.stop_words(load_stop_words(path)?);
+    }
+
+    // With --intern the segments kept for --dedup are pooled tokens
+    let interner = args.intern.then(Interner::new);
+    let count = match &interner {
+        Some(interner) => pipeline.run(
+            lines,
+            |line| {
+                let segments = segment_line(line);
+                (kinds(&segments), interner.intern_all(segments))
+            },
+            |id, line, (kinds, segments)| {
+                if let Some(report) = &mut unknown_report {
+                    report.add(segments, kinds);
+                }
+                write_record(filtered_record(&filter, args.filter_mode, skipped + id, line, segments))
+            },
+        )?,
+        None => pipeline.run(
+            lines,
+            |line| {
+                let segments = segment_line(line);
+                (kinds(&segments), segments)
+            },
+            |id, line, (kinds, segments)| {
+                if let Some(report) = &mut unknown_report {
+                    report.add(segments, kinds);
+                }
+                write_record(filtered_record(&filter, args.filter_mode, skipped + id, line, segments))
+            },
+        )?,
+    };
+    if let (Some(report), Some(path)) = (&unknown_report, &args.unknown_report) {
+        let mut file = BufWriter::new(File::create(path)?);
+        report.write_tsv(&mut file)?;
+        file.flush()?;
+        println!(
+            "Unknown tokens: {} of {} ({:.2}%), {} distinct. Report saved to {}",
+            report.unknown(),
+            report.tokens(),
+            report.unknown() as f64 * 100.0 / report.tokens().max(1) as f64,
+            report.distinct(),
+            path
+        );
+    }
+    if let Some(writer) = &mut writer {
         writer.flush()?;
     }
+    #[cfg(feature = "sqlite")]
+    if let Some(database) = database {
+        database.finish()?;
+    }
+    // The run is complete: nothing left to resume
+    if let (Some(_), Some(path)) = (checkpoint_every, &checkpoint_path) {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    println!("{} lines", count);
+    if let Some(interner) = &interner {
+        println!("{} distinct tokens", interner.len());
+    }
+    report_invalid_utf8(&decoder.invalid_utf8());
+    if let Some(sanitizer) = &sanitizer {
+        report_sanitized(&sanitizer.counts());
+    }
 
     let duration = start_process.elapsed();
-    if let Some(ref output_path) = args.output {
-        println!("Done. Saved to {}", output_path);
+    #[cfg(feature = "profile")]
+    if let (Some(profiler), Some(path)) = (profiler, &args.profile) {
+        write_flamegraph(&profiler, path)?;
+    }
+    if let Some(output_path) = &args.output {
+        match &writer {
+            Some(writer) if shard_lines.is_some() && writer.files() > 0 => {
+                let path = Path::new(output_path);
+                println!(
+                    "Done. Saved to {} shards, {} .. {}",
+                    writer.files(),
+                    shard_path(path, 0).display(),
+                    shard_path(path, writer.files() - 1).display()
+                );
+            }
+            _ => println!("Done. Saved to {}", output_path),
+        }
     }
     println!("Time taken: {:.2}s", duration.as_secs_f32());
-    println!("Speed: {:.2} lines/sec", lines.len() as f32 / duration.as_secs_f32());
+    println!("Speed: {:.2} lines/sec", count as f32 / duration.as_secs_f32());
+    if let Some(stats) = segmenter.cache_stats() {
+        println!(
+            "Cache: {} hits of {} lookups ({:.2}%), {} of {} lines cached",
+            stats.hits,
+            stats.lookups(),
+            stats.hit_rate() * 100.0,
+            stats.entries,
+            stats.capacity
+        );
+    }
+    if args.memory {
+        report_memory("after processing");
+    }
 
     Ok(())
 }
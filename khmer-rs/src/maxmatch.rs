@@ -0,0 +1,54 @@
+//! Greedy longest-match-first segmentation over the same dictionary trie.
+//!
+//! At each position the longest dictionary word wins. Where no word starts,
+//! one Khmer cluster, one run of Latin letters/digits, or one other character
+//! becomes a token. There is no cost model and no post-processing, so this
+//! is a speed/quality baseline rather than a replacement for `KhmerSegmenter`.
+
+use std::sync::Arc;
+
+use crate::clusters::get_khmer_cluster_length_cps;
+use crate::constants::is_khmer_char;
+use crate::dictionary::Dictionary;
+
+pub struct MaxMatchSegmenter {
+    dictionary: Arc<Dictionary>,
+}
+
+impl MaxMatchSegmenter {
+    pub fn new(dictionary: impl Into<Arc<Dictionary>>) -> Self {
+        MaxMatchSegmenter { dictionary: dictionary.into() }
+    }
+
+    pub fn dictionary(&self) -> &Dictionary {
+        &self.dictionary
+    }
+
+    pub fn segment(&self, text: &str) -> Vec<String> {
+        let cps: Vec<char> = text.chars().filter(|&c| c != '\u{200b}').collect();
+        let mut segments = Vec::new();
+        let mut i = 0;
+
+        while i < cps.len() {
+            let end = match self.dictionary.common_prefixes(&cps, i).last() {
+                Some((end, _)) => end,
+                None => i + fallback_length(&cps, i),
+            };
+            segments.push(cps[i..end].iter().collect());
+            i = end;
+        }
+
+        segments
+    }
+}
+
+fn fallback_length(cps: &[char], start: usize) -> usize {
+    let c = cps[start];
+    if is_khmer_char(c) {
+        get_khmer_cluster_length_cps(cps, start)
+    } else if c.is_alphanumeric() {
+        cps[start..].iter().take_while(|c| c.is_alphanumeric() && !is_khmer_char(**c)).count()
+    } else {
+        1
+    }
+}
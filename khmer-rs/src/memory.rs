@@ -0,0 +1,62 @@
+//! Resident memory sampling via `/proc/self/status` (Linux only).
+//!
+//! The kernel already tracks the high-water mark (`VmHWM`), so no allocator
+//! wrapper is needed: sampling after each phase is enough to attribute memory
+//! to the dictionary/trie vs the results buffer.
+
+use std::fs;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemorySample {
+    /// Current resident set size in bytes (`VmRSS`)
+    pub rss_bytes: u64,
+    /// Peak resident set size in bytes (`VmHWM`)
+    pub peak_rss_bytes: u64,
+}
+
+impl MemorySample {
+    /// Returns `None` on platforms without `/proc/self/status`.
+    pub fn current() -> Option<Self> {
+        let status = fs::read_to_string("/proc/self/status").ok()?;
+        Some(MemorySample {
+            rss_bytes: parse_status_kb(&status, "VmRSS:")? * 1024,
+            peak_rss_bytes: parse_status_kb(&status, "VmHWM:")? * 1024,
+        })
+    }
+}
+
+fn parse_status_kb(status: &str, key: &str) -> Option<u64> {
+    status
+        .lines()
+        .find(|l| l.starts_with(key))?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    format!("{:.1} MB", bytes as f64 / MB)
+}
+
+/// Parse a size such as `512M`, `1.5G`, `64k` or `1000000` (bytes); the
+/// suffixes are binary (`K` = 1024) and case-insensitive, with an optional `B`.
+pub fn parse_bytes(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let text = text.strip_suffix(['b', 'B']).unwrap_or(text);
+    let (number, unit) = match text.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&text[..i], c.to_ascii_uppercase()),
+        _ => (text, ' '),
+    };
+    let scale: u64 = match unit {
+        ' ' => 1,
+        'K' => 1 << 10,
+        'M' => 1 << 20,
+        'G' => 1 << 30,
+        'T' => 1 << 40,
+        _ => return None,
+    };
+    let number: f64 = number.trim().parse().ok()?;
+    (number.is_finite() && number >= 0.0).then_some((number * scale as f64) as u64)
+}
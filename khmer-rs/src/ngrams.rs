@@ -0,0 +1,172 @@
+//! N-gram counts over segmented text (`ngrams`), for collocation analysis
+//! and for building a bigram cost table (see `bigram`).
+//!
+//! Whitespace never takes part in an n-gram. Neither do tokens containing a
+//! tab (`ខ្ញុំ\t`, `😀\t😀`), which the tab-separated keys and output cannot
+//! hold; no n-gram spans one. With `skip_separators`, separators do not
+//! either, and no n-gram spans one: the tokens on each side of a `។` are not
+//! counted as neighbours.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use crate::dictionary::Cost;
+use crate::train::merge_counts;
+
+/// Tokens of an n-gram key are joined with a tab, which no counted token
+/// contains.
+const JOIN: char = '\t';
+
+/// N-gram counts gathered from parallel workers, one map per worker as in
+/// `train::TokenCounter`.
+pub struct NgramCounter {
+    orders: Vec<usize>,
+    skip_separators: bool,
+    /// Per worker, one map per order
+    pool: Mutex<Vec<Vec<HashMap<String, u64>>>>,
+}
+
+impl NgramCounter {
+    /// Count n-grams of each order in `orders` (e.g. `[2, 3]`).
+    pub fn new(orders: &[usize], skip_separators: bool) -> Self {
+        let mut orders: Vec<usize> = orders.iter().copied().filter(|&n| n > 0).collect();
+        orders.sort_unstable();
+        orders.dedup();
+        NgramCounter { orders, skip_separators, pool: Mutex::new(Vec::new()) }
+    }
+
+    /// Count the n-grams of one line. `is_separator` tells separators apart
+    /// for `skip_separators`. Safe to call from many threads.
+    pub fn add<S: AsRef<str>>(&self, segments: &[S], is_separator: impl Fn(&str) -> bool) {
+        let mut counts = self.pool.lock().unwrap().pop().unwrap_or_else(|| vec![HashMap::new(); self.orders.len()]);
+        let mut run: Vec<&str> = Vec::with_capacity(segments.len());
+        for segment in segments {
+            let token = segment.as_ref();
+            if token.trim().is_empty() {
+                continue;
+            }
+            if token.contains(JOIN) || (self.skip_separators && is_separator(token)) {
+                self.count_run(&run, &mut counts);
+                run.clear();
+                continue;
+            }
+            run.push(token);
+        }
+        self.count_run(&run, &mut counts);
+        self.pool.lock().unwrap().push(counts);
+    }
+
+    fn count_run(&self, run: &[&str], counts: &mut [HashMap<String, u64>]) {
+        let mut key = String::new();
+        for (&n, counts) in self.orders.iter().zip(counts) {
+            for window in run.windows(n) {
+                key.clear();
+                for (i, token) in window.iter().enumerate() {
+                    if i > 0 {
+                        key.push(JOIN);
+                    }
+                    key.push_str(token);
+                }
+                match counts.get_mut(&key) {
+                    Some(count) => *count += 1,
+                    None => {
+                        counts.insert(key.clone(), 1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The merged counts of every worker.
+    pub fn finish(self) -> Ngrams {
+        let mut merged = vec![HashMap::new(); self.orders.len()];
+        for worker in self.pool.into_inner().unwrap() {
+            for (merged, counts) in merged.iter_mut().zip(worker) {
+                *merged = merge_counts(std::mem::take(merged), counts);
+            }
+        }
+        Ngrams { counts: self.orders.into_iter().zip(merged).collect() }
+    }
+}
+
+/// N-gram counts by order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ngrams {
+    counts: Vec<(usize, HashMap<String, u64>)>,
+}
+
+impl Ngrams {
+    /// Count of the n-gram `tokens`, 0 if unseen or its order not counted.
+    pub fn get(&self, tokens: &[&str]) -> u64 {
+        let key = tokens.join(&JOIN.to_string());
+        self.counts
+            .iter()
+            .find(|(n, _)| *n == tokens.len())
+            .and_then(|(_, counts)| counts.get(&key).copied())
+            .unwrap_or(0)
+    }
+
+    /// Distinct n-grams of order `n`.
+    pub fn distinct(&self, n: usize) -> usize {
+        self.counts.iter().find(|(order, _)| *order == n).map_or(0, |(_, counts)| counts.len())
+    }
+
+    /// N-grams seen at least `min_count` times, each order from the lowest,
+    /// most frequent first (ties in token order): `(tokens, count)`.
+    pub fn ranked(&self, min_count: u64) -> Vec<(Vec<&str>, u64)> {
+        let mut ranked = Vec::new();
+        for (_, counts) in &self.counts {
+            let mut entries: Vec<(&String, u64)> =
+                counts.iter().filter(|(_, &count)| count >= min_count).map(|(key, &count)| (key, count)).collect();
+            entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+            ranked.extend(entries.into_iter().map(|(key, count)| (key.split(JOIN).collect(), count)));
+        }
+        ranked
+    }
+
+    /// Write `ranked(min_count)` as TSV, one n-gram per line: its tokens,
+    /// then its count.
+    pub fn write_tsv<W: Write>(&self, w: &mut W, min_count: u64) -> io::Result<()> {
+        for (tokens, count) in self.ranked(min_count) {
+            writeln!(w, "{}\t{}", tokens.join("\t"), count)?;
+        }
+        Ok(())
+    }
+
+    /// Bigram costs `-log10 P(word | previous)`, estimated from the bigram
+    /// counts, for the pairs seen at least `min_count` times: the
+    /// `previous<TAB>word<TAB>cost` entries of a `bigram::BigramTable` file.
+    /// Empty if bigrams were not counted.
+    pub fn bigram_costs(&self, min_count: u64) -> Vec<(&str, &str, Cost)> {
+        let Some((_, bigrams)) = self.counts.iter().find(|(n, _)| *n == 2) else {
+            return Vec::new();
+        };
+        // How often each word starts a bigram, rare pairs included
+        let mut starts: HashMap<&str, u64> = HashMap::new();
+        for (key, &count) in bigrams {
+            let (previous, _) = key.split_once(JOIN).unwrap();
+            *starts.entry(previous).or_insert(0) += count;
+        }
+        let mut costs: Vec<(&str, &str, Cost, u64)> = bigrams
+            .iter()
+            .filter(|(_, &count)| count >= min_count)
+            .map(|(key, &count)| {
+                let (previous, word) = key.split_once(JOIN).unwrap();
+                let probability = count as f64 / starts[previous] as f64;
+                (previous, word, -probability.log10() as Cost, count)
+            })
+            .collect();
+        costs.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| (a.0, a.1).cmp(&(b.0, b.1))));
+        costs.into_iter().map(|(previous, word, cost, _)| (previous, word, cost)).collect()
+    }
+
+    /// Write `bigram_costs(min_count)` in the `bigram::BigramTable` file
+    /// format, ready for `--bigrams`.
+    pub fn write_bigram_costs<W: Write>(&self, w: &mut W, min_count: u64) -> io::Result<()> {
+        for (previous, word, cost) in self.bigram_costs(min_count) {
+            writeln!(w, "{}\t{}\t{:.4}", previous, word, cost)?;
+        }
+        Ok(())
+    }
+}
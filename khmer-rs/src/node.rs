@@ -0,0 +1,76 @@
+//! Node.js N-API bindings (`--features node`).
+//!
+//! `segment` and `segmentBatch` return promises; the work runs on the libuv
+//! thread pool so the event loop is never blocked by segmentation.
+
+use std::sync::Arc;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+
+use crate::dictionary::Dictionary;
+use crate::segmenter::KhmerSegmenter;
+
+#[napi(js_name = "KhmerSegmenter")]
+pub struct NodeSegmenter {
+    inner: Arc<KhmerSegmenter>,
+}
+
+#[napi]
+impl NodeSegmenter {
+    /// Loads the dictionary synchronously; create one instance at startup and reuse it.
+    #[napi(constructor)]
+    pub fn new(dict_path: String, freq_path: String) -> Result<Self> {
+        let dictionary = Dictionary::new(dict_path.as_ref(), freq_path.as_ref())
+            .map_err(|e| Error::from_reason(format!("Failed to load dictionary: {}", e)))?;
+        Ok(NodeSegmenter { inner: Arc::new(KhmerSegmenter::new(dictionary)) })
+    }
+
+    #[napi(ts_return_type = "Promise<string[]>")]
+    pub fn segment(&self, text: String) -> AsyncTask<SegmentTask> {
+        AsyncTask::new(SegmentTask { segmenter: Arc::clone(&self.inner), text })
+    }
+
+    #[napi(ts_return_type = "Promise<string[][]>")]
+    pub fn segment_batch(&self, texts: Vec<String>) -> AsyncTask<SegmentBatchTask> {
+        AsyncTask::new(SegmentBatchTask { segmenter: Arc::clone(&self.inner), texts })
+    }
+}
+
+pub struct SegmentTask {
+    segmenter: Arc<KhmerSegmenter>,
+    text: String,
+}
+
+impl Task for SegmentTask {
+    type Output = Vec<String>;
+    type JsValue = Vec<String>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        Ok(self.segmenter.segment(&self.text))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+pub struct SegmentBatchTask {
+    segmenter: Arc<KhmerSegmenter>,
+    texts: Vec<String>,
+}
+
+impl Task for SegmentBatchTask {
+    type Output = Vec<Vec<String>>;
+    type JsValue = Vec<Vec<String>>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        // One libuv task per batch; rayon spreads the lines across cores
+        Ok(self.texts.par_iter().map(|t| self.segmenter.segment(t)).collect())
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
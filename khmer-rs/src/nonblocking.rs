@@ -0,0 +1,113 @@
+//! Async-friendly segmentation for servers (axum, tonic, ...): the
+//! CPU-bound work runs on a rayon pool and the caller awaits a future, so
+//! request handlers never block the async runtime's threads.
+//!
+//! The futures do not depend on any runtime: a pool thread stores the result
+//! and wakes the awaiting task. Dropping a future does not cancel its work;
+//! the result is dropped when it is done. A panic in the segmenter is raised
+//! again where the future is awaited, as a direct `segment` call would.
+
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use rayon::prelude::*;
+use rayon::ThreadPool;
+
+use crate::algorithm::Segmenter;
+
+/// A segmenter whose calls run on a worker pool and return futures.
+pub struct AsyncSegmenter<S: ?Sized> {
+    segmenter: Arc<S>,
+    pool: Option<Arc<ThreadPool>>,
+}
+
+impl<S: ?Sized> Clone for AsyncSegmenter<S> {
+    fn clone(&self) -> Self {
+        AsyncSegmenter { segmenter: Arc::clone(&self.segmenter), pool: self.pool.clone() }
+    }
+}
+
+impl<S: Segmenter + ?Sized + 'static> AsyncSegmenter<S> {
+    /// Run on rayon's global pool.
+    pub fn new(segmenter: Arc<S>) -> Self {
+        AsyncSegmenter { segmenter, pool: None }
+    }
+
+    /// Run on `pool` instead of the global one, e.g. to bound the threads
+    /// segmentation may take next to the server's own.
+    pub fn pool(mut self, pool: Arc<ThreadPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    pub fn segmenter(&self) -> &Arc<S> {
+        &self.segmenter
+    }
+
+    /// Segment `text` on the pool.
+    pub fn segment_async(&self, text: impl Into<String>) -> Pending<Vec<String>> {
+        let segmenter = Arc::clone(&self.segmenter);
+        let text = text.into();
+        self.spawn(move || segmenter.segment(&text))
+    }
+
+    /// Segment every line on the pool, in parallel; the results keep the
+    /// order of `lines`.
+    pub fn segment_batch_async(&self, lines: Vec<String>) -> Pending<Vec<Vec<String>>> {
+        let segmenter = Arc::clone(&self.segmenter);
+        self.spawn(move || lines.par_iter().map(|line| segmenter.segment(line)).collect())
+    }
+
+    fn spawn<T: Send + 'static>(&self, work: impl FnOnce() -> T + Send + 'static) -> Pending<T> {
+        let shared = Arc::new(Mutex::new(State { result: None, waker: None }));
+        let done = Arc::clone(&shared);
+        let job = move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(work));
+            let waker = {
+                let mut state = done.lock().unwrap_or_else(|e| e.into_inner());
+                state.result = Some(result);
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        };
+        match &self.pool {
+            Some(pool) => pool.spawn(job),
+            None => rayon::spawn(job),
+        }
+        Pending { shared }
+    }
+}
+
+struct State<T> {
+    result: Option<Result<T, Box<dyn Any + Send>>>,
+    waker: Option<Waker>,
+}
+
+/// Future of a segmentation running on the pool.
+pub struct Pending<T> {
+    shared: Arc<Mutex<State<T>>>,
+}
+
+impl<T> Future for Pending<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        match state.result.take() {
+            Some(result) => {
+                drop(state);
+                Poll::Ready(result.unwrap_or_else(|payload| panic::resume_unwind(payload)))
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
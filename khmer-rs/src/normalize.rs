@@ -0,0 +1,140 @@
+//! Text normalization applied before segmentation (`--normalize`).
+//!
+//! Steps, in order:
+//! 1. Unicode NFC.
+//! 2. Deprecated Khmer characters: U+17A3 -> U+17A2, U+17A4 -> U+17A2 U+17B6,
+//!    U+17D8 -> `។ល។`, and the invisible inherent vowels U+17B4/U+17B5 are removed.
+//! 3. Invisible joiners: ZWNJ/ZWJ next to a Khmer character are removed (they
+//!    only change how Khmer is rendered), while those of emoji sequences and
+//!    other scripts are kept; U+FEFF and U+2060 become ZWSP, runs of ZWSP
+//!    collapse to one and ZWSP next to whitespace is dropped.
+//! 4. Repeated coeng (`្្`) collapses to a single coeng.
+//! 5. Marks following a base character are put in canonical order: robat,
+//!    coeng subscripts, register shifters, dependent vowel, above signs, then
+//!    nikahit/reahmuk/yuukaleapintu (e.g. `ក ា ្ រ` -> `ក ្ រ ា`).
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::constants::{is_coeng, is_consonant, is_dependent_vowel, is_khmer_char, is_sign};
+
+const ZWSP: char = '\u{200b}';
+
+pub fn normalize(text: &str) -> String {
+    let nfc: String = text.nfc().collect();
+    let mapped = map_deprecated(&nfc);
+    let joined = normalize_joiners(&mapped);
+    let cps: Vec<char> = collapse_coeng(&joined);
+    reorder_marks(&cps).into_iter().collect()
+}
+
+fn map_deprecated(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\u{17A3}' => out.push('\u{17A2}'),
+            '\u{17A4}' => out.push_str("\u{17A2}\u{17B6}"),
+            '\u{17D8}' => out.push_str("\u{17D4}\u{179B}\u{17D4}"),
+            '\u{17B4}' | '\u{17B5}' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn normalize_joiners(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{200C}' | '\u{200D}' => {
+                let prev = out.chars().next_back();
+                if !prev.is_some_and(is_khmer_char) && !chars.peek().copied().is_some_and(is_khmer_char) {
+                    out.push(c);
+                }
+            }
+            ZWSP | '\u{FEFF}' | '\u{2060}' => {
+                let prev = out.chars().next_back();
+                if !matches!(prev, None | Some(ZWSP)) && !prev.is_some_and(char::is_whitespace) {
+                    out.push(ZWSP);
+                }
+            }
+            c if c.is_whitespace() => {
+                if out.ends_with(ZWSP) {
+                    out.pop();
+                }
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    if out.ends_with(ZWSP) {
+        out.pop();
+    }
+    out
+}
+
+fn collapse_coeng(text: &str) -> Vec<char> {
+    let mut out: Vec<char> = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if is_coeng(c) && out.last().is_some_and(|&p| is_coeng(p)) {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Canonical position of a mark (or coeng+consonant pair) after a base.
+fn mark_rank(c: char) -> u8 {
+    match c {
+        // Robat
+        '\u{17CC}' => 0,
+        // Coeng (+ consonant)
+        '\u{17D2}' => 1,
+        // Register shifters
+        '\u{17C9}' | '\u{17CA}' => 2,
+        c if is_dependent_vowel(c) => 3,
+        // Nikahit, Reahmuk, Yuukaleapintu
+        '\u{17C6}' | '\u{17C7}' | '\u{17C8}' => 5,
+        // Remaining above signs
+        _ => 4,
+    }
+}
+
+fn reorder_marks(cps: &[char]) -> Vec<char> {
+    let mut out = Vec::with_capacity(cps.len());
+    let mut i = 0;
+
+    while i < cps.len() {
+        let c = cps[i];
+        out.push(c);
+        i += 1;
+
+        // Only marks attached to a base consonant / independent vowel are reordered
+        if !('\u{1780}'..='\u{17B3}').contains(&c) {
+            continue;
+        }
+
+        // Collect mark units: coeng+consonant pairs count as one unit
+        let mut units: Vec<(u8, &[char])> = Vec::new();
+        while i < cps.len() {
+            let m = cps[i];
+            if is_coeng(m) && i + 1 < cps.len() && is_consonant(cps[i + 1]) {
+                units.push((mark_rank(m), &cps[i..i + 2]));
+                i += 2;
+            } else if is_dependent_vowel(m) || is_sign(m) {
+                units.push((mark_rank(m), &cps[i..i + 1]));
+                i += 1;
+            } else {
+                break;
+            }
+        }
+
+        units.sort_by_key(|&(rank, _)| rank);
+        for (_, unit) in units {
+            out.extend_from_slice(unit);
+        }
+    }
+
+    out
+}
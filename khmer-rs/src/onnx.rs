@@ -0,0 +1,150 @@
+//! Neural character-tagging backend (`--features onnx`).
+//!
+//! Runs a BiLSTM/CRF-style tagger exported to ONNX through `ort`. The model
+//! is expected to have:
+//! - input `input_ids`: int64 `[1, n]`, one vocabulary id per character
+//! - first output: float `[1, n, tags]`, one score per tag and character
+//!
+//! Tags are in B, I, E, S order (a B/I model uses just the first two), and a
+//! word starts at every character whose best tag is B or S. Decoding is a
+//! per-character argmax, so a CRF must be exported with its transitions
+//! folded into the emission scores.
+//!
+//! ONNX Runtime is loaded at run time (`ORT_DYLIB_PATH` or the system
+//! library), so the feature builds without downloading binaries.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::sync::Mutex;
+
+use fxhash::FxHashMap;
+use ort::session::Session;
+use ort::value::Tensor;
+
+use crate::algorithm::Segmenter;
+
+const TAG_BEGIN: usize = 0;
+const TAG_SINGLE: usize = 3;
+
+/// Character -> model input id.
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    ids: FxHashMap<char, i64>,
+    unknown: i64,
+}
+
+impl Vocabulary {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Vocabulary::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// One entry per line; the line index is the id. A `<unk>` line sets the
+    /// id of unlisted characters (0 otherwise). Other multi-character lines
+    /// (`<pad>` and the like) only reserve their id.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut vocab = Vocabulary::default();
+        for (id, line) in reader.lines().enumerate() {
+            // Not trimmed: a line holding a single space is the space entry
+            let entry = line?;
+            let mut chars = entry.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => {
+                    vocab.ids.entry(c).or_insert(id as i64);
+                }
+                _ if entry == "<unk>" => vocab.unknown = id as i64,
+                _ => {}
+            }
+        }
+        Ok(vocab)
+    }
+
+    pub fn id(&self, c: char) -> i64 {
+        self.ids.get(&c).copied().unwrap_or(self.unknown)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+pub struct OnnxSegmenter {
+    // `Session::run` takes `&mut self`; rayon workers take turns and the
+    // runtime's own intra-op threads do the parallel work.
+    session: Mutex<Session>,
+    vocab: Vocabulary,
+}
+
+impl OnnxSegmenter {
+    pub fn new(session: Session, vocab: Vocabulary) -> Self {
+        OnnxSegmenter { session: Mutex::new(session), vocab }
+    }
+
+    pub fn open(model: &Path, vocab: &Path) -> io::Result<Self> {
+        let session = Session::builder().and_then(|b| b.commit_from_file(model)).map_err(io::Error::other)?;
+        Ok(OnnxSegmenter::new(session, Vocabulary::open(vocab)?))
+    }
+
+    pub fn try_segment(&self, text: &str) -> io::Result<Vec<String>> {
+        let cps: Vec<char> = text.chars().filter(|&c| c != '\u{200b}').collect();
+        if cps.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids: Vec<i64> = cps.iter().map(|&c| self.vocab.id(c)).collect();
+        let input = Tensor::from_array(([1, cps.len()], ids)).map_err(io::Error::other)?;
+        let mut session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let outputs = session.run(ort::inputs!["input_ids" => input]).map_err(io::Error::other)?;
+        let (shape, scores) = outputs[0].try_extract_tensor::<f32>().map_err(io::Error::other)?;
+
+        let tags = match **shape {
+            [1, n, tags] if n as usize == cps.len() && tags > 0 => tags as usize,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected scores of shape [1, {}, tags], got {:?}", cps.len(), &**shape),
+                ))
+            }
+        };
+        Ok(segments_from_scores(&cps, scores, tags))
+    }
+}
+
+impl Segmenter for OnnxSegmenter {
+    /// Panics if inference fails; use `try_segment` to handle the error.
+    fn segment(&self, text: &str) -> Vec<String> {
+        self.try_segment(text).expect("ONNX inference failed")
+    }
+}
+
+/// Split `cps` where the best tag is B or S. `scores` holds `tags` scores per
+/// character. Whitespace always stands alone, whatever the model says.
+pub fn segments_from_scores(cps: &[char], scores: &[f32], tags: usize) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+
+    for (i, &c) in cps.iter().enumerate() {
+        let row = &scores[i * tags..(i + 1) * tags];
+        let best = row
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map_or(TAG_BEGIN, |(tag, _)| tag);
+        let prev_space = i > 0 && cps[i - 1].is_whitespace();
+        let starts = best == TAG_BEGIN || best == TAG_SINGLE || c.is_whitespace() || prev_space;
+
+        if starts && !current.is_empty() {
+            segments.push(std::mem::take(&mut current));
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        segments.push(current);
+    }
+
+    segments
+}
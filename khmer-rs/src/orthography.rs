@@ -0,0 +1,178 @@
+//! Khmer orthographic syllable structure, and a validator reporting where
+//! text breaks it (`validate_syllables`).
+//!
+//! A syllable is a base, a consonant or independent vowel, followed by at
+//! most `MAX_SUBSCRIPTS` subscripts (coeng + consonant or independent
+//! vowel), then at most one dependent vowel, with signs anywhere after the
+//! base. Marks cannot start a syllable: the segmenter's repair mode consumes
+//! them one by one (`attaches_to_previous`), and the dictionary validator
+//! reports them (`validate::check_word`).
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{is_coeng, is_consonant, is_dependent_vowel, is_independent_vowel, is_sign};
+
+/// Subscripts a base can carry, as in `ស្ត្រី`.
+pub const MAX_SUBSCRIPTS: usize = 2;
+
+/// Ways a character breaks the syllable structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyllableIssueKind {
+    /// Coeng not followed by a consonant or independent vowel
+    DanglingCoeng,
+    /// Coeng with no base before it
+    CoengWithoutBase,
+    /// Subscript beyond `MAX_SUBSCRIPTS`
+    TooManySubscripts,
+    /// Subscript after the dependent vowel, which comes last
+    SubscriptAfterVowel,
+    /// Dependent vowel with no base, or right after a coeng
+    DanglingVowel,
+    /// Second dependent vowel in a syllable
+    DoubleVowel,
+    /// Sign with no base, or right after a coeng
+    DanglingSign,
+    /// Sign already carried by the syllable
+    RepeatedSign,
+}
+
+impl SyllableIssueKind {
+    pub const ALL: [SyllableIssueKind; 8] = [
+        SyllableIssueKind::DanglingCoeng,
+        SyllableIssueKind::CoengWithoutBase,
+        SyllableIssueKind::TooManySubscripts,
+        SyllableIssueKind::SubscriptAfterVowel,
+        SyllableIssueKind::DanglingVowel,
+        SyllableIssueKind::DoubleVowel,
+        SyllableIssueKind::DanglingSign,
+        SyllableIssueKind::RepeatedSign,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SyllableIssueKind::DanglingCoeng => "dangling-coeng",
+            SyllableIssueKind::CoengWithoutBase => "coeng-without-base",
+            SyllableIssueKind::TooManySubscripts => "too-many-subscripts",
+            SyllableIssueKind::SubscriptAfterVowel => "subscript-after-vowel",
+            SyllableIssueKind::DanglingVowel => "dangling-vowel",
+            SyllableIssueKind::DoubleVowel => "double-vowel",
+            SyllableIssueKind::DanglingSign => "dangling-sign",
+            SyllableIssueKind::RepeatedSign => "repeated-sign",
+        }
+    }
+}
+
+impl fmt::Display for SyllableIssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SyllableIssueKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SyllableIssueKind::ALL.into_iter().find(|kind| kind.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = SyllableIssueKind::ALL.iter().map(|kind| kind.name()).collect();
+            format!("unknown syllable issue {:?} (expected one of {})", s, names.join(", "))
+        })
+    }
+}
+
+/// One character that breaks the syllable structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyllableIssue {
+    /// Byte offset of the character in the text
+    pub offset: usize,
+    pub character: char,
+    pub kind: SyllableIssueKind,
+}
+
+impl fmt::Display for SyllableIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (U+{:04X}) at offset {}", self.kind, self.character as u32, self.offset)
+    }
+}
+
+/// Whether `c` can follow a coeng as a subscript.
+fn is_subscript(c: char) -> bool {
+    is_consonant(c) || is_independent_vowel(c)
+}
+
+/// Whether `cps[i]` belongs to the syllable before it, so that no word can
+/// start there: a dependent vowel, or any character right after a coeng.
+#[inline]
+pub fn attaches_to_previous(cps: &[char], i: usize) -> bool {
+    is_dependent_vowel(cps[i]) || (i > 0 && is_coeng(cps[i - 1]))
+}
+
+/// The syllable being read.
+#[derive(Default)]
+struct Syllable {
+    has_base: bool,
+    subscripts: usize,
+    has_vowel: bool,
+    signs: Vec<char>,
+}
+
+/// Every character of `text` that breaks the syllable structure, in order.
+/// A character may have several issues. Characters outside the Khmer
+/// letters and marks end the syllable before them; zero-width joiners and
+/// non-joiners do not.
+pub fn validate_syllables(text: &str) -> Vec<SyllableIssue> {
+    let mut issues = Vec::new();
+    let mut syllable = Syllable::default();
+    let mut chars = text.char_indices().peekable();
+    let mut after_coeng = false;
+    while let Some((offset, c)) = chars.next() {
+        let mut report = |kind| issues.push(SyllableIssue { offset, character: c, kind });
+        let follows_coeng = std::mem::take(&mut after_coeng);
+        if is_coeng(c) {
+            if !syllable.has_base {
+                report(SyllableIssueKind::CoengWithoutBase);
+            }
+            match chars.peek() {
+                Some(&(_, next)) if is_subscript(next) => {
+                    if syllable.has_vowel {
+                        report(SyllableIssueKind::SubscriptAfterVowel);
+                    }
+                    syllable.subscripts += 1;
+                    if syllable.subscripts > MAX_SUBSCRIPTS {
+                        report(SyllableIssueKind::TooManySubscripts);
+                    }
+                    // The subscript is part of this syllable, and a base
+                    // for the marks after it
+                    chars.next();
+                    syllable.has_base = true;
+                }
+                _ => {
+                    report(SyllableIssueKind::DanglingCoeng);
+                    after_coeng = true;
+                }
+            }
+        } else if is_dependent_vowel(c) {
+            if !syllable.has_base || follows_coeng {
+                report(SyllableIssueKind::DanglingVowel);
+            } else if syllable.has_vowel {
+                report(SyllableIssueKind::DoubleVowel);
+            }
+            syllable.has_vowel = true;
+        } else if is_sign(c) {
+            if !syllable.has_base || follows_coeng {
+                report(SyllableIssueKind::DanglingSign);
+            } else if syllable.signs.contains(&c) {
+                report(SyllableIssueKind::RepeatedSign);
+            }
+            syllable.signs.push(c);
+        } else if is_consonant(c) || is_independent_vowel(c) {
+            syllable = Syllable { has_base: true, ..Syllable::default() };
+        } else if c != '\u{200C}' && c != '\u{200D}' {
+            syllable = Syllable::default();
+        }
+    }
+    issues
+}
@@ -0,0 +1,324 @@
+//! Encodings of the output records (`--format`): JSONL, and with the
+//! `msgpack` and `cbor` features MessagePack and CBOR, which are smaller and
+//! cheaper to parse for pipelines that read the output back, and with
+//! `protobuf` length-delimited protobuf messages. With the `sqlite` feature
+//! records can also go to a database (see `sqlite`).
+//!
+//! Every format writes the same `Record` (see `schema`). JSONL records end
+//! with a newline; MessagePack and CBOR records are maps written one after
+//! the other with no separator, as both formats delimit their values;
+//! protobuf messages do not, so each is preceded by its length.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::schema::SCHEMA_VERSION;
+
+/// One record of the output: an input line and its segments.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Record<'a> {
+    pub schema_version: u32,
+    pub id: usize,
+    pub input: &'a str,
+    pub segments: Vec<&'a str>,
+    /// Indexes into `segments` of the tokens a `TokenFilter` matched
+    /// (`--filter-mode tag`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filtered: Option<Vec<usize>>,
+}
+
+impl<'a> Record<'a> {
+    pub fn new<S: AsRef<str>>(id: usize, input: &'a str, segments: &'a [S]) -> Self {
+        Record {
+            schema_version: SCHEMA_VERSION,
+            id,
+            input,
+            segments: segments.iter().map(AsRef::as_ref).collect(),
+            filtered: None,
+        }
+    }
+
+    pub fn filtered(mut self, indexes: Vec<usize>) -> Self {
+        self.filtered = Some(indexes);
+        self
+    }
+}
+
+/// Encodes records for one output format.
+pub trait OutputFormatter: Send + Sync {
+    /// Append the encoding of `record` to `out`, its terminator included.
+    fn encode(&self, record: &Record<'_>, out: &mut Vec<u8>) -> io::Result<()>;
+}
+
+/// One JSON object per line.
+///
+/// 1BRC: built by hand rather than through serde_json, which is measurably
+/// slower on the hot path of large runs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Jsonl;
+
+/// MessagePack maps, field names included (`msgpack` feature).
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePack;
+
+/// CBOR maps (`cbor` feature).
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cbor;
+
+/// `SegmentationRecord` messages of `proto/segmentation.proto`, each
+/// preceded by its length as a varint (`protobuf` feature). Encoded by hand:
+/// the message is small enough not to need a protobuf library.
+#[cfg(feature = "protobuf")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Protobuf;
+
+impl OutputFormatter for Jsonl {
+    fn encode(&self, record: &Record<'_>, out: &mut Vec<u8>) -> io::Result<()> {
+        // {"schema_version":V,"id":N,"input":"...","segments":["...", ...]}
+        out.extend_from_slice(b"{\"schema_version\":");
+        append_int(out, record.schema_version as usize);
+        out.extend_from_slice(b",\"id\":");
+        append_int(out, record.id);
+        out.extend_from_slice(b",\"input\":\"");
+        escape_json_to(out, record.input);
+        out.extend_from_slice(b"\",\"segments\":[");
+        for (i, seg) in record.segments.iter().enumerate() {
+            if i > 0 {
+                out.push(b',');
+            }
+            out.push(b'"');
+            escape_json_to(out, seg);
+            out.push(b'"');
+        }
+        out.push(b']');
+
+        if let Some(filtered) = &record.filtered {
+            out.extend_from_slice(b",\"filtered\":[");
+            for (i, &index) in filtered.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                append_int(out, index);
+            }
+            out.push(b']');
+        }
+
+        out.extend_from_slice(b"}\n");
+        Ok(())
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl OutputFormatter for MessagePack {
+    fn encode(&self, record: &Record<'_>, out: &mut Vec<u8>) -> io::Result<()> {
+        rmp_serde::encode::write_named(out, record).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl OutputFormatter for Cbor {
+    fn encode(&self, record: &Record<'_>, out: &mut Vec<u8>) -> io::Result<()> {
+        ciborium::into_writer(record, out).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+}
+
+#[cfg(feature = "protobuf")]
+impl Protobuf {
+    // Field keys: field number << 3 | wire type (0 varint, 2 length-delimited)
+    const SCHEMA_VERSION: u8 = 1 << 3;
+    const ID: u8 = 2 << 3;
+    const INPUT: u8 = 3 << 3 | 2;
+    const SEGMENTS: u8 = 4 << 3 | 2;
+    const FILTERED: u8 = 5 << 3 | 2;
+
+    /// Length of the message body, for its prefix. Fields at their default
+    /// (zero, empty) are left out, as protobuf encoders do.
+    fn message_len(record: &Record<'_>) -> usize {
+        let bytes = |len: usize| if len == 0 { 0 } else { 1 + varint_len(len as u64) + len };
+        let scalar = |value: u64| if value == 0 { 0 } else { 1 + varint_len(value) };
+        scalar(record.schema_version as u64)
+            + scalar(record.id as u64)
+            + bytes(record.input.len())
+            + record.segments.iter().map(|seg| 1 + varint_len(seg.len() as u64) + seg.len()).sum::<usize>()
+            + bytes(Protobuf::packed_len(record))
+    }
+
+    fn packed_len(record: &Record<'_>) -> usize {
+        record.filtered.iter().flatten().map(|&index| varint_len(index as u64)).sum()
+    }
+}
+
+#[cfg(feature = "protobuf")]
+impl OutputFormatter for Protobuf {
+    fn encode(&self, record: &Record<'_>, out: &mut Vec<u8>) -> io::Result<()> {
+        put_varint(out, Protobuf::message_len(record) as u64);
+        if record.schema_version != 0 {
+            out.push(Protobuf::SCHEMA_VERSION);
+            put_varint(out, record.schema_version as u64);
+        }
+        if record.id != 0 {
+            out.push(Protobuf::ID);
+            put_varint(out, record.id as u64);
+        }
+        if !record.input.is_empty() {
+            out.push(Protobuf::INPUT);
+            put_varint(out, record.input.len() as u64);
+            out.extend_from_slice(record.input.as_bytes());
+        }
+        for seg in &record.segments {
+            out.push(Protobuf::SEGMENTS);
+            put_varint(out, seg.len() as u64);
+            out.extend_from_slice(seg.as_bytes());
+        }
+        let packed = Protobuf::packed_len(record);
+        if packed > 0 {
+            out.push(Protobuf::FILTERED);
+            put_varint(out, packed as u64);
+            for &index in record.filtered.iter().flatten() {
+                put_varint(out, index as u64);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bytes of `value` as a protobuf varint.
+#[cfg(feature = "protobuf")]
+fn varint_len(value: u64) -> usize {
+    (64 - (value | 1).leading_zeros() as usize).div_ceil(7)
+}
+
+/// Append `value` as a protobuf varint: 7 bits per byte, low bits first,
+/// the high bit set on every byte but the last.
+#[cfg(feature = "protobuf")]
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Pre-computed hex digits table (avoids snprintf overhead)
+const HEX_DIGITS: &[u8] = b"0123456789abcdef";
+
+/// Fast JSON string escaper - appends directly to buffer. Bytes of
+/// multi-byte characters never need escaping, so this works on bytes.
+#[inline]
+fn escape_json_to(out: &mut Vec<u8>, s: &str) {
+    for &b in s.as_bytes() {
+        match b {
+            b'"' => out.extend_from_slice(b"\\\""),
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            b if b < 0x20 => {
+                out.extend_from_slice(b"\\u00");
+                out.push(HEX_DIGITS[(b >> 4) as usize]);
+                out.push(HEX_DIGITS[(b & 0xF) as usize]);
+            }
+            b => out.push(b),
+        }
+    }
+}
+
+/// Fast integer to string - appends directly to buffer
+#[inline]
+fn append_int(out: &mut Vec<u8>, val: usize) {
+    let mut buf = [0u8; 20];
+    let mut i = 20;
+    let mut v = val;
+    loop {
+        i -= 1;
+        buf[i] = b'0' + (v % 10) as u8;
+        v /= 10;
+        if v == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(&buf[i..]);
+}
+
+/// Output formats of the main command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OutputFormat {
+    #[default]
+    Jsonl,
+    #[cfg(feature = "msgpack")]
+    Msgpack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+    #[cfg(feature = "protobuf")]
+    Protobuf,
+    /// A database rather than a stream of records (see `sqlite`)
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl OutputFormat {
+    /// The formats this build supports.
+    pub const ALL: &'static [OutputFormat] = &[
+        OutputFormat::Jsonl,
+        #[cfg(feature = "msgpack")]
+        OutputFormat::Msgpack,
+        #[cfg(feature = "cbor")]
+        OutputFormat::Cbor,
+        #[cfg(feature = "protobuf")]
+        OutputFormat::Protobuf,
+        #[cfg(feature = "sqlite")]
+        OutputFormat::Sqlite,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Jsonl => "jsonl",
+            #[cfg(feature = "msgpack")]
+            OutputFormat::Msgpack => "msgpack",
+            #[cfg(feature = "cbor")]
+            OutputFormat::Cbor => "cbor",
+            #[cfg(feature = "protobuf")]
+            OutputFormat::Protobuf => "protobuf",
+            #[cfg(feature = "sqlite")]
+            OutputFormat::Sqlite => "sqlite",
+        }
+    }
+
+    /// The encoder of this format's records; `None` for `Sqlite`, which
+    /// `sqlite::SqliteWriter` writes instead.
+    pub fn formatter(self) -> Option<Box<dyn OutputFormatter>> {
+        match self {
+            OutputFormat::Jsonl => Some(Box::new(Jsonl)),
+            #[cfg(feature = "msgpack")]
+            OutputFormat::Msgpack => Some(Box::new(MessagePack)),
+            #[cfg(feature = "cbor")]
+            OutputFormat::Cbor => Some(Box::new(Cbor)),
+            #[cfg(feature = "protobuf")]
+            OutputFormat::Protobuf => Some(Box::new(Protobuf)),
+            #[cfg(feature = "sqlite")]
+            OutputFormat::Sqlite => None,
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OutputFormat::ALL.iter().copied().find(|format| format.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = OutputFormat::ALL.iter().map(|format| format.name()).collect();
+            format!("unknown output format {:?} (expected one of {})", s, names.join(", "))
+        })
+    }
+}
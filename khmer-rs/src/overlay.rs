@@ -0,0 +1,119 @@
+//! Overlay lexicons applied on top of a base dictionary, e.g. a domain word
+//! list that adds terms or makes some words cheaper.
+//!
+//! An overlay file has one entry per line: `word` or `word<TAB>cost`. Entries
+//! without their own cost use the overlay's cost if one is set; otherwise new
+//! words get the dictionary's default cost and existing words keep theirs.
+//! Overlays are applied in the order they were added, so a later overlay wins
+//! over an earlier one and over the base dictionary.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::constants::is_valid_single_word;
+use crate::dictionary::{normalize_latin, Cost, Dictionary};
+use crate::variants::VariantGenerator;
+
+#[derive(Debug, Clone, Default)]
+pub struct Overlay {
+    entries: Vec<(String, Option<Cost>)>,
+    cost: Option<Cost>,
+}
+
+impl Overlay {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Overlay::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut entries = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let (word, cost) = match line.split_once('\t') {
+                Some((word, cost)) => {
+                    let cost = cost.trim().parse::<Cost>().map_err(|e| {
+                        io::Error::new(io::ErrorKind::InvalidData, format!("line {}: invalid cost: {}", i + 1, e))
+                    })?;
+                    (word.trim(), Some(cost))
+                }
+                None => (line.trim(), None),
+            };
+            if !word.is_empty() {
+                entries.push((word.to_string(), cost));
+            }
+        }
+        Ok(Overlay { entries, cost: None })
+    }
+
+    /// Cost for entries that do not carry their own.
+    pub fn cost(mut self, cost: Cost) -> Self {
+        self.cost = Some(cost);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Dictionary {
+    /// Add or re-cost the overlay's words and their spelling variants.
+    pub(crate) fn apply_overlay(&mut self, overlay: &Overlay, generator: &VariantGenerator) {
+        for (word, entry_cost) in &overlay.entries {
+            let mut chars = word.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                if !is_valid_single_word(c) {
+                    continue;
+                }
+            }
+
+            let cost = match entry_cost.or(overlay.cost) {
+                Some(cost) => cost,
+                None if self.contains(word) => continue,
+                None => self.default_cost,
+            };
+
+            // Listed in an overlay, so no longer only a generated variant
+            self.variant_of.remove(word);
+            self.set_word_cost(word, cost);
+
+            let mut variants: Vec<String> = generator.generate(word).into_iter().collect();
+            variants.sort();
+            for v in variants {
+                if !self.contains(&v) {
+                    self.variant_of.insert(v.clone(), word.clone());
+                }
+                if self.variant_of.contains_key(&v) {
+                    self.set_word_cost(&v, cost);
+                }
+            }
+        }
+    }
+
+    /// Add the list's words to the Latin word table. Unlike `apply_overlay`,
+    /// every entry gets a cost: its own, the list's, or the default cost.
+    pub(crate) fn apply_latin_words(&mut self, words: &Overlay) {
+        for (word, entry_cost) in &words.entries {
+            let cost = entry_cost.or(words.cost).unwrap_or(self.default_cost);
+            self.latin_words.insert(normalize_latin(word), cost);
+        }
+    }
+
+    fn set_word_cost(&mut self, word: &str, cost: Cost) {
+        match self.words.get(word) {
+            Some(&idx) => self.costs[idx] = cost,
+            None => {
+                self.words.insert(word.to_string(), self.costs.len());
+                self.costs.push(cost);
+                self.max_word_length = self.max_word_length.max(word.chars().count());
+            }
+        }
+
+        self.trie.insert(word, cost);
+    }
+}
@@ -0,0 +1,756 @@
+//! Recognizers for tokens the DP keeps whole although they are not
+//! dictionary words: URLs, email addresses, hashtags, @mentions, emoji,
+//! Latin-script words, Roman numerals, dates, times of day, phone numbers
+//! and currency amounts.
+//!
+//! Each `*_length_cps` function takes the text as codepoints and a start
+//! position and returns the length of the match in codepoints, 0 if there is
+//! none. Matches only start at a word boundary, so a recognizer never picks
+//! up the tail of a longer run of Latin text.
+
+use std::ops::Range;
+
+use crate::constants::{
+    is_emoji_modifier, is_khmer_char, is_latin_letter, is_pictographic, is_regional_indicator, is_separator,
+};
+
+/// Top-level domains accepted for bare domains (`example.com`). URLs with a
+/// scheme or `www.` accept any domain.
+const TLDS: &[&str] = &[
+    "com", "org", "net", "edu", "gov", "int", "mil", "info", "biz", "io", "co", "app", "dev", "me", "tv", "asia",
+    "kh", "au", "ca", "cn", "de", "fr", "in", "jp", "kr", "la", "my", "sg", "th", "uk", "us", "vn",
+];
+
+fn starts_with_ignore_case(cps: &[char], start: usize, prefix: &str) -> bool {
+    let mut i = start;
+    for p in prefix.chars() {
+        match cps.get(i) {
+            Some(c) if c.eq_ignore_ascii_case(&p) => i += 1,
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn is_label_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-'
+}
+
+/// Characters allowed in the path, query and fragment (RFC 3986 unreserved,
+/// reserved and `%`).
+fn is_url_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+,;=%".contains(c)
+}
+
+/// Whether `start` follows a character that would make it the middle of a
+/// word, a domain or an address.
+fn inside_word(cps: &[char], start: usize) -> bool {
+    start > 0 && {
+        let prev = cps[start - 1];
+        prev.is_ascii_alphanumeric() || matches!(prev, '-' | '.' | '_' | '@')
+    }
+}
+
+/// Dot-separated labels starting at `start`: their total length, their
+/// number and the range of the last one. A dot is only taken when a label
+/// follows it.
+fn domain_length(cps: &[char], start: usize) -> (usize, usize, Range<usize>) {
+    let (mut i, mut labels, mut last) = (start, 0, start..start);
+    loop {
+        let label_start = i;
+        while i < cps.len() && is_label_char(cps[i]) {
+            i += 1;
+        }
+        if i == label_start {
+            break;
+        }
+        labels += 1;
+        last = label_start..i;
+        if i + 1 < cps.len() && cps[i] == '.' && is_label_char(cps[i + 1]) {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    (last.end - start, labels, last)
+}
+
+/// `http(s)://` URLs, `www.` addresses and bare domains with a known
+/// top-level domain, with optional port, path, query and fragment. Trailing
+/// sentence punctuation and unbalanced closing brackets are left out.
+pub fn url_length_cps(cps: &[char], start: usize) -> usize {
+    if start >= cps.len() || inside_word(cps, start) {
+        return 0;
+    }
+
+    let scheme = ["https://", "http://"]
+        .iter()
+        .find(|scheme| starts_with_ignore_case(cps, start, scheme))
+        .map_or(0, |scheme| scheme.len());
+    let host = start + scheme;
+    let (host_len, labels, tld) = domain_length(cps, host);
+    let accepted = if scheme > 0 {
+        labels >= 1
+    } else if starts_with_ignore_case(cps, host, "www.") {
+        labels >= 3
+    } else {
+        let tld: String = cps[tld].iter().map(|c| c.to_ascii_lowercase()).collect();
+        labels >= 2 && TLDS.contains(&tld.as_str())
+    };
+    if !accepted {
+        return 0;
+    }
+
+    let host_end = host + host_len;
+    let mut end = host_end;
+    // Port
+    if end + 1 < cps.len() && cps[end] == ':' && cps[end + 1].is_ascii_digit() {
+        end += 1;
+        while end < cps.len() && cps[end].is_ascii_digit() {
+            end += 1;
+        }
+    }
+    // Path, query, fragment
+    if end < cps.len() && matches!(cps[end], '/' | '?' | '#') {
+        while end < cps.len() && is_url_char(cps[end]) {
+            end += 1;
+        }
+    }
+    // Trailing punctuation belongs to the sentence
+    while end > host_end {
+        let last = cps[end - 1];
+        let count = |c: char| cps[start..end].iter().filter(|&&x| x == c).count();
+        let unbalanced = |open: char, close: char| last == close && count(open) < count(close);
+        if matches!(last, '.' | ',' | ';' | ':' | '!' | '?' | '\'' | '"') || unbalanced('(', ')') || unbalanced('[', ']') {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+
+    end - start
+}
+
+/// `local@domain.tld` addresses. The local part takes letters, digits and
+/// `._%+-`; the domain needs at least two labels and an alphabetic
+/// top-level domain.
+pub fn email_length_cps(cps: &[char], start: usize) -> usize {
+    if start >= cps.len() || inside_word(cps, start) {
+        return 0;
+    }
+
+    let mut at = start;
+    while at < cps.len() && (cps[at].is_ascii_alphanumeric() || "._%+-".contains(cps[at])) {
+        at += 1;
+    }
+    if at == start || at >= cps.len() || cps[at] != '@' || cps[at - 1] == '.' {
+        return 0;
+    }
+
+    let (domain_len, labels, tld) = domain_length(cps, at + 1);
+    if labels < 2 || tld.len() < 2 || !cps[tld].iter().all(|c| c.is_ascii_alphabetic()) {
+        return 0;
+    }
+    at + 1 + domain_len - start
+}
+
+/// Whether all of `token` is one email address.
+pub fn is_email(token: &str) -> bool {
+    if !token.contains('@') {
+        return false;
+    }
+    let cps: Vec<char> = token.chars().collect();
+    email_length_cps(&cps, 0) == cps.len()
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || (is_khmer_char(c) && !is_separator(c))
+}
+
+/// `#tag` with Latin letters, digits, `_` and Khmer text (`#ខ្មែរ`).
+pub fn hashtag_length_cps(cps: &[char], start: usize) -> usize {
+    if start >= cps.len() || cps[start] != '#' || (start > 0 && is_tag_char(cps[start - 1])) {
+        return 0;
+    }
+    let len = cps[start + 1..].iter().take_while(|&&c| is_tag_char(c)).count();
+    if len == 0 { 0 } else { 1 + len }
+}
+
+/// `@handle` with Latin letters, digits, `_` and inner dots (`@khmer.news`).
+pub fn mention_length_cps(cps: &[char], start: usize) -> usize {
+    if start >= cps.len() || cps[start] != '@' || inside_word(cps, start) {
+        return 0;
+    }
+    let mut len = cps[start + 1..]
+        .iter()
+        .take_while(|&&c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        .count();
+    while len > 0 && cps[start + len] == '.' {
+        len -= 1;
+    }
+    if len == 0 { 0 } else { 1 + len }
+}
+
+const ZWJ: char = '\u{200D}';
+const VARIATION_SELECTOR: char = '\u{FE0F}';
+const TEXT_SELECTOR: char = '\u{FE0E}';
+const KEYCAP: char = '\u{20E3}';
+
+/// One emoji without ZWJ continuation: a flag (two regional indicators), a
+/// keycap (`1️⃣`) or a pictograph with its presentation selector, skin tone
+/// and tag sequence (subdivision flags).
+fn emoji_element_length(cps: &[char], start: usize) -> usize {
+    let at = |i: usize| cps.get(i).copied();
+    let c = cps[start];
+
+    if is_regional_indicator(c) {
+        return if at(start + 1).is_some_and(is_regional_indicator) { 2 } else { 1 };
+    }
+    if c.is_ascii_digit() || c == '#' || c == '*' {
+        return match (at(start + 1), at(start + 2)) {
+            (Some(KEYCAP), _) => 2,
+            (Some(VARIATION_SELECTOR), Some(KEYCAP)) => 3,
+            _ => 0,
+        };
+    }
+    if !is_pictographic(c) {
+        return 0;
+    }
+
+    let mut i = start + 1;
+    if matches!(at(i), Some(VARIATION_SELECTOR | TEXT_SELECTOR)) {
+        i += 1;
+    }
+    if at(i).is_some_and(is_emoji_modifier) {
+        i += 1;
+    }
+    // Tag sequence: tag characters closed by CANCEL TAG
+    let tags = cps[i..].iter().take_while(|&&c| ('\u{E0020}'..='\u{E007E}').contains(&c)).count();
+    if tags > 0 && at(i + tags) == Some('\u{E007F}') {
+        i += tags + 1;
+    }
+    i - start
+}
+
+/// An emoji sequence: emoji joined by ZWJ (`👨‍👩‍👧`), each with its
+/// modifiers, so families, professions and flags stay one token.
+pub fn emoji_length_cps(cps: &[char], start: usize) -> usize {
+    if start >= cps.len() {
+        return 0;
+    }
+    let mut end = start + emoji_element_length(cps, start);
+    if end == start {
+        return 0;
+    }
+    while end + 1 < cps.len() && cps[end] == ZWJ {
+        match emoji_element_length(cps, end + 1) {
+            0 => break,
+            len => end += 1 + len,
+        }
+    }
+    end - start
+}
+
+/// Whether all of `token` is one emoji sequence.
+pub fn is_emoji(token: &str) -> bool {
+    if token.is_ascii() || token.starts_with(is_khmer_char) {
+        return false;
+    }
+    let cps: Vec<char> = token.chars().collect();
+    emoji_length_cps(&cps, 0) == cps.len()
+}
+
+/// A Latin-script word: letters with internal apostrophes and hyphens
+/// (`don't`, `e-mail`, `Phnom-Penh`).
+pub fn latin_word_length_cps(cps: &[char], start: usize) -> usize {
+    if start >= cps.len() || !is_latin_letter(cps[start]) || (start > 0 && is_latin_letter(cps[start - 1])) {
+        return 0;
+    }
+    let mut end = start + 1;
+    while end < cps.len() {
+        if is_latin_letter(cps[end]) {
+            end += 1;
+        } else if matches!(cps[end], '\'' | '’' | '-') && end + 1 < cps.len() && is_latin_letter(cps[end + 1]) {
+            end += 2;
+        } else {
+            break;
+        }
+    }
+    end - start
+}
+
+/// Whether all of `token` is one Latin-script word.
+pub fn is_latin_word(token: &str) -> bool {
+    if !token.starts_with(is_latin_letter) {
+        return false;
+    }
+    let cps: Vec<char> = token.chars().collect();
+    latin_word_length_cps(&cps, 0) == cps.len()
+}
+
+const ROMAN_DIGITS: [(&str, u32); 13] = [
+    ("M", 1000),
+    ("CM", 900),
+    ("D", 500),
+    ("CD", 400),
+    ("C", 100),
+    ("XC", 90),
+    ("L", 50),
+    ("XL", 40),
+    ("X", 10),
+    ("IX", 9),
+    ("V", 5),
+    ("IV", 4),
+    ("I", 1),
+];
+
+fn roman_digit(c: char) -> Option<u32> {
+    match c {
+        'I' => Some(1),
+        'V' => Some(5),
+        'X' => Some(10),
+        'L' => Some(50),
+        'C' => Some(100),
+        'D' => Some(500),
+        'M' => Some(1000),
+        _ => None,
+    }
+}
+
+/// Value of an upper-case Roman numeral in standard form (`XIV`, not
+/// `XIIII` or `IXX`), up to 3999.
+fn roman_value(cps: &[char]) -> Option<u32> {
+    let mut value = 0;
+    for (i, &c) in cps.iter().enumerate() {
+        let v = roman_digit(c)?;
+        match cps.get(i + 1).copied().and_then(roman_digit) {
+            Some(next) if next > v => value -= v as i64,
+            _ => value += v as i64,
+        }
+    }
+    let value = u32::try_from(value).ok().filter(|v| (1..4000).contains(v))?;
+
+    // Only the standard spelling of that value
+    let mut rest = value;
+    let mut standard = String::new();
+    for (symbol, symbol_value) in ROMAN_DIGITS {
+        while rest >= symbol_value {
+            standard.push_str(symbol);
+            rest -= symbol_value;
+        }
+    }
+    standard.chars().eq(cps.iter().copied()).then_some(value)
+}
+
+/// An upper-case Roman numeral (`III`, `XXXV`) standing alone: not next to
+/// another Latin letter or a digit. Single letters are not taken: `I` is far
+/// more often the pronoun than one.
+pub fn roman_numeral_length_cps(cps: &[char], start: usize) -> usize {
+    let is_word_char = |c: char| is_latin_letter(c) || c.is_ascii_digit();
+    if start >= cps.len() || (start > 0 && is_word_char(cps[start - 1])) {
+        return 0;
+    }
+    let len = cps[start..].iter().take_while(|&&c| roman_digit(c).is_some()).count();
+    let end = start + len;
+    if len < 2 || cps.get(end).is_some_and(|&c| is_word_char(c)) || roman_value(&cps[start..end]).is_none() {
+        return 0;
+    }
+    len
+}
+
+/// Words after which a Roman numeral is a number: `Chapter XIV`, `Vol. II`.
+const NUMERAL_MARKERS: &[&str] = &[
+    "act", "annex", "appendix", "article", "book", "chapter", "no", "part", "phase", "scene", "section",
+    "title", "vol", "volume",
+];
+
+/// Whether a Roman numeral after `before`, the text before it read backwards
+/// (nearest character first), reads as a number rather than a word such as
+/// `MIX`: it follows Khmer text (`ជំពូក XIV`, `សម័យកាល III`) or one of
+/// `NUMERAL_MARKERS`, spaces and a full stop in between allowed.
+pub fn is_numeral_context<I: Iterator<Item = char>>(before: I) -> bool {
+    let mut before = before.skip_while(|&c| c.is_whitespace() || c == '\u{200b}').peekable();
+    // An abbreviated marker, `Vol. II`
+    before.next_if_eq(&'.');
+    match before.peek() {
+        Some(&c) if is_khmer_char(c) => true,
+        Some(&c) if is_latin_letter(c) => {
+            let mut word: Vec<char> = before.take_while(|&c| is_latin_letter(c)).collect();
+            word.reverse();
+            let word = String::from_iter(word).to_ascii_lowercase();
+            NUMERAL_MARKERS.contains(&word.as_str())
+        }
+        _ => false,
+    }
+}
+
+/// The value of `token` if all of it is one Roman numeral.
+pub fn parse_roman_numeral(token: &str) -> Option<u32> {
+    if !token.is_ascii() {
+        return None;
+    }
+    let cps: Vec<char> = token.chars().collect();
+    (roman_numeral_length_cps(&cps, 0) == cps.len()).then(|| roman_value(&cps)).flatten()
+}
+
+/// Whether all of `token` is one hashtag.
+pub fn is_hashtag(token: &str) -> bool {
+    if !token.starts_with('#') {
+        return false;
+    }
+    let cps: Vec<char> = token.chars().collect();
+    hashtag_length_cps(&cps, 0) == cps.len()
+}
+
+/// Whether all of `token` is one @mention.
+pub fn is_mention(token: &str) -> bool {
+    if !token.starts_with('@') {
+        return false;
+    }
+    let cps: Vec<char> = token.chars().collect();
+    mention_length_cps(&cps, 0) == cps.len()
+}
+
+/// Whether all of `token` is one URL.
+pub fn is_url(token: &str) -> bool {
+    // Cheap rejection first: every URL has a dot or a scheme
+    if !token.contains('.') && !token.contains("://") {
+        return false;
+    }
+    let cps: Vec<char> = token.chars().collect();
+    !cps.is_empty() && url_length_cps(&cps, 0) == cps.len()
+}
+
+/// Calendar fields of a date token. Years are as written: `៦៦` in
+/// `១-៦-៦៦` is 66.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Date {
+    pub day: Option<u32>,
+    pub month: Option<u32>,
+    pub year: Option<u32>,
+}
+
+/// Gregorian month names, January first.
+const KHMER_MONTHS: [&str; 12] =
+    ["មករា", "កុម្ភៈ", "មីនា", "មេសា", "ឧសភា", "មិថុនា", "កក្កដា", "សីហា", "កញ្ញា", "តុលា", "វិច្ឆិកា", "ធ្នូ"];
+
+fn digit_value(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => Some(c as u32 - '0' as u32),
+        '\u{17E0}'..='\u{17E9}' => Some(c as u32 - 0x17E0),
+        _ => None,
+    }
+}
+
+/// Length and value of the run of ASCII or Khmer digits at `start`.
+fn digit_run(cps: &[char], start: usize) -> (usize, u32) {
+    let mut value: u32 = 0;
+    let mut len = 0;
+    while let Some(d) = cps.get(start + len).copied().and_then(digit_value) {
+        value = value.saturating_mul(10).saturating_add(d);
+        len += 1;
+    }
+    (len, value)
+}
+
+/// Days in `month` of `year`. A two-digit year is a leap year when it is
+/// divisible by 4, as all of 1901-2099 are.
+fn days_in_month(month: u32, year: u32, year_len: usize) -> u32 {
+    let is_leap = year.is_multiple_of(4) && (year_len < 4 || !year.is_multiple_of(100) || year.is_multiple_of(400));
+    match month {
+        2 if is_leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// `d/m/y` (day first unless only the other order is valid), `y-m-d`, with
+/// `/`, `-` or `.` used for both separators and ASCII or Khmer digits. The
+/// day must exist in its month, and `.` dates need a four-digit year.
+fn numeric_date(cps: &[char], start: usize) -> Option<(usize, Date)> {
+    if start > 0 && digit_value(cps[start - 1]).is_some() {
+        return None;
+    }
+    let (a_len, a) = digit_run(cps, start);
+    let sep = *cps.get(start + a_len).filter(|c| matches!(c, '/' | '-' | '.'))?;
+    let b_start = start + a_len + 1;
+    let (b_len, b) = digit_run(cps, b_start);
+    if cps.get(b_start + b_len) != Some(&sep) {
+        return None;
+    }
+    let c_start = b_start + b_len + 1;
+    let (c_len, c) = digit_run(cps, c_start);
+    let end = c_start + c_len;
+    // `1.2.3.4` is not a date
+    if cps.get(end) == Some(&sep) && cps.get(end + 1).copied().and_then(digit_value).is_some() {
+        return None;
+    }
+
+    let is_valid = |day: u32, month: u32, year: u32, year_len: usize| {
+        (1..=12).contains(&month) && (1..=days_in_month(month, year, year_len)).contains(&day)
+    };
+    let (day, month, year, year_len) = match (a_len, b_len, c_len) {
+        (4, 1..=2, 1..=2) => (c, b, a, a_len),
+        (1..=2, 1..=2, 2 | 4) if is_valid(a, b, c, c_len) => (a, b, c, c_len),
+        (1..=2, 1..=2, 2 | 4) => (b, a, c, c_len),
+        _ => return None,
+    };
+    // `1.2.24` and `3.14.15` are version numbers more often than dates
+    if !is_valid(day, month, year, year_len) || (sep == '.' && year_len != 4) {
+        return None;
+    }
+    Some((end - start, Date { day: Some(day), month: Some(month), year: Some(year) }))
+}
+
+/// Time of day of a time token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Time {
+    pub hour: u32,
+    pub minute: u32,
+    pub second: Option<u32>,
+}
+
+/// `H:MM` or `HH:MM:SS` with ASCII or Khmer digits, hours up to 23, and
+/// `24:00` (`24:00:00`) for the end of the day.
+fn time(cps: &[char], start: usize) -> Option<(usize, Time)> {
+    if start > 0 && digit_value(cps[start - 1]).is_some() {
+        return None;
+    }
+    let (h_len, hour) = digit_run(cps, start);
+    if !(1..=2).contains(&h_len) || hour > 24 {
+        return None;
+    }
+
+    // Two-digit fields after colons, at most two of them
+    let mut fields = Vec::with_capacity(2);
+    let mut end = start + h_len;
+    while cps.get(end) == Some(&':') {
+        let (len, value) = digit_run(cps, end + 1);
+        if len == 0 {
+            break;
+        }
+        if len != 2 || value > 59 || fields.len() == 2 {
+            return None;
+        }
+        fields.push(value);
+        end += 1 + len;
+    }
+    let minute = *fields.first()?;
+    if hour == 24 && fields.iter().any(|&v| v != 0) {
+        return None;
+    }
+    Some((end - start, Time { hour, minute, second: fields.get(1).copied() }))
+}
+
+/// Times of day (`8:30`, `៨:៣០`, `23:59:59`).
+pub fn time_length_cps(cps: &[char], start: usize) -> usize {
+    time(cps, start).map_or(0, |(len, _)| len)
+}
+
+/// The fields of `token` if all of it is one time of day.
+pub fn parse_time(token: &str) -> Option<Time> {
+    if !token.starts_with(|c| digit_value(c).is_some()) {
+        return None;
+    }
+    let cps: Vec<char> = token.chars().collect();
+    time(&cps, 0).filter(|&(len, _)| len == cps.len()).map(|(_, time)| time)
+}
+
+/// Whether all of `token` is one time of day.
+pub fn is_time(token: &str) -> bool {
+    parse_time(token).is_some()
+}
+
+/// Length of `pattern` matched at `start`, 0 if it does not match.
+fn phone_pattern_length(cps: &[char], start: usize, pattern: &str) -> usize {
+    let mut i = start;
+    for p in pattern.chars() {
+        let Some(&c) = cps.get(i) else { return 0 };
+        let matches = match (p, digit_value(c)) {
+            ('#', Some(_)) => true,
+            (_, Some(d)) => digit_value(p) == Some(d),
+            _ => p == c,
+        };
+        if !matches {
+            return 0;
+        }
+        i += 1;
+    }
+    // A longer run of digits is not this number
+    if cps.get(i).copied().and_then(digit_value).is_some() {
+        return 0;
+    }
+    i - start
+}
+
+/// Longest phone number matching one of `patterns` (see
+/// `SegmenterConfig::phone_patterns`).
+pub fn phone_length_cps<S: AsRef<str>>(cps: &[char], start: usize, patterns: &[S]) -> usize {
+    if start >= cps.len() || (start > 0 && (digit_value(cps[start - 1]).is_some() || cps[start - 1] == '+')) {
+        return 0;
+    }
+    patterns.iter().map(|p| phone_pattern_length(cps, start, p.as_ref())).max().unwrap_or(0)
+}
+
+/// Whether all of `token` is one phone number matching `patterns`.
+pub fn is_phone<S: AsRef<str>>(token: &str, patterns: &[S]) -> bool {
+    if !token.starts_with(|c| c == '+' || digit_value(c).is_some()) {
+        return false;
+    }
+    let cps: Vec<char> = token.chars().collect();
+    phone_length_cps(&cps, 0, patterns) == cps.len()
+}
+
+/// Whether `currency` is a code (`USD`, `រៀល`) rather than a symbol; codes
+/// may be separated from the amount by a space.
+pub fn is_currency_code(currency: &str) -> bool {
+    currency.chars().all(char::is_alphabetic)
+}
+
+/// Length of the longest of `currencies` at `start`. Codes must not touch
+/// other Latin letters (`XUSD`, `USDT`).
+pub fn currency_length_cps<S: AsRef<str>>(cps: &[char], start: usize, currencies: &[S]) -> usize {
+    let mut best = 0;
+    for currency in currencies {
+        let currency = currency.as_ref();
+        let len = currency.chars().count();
+        if len <= best || !cps.get(start..start + len).is_some_and(|s| s.iter().copied().eq(currency.chars())) {
+            continue;
+        }
+        if is_currency_code(currency) {
+            let touches = |i: Option<usize>| i.and_then(|i| cps.get(i)).is_some_and(|&c| is_latin_letter(c));
+            if touches(start.checked_sub(1)) || touches(Some(start + len)) {
+                continue;
+            }
+        }
+        best = len;
+    }
+    best
+}
+
+/// A currency amount split into its symbol or code and the number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrencyAmount<'a> {
+    pub currency: &'a str,
+    pub amount: &'a str,
+}
+
+fn is_amount(text: &str) -> bool {
+    text.starts_with(|c| digit_value(c).is_some())
+        && text.ends_with(|c| digit_value(c).is_some())
+        && text.chars().all(|c| digit_value(c).is_some() || matches!(c, ',' | '.' | ' ' | '-' | '–'))
+}
+
+/// The currency and amount of `token` if it is one of `currencies` next to
+/// a number (see `SegmenterConfig::currencies`).
+pub fn parse_currency<'a, S: AsRef<str>>(token: &'a str, currencies: &[S]) -> Option<CurrencyAmount<'a>> {
+    let mut best: Option<CurrencyAmount<'a>> = None;
+    for currency in currencies {
+        let currency = currency.as_ref();
+        if currency.is_empty() || best.is_some_and(|b| b.currency.len() >= currency.len()) {
+            continue;
+        }
+        let code = is_currency_code(currency);
+        let found = if let Some(rest) = token.strip_prefix(currency) {
+            let amount = if code { rest.strip_prefix(' ').unwrap_or(rest) } else { rest };
+            Some((&token[..currency.len()], amount))
+        } else if let Some(rest) = token.strip_suffix(currency) {
+            let amount = if code { rest.strip_suffix(' ').unwrap_or(rest) } else { rest };
+            Some((&token[token.len() - currency.len()..], amount))
+        } else {
+            None
+        };
+        if let Some((currency, amount)) = found.filter(|&(_, amount)| is_amount(amount)) {
+            best = Some(CurrencyAmount { currency, amount });
+        }
+    }
+    best
+}
+
+fn skip_spaces(cps: &[char], mut i: usize) -> usize {
+    while cps.get(i) == Some(&' ') {
+        i += 1;
+    }
+    i
+}
+
+fn keyword_at(cps: &[char], start: usize, keyword: &str) -> Option<usize> {
+    let len = keyword.chars().count();
+    let matches = cps.get(start..start + len).is_some_and(|s| s.iter().copied().eq(keyword.chars()));
+    matches.then_some(start + len)
+}
+
+/// `ថ្ងៃទី១៥ ខែមករា ឆ្នាំ២០២៤`: day, month and year parts in that order,
+/// each optionally followed by spaces, at least two of them, starting with
+/// the day or the month.
+fn khmer_date(cps: &[char], start: usize) -> Option<(usize, Date)> {
+    let mut date = Date::default();
+    let mut parts = 0;
+    let mut end = start;
+
+    if let Some(i) = keyword_at(cps, start, "ថ្ងៃទី") {
+        let i = skip_spaces(cps, i);
+        let (len, day) = digit_run(cps, i);
+        if !(1..=2).contains(&len) || !(1..=31).contains(&day) {
+            return None;
+        }
+        date.day = Some(day);
+        parts += 1;
+        end = i + len;
+    }
+
+    let i = if parts > 0 { skip_spaces(cps, end) } else { start };
+    if let Some(i) = keyword_at(cps, i, "ខែ") {
+        let i = skip_spaces(cps, i);
+        if let Some(month) = KHMER_MONTHS.iter().position(|name| keyword_at(cps, i, name).is_some()) {
+            date.month = Some(month as u32 + 1);
+            parts += 1;
+            end = i + KHMER_MONTHS[month].chars().count();
+        }
+    }
+    if parts == 0 {
+        return None;
+    }
+
+    let i = skip_spaces(cps, end);
+    if let Some(i) = keyword_at(cps, i, "ឆ្នាំ") {
+        let i = skip_spaces(cps, i);
+        let (len, year) = digit_run(cps, i);
+        if (1..=4).contains(&len) {
+            date.year = Some(year);
+            parts += 1;
+            end = i + len;
+        }
+    }
+
+    (parts >= 2).then_some((end - start, date))
+}
+
+/// Numeric dates (`15/01/2024`, `2024-01-15`, `១-៦-៦៦`).
+pub fn numeric_date_length_cps(cps: &[char], start: usize) -> usize {
+    numeric_date(cps, start).map_or(0, |(len, _)| len)
+}
+
+/// Khmer date expressions (`ថ្ងៃទី១៥ ខែមករា ឆ្នាំ២០២៤`, `ខែមករា ឆ្នាំ២០២៤`).
+pub fn khmer_date_length_cps(cps: &[char], start: usize) -> usize {
+    khmer_date(cps, start).map_or(0, |(len, _)| len)
+}
+
+/// The fields of `token` if all of it is one numeric or Khmer date.
+pub fn parse_date(token: &str) -> Option<Date> {
+    let first = token.chars().next()?;
+    if digit_value(first).is_none() && first != 'ថ' && first != 'ខ' {
+        return None;
+    }
+    let cps: Vec<char> = token.chars().collect();
+    numeric_date(&cps, 0)
+        .or_else(|| khmer_date(&cps, 0))
+        .filter(|&(len, _)| len == cps.len())
+        .map(|(_, date)| date)
+}
+
+/// Whether all of `token` is one numeric or Khmer date.
+pub fn is_date(token: &str) -> bool {
+    parse_date(token).is_some()
+}
@@ -0,0 +1,135 @@
+//! Post-processing stages run on the DP output, in order.
+//!
+//! The built-in passes (`SnapInvalidSingles`, `SignMerge`, `UnknownMerge`)
+//! implement `PostProcess` like any application stage. By default a
+//! segmenter runs the built-in passes not disabled in its `SegmenterConfig`;
+//! `KhmerSegmenterBuilder::post_processors` replaces that list and
+//! `KhmerSegmenterBuilder::add_post_processor` appends to it.
+
+use crate::config::{Pass, SegmenterConfig};
+use crate::dictionary::Dictionary;
+use crate::heuristics::{
+    apply_heuristics_string, post_process_unknowns_string, post_process_unknowns_with, snap_invalid_singles_string,
+    snap_invalid_singles_with,
+};
+use crate::rules::RuleSet;
+
+pub trait PostProcess: Send + Sync {
+    /// Rewrite one line's segments. Segments may be merged or split, but
+    /// must still concatenate to the same text.
+    fn process(&self, segments: Vec<String>, dictionary: &Dictionary) -> Vec<String>;
+
+    /// Name of the stage in `KhmerSegmenter::explain`.
+    fn name(&self) -> &str {
+        "custom"
+    }
+}
+
+impl<F> PostProcess for F
+where
+    F: Fn(Vec<String>, &Dictionary) -> Vec<String> + Send + Sync,
+{
+    fn process(&self, segments: Vec<String>, dictionary: &Dictionary) -> Vec<String> {
+        self(segments, dictionary)
+    }
+}
+
+/// See `Pass::SnapInvalidSingles`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SnapInvalidSingles;
+
+/// See `Pass::SignMerge`. A `RuleSet` is the same pass with rules from a file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SignMerge;
+
+/// See `Pass::UnknownMerge`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnknownMerge;
+
+impl PostProcess for SnapInvalidSingles {
+    fn process(&self, segments: Vec<String>, dictionary: &Dictionary) -> Vec<String> {
+        snap_invalid_singles_string(segments, dictionary)
+    }
+
+    fn name(&self) -> &str {
+        Pass::SnapInvalidSingles.name()
+    }
+}
+
+impl PostProcess for SignMerge {
+    fn process(&self, segments: Vec<String>, dictionary: &Dictionary) -> Vec<String> {
+        apply_heuristics_string(segments, dictionary)
+    }
+
+    fn name(&self) -> &str {
+        Pass::SignMerge.name()
+    }
+}
+
+impl PostProcess for UnknownMerge {
+    fn process(&self, segments: Vec<String>, dictionary: &Dictionary) -> Vec<String> {
+        post_process_unknowns_string(segments, dictionary)
+    }
+
+    fn name(&self) -> &str {
+        Pass::UnknownMerge.name()
+    }
+}
+
+impl PostProcess for RuleSet {
+    fn process(&self, segments: Vec<String>, dictionary: &Dictionary) -> Vec<String> {
+        self.apply(segments, dictionary)
+    }
+
+    fn name(&self) -> &str {
+        Pass::SignMerge.name()
+    }
+}
+
+/// `SnapInvalidSingles` or `UnknownMerge` with the separator set of `config`.
+struct WithSeparators {
+    pass: Pass,
+    config: SegmenterConfig,
+}
+
+impl PostProcess for WithSeparators {
+    fn process(&self, segments: Vec<String>, dictionary: &Dictionary) -> Vec<String> {
+        let is_separator = |c| self.config.is_separator(c);
+        match self.pass {
+            Pass::SnapInvalidSingles => snap_invalid_singles_with(segments, dictionary, is_separator),
+            Pass::UnknownMerge => post_process_unknowns_with(segments, dictionary, is_separator),
+            Pass::SignMerge => apply_heuristics_string(segments, dictionary),
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.pass.name()
+    }
+}
+
+/// The built-in passes enabled in `config`, with `rules` (if any) in place
+/// of the built-in sign-merge patterns. With a custom separator set, the
+/// passes that look at separators use it instead of the built-in one.
+pub fn default_post_processors(config: &SegmenterConfig, rules: Option<RuleSet>) -> Vec<Box<dyn PostProcess>> {
+    let mut passes: Vec<Box<dyn PostProcess>> = Vec::with_capacity(Pass::ALL.len());
+    let custom_separators = (!config.has_default_separators()).then(|| config.clone());
+    if config.is_enabled(Pass::SnapInvalidSingles) {
+        match custom_separators.clone() {
+            Some(config) => passes.push(Box::new(WithSeparators { pass: Pass::SnapInvalidSingles, config })),
+            None => passes.push(Box::new(SnapInvalidSingles)),
+        }
+    }
+    if config.is_enabled(Pass::SignMerge) {
+        match rules {
+            Some(rules) => passes.push(Box::new(rules)),
+            None => passes.push(Box::new(SignMerge)),
+        }
+    }
+    if config.is_enabled(Pass::UnknownMerge) {
+        match custom_separators {
+            Some(config) => passes.push(Box::new(WithSeparators { pass: Pass::UnknownMerge, config })),
+            None => passes.push(Box::new(UnknownMerge)),
+        }
+    }
+    passes
+}
@@ -0,0 +1,221 @@
+//! Corpus-level reports built while segmenting, from each line's segments and
+//! their `TokenKind`s (see `Segmenter::token_kind`).
+
+use std::io::{self, Write};
+
+use fxhash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::token::TokenKind;
+
+/// Segments shown on each side of an unknown token in its examples
+const CONTEXT: usize = 2;
+/// Tokens this long or longer share the last row of `CoverageReport::by_length`
+const MAX_LENGTH_ROW: usize = 10;
+
+/// How often one unknown token occurred, and where.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnknownEntry {
+    pub count: u64,
+    /// Lines it occurs in
+    pub lines: u64,
+    /// The token in context, `[bracketed]` between up to two segments on
+    /// each side (whitespace aside), from the first lines it occurs in
+    pub examples: Vec<String>,
+}
+
+/// Every token classified as `TokenKind::Unknown` over a run, with counts and
+/// examples: the list of candidates for growing the dictionary.
+#[derive(Debug, Clone, Default)]
+pub struct UnknownReport {
+    entries: FxHashMap<String, UnknownEntry>,
+    max_examples: usize,
+    tokens: u64,
+    unknown: u64,
+}
+
+impl UnknownReport {
+    /// Keep up to `max_examples` examples of each token.
+    pub fn new(max_examples: usize) -> Self {
+        UnknownReport { max_examples, ..Default::default() }
+    }
+
+    /// Count the unknown tokens of one line; `kinds[i]` is the kind of
+    /// `segments[i]`. Whitespace counts as neither known nor unknown.
+    pub fn add<S: AsRef<str>>(&mut self, segments: &[S], kinds: &[TokenKind]) {
+        let mut seen_in_line: Vec<&str> = Vec::new();
+        for (i, (segment, &kind)) in segments.iter().zip(kinds).enumerate() {
+            if kind == TokenKind::Whitespace {
+                continue;
+            }
+            self.tokens += 1;
+            if kind != TokenKind::Unknown {
+                continue;
+            }
+            self.unknown += 1;
+            let token = segment.as_ref();
+            if !self.entries.contains_key(token) {
+                self.entries.insert(token.to_string(), UnknownEntry::default());
+            }
+            let entry = self.entries.get_mut(token).unwrap();
+            entry.count += 1;
+            if !seen_in_line.contains(&token) {
+                seen_in_line.push(token);
+                entry.lines += 1;
+            }
+            if entry.examples.len() < self.max_examples {
+                entry.examples.push(context(segments, kinds, i));
+            }
+        }
+    }
+
+    /// Tokens counted, whitespace aside.
+    pub fn tokens(&self) -> u64 {
+        self.tokens
+    }
+
+    /// Unknown tokens counted, repeats included.
+    pub fn unknown(&self) -> u64 {
+        self.unknown
+    }
+
+    /// Distinct unknown tokens.
+    pub fn distinct(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Entries, most frequent first (ties in token order).
+    pub fn ranked(&self) -> Vec<(&str, &UnknownEntry)> {
+        let mut ranked: Vec<(&str, &UnknownEntry)> =
+            self.entries.iter().map(|(token, entry)| (token.as_str(), entry)).collect();
+        ranked.sort_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(b.0)));
+        ranked
+    }
+
+    /// Write the ranked report as TSV: rank, count, lines, share of all
+    /// unknown tokens (%), cumulative share (%), token, and the examples
+    /// separated by ` ‖ `.
+    pub fn write_tsv<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "rank\tcount\tlines\tshare\tcumulative\ttoken\texamples")?;
+        let total = self.unknown.max(1) as f64;
+        let mut cumulative = 0;
+        for (rank, (token, entry)) in self.ranked().into_iter().enumerate() {
+            cumulative += entry.count;
+            writeln!(
+                w,
+                "{}\t{}\t{}\t{:.2}\t{:.2}\t{}\t{}",
+                rank + 1,
+                entry.count,
+                entry.lines,
+                entry.count as f64 * 100.0 / total,
+                cumulative as f64 * 100.0 / total,
+                tsv_field(token),
+                tsv_field(&entry.examples.join(" ‖ "))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Segment `i` of `segments` between its neighbours, whitespace aside:
+/// `ក | ខ | [គ] | ឃ`.
+fn context<S: AsRef<str>>(segments: &[S], kinds: &[TokenKind], i: usize) -> String {
+    let shown = |j: &usize| kinds[*j] != TokenKind::Whitespace;
+    let mut before: Vec<usize> = (0..i).rev().filter(shown).take(CONTEXT).collect();
+    before.reverse();
+    let after = (i + 1..segments.len()).filter(shown).take(CONTEXT);
+    let mut parts: Vec<String> = before.into_iter().map(|j| segments[j].as_ref().to_string()).collect();
+    parts.push(format!("[{}]", segments[i].as_ref()));
+    parts.extend(after.map(|j| segments[j].as_ref().to_string()));
+    parts.join(" | ")
+}
+
+/// `text` with tabs and line breaks turned into spaces.
+fn tsv_field(text: &str) -> String {
+    text.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Tokens and the characters (codepoints) in them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counts {
+    pub tokens: u64,
+    pub chars: u64,
+}
+
+impl Counts {
+    fn add(&mut self, chars: usize) {
+        self.tokens += 1;
+        self.chars += chars as u64;
+    }
+
+    fn plus(self, other: Counts) -> Counts {
+        Counts { tokens: self.tokens + other.tokens, chars: self.chars + other.chars }
+    }
+}
+
+/// Tokens split by how the segmenter matched them: dictionary words,
+/// unknown fallbacks, and everything else (numbers, punctuation, Latin
+/// words, URLs, ...). Whitespace is left out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageRow {
+    pub word: Counts,
+    pub unknown: Counts,
+    pub other: Counts,
+}
+
+impl CoverageRow {
+    fn add(&mut self, kind: TokenKind, chars: usize) {
+        match kind {
+            TokenKind::Word => self.word.add(chars),
+            TokenKind::Unknown => self.unknown.add(chars),
+            _ => self.other.add(chars),
+        }
+    }
+
+    pub fn total(&self) -> Counts {
+        self.word.plus(self.unknown).plus(self.other)
+    }
+
+    /// Share of the words and unknown tokens that are words, by tokens and
+    /// by characters: how much of the text the dictionary covers.
+    pub fn coverage(&self) -> (f64, f64) {
+        let text = self.word.plus(self.unknown);
+        (
+            self.word.tokens as f64 / text.tokens.max(1) as f64,
+            self.word.chars as f64 / text.chars.max(1) as f64,
+        )
+    }
+}
+
+/// How much of a corpus the dictionary covers, overall and by token length,
+/// to quantify its gaps per domain (`coverage`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub lines: u64,
+    pub overall: CoverageRow,
+    /// `by_length[n - 1]` covers the tokens of `n` codepoints, the last row
+    /// those of 10 or more
+    pub by_length: Vec<CoverageRow>,
+}
+
+impl CoverageReport {
+    pub fn new() -> Self {
+        CoverageReport { by_length: vec![CoverageRow::default(); MAX_LENGTH_ROW], ..Default::default() }
+    }
+
+    /// Count one line; `kinds[i]` is the kind of `segments[i]`.
+    pub fn add<S: AsRef<str>>(&mut self, segments: &[S], kinds: &[TokenKind]) {
+        self.lines += 1;
+        for (segment, &kind) in segments.iter().zip(kinds) {
+            if kind == TokenKind::Whitespace {
+                continue;
+            }
+            let chars = segment.as_ref().chars().count();
+            if chars == 0 {
+                continue;
+            }
+            self.overall.add(kind, chars);
+            self.by_length[chars.min(MAX_LENGTH_ROW) - 1].add(kind, chars);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Merge rules for the sign-merge pass, loaded from a TOML file instead of
+//! the built-in patterns in `heuristics`.
+//!
+//! ```toml
+//! [[rule]]
+//! name = "samyok-sannya"
+//! pattern = ["consonant", "U+17D0"]
+//! action = "merge-next"
+//! ```
+//!
+//! A rule applies to a segment that is not a dictionary word and whose
+//! characters match `pattern` one for one. Each pattern element matches one
+//! character: a class (`consonant`, `independent-vowel`, `dependent-vowel`,
+//! `sign`, `coeng`, `digit`, `khmer`, `any`), a literal character or a
+//! `U+XXXX` code point. Alternatives are separated by `|`.
+//!
+//! `merge-previous` appends the segment to the one before it and
+//! `merge-next` prepends it to the one after it. Rules are tried in file
+//! order and the first one that matches and can act wins; a segment at the
+//! start of a line cannot merge with a previous one. `rules/sign_merge.toml`
+//! reproduces the built-in rules.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::constants::{is_coeng, is_consonant, is_dependent_vowel, is_digit, is_independent_vowel, is_khmer_char, is_sign};
+use crate::dictionary::Dictionary;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    MergePrevious,
+    MergeNext,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Atom {
+    Consonant,
+    IndependentVowel,
+    DependentVowel,
+    Sign,
+    Coeng,
+    Digit,
+    Khmer,
+    Any,
+    Char(char),
+}
+
+impl Atom {
+    fn parse(s: &str) -> Option<Atom> {
+        Some(match s {
+            "consonant" => Atom::Consonant,
+            "independent-vowel" => Atom::IndependentVowel,
+            "dependent-vowel" => Atom::DependentVowel,
+            "sign" => Atom::Sign,
+            "coeng" => Atom::Coeng,
+            "digit" => Atom::Digit,
+            "khmer" => Atom::Khmer,
+            "any" => Atom::Any,
+            _ => {
+                if let Some(hex) = s.strip_prefix("U+").or_else(|| s.strip_prefix("u+")) {
+                    return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32).map(Atom::Char);
+                }
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Atom::Char(c),
+                    _ => return None,
+                }
+            }
+        })
+    }
+
+    fn matches(self, c: char) -> bool {
+        match self {
+            Atom::Consonant => is_consonant(c),
+            Atom::IndependentVowel => is_independent_vowel(c),
+            Atom::DependentVowel => is_dependent_vowel(c),
+            Atom::Sign => is_sign(c),
+            Atom::Coeng => is_coeng(c),
+            Atom::Digit => is_digit(c),
+            Atom::Khmer => is_khmer_char(c),
+            Atom::Any => true,
+            Atom::Char(expected) => c == expected,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    /// One set of alternatives per character
+    pattern: Vec<Vec<Atom>>,
+    pub action: Action,
+}
+
+impl Rule {
+    /// Whether `segment` matches the pattern, character for character.
+    pub fn matches(&self, segment: &str) -> bool {
+        let mut chars = segment.chars();
+        self.pattern.iter().all(|alternatives| match chars.next() {
+            Some(c) => alternatives.iter().any(|atom| atom.matches(c)),
+            None => false,
+        }) && chars.next().is_none()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleEntry {
+    name: Option<String>,
+    pattern: Vec<String>,
+    action: Action,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    #[serde(default)]
+    rule: Vec<RuleEntry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        RuleSet::from_toml(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(text: &str) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let file: RuleFile = toml::from_str(text).map_err(|e| invalid(e.to_string()))?;
+
+        let mut rules = Vec::with_capacity(file.rule.len());
+        for (i, entry) in file.rule.into_iter().enumerate() {
+            let name = entry.name.unwrap_or_else(|| format!("rule {}", i + 1));
+            if entry.pattern.is_empty() {
+                return Err(invalid(format!("{}: empty pattern", name)));
+            }
+            let mut pattern = Vec::with_capacity(entry.pattern.len());
+            for element in &entry.pattern {
+                let alternatives = element
+                    .split('|')
+                    .map(|atom| Atom::parse(atom.trim()))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| invalid(format!("{}: invalid pattern element {:?}", name, element)))?;
+                pattern.push(alternatives);
+            }
+            rules.push(Rule { name, pattern, action: entry.action });
+        }
+        Ok(RuleSet { rules })
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The sign-merge pass with these rules in place of the built-in ones.
+    pub fn apply(&self, segments: Vec<String>, dictionary: &Dictionary) -> Vec<String> {
+        let mut merged: Vec<String> = Vec::with_capacity(segments.len());
+        let mut segments = segments.into_iter().peekable();
+
+        while let Some(curr) = segments.next() {
+            let rule = if dictionary.contains(&curr) {
+                None
+            } else {
+                self.rules.iter().find(|rule| {
+                    let can_act = match rule.action {
+                        Action::MergePrevious => !merged.is_empty(),
+                        Action::MergeNext => segments.peek().is_some(),
+                    };
+                    can_act && rule.matches(&curr)
+                })
+            };
+
+            match rule.map(|rule| rule.action) {
+                Some(Action::MergePrevious) => merged.last_mut().unwrap().push_str(&curr),
+                Some(Action::MergeNext) => {
+                    let next = segments.next().unwrap();
+                    merged.push(curr + &next);
+                }
+                None => merged.push(curr),
+            }
+        }
+
+        merged
+    }
+}
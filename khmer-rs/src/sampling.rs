@@ -0,0 +1,269 @@
+//! Reproducible slices of a large corpus for quick experiments: the first
+//! bytes of a file, lines skipped from the start, a seeded random sample and
+//! a line limit (`--head-bytes`, `--skip`, `--sample`, `--seed`, `--limit`);
+//! and seeded shuffling of corpora larger than memory (`shuffle`).
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::input::LineDecoder;
+
+/// SplitMix64: a small, fast generator whose output depends only on its seed.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mix(self.state)
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `0..n`; `n` must not be 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        // Multiply-shift: the bias is negligible for corpus-sized `n`
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+}
+
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Which lines of an input to take. Empty lines are never taken and not
+/// counted. Applied in order: `head_bytes`, `skip`, `sample`, `limit`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LineSelection {
+    /// Only read lines that end within the first this many bytes
+    pub head_bytes: Option<u64>,
+    /// Non-empty lines to skip first
+    pub skip: usize,
+    /// Share of the remaining lines to keep, chosen at random
+    pub sample: Option<f64>,
+    /// Seed of `sample`: the same seed keeps the same lines
+    pub seed: u64,
+    /// Lines to take at most
+    pub limit: Option<usize>,
+}
+
+impl LineSelection {
+    /// Whether `sample` keeps the line at `index` (counted after `skip`). It
+    /// depends only on the seed and the index, so a larger `limit` or a
+    /// resumed run keeps the same lines.
+    pub fn keeps(&self, index: usize) -> bool {
+        match self.sample {
+            Some(rate) => {
+                let mut rng = SplitMix64::new(self.seed ^ mix(index as u64));
+                rng.next_f64() < rate
+            }
+            None => true,
+        }
+    }
+
+    /// The selected lines of `reader`, without their line endings.
+    pub fn lines<R: BufRead>(self, reader: R) -> impl Iterator<Item = io::Result<String>> {
+        self.decoded_lines(reader, LineDecoder::new())
+    }
+
+    /// `lines`, with `decoder` turning their bytes into text (it also
+    /// removes the line endings).
+    pub fn decoded_lines<R: BufRead>(
+        self,
+        reader: R,
+        decoder: LineDecoder,
+    ) -> impl Iterator<Item = io::Result<String>> {
+        HeadLines { reader, decoder, number: 0, remaining: self.head_bytes.unwrap_or(u64::MAX), done: false }
+            .filter(|line| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+            .skip(self.skip)
+            .enumerate()
+            .filter(move |(index, line)| line.is_err() || self.keeps(*index))
+            .map(|(_, line)| line)
+            .take(self.limit.unwrap_or(usize::MAX))
+    }
+}
+
+/// Lines of `reader` ending within `remaining` bytes.
+struct HeadLines<R> {
+    reader: R,
+    decoder: LineDecoder,
+    /// Lines read so far
+    number: usize,
+    remaining: u64,
+    done: bool,
+}
+
+impl<R: BufRead> Iterator for HeadLines<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut line = Vec::new();
+        match self.reader.read_until(b'\n', &mut line) {
+            Ok(0) => None,
+            Ok(read) => {
+                if read as u64 > self.remaining {
+                    self.done = true;
+                    return None;
+                }
+                self.remaining -= read as u64;
+                self.number += 1;
+                let line = self.decoder.decode(self.number, line);
+                self.done = line.is_err();
+                Some(line)
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Seeded shuffle of the lines of a file, in memory or, for files larger
+/// than the memory budget, in two passes over temporary bucket files: each
+/// line goes to a random bucket, then each bucket is shuffled in memory and
+/// appended to the output. Every order of the lines is equally likely, and
+/// the same seed, budget and input give the same order.
+#[derive(Debug, Clone)]
+pub struct Shuffle {
+    seed: u64,
+    max_memory: u64,
+    temp_dir: Option<PathBuf>,
+}
+
+/// What a `Shuffle` did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShuffleStats {
+    pub lines: usize,
+    /// Temporary bucket files used, 1 when shuffled in memory
+    pub buckets: usize,
+}
+
+impl Shuffle {
+    const DEFAULT_MAX_MEMORY: u64 = 256 << 20;
+    const BUCKET_BUFFER: usize = 64 << 10;
+
+    pub fn new(seed: u64) -> Self {
+        Shuffle { seed, max_memory: Self::DEFAULT_MAX_MEMORY, temp_dir: None }
+    }
+
+    /// Memory for the lines of one bucket (Default: 256 MB); about half
+    /// of it holds text, the rest their allocations.
+    pub fn max_memory(mut self, bytes: u64) -> Self {
+        self.max_memory = bytes.max(1);
+        self
+    }
+
+    /// Where the bucket files go (Default: next to the output).
+    pub fn temp_dir(mut self, dir: &Path) -> Self {
+        self.temp_dir = Some(dir.to_path_buf());
+        self
+    }
+
+    /// Write the lines of `input` to `output` in shuffled order. Lines are
+    /// copied byte for byte, empty ones included; each ends with a newline.
+    pub fn run(&self, input: &Path, output: &Path) -> io::Result<ShuffleStats> {
+        let size = fs::metadata(input)?.len();
+        let buckets = size.div_ceil((self.max_memory / 2).max(1)).max(1) as usize;
+        let mut rng = SplitMix64::new(self.seed);
+        let mut writer = BufWriter::new(File::create(output)?);
+
+        if buckets == 1 {
+            let mut lines = read_lines(BufReader::new(File::open(input)?))?;
+            shuffle(&mut lines, &mut rng);
+            write_lines(&mut writer, &lines)?;
+            writer.flush()?;
+            return Ok(ShuffleStats { lines: lines.len(), buckets });
+        }
+
+        let temp_dir = match &self.temp_dir {
+            Some(dir) => dir.clone(),
+            None => output.parent().map_or_else(PathBuf::new, Path::to_path_buf),
+        };
+        let name = output.file_name().map_or_else(Default::default, |name| name.to_string_lossy());
+        let paths: Vec<PathBuf> =
+            (0..buckets).map(|i| temp_dir.join(format!(".{}.shuffle-{:05}.tmp", name, i))).collect();
+        let result = self.scatter_and_gather(input, &paths, &mut rng, &mut writer);
+        for path in &paths {
+            let _ = fs::remove_file(path);
+        }
+        Ok(ShuffleStats { lines: result?, buckets })
+    }
+
+    fn scatter_and_gather(
+        &self,
+        input: &Path,
+        paths: &[PathBuf],
+        rng: &mut SplitMix64,
+        writer: &mut BufWriter<File>,
+    ) -> io::Result<usize> {
+        let mut bucket_writers = paths
+            .iter()
+            .map(|path| Ok(BufWriter::with_capacity(Self::BUCKET_BUFFER, File::create(path)?)))
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut reader = BufReader::new(File::open(input)?);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            if line.last() != Some(&b'\n') {
+                line.push(b'\n');
+            }
+            bucket_writers[rng.below(paths.len() as u64) as usize].write_all(&line)?;
+            line.clear();
+        }
+        for mut bucket in bucket_writers {
+            bucket.flush()?;
+        }
+
+        let mut count = 0;
+        for path in paths {
+            let mut lines = read_lines(BufReader::new(File::open(path)?))?;
+            fs::remove_file(path)?;
+            shuffle(&mut lines, rng);
+            write_lines(writer, &lines)?;
+            count += lines.len();
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+}
+
+/// Lines with their newlines, one added to a last line without one.
+fn read_lines<R: BufRead>(mut reader: R) -> io::Result<Vec<Vec<u8>>> {
+    let mut lines = Vec::new();
+    loop {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(lines);
+        }
+        if line.last() != Some(&b'\n') {
+            line.push(b'\n');
+        }
+        lines.push(line);
+    }
+}
+
+fn write_lines<W: Write>(writer: &mut W, lines: &[Vec<u8>]) -> io::Result<()> {
+    lines.iter().try_for_each(|line| writer.write_all(line))
+}
+
+/// Fisher-Yates.
+fn shuffle<T>(items: &mut [T], rng: &mut SplitMix64) {
+    for i in (1..items.len()).rev() {
+        items.swap(i, rng.below(i as u64 + 1) as usize);
+    }
+}
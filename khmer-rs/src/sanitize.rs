@@ -0,0 +1,181 @@
+//! Removal of control and invisible characters before segmentation
+//! (`--sanitize`). Scraped web text carries many of them, and left in they
+//! end up inside tokens: a word followed by a left-to-right mark is an
+//! unknown token rather than the word.
+//!
+//! The characters, by `InvisibleClass`:
+//! - `Control`: C0 controls but tab, line feed and carriage return, which
+//!   are whitespace; DEL; C1 controls.
+//! - `Bom`: U+FEFF, the byte order mark, anywhere in a line (files joined
+//!   together leave them in the middle of the text).
+//! - `Bidi`: the directional marks, embeddings, overrides and isolates
+//!   (U+061C, U+200E, U+200F, U+202A..=U+202E, U+2066..=U+2069).
+//! - `Invisible`: other characters with no appearance: the soft hyphen,
+//!   the combining grapheme joiner, the word joiner and invisible operators,
+//!   deprecated format characters, Hangul fillers, the Mongolian vowel
+//!   separator and the interlinear annotation characters.
+//!
+//! Zero-width spaces and joiners are left alone: ZWSP marks word boundaries
+//! in Khmer text (`--zwsp-boundaries`) and ZWJ holds emoji sequences
+//! together; `--normalize` drops the joiners inside Khmer words and keeps the
+//! others.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// The kinds of characters a `Sanitizer` removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InvisibleClass {
+    Control,
+    Bom,
+    Bidi,
+    Invisible,
+}
+
+impl InvisibleClass {
+    pub const ALL: [InvisibleClass; 4] =
+        [InvisibleClass::Control, InvisibleClass::Bom, InvisibleClass::Bidi, InvisibleClass::Invisible];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InvisibleClass::Control => "control",
+            InvisibleClass::Bom => "bom",
+            InvisibleClass::Bidi => "bidi",
+            InvisibleClass::Invisible => "invisible",
+        }
+    }
+
+    /// The class of `c`, if a `Sanitizer` removes it.
+    pub fn of(c: char) -> Option<InvisibleClass> {
+        match c {
+            '\t' | '\n' | '\r' => None,
+            '\u{0}'..='\u{1F}' | '\u{7F}'..='\u{9F}' => Some(InvisibleClass::Control),
+            '\u{FEFF}' => Some(InvisibleClass::Bom),
+            '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => {
+                Some(InvisibleClass::Bidi)
+            }
+            '\u{00AD}' | '\u{034F}' | '\u{115F}' | '\u{1160}' | '\u{180E}' | '\u{2060}'..='\u{2064}'
+            | '\u{206A}'..='\u{206F}' | '\u{3164}' | '\u{FFA0}' | '\u{FFF9}'..='\u{FFFB}' => {
+                Some(InvisibleClass::Invisible)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What becomes of the characters a `Sanitizer` finds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SanitizeAction {
+    /// Remove them all
+    #[default]
+    Strip,
+    /// Replace each control character with a space, as they mostly stand for
+    /// line or page breaks, and remove the others, which have no width and
+    /// often sit inside words
+    Space,
+}
+
+impl SanitizeAction {
+    pub const ALL: [SanitizeAction; 2] = [SanitizeAction::Strip, SanitizeAction::Space];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SanitizeAction::Strip => "strip",
+            SanitizeAction::Space => "space",
+        }
+    }
+}
+
+impl fmt::Display for SanitizeAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SanitizeAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SanitizeAction::ALL.into_iter().find(|action| action.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = SanitizeAction::ALL.iter().map(|action| action.name()).collect();
+            format!("unknown sanitize action {:?} (expected one of {})", s, names.join(", "))
+        })
+    }
+}
+
+/// Characters a `Sanitizer` has removed or replaced, by class.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SanitizeCounts {
+    pub control: usize,
+    pub bom: usize,
+    pub bidi: usize,
+    pub invisible: usize,
+}
+
+impl SanitizeCounts {
+    pub fn get(&self, class: InvisibleClass) -> usize {
+        match class {
+            InvisibleClass::Control => self.control,
+            InvisibleClass::Bom => self.bom,
+            InvisibleClass::Bidi => self.bidi,
+            InvisibleClass::Invisible => self.invisible,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.control + self.bom + self.bidi + self.invisible
+    }
+}
+
+/// Removes control and invisible characters from text, counting them. It
+/// can be shared between threads.
+#[derive(Debug, Default)]
+pub struct Sanitizer {
+    action: SanitizeAction,
+    /// Indexed by `InvisibleClass as usize`, in the order of `InvisibleClass::ALL`
+    counts: [AtomicUsize; 4],
+}
+
+impl Sanitizer {
+    pub fn new(action: SanitizeAction) -> Self {
+        Sanitizer { action, counts: Default::default() }
+    }
+
+    /// `text` without the characters of `InvisibleClass`; borrowed when it
+    /// has none.
+    pub fn sanitize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let Some(first) = text.find(|c| InvisibleClass::of(c).is_some()) else {
+            return Cow::Borrowed(text);
+        };
+        let mut out = String::with_capacity(text.len());
+        out.push_str(&text[..first]);
+        let mut counts = [0; 4];
+        for c in text[first..].chars() {
+            match InvisibleClass::of(c) {
+                None => out.push(c),
+                Some(class) => {
+                    counts[class as usize] += 1;
+                    if class == InvisibleClass::Control && self.action == SanitizeAction::Space {
+                        out.push(' ');
+                    }
+                }
+            }
+        }
+        for (count, found) in self.counts.iter().zip(counts) {
+            if found > 0 {
+                count.fetch_add(found, Ordering::Relaxed);
+            }
+        }
+        Cow::Owned(out)
+    }
+
+    /// Characters removed or replaced so far.
+    pub fn counts(&self) -> SanitizeCounts {
+        let [control, bom, bidi, invisible] = self.counts.each_ref().map(|count| count.load(Ordering::Relaxed));
+        SanitizeCounts { control, bom, bidi, invisible }
+    }
+}
@@ -0,0 +1,53 @@
+//! Layout of the records the main command writes, one per input line, here
+//! in JSONL (see `output` for the other formats):
+//!
+//! ```json
+//! {"schema_version":1,"id":0,"input":"សួស្តីបង","segments":["សួស្តី","បង"]}
+//! ```
+//!
+//! `SCHEMA_VERSION` is bumped whenever a field is removed or changes meaning,
+//! so consumers can reject records they do not understand. Adding an optional
+//! field keeps the version; consumers should ignore fields they do not know.
+//! `--print-schema` prints `json_schema()`.
+
+use serde_json::{json, Value};
+
+/// Value of the `schema_version` field of each record.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema (draft 2020-12) of one output record.
+pub fn json_schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": format!("urn:khmer-rs:segmentation-record:v{}", SCHEMA_VERSION),
+        "title": "khmer-rs segmentation record",
+        "description": "One record of the --output file (a JSONL line, or a MessagePack or CBOR map): an input line and its segments.",
+        "type": "object",
+        "properties": {
+            "schema_version": {
+                "description": "Version of this layout; bumped when a field is removed or changes meaning.",
+                "const": SCHEMA_VERSION,
+            },
+            "id": {
+                "description": "0-based index of the record in the run, counted on across --resume and output shards.",
+                "type": "integer",
+                "minimum": 0,
+            },
+            "input": {
+                "description": "The input line, trimmed unless --lossless or --whitespace keep.",
+                "type": "string",
+            },
+            "segments": {
+                "description": "The segments of the line, in order.",
+                "type": "array",
+                "items": { "type": "string" },
+            },
+            "filtered": {
+                "description": "With --filter-mode tag: indexes into segments of the tokens the filters matched.",
+                "type": "array",
+                "items": { "type": "integer", "minimum": 0 },
+            },
+        },
+        "required": ["schema_version", "id", "input", "segments"],
+    })
+}
@@ -0,0 +1,111 @@
+//! Script of characters and maximal same-script runs.
+//!
+//! Thai, Lao and Han runs have neither dictionary words nor recognizers, so
+//! the segmenter takes each such run as one token without running the DP
+//! over it (see `Script::bypasses_dp`). Latin, digits and other characters
+//! still go through the DP: URLs, numbers, emoji and Latin words are
+//! recognized there and may span several scripts (`rfa.org/khmer`, `$1,000`).
+
+use std::fmt;
+
+use crate::constants::{is_digit, is_khmer_char, is_latin_letter};
+use crate::span::Span;
+use serde::{Deserialize, Serialize};
+
+/// Serialized under its `name`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Script {
+    Khmer,
+    Latin,
+    Thai,
+    Lao,
+    Han,
+    /// ASCII and Khmer digits
+    Digit,
+    /// Whitespace, punctuation, symbols, emoji and any other script
+    Other,
+}
+
+impl Script {
+    pub fn of(c: char) -> Script {
+        if is_digit(c) {
+            return Script::Digit;
+        }
+        if is_khmer_char(c) {
+            return Script::Khmer;
+        }
+        if is_latin_letter(c) {
+            return Script::Latin;
+        }
+        match c as u32 {
+            0x0E00..=0x0E7F => Script::Thai,
+            0x0E80..=0x0EFF => Script::Lao,
+            0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x3134F => Script::Han,
+            _ => Script::Other,
+        }
+    }
+
+    /// Script of a token: that of its first character not in `Other`, or
+    /// `Other` if there is none (`"https://rfa.org"` is Latin, `"?!"` Other).
+    pub fn of_token(token: &str) -> Script {
+        token.chars().map(Script::of).find(|&s| s != Script::Other).unwrap_or(Script::Other)
+    }
+
+    /// Whether runs of this script are taken whole instead of segmented.
+    pub fn bypasses_dp(self) -> bool {
+        matches!(self, Script::Thai | Script::Lao | Script::Han)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Script::Khmer => "khmer",
+            Script::Latin => "latin",
+            Script::Thai => "thai",
+            Script::Lao => "lao",
+            Script::Han => "han",
+            Script::Digit => "digit",
+            Script::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Length in codepoints of the run of `cps[start]`'s script starting at `start`.
+pub fn run_length_cps(cps: &[char], start: usize) -> usize {
+    match cps.get(start) {
+        Some(&c) => {
+            let script = Script::of(c);
+            cps[start..].iter().take_while(|&&c| Script::of(c) == script).count()
+        }
+        None => 0,
+    }
+}
+
+/// Maximal same-script runs of `text`, with their byte spans.
+pub fn script_runs(text: &str) -> Vec<(Span, Script)> {
+    let mut runs: Vec<(Span, Script)> = Vec::new();
+    for (i, c) in text.char_indices() {
+        let script = Script::of(c);
+        let end = i + c.len_utf8();
+        match runs.last_mut() {
+            Some((span, last)) if *last == script => *span = Span::new(span.start, end),
+            _ => runs.push((Span::new(i, end), script)),
+        }
+    }
+    runs
+}
+
+/// Whether all of `token` is one run of a script that bypasses the DP.
+pub fn is_bypassed_run(token: &str) -> bool {
+    let mut chars = token.chars();
+    match chars.next().map(Script::of) {
+        Some(script) => script.bypasses_dp() && chars.all(|c| Script::of(c) == script),
+        None => false,
+    }
+}
@@ -1,7 +1,30 @@
+use crate::bigram::BigramTable;
+use crate::cache::{CacheStats, SegmentCache};
+use crate::confusables::Confusables;
+use crate::clusters::{count_khmer_clusters_cps, get_khmer_cluster_length_cps};
+use crate::config::{
+    NumberFormat, Pass, SegmenterConfig, WhitespacePolicy, DEFAULT_CURRENCIES, DEFAULT_INVALID_SINGLE_PENALTY,
+    DEFAULT_PHONE_PATTERNS, DEFAULT_REPAIR_PENALTY,
+};
 use crate::constants::*;
-use crate::dictionary::Dictionary;
-use crate::heuristics::{apply_heuristics_string, post_process_unknowns_string};
+use crate::dictionary::{Cost, Dictionary};
+use crate::orthography::attaches_to_previous;
+use crate::postprocess::{default_post_processors, PostProcess};
+use crate::rules::RuleSet;
+use crate::patterns::{
+    currency_length_cps, email_length_cps, emoji_length_cps, hashtag_length_cps, is_phone, khmer_date_length_cps, latin_word_length_cps,
+    is_numeral_context, mention_length_cps, numeric_date_length_cps, phone_length_cps, roman_numeral_length_cps, time_length_cps,
+    parse_currency, url_length_cps, CurrencyAmount,
+};
+use crate::script::{run_length_cps, Script};
+use crate::span::Span;
+use crate::token::{Token, TokenKind};
+use crate::trace::{ArcKind, Trace, TraceArc};
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::cell::RefCell;
+use std::sync::Arc;
 
 // ============================================================================
 // 1BRC Optimization: Thread-local buffers for zero-allocation hot path
@@ -13,9 +36,9 @@ thread_local! {
 
 struct ThreadLocalBuffers {
     codepoints: Vec<char>,
-    dp_cost: Vec<f32>,
+    dp_cost: Vec<Cost>,
     dp_parent: Vec<isize>,
-    segments: Vec<String>,
+    fuzzy_matches: Vec<(usize, Cost)>,
 }
 
 impl ThreadLocalBuffers {
@@ -24,98 +47,714 @@ impl ThreadLocalBuffers {
             codepoints: Vec::with_capacity(4096),
             dp_cost: Vec::with_capacity(4096),
             dp_parent: Vec::with_capacity(4096),
-            segments: Vec::with_capacity(256),
+            fuzzy_matches: Vec::with_capacity(16),
         }
     }
 }
 
+/// One step of `KhmerSegmenter::explain`: a stage and the segments it returned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stage {
+    pub name: String,
+    pub segments: Vec<String>,
+}
+
 pub struct KhmerSegmenter {
-    dictionary: Dictionary,
+    dictionary: ArcSwap<Dictionary>,
+    config: SegmenterConfig,
+    bigrams: Option<BigramTable>,
+    confusables: Option<Confusables>,
+    post_processors: Vec<Box<dyn PostProcess>>,
+    cache: Option<SegmentCache>,
+}
+
+pub struct KhmerSegmenterBuilder {
+    dictionary: Arc<Dictionary>,
+    config: SegmenterConfig,
+    bigrams: Option<BigramTable>,
+    confusables: Option<Confusables>,
+    rules: Option<RuleSet>,
+    post_processors: Option<Vec<Box<dyn PostProcess>>>,
+    extra_post_processors: Vec<Box<dyn PostProcess>>,
+}
+
+impl KhmerSegmenterBuilder {
+    pub fn config(mut self, config: SegmenterConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// See `SegmenterConfig::canonicalize_variants`.
+    pub fn canonicalize_variants(mut self, enabled: bool) -> Self {
+        self.config.canonicalize_variants = enabled;
+        self
+    }
+
+    /// See `SegmenterConfig::social_media`.
+    pub fn social_media(mut self, enabled: bool) -> Self {
+        self.config.social_media = enabled;
+        self
+    }
+
+    /// See `SegmenterConfig::dates`.
+    pub fn dates(mut self, enabled: bool) -> Self {
+        self.config.dates = enabled;
+        self
+    }
+
+    /// See `SegmenterConfig::phone_patterns`.
+    pub fn phone_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.config.phone_patterns = Some(patterns);
+        self
+    }
+
+    /// See `SegmenterConfig::currencies`.
+    pub fn currencies(mut self, currencies: Vec<String>) -> Self {
+        self.config.currencies = Some(currencies);
+        self
+    }
+
+    /// See `SegmenterConfig::number_format`.
+    pub fn number_format(mut self, format: NumberFormat) -> Self {
+        self.config.number_format = format;
+        self
+    }
+
+    /// See `SegmenterConfig::number_ranges`.
+    pub fn number_ranges(mut self, enabled: bool) -> Self {
+        self.config.number_ranges = enabled;
+        self
+    }
+
+    /// See `SegmenterConfig::separators`.
+    pub fn separators(mut self, separators: Vec<char>) -> Self {
+        self.config.separators = Some(separators);
+        self
+    }
+
+    /// See `SegmenterConfig::extra_separators`.
+    pub fn extra_separators(mut self, separators: Vec<char>) -> Self {
+        self.config.extra_separators = separators;
+        self
+    }
+
+    /// See `SegmenterConfig::zwsp_boundaries`.
+    pub fn zwsp_boundaries(mut self, enabled: bool) -> Self {
+        self.config.zwsp_boundaries = enabled;
+        self
+    }
+
+    /// See `SegmenterConfig::keep_zwsp`.
+    pub fn keep_zwsp(mut self, enabled: bool) -> Self {
+        self.config.keep_zwsp = enabled;
+        self
+    }
+
+    /// See `SegmenterConfig::lossless`.
+    pub fn lossless(mut self, enabled: bool) -> Self {
+        self.config.lossless = enabled;
+        self
+    }
+
+    /// See `SegmenterConfig::whitespace`.
+    pub fn whitespace(mut self, policy: WhitespacePolicy) -> Self {
+        self.config.whitespace = policy;
+        self
+    }
+
+    /// See `SegmenterConfig::cache_size`.
+    pub fn cache(mut self, lines: usize) -> Self {
+        self.config.cache_size = Some(lines);
+        self
+    }
+
+    /// See `SegmenterConfig::fuzzy_match_cost`.
+    pub fn fuzzy_match(mut self, extra_cost: Cost) -> Self {
+        self.config.fuzzy_match_cost = Some(extra_cost);
+        self
+    }
+
+    /// See `SegmenterConfig::length_normalization`.
+    pub fn length_normalization(mut self, exponent: Cost) -> Self {
+        self.config.length_normalization = Some(exponent);
+        self
+    }
+
+    /// See `SegmenterConfig::beam_margin`.
+    pub fn beam(mut self, margin: Cost) -> Self {
+        self.config.beam_margin = Some(margin);
+        self
+    }
+
+    /// See `SegmenterConfig::repair_penalty`.
+    pub fn repair_penalty(mut self, penalty: Cost) -> Self {
+        self.config.repair_penalty = Some(penalty);
+        self
+    }
+
+    /// See `SegmenterConfig::invalid_single_penalty`.
+    pub fn invalid_single_penalty(mut self, penalty: Cost) -> Self {
+        self.config.invalid_single_penalty = Some(penalty);
+        self
+    }
+
+    /// Skip a post-processing pass (see `Pass`).
+    pub fn disable_pass(mut self, pass: Pass) -> Self {
+        if self.config.is_enabled(pass) {
+            self.config.disabled_passes.push(pass);
+        }
+        self
+    }
+
+    /// Score word pairs found in `table` with their bigram cost instead of
+    /// the unigram cost (see `bigram`). Unigram-only by default. The previous
+    /// word is taken from the best path to each position, a greedy
+    /// approximation that does not always find the cheapest bigram path.
+    pub fn bigrams(mut self, table: BigramTable) -> Self {
+        self.bigrams = Some(table);
+        self
+    }
+
+    /// Look words up with each codepoint folded through `confusables` (see
+    /// `confusables`). Segments keep the input's spelling unless
+    /// `canonicalize_variants`.
+    pub fn confusables(mut self, confusables: Confusables) -> Self {
+        self.confusables = (!confusables.is_empty()).then_some(confusables);
+        self
+    }
+
+    /// Run the sign-merge pass with `rules` instead of the built-in
+    /// patterns (see `rules`).
+    pub fn rules(mut self, rules: RuleSet) -> Self {
+        self.rules = Some(rules);
+        self
+    }
+
+    /// Run `stages`, in order, instead of the built-in passes. The
+    /// disabled passes and rules of this builder no longer apply.
+    pub fn post_processors(mut self, stages: Vec<Box<dyn PostProcess>>) -> Self {
+        self.post_processors = Some(stages);
+        self
+    }
+
+    /// Run `stage` after the built-in passes (or those given to
+    /// `post_processors`) and any stage added before it.
+    pub fn add_post_processor(mut self, stage: impl PostProcess + 'static) -> Self {
+        self.extra_post_processors.push(Box::new(stage));
+        self
+    }
+
+    pub fn build(self) -> KhmerSegmenter {
+        let mut post_processors = match self.post_processors {
+            Some(stages) => stages,
+            None => default_post_processors(&self.config, self.rules),
+        };
+        post_processors.extend(self.extra_post_processors);
+        KhmerSegmenter {
+            dictionary: ArcSwap::new(self.dictionary),
+            cache: self.config.cache_size.map(SegmentCache::new),
+            config: self.config,
+            bigrams: self.bigrams,
+            confusables: self.confusables,
+            post_processors,
+        }
+    }
 }
 
 impl KhmerSegmenter {
     pub fn new(dictionary: Dictionary) -> Self {
-        KhmerSegmenter { dictionary }
+        KhmerSegmenter::builder(dictionary).build()
+    }
+
+    pub fn builder(dictionary: impl Into<Arc<Dictionary>>) -> KhmerSegmenterBuilder {
+        KhmerSegmenterBuilder {
+            dictionary: dictionary.into(),
+            config: SegmenterConfig::default(),
+            bigrams: None,
+            confusables: None,
+            rules: None,
+            post_processors: None,
+            extra_post_processors: Vec::new(),
+        }
+    }
+
+    /// The dictionary currently in use.
+    pub fn dictionary(&self) -> Arc<Dictionary> {
+        self.dictionary.load_full()
+    }
+
+    /// Atomically replace the dictionary and return the previous one.
+    ///
+    /// Calls already in progress finish with the dictionary they started
+    /// with; calls made after this returns use `dictionary`. Load the new
+    /// dictionary before calling this so segmentation never waits on I/O.
+    ///
+    /// The cache, if any, is cleared.
+    pub fn swap_dictionary(&self, dictionary: Arc<Dictionary>) -> Arc<Dictionary> {
+        let previous = self.dictionary.swap(dictionary);
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+        previous
+    }
+
+    pub fn config(&self) -> &SegmenterConfig {
+        &self.config
     }
 
+    /// Hits and misses of the line cache so far, if `SegmenterConfig::cache_size` is set.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(SegmentCache::stats)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(bytes = text.len())))]
     pub fn segment(&self, text: &str) -> Vec<String> {
+        let Some(cache) = &self.cache else {
+            return self.segment_uncached(text);
+        };
+        if let Some(segments) = cache.get(text) {
+            return segments;
+        }
+        // Read before the dictionary is: a swap meanwhile clears the cache
+        // after replacing it, and these segments may be from the old one
+        let generation = cache.generation();
+        let segments = self.segment_uncached(text);
+        cache.insert_if_current(text, segments.clone(), generation);
+        segments
+    }
+
+    /// `segment` into `out`, which is cleared first. Reusing one buffer
+    /// across calls, e.g. over millions of short strings, saves allocating
+    /// the result each time; post-processing stages that rebuild the
+    /// segments still allocate their own.
+    pub fn segment_into(&self, text: &str, out: &mut Vec<String>) {
+        match &self.cache {
+            Some(_) => {
+                out.clear();
+                out.extend(self.segment(text));
+            }
+            None => self.segment_uncached_into(text, out),
+        }
+    }
+
+    fn segment_uncached(&self, text: &str) -> Vec<String> {
+        let mut segments = Vec::new();
+        self.segment_uncached_into(text, &mut segments);
+        segments
+    }
+
+    fn segment_uncached_into(&self, text: &str, out: &mut Vec<String>) {
+        if self.config.lossless {
+            out.clear();
+            out.extend(self.lossless_spans(text).into_iter().map(|span| span.as_str(text).to_string()));
+            return;
+        }
+        if text.contains('\u{200b}') {
+            *out = self.segment_verbatim(text, self.config.keep_zwsp);
+        } else {
+            self.segment_raw_into(text, out);
+        }
+        match self.config.whitespace {
+            WhitespacePolicy::Split | WhitespacePolicy::Keep => {}
+            WhitespacePolicy::Collapse => {
+                for seg in out.iter_mut().filter(|seg| is_whitespace_run(seg)) {
+                    seg.clear();
+                    seg.push(' ');
+                }
+            }
+            WhitespacePolicy::Drop => out.retain(|seg| !is_whitespace_run(seg)),
+        }
+    }
+
+    /// Like `segment`, but the segments always concatenate to `text`
+    /// exactly: zero-width spaces come back as with `keep_zwsp`, whitespace
+    /// runs are kept verbatim whatever the `whitespace` policy, and each
+    /// segment is spelled as in the input even with `canonicalize_variants`.
+    /// `segment` returns the same under `SegmenterConfig::lossless`.
+    pub fn segment_lossless(&self, text: &str) -> Vec<String> {
+        self.lossless_spans(text).into_iter().map(|span| span.as_str(text).to_string()).collect()
+    }
+
+    /// Spans of `segment_lossless`: contiguous and covering `text`, even if
+    /// a post-processing stage broke its contract and dropped characters.
+    fn lossless_spans(&self, text: &str) -> Vec<Span> {
+        let segments = self.segment_verbatim(text, true);
+        let mut spans = Vec::with_capacity(segments.len() + 1);
+        let mut start = 0;
+        for span in segments_to_spans(text, &segments) {
+            if span.end > start {
+                spans.push(Span::new(start, span.end));
+                start = span.end;
+            }
+        }
+        if start < text.len() {
+            spans.push(Span::new(start, text.len()));
+        }
+        spans
+    }
+
+    /// `segment` with whitespace runs kept verbatim, so that the segments
+    /// concatenate to `text` (minus zero-width spaces unless `keep_zwsp`).
+    fn segment_verbatim(&self, text: &str, keep_zwsp: bool) -> Vec<String> {
         if !text.contains('\u{200b}') {
             return self.segment_raw(text);
         }
-        let text_cleaned = text.replace('\u{200b}', "");
-        self.segment_raw(&text_cleaned)
+        let segments = if self.config.zwsp_boundaries {
+            text.split('\u{200b}').flat_map(|chunk| self.segment_raw(chunk)).collect()
+        } else {
+            self.segment_raw(&text.replace('\u{200b}', ""))
+        };
+        if keep_zwsp {
+            restore_zwsp(text, segments)
+        } else {
+            segments
+        }
+    }
+
+    /// Like `segment`, with each segment's byte span and `TokenKind`.
+    pub fn segment_tokens(&self, text: &str) -> Vec<Token> {
+        let dictionary = self.dictionary.load();
+        let (segments, spans, whitespace) = if self.config.lossless {
+            let spans = self.lossless_spans(text);
+            let segments = spans.iter().map(|span| span.as_str(text).to_string()).collect();
+            (segments, spans, WhitespacePolicy::Keep)
+        } else {
+            let segments = self.segment_verbatim(text, self.config.keep_zwsp);
+            let spans = segments_to_spans(text, &segments);
+            (segments, spans, self.config.whitespace)
+        };
+        let line = text;
+        segments
+            .into_iter()
+            .zip(spans)
+            .filter(|(text, _)| whitespace != WhitespacePolicy::Drop || !is_whitespace_run(text))
+            .map(|(mut text, span)| {
+                if whitespace == WhitespacePolicy::Collapse && is_whitespace_run(&text) {
+                    text = " ".to_string();
+                }
+                let mut kind = self.kind_with(&text, &dictionary);
+                if kind == TokenKind::RomanNumeral && !is_numeral_context(line[..span.start].chars().rev()) {
+                    kind = TokenKind::Latin;
+                }
+                let script = Script::of_token(&text);
+                Token { text, span, kind, script }
+            })
+            .collect()
+    }
+
+    /// Like `segment`, but returns byte spans into `text` instead of strings.
+    ///
+    /// Zero-width spaces are skipped unless `keep_zwsp`: they never start or
+    /// end a span, but a span may contain one when the segmenter merged
+    /// across it.
+    ///
+    /// Under `WhitespacePolicy::Collapse` the span of a whitespace token
+    /// covers the whole run; under `Drop` whitespace has no spans.
+    pub fn segment_spans(&self, text: &str) -> Vec<Span> {
+        let mut spans = Vec::new();
+        self.segment_spans_into(text, &mut spans);
+        spans
+    }
+
+    /// `segment_spans` into `out`, which is cleared first, as
+    /// `segment_into` does for `segment`.
+    pub fn segment_spans_into(&self, text: &str, out: &mut Vec<Span>) {
+        out.clear();
+        if self.config.lossless {
+            out.extend(self.lossless_spans(text));
+            return;
+        }
+        let segments = self.segment_verbatim(text, self.config.keep_zwsp);
+        extend_spans(text, &segments, out);
+        if self.config.whitespace == WhitespacePolicy::Drop {
+            // `retain` visits the spans in order, one per segment
+            let mut segments = segments.iter();
+            out.retain(|_| segments.next().is_some_and(|seg| !is_whitespace_run(seg)));
+        }
+    }
+
+    /// The segments of `text` after each stage of `segment`: the Viterbi
+    /// pass (`"viterbi"`), each post-processing stage under its
+    /// `PostProcess::name`, whitespace-run merging and variant
+    /// canonicalization when enabled, and last the `"output"` of `segment`.
+    /// Shows which stage a difference from an expected segmentation comes from.
+    pub fn explain(&self, text: &str) -> Vec<Stage> {
+        let dictionary = self.dictionary.load();
+        let cleaned;
+        let chunks: Vec<&str> = if self.config.zwsp_boundaries {
+            text.split('\u{200b}').collect()
+        } else {
+            cleaned = text.replace('\u{200b}', "");
+            vec![cleaned.as_str()]
+        };
+
+        let mut stages: Vec<Stage> = Vec::new();
+        for chunk in chunks.into_iter().filter(|chunk| !chunk.is_empty()) {
+            let mut chunk_stages = Vec::new();
+            TL_BUFFERS.with(|buffers| {
+                let mut buf = buffers.borrow_mut();
+                let mut segments = Vec::new();
+                self.segment_with_buffers(&dictionary, chunk, &mut buf, &mut segments, Some(&mut chunk_stages), None)
+            });
+            if stages.is_empty() {
+                stages = chunk_stages;
+            } else {
+                for (stage, chunk_stage) in stages.iter_mut().zip(chunk_stages) {
+                    stage.segments.extend(chunk_stage.segments);
+                }
+            }
+        }
+        stages.push(Stage { name: "output".to_string(), segments: self.segment(text) });
+        stages
+    }
+
+    /// Every arc the Viterbi pass relaxed on `text`, with its cost, the path
+    /// cost it reached and whether it won (see `Trace`). Shows why the DP
+    /// preferred one segmentation over another. Positions skipped by beam
+    /// pruning have no arcs; with `zwsp_boundaries` each chunk is a DP of its
+    /// own, and positions run on across chunks.
+    pub fn segment_trace(&self, text: &str) -> Trace {
+        let dictionary = self.dictionary.load();
+        let cleaned = text.replace('\u{200b}', "");
+        let chunks: Vec<&str> = if self.config.zwsp_boundaries {
+            text.split('\u{200b}').collect()
+        } else {
+            vec![cleaned.as_str()]
+        };
+
+        let mut arcs = Vec::new();
+        let mut offset = 0;
+        for chunk in chunks.into_iter().filter(|chunk| !chunk.is_empty()) {
+            let mut chunk_arcs = Vec::new();
+            TL_BUFFERS.with(|buffers| {
+                let mut buf = buffers.borrow_mut();
+                let mut segments = Vec::new();
+                self.segment_with_buffers(&dictionary, chunk, &mut buf, &mut segments, None, Some(&mut chunk_arcs))
+            });
+            arcs.extend(chunk_arcs.into_iter().map(|arc| TraceArc { start: arc.start + offset, end: arc.end + offset, ..arc }));
+            offset += chunk.chars().count();
+        }
+
+        // The last arc to improve a position is the one its best path ends with
+        let chars: Vec<char> = cleaned.chars().collect();
+        let mut decided = vec![false; chars.len() + 1];
+        for arc in arcs.iter_mut().rev() {
+            arc.text = chars[arc.start..arc.end].iter().collect();
+            if arc.improved && !decided[arc.end] {
+                arc.won = true;
+                decided[arc.end] = true;
+            }
+        }
+
+        let mut trace = Trace { text: cleaned, arcs, segments: Vec::new() };
+        trace.segments = trace.path().into_iter().map(|arc| arc.text.clone()).collect();
+        trace
+    }
+
+    /// Cost of `segment` in the current dictionary, if it is a dictionary
+    /// word (generated spelling variants included).
+    pub fn word_cost(&self, segment: &str) -> Option<Cost> {
+        let dictionary = self.dictionary.load();
+        let segment = self.fold(segment);
+        dictionary.contains(&segment).then(|| dictionary.get_word_cost(&segment))
+    }
+
+    /// The `TokenKind` of one of this segmenter's segments, as
+    /// `segment_tokens` gives it.
+    pub fn token_kind(&self, segment: &str) -> TokenKind {
+        self.kind_with(segment, &self.dictionary.load())
+    }
+
+    fn kind_with(&self, segment: &str, dictionary: &Dictionary) -> TokenKind {
+        let segment = &*self.fold(segment);
+        if self.is_phone(segment) {
+            TokenKind::Phone
+        } else if self.parse_currency(segment).is_some() {
+            TokenKind::Currency
+        } else {
+            self.classify(segment, dictionary)
+        }
+    }
+
+    fn classify(&self, token: &str, dictionary: &Dictionary) -> TokenKind {
+        match TokenKind::classify(token, dictionary) {
+            TokenKind::Separator | TokenKind::Unknown if !self.config.has_default_separators() => {
+                if token.chars().all(|c| self.config.is_separator(c)) {
+                    TokenKind::Separator
+                } else {
+                    TokenKind::Unknown
+                }
+            }
+            // A number such as `15.01.2024`, kept whole by the number grouping
+            TokenKind::Date if !self.config.dates => {
+                if token.starts_with(is_digit) {
+                    TokenKind::Number
+                } else {
+                    TokenKind::Unknown
+                }
+            }
+            kind => kind,
+        }
+    }
+
+    /// `segment` as the DP looks it up, through the confusables.
+    fn fold<'a>(&self, segment: &'a str) -> Cow<'a, str> {
+        match &self.confusables {
+            Some(confusables) => confusables.fold_str(segment),
+            None => Cow::Borrowed(segment),
+        }
+    }
+
+    fn phone_length(&self, cps: &[char], start: usize) -> usize {
+        match &self.config.phone_patterns {
+            Some(patterns) => phone_length_cps(cps, start, patterns),
+            None => phone_length_cps(cps, start, DEFAULT_PHONE_PATTERNS),
+        }
+    }
+
+    fn is_phone(&self, token: &str) -> bool {
+        match &self.config.phone_patterns {
+            Some(patterns) => is_phone(token, patterns),
+            None => is_phone(token, DEFAULT_PHONE_PATTERNS),
+        }
+    }
+
+    fn currency_amount_length(&self, cps: &[char], start: usize) -> usize {
+        let (format, ranges) = (self.config.number_format, self.config.number_ranges);
+        match &self.config.currencies {
+            Some(currencies) => get_currency_amount_length_cps(cps, start, currencies, format, ranges),
+            None => get_currency_amount_length_cps(cps, start, DEFAULT_CURRENCIES, format, ranges),
+        }
+    }
+
+    /// The currency and amount of `token` if it is an amount with one of
+    /// this segmenter's currencies (see `SegmenterConfig::currencies`).
+    pub fn parse_currency<'a>(&self, token: &'a str) -> Option<CurrencyAmount<'a>> {
+        match &self.config.currencies {
+            Some(currencies) => parse_currency(token, currencies),
+            None => parse_currency(token, DEFAULT_CURRENCIES),
+        }
     }
 
     fn segment_raw(&self, text_raw: &str) -> Vec<String> {
+        let mut segments = Vec::new();
+        self.segment_raw_into(text_raw, &mut segments);
+        segments
+    }
+
+    /// `segment_raw` into `out`, which is cleared first.
+    fn segment_raw_into(&self, text_raw: &str, out: &mut Vec<String>) {
+        out.clear();
         if text_raw.is_empty() {
-            return Vec::new();
+            return;
         }
 
+        // One dictionary for the whole call, even if it is swapped meanwhile
+        let dictionary = self.dictionary.load();
+
         // 1BRC: Use thread-local buffers to avoid per-call allocations
         TL_BUFFERS.with(|buffers| {
             let mut buf = buffers.borrow_mut();
-            self.segment_with_buffers(text_raw, &mut buf)
+            self.segment_with_buffers(&dictionary, text_raw, &mut buf, out, None, None)
         })
     }
 
+    /// Viterbi pass over `text_raw`, then post-processing, into `out`.
+    ///
+    /// Tie-breaking, identical to the Python reference: a position's cost is
+    /// only replaced by a strictly lower one (`<`), so among equal-cost paths
+    /// the first one relaxed wins. Start positions are visited left to right,
+    /// and at each start the candidates are tried in this order: Thai, Lao or
+    /// Han run (taken whole, nothing else is tried), repair step, number,
+    /// date, time or phone number, currency amount, separator, acronym, email
+    /// or URL, hashtag or mention, emoji, Roman numeral, Latin word,
+    /// dictionary words (shortest first), fuzzy matches, unknown cluster or
+    /// character. With the `f64-costs` feature the costs and their sums are
+    /// `f64`, computed in the same order as in Python, so paths tie exactly
+    /// when they tie there.
+    ///
+    /// With `trace`, each stage's output is also appended to it (see
+    /// `explain`); with `arcs`, every arc relaxed (see `segment_trace`).
     #[inline]
-    fn segment_with_buffers(&self, text_raw: &str, buf: &mut ThreadLocalBuffers) -> Vec<String> {
+    fn segment_with_buffers(
+        &self,
+        dictionary: &Dictionary,
+        text_raw: &str,
+        buf: &mut ThreadLocalBuffers,
+        out: &mut Vec<String>,
+        mut trace: Option<&mut Vec<Stage>>,
+        mut arcs: Option<&mut Vec<TraceArc>>,
+    ) {
+        out.clear();
         // Reset and fill codepoint buffer
         buf.codepoints.clear();
         buf.codepoints.extend(text_raw.chars());
+        let folded = self.confusables.as_ref().is_some_and(|confusables| confusables.fold_chars(&mut buf.codepoints));
         let cps = &buf.codepoints;
         let n = cps.len();
 
         if n == 0 {
-            return Vec::new();
+            return;
         }
 
         // Resize DP buffers if needed, then reset
         if buf.dp_cost.len() < n + 1 {
-            buf.dp_cost.resize(n + 1, f32::INFINITY);
+            buf.dp_cost.resize(n + 1, Cost::INFINITY);
             buf.dp_parent.resize(n + 1, -1);
         }
 
         // Reset DP arrays (reuse allocated memory)
         for i in 0..=n {
-            buf.dp_cost[i] = f32::INFINITY;
+            buf.dp_cost[i] = Cost::INFINITY;
             buf.dp_parent[i] = -1;
         }
         buf.dp_cost[0] = 0.0;
 
+        #[cfg(feature = "tracing")]
+        let viterbi_span = tracing::trace_span!("viterbi", chars = n).entered();
+
         // Cache frequently used values
-        let max_word_len = self.dictionary.max_word_length;
-        let unknown_cost = self.dictionary.unknown_cost;
+        let unknown_cost = dictionary.unknown_cost;
+        let repair_penalty = self.config.repair_penalty.unwrap_or(DEFAULT_REPAIR_PENALTY);
+        let invalid_single_penalty = self.config.invalid_single_penalty.unwrap_or(DEFAULT_INVALID_SINGLE_PENALTY);
 
         for i in 0..n {
             // Check valid path to here
-            if buf.dp_cost[i] == f32::INFINITY {
+            if buf.dp_cost[i] == Cost::INFINITY {
                 continue;
             }
 
             let current_cost = buf.dp_cost[i];
             let c = cps[i];
 
-            // --- Constraint Checks & Fallback (Repair Mode) ---
-            let mut force_repair = false;
-
-            // 1. Previous char was Coeng (\u{17D2})
-            if i > 0 && cps[i - 1] == '\u{17D2}' {
-                force_repair = true;
+            // Beam pruning: skip paths far behind one that already got as far
+            if let Some(margin) = self.config.beam_margin {
+                let horizon = (i + dictionary.max_word_length).min(n);
+                let best_ahead = buf.dp_cost[i..=horizon].iter().fold(Cost::INFINITY, |a, &b| a.min(b));
+                if current_cost > best_ahead + margin {
+                    continue;
+                }
             }
 
-            // 2. Current char is Dependent Vowel
-            if is_dependent_vowel(c) {
-                force_repair = true;
+            // 0. Runs of scripts without dictionary words or recognizers are
+            // one token each; the positions inside are never visited
+            if Script::of(c).bypasses_dp() {
+                let next_idx = i + run_length_cps(cps, i);
+                let new_cost = current_cost + unknown_cost;
+                relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Bypass, i, next_idx, new_cost);
+                continue;
             }
 
+            // --- Constraint Checks & Fallback (Repair Mode) ---
+            // A dependent vowel, or anything after a coeng, cannot start a word
+            let force_repair = attaches_to_previous(cps, i);
+
             if force_repair {
                 // Recovery Mode: Consume 1 char with high penalty
                 let next_idx = i + 1;
-                let new_cost = current_cost + unknown_cost + 50.0;
-                if next_idx <= n && new_cost < buf.dp_cost[next_idx] {
-                    buf.dp_cost[next_idx] = new_cost;
-                    buf.dp_parent[next_idx] = i as isize;
+                let new_cost = current_cost + unknown_cost + repair_penalty;
+                if next_idx <= n {
+                    relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Repair, i, next_idx, new_cost);
                 }
                 continue;
             }
@@ -132,28 +771,47 @@ impl KhmerSegmenter {
             } else { false };
 
             if is_digit_char || is_curr {
-                let len_cps = get_number_length_cps(cps, i);
+                let len_cps = get_number_length_cps(cps, i, self.config.number_format, self.config.number_ranges);
                 let next_idx = i + len_cps;
                 let step_cost = 1.0;
                 if next_idx <= n {
                     let new_cost = current_cost + step_cost;
-                    if new_cost < buf.dp_cost[next_idx] {
-                        buf.dp_cost[next_idx] = new_cost;
-                        buf.dp_parent[next_idx] = i as isize;
-                    }
+                    relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Number, i, next_idx, new_cost);
                 }
             }
 
+            // 1b. Times and phone numbers always, dates opt-in. A colon
+            // inside a time, or a space or hyphen inside a phone number, is
+            // not a separator.
+            let len_cps = if is_digit_char || c == '+' {
+                let date_len = if self.config.dates { numeric_date_length_cps(cps, i) } else { 0 };
+                date_len.max(time_length_cps(cps, i)).max(self.phone_length(cps, i))
+            } else if self.config.dates && (c == 'ថ' || c == 'ខ') {
+                khmer_date_length_cps(cps, i)
+            } else {
+                0
+            };
+            if len_cps > 0 {
+                let next_idx = i + len_cps;
+                let new_cost = current_cost + 1.0;
+                relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Date, i, next_idx, new_cost);
+            }
+
+            // 1c. Amounts with a currency symbol or code
+            let len_cps = self.currency_amount_length(cps, i);
+            if len_cps > 0 {
+                let next_idx = i + len_cps;
+                let new_cost = current_cost + 1.0;
+                relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Currency, i, next_idx, new_cost);
+            }
+
             // 2. Separators
-            if is_separator(c) {
+            if self.config.is_separator(c) {
                 let next_idx = i + 1;
                 let step_cost = 0.1;
                 if next_idx <= n {
                     let new_cost = current_cost + step_cost;
-                    if new_cost < buf.dp_cost[next_idx] {
-                        buf.dp_cost[next_idx] = new_cost;
-                        buf.dp_parent[next_idx] = i as isize;
-                    }
+                    relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Separator, i, next_idx, new_cost);
                 }
             }
 
@@ -164,23 +822,98 @@ impl KhmerSegmenter {
                 let step_cost = 1.0;
                 if next_idx <= n {
                     let new_cost = current_cost + step_cost;
-                    if new_cost < buf.dp_cost[next_idx] {
-                        buf.dp_cost[next_idx] = new_cost;
-                        buf.dp_parent[next_idx] = i as isize;
-                    }
+                    relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Acronym, i, next_idx, new_cost);
                 }
             }
 
-            // 4. Dictionary Match - Use trie lookup
-            let end_limit = (i + max_word_len).min(n);
-            for j in (i + 1)..=end_limit {
-                if let Some(word_cost) = self.dictionary.lookup_codepoints(cps, i, j) {
-                    let new_cost = current_cost + word_cost;
-                    if new_cost < buf.dp_cost[j] {
-                        buf.dp_cost[j] = new_cost;
-                        buf.dp_parent[j] = i as isize;
+            // 3b. Email addresses, URLs and bare domains
+            if c.is_ascii_alphanumeric() {
+                let len_cps = match email_length_cps(cps, i) {
+                    0 => url_length_cps(cps, i),
+                    len => len,
+                };
+                if len_cps > 0 {
+                    let next_idx = i + len_cps;
+                    let new_cost = current_cost + 1.0;
+                    relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Url, i, next_idx, new_cost);
+                }
+            }
+
+            // 3c. Hashtags and @mentions, opt-in
+            if self.config.social_media && (c == '#' || c == '@') {
+                let len_cps = if c == '#' { hashtag_length_cps(cps, i) } else { mention_length_cps(cps, i) };
+                if len_cps > 0 {
+                    let next_idx = i + len_cps;
+                    let new_cost = current_cost + 1.0;
+                    relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Social, i, next_idx, new_cost);
+                }
+            }
+
+            // 3d. Emoji sequences
+            if !is_khmer_char(c) {
+                let len_cps = emoji_length_cps(cps, i);
+                if len_cps > 0 {
+                    let next_idx = i + len_cps;
+                    let new_cost = current_cost + 1.0;
+                    relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Emoji, i, next_idx, new_cost);
+                }
+            }
+
+            // 3e. Roman numerals, then Latin-script words, as one token rather
+            // than one per letter: at their cost in the Latin word list, or as
+            // one unknown token. A numeral costs what the same letters do as an
+            // unknown word and wins the tie; listed words (`MIX`) and letters
+            // outside numeral context are left to the Latin word arc
+            if is_latin_letter(c) {
+                let len_cps = roman_numeral_length_cps(cps, i);
+                if len_cps > 0 && is_numeral_context(cps[..i].iter().rev().copied()) {
+                    let next_idx = i + len_cps;
+                    let word: String = cps[i..next_idx].iter().collect();
+                    if dictionary.latin_word_cost(&word).is_none() {
+                        let new_cost = current_cost + unknown_cost;
+                        relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::RomanNumeral, i, next_idx, new_cost);
                     }
                 }
+
+                let len_cps = latin_word_length_cps(cps, i);
+                if len_cps > 0 {
+                    let next_idx = i + len_cps;
+                    let word_cost = if dictionary.latin_words.is_empty() {
+                        unknown_cost
+                    } else {
+                        let word: String = cps[i..next_idx].iter().collect();
+                        dictionary.latin_word_cost(&word).unwrap_or(unknown_cost)
+                    };
+                    let new_cost = current_cost + word_cost;
+                    relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Latin, i, next_idx, new_cost);
+                }
+            }
+
+            // 4. Dictionary Match - single trie walk yields every word starting at i
+            // Bigram entries for the word on the best path to i, if enabled:
+            // greedy, other paths to i are not kept (see `bigram`)
+            let successors = match (&self.bigrams, buf.dp_parent[i]) {
+                (Some(table), prev) if prev >= 0 => table.successors(&cps[prev as usize..i]),
+                _ => None,
+            };
+            for (j, word_cost) in dictionary.common_prefixes(cps, i) {
+                let word_cost = successors.and_then(|next| next.get(&cps[i..j])).copied().unwrap_or(word_cost);
+                let word_cost = match self.config.length_normalization {
+                    Some(exponent) => word_cost * (count_khmer_clusters_cps(&cps[i..j]) as Cost).powf(exponent),
+                    None => word_cost,
+                };
+                let new_cost = current_cost + word_cost;
+                relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Word, i, j, new_cost);
+            }
+
+            // 4b. Typo-tolerant match (one vowel/sign edit), opt-in
+            if let Some(fuzzy_cost) = self.config.fuzzy_match_cost {
+                buf.fuzzy_matches.clear();
+                dictionary.fuzzy_prefixes(cps, i, &mut buf.fuzzy_matches);
+                for &(j, word_cost) in &buf.fuzzy_matches {
+                    let new_cost = current_cost + word_cost + fuzzy_cost;
+                    relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Fuzzy, i, j, new_cost);
+                }
             }
 
             // 5. Unknown Cluster Fallback
@@ -190,16 +923,13 @@ impl KhmerSegmenter {
 
                 // Penalty for invalid single consonants
                 if len_cps == 1 && !is_valid_single_word(c) {
-                    step_cost += 10.0;
+                    step_cost += invalid_single_penalty;
                 }
 
                 let next_idx = i + len_cps;
                 if next_idx <= n {
                     let new_cost = current_cost + step_cost;
-                    if new_cost < buf.dp_cost[next_idx] {
-                        buf.dp_cost[next_idx] = new_cost;
-                        buf.dp_parent[next_idx] = i as isize;
-                    }
+                    relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::UnknownCluster, i, next_idx, new_cost);
                 }
             } else {
                 // Non-Khmer (Symbol, English, etc)
@@ -207,16 +937,12 @@ impl KhmerSegmenter {
                 let next_idx = i + 1;
                 if next_idx <= n {
                     let new_cost = current_cost + step_cost;
-                    if new_cost < buf.dp_cost[next_idx] {
-                        buf.dp_cost[next_idx] = new_cost;
-                        buf.dp_parent[next_idx] = i as isize;
-                    }
+                    relax(&mut buf.dp_cost, &mut buf.dp_parent, &mut arcs, ArcKind::Unknown, i, next_idx, new_cost);
                 }
             }
         }
 
-        // Backtrack using thread-local segment buffer
-        buf.segments.clear();
+        // Backtrack straight into the output buffer
         let mut curr = n;
         while curr > 0 {
             let prev = buf.dp_parent[curr];
@@ -226,157 +952,270 @@ impl KhmerSegmenter {
             }
             let prev_idx = prev as usize;
             let segment: String = cps[prev_idx..curr].iter().collect();
-            buf.segments.push(segment);
+            out.push(segment);
             curr = prev_idx;
         }
-        buf.segments.reverse();
+        out.reverse();
+        #[cfg(feature = "tracing")]
+        drop(viterbi_span);
 
-        // Clone segments out for post-processing (need to return owned data)
-        let segments: Vec<String> = buf.segments.clone();
+        let mut record = |name: &str, segments: &[String]| {
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.push(Stage { name: name.to_string(), segments: segments.to_vec() });
+            }
+        };
+        record("viterbi", out);
+
+        // Post Processing (see `postprocess`)
+        let final_segments = self.post_processors.iter().fold(std::mem::take(out), |segments, stage| {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::trace_span!("post_process", stage = stage.name()).entered();
+            let segments = stage.process(segments, dictionary);
+            record(stage.name(), &segments);
+            segments
+        });
+        *out = final_segments;
+
+        if self.config.whitespace != WhitespacePolicy::Split {
+            *out = merge_whitespace_runs(std::mem::take(out));
+            record("whitespace-runs", out);
+        }
 
-        // Post Processing
-        // Pass 1: Snap Invalid Single Consonants - use optimized inline helpers
-        let pass1_segments = self.snap_invalid_single_consonants_fast(&segments);
+        // Back to the input's spelling, unless canonical forms are wanted
+        if folded && !self.config.canonicalize_variants {
+            restore_spelling(text_raw, out);
+            record("confusables", out);
+        }
+
+        if self.config.canonicalize_variants {
+            for seg in out.iter_mut() {
+                if let Some(canonical) = dictionary.variant_of.get(seg.as_str()) {
+                    seg.clone_from(canonical);
+                }
+            }
+            record("canonicalize", out);
+        }
 
-        let pass2_segments = apply_heuristics_string(pass1_segments, &self.dictionary);
-        post_process_unknowns_string(pass2_segments, &self.dictionary)
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            monotonic_counter.segmented_chars = n as u64,
+            monotonic_counter.segments = out.len() as u64,
+        );
     }
+}
 
-    // 1BRC: Optimized snap_invalid_single_consonants with inline char extraction
-    #[inline]
-    fn snap_invalid_single_consonants_fast(&self, segments: &[String]) -> Vec<String> {
-        let mut pass1_segments: Vec<String> = Vec::with_capacity(segments.len());
-
-        for (j, seg) in segments.iter().enumerate() {
-            // 1BRC: Use fast inline first char + length extraction
-            let (first_char, seg_len) = get_first_char_and_len(seg);
-
-            let is_invalid_single = seg_len == 1
-                && !is_valid_single_word(first_char)
-                && !self.dictionary.contains(seg)
-                && !is_digit(first_char)
-                && !is_separator(first_char);
-
-            if is_invalid_single {
-                // Check Valid Context (surrounded by separators?)
-                let mut prev_is_sep = false;
-                if !pass1_segments.is_empty() {
-                    let prev_seg = pass1_segments.last().unwrap();
-                    let p_char = get_first_char(prev_seg);
-                    if is_separator(p_char) || prev_seg == " " || prev_seg == "\u{200b}" {
-                        prev_is_sep = true;
-                    }
-                } else if j == 0 {
-                    prev_is_sep = true;
-                }
+/// Relax the arc `i..j` reaching `j` at `new_cost`: it replaces the best path
+/// to `j` only if strictly cheaper. With `arcs`, the arc is also recorded.
+#[inline(always)]
+fn relax(
+    dp_cost: &mut [Cost],
+    dp_parent: &mut [isize],
+    arcs: &mut Option<&mut Vec<TraceArc>>,
+    kind: ArcKind,
+    i: usize,
+    j: usize,
+    new_cost: Cost,
+) {
+    let improved = new_cost < dp_cost[j];
+    if let Some(arcs) = arcs.as_deref_mut() {
+        let cost = new_cost - dp_cost[i];
+        arcs.push(TraceArc { start: i, end: j, text: String::new(), kind, cost, total: new_cost, improved, won: false });
+    }
+    if improved {
+        dp_cost[j] = new_cost;
+        dp_parent[j] = i as isize;
+    }
+}
 
-                let mut next_is_sep = false;
-                if j + 1 < segments.len() {
-                    let next_seg = &segments[j + 1];
-                    let n_char = get_first_char(next_seg);
-                    if is_separator(n_char) || next_seg == " " || next_seg == "\u{200b}" {
-                        next_is_sep = true;
-                    }
-                } else {
-                    next_is_sep = true;
-                }
+/// Whether `seg` is all whitespace (and not empty).
+fn is_whitespace_run(seg: &str) -> bool {
+    !seg.is_empty() && seg.chars().all(char::is_whitespace)
+}
 
-                if prev_is_sep && next_is_sep {
-                    pass1_segments.push(seg.clone());
-                    continue;
-                }
+/// Join consecutive whitespace segments into one.
+fn merge_whitespace_runs(segments: Vec<String>) -> Vec<String> {
+    let mut merged: Vec<String> = Vec::with_capacity(segments.len());
+    for seg in segments {
+        match merged.last_mut() {
+            Some(prev) if is_whitespace_run(prev) && is_whitespace_run(&seg) => prev.push_str(&seg),
+            _ => merged.push(seg),
+        }
+    }
+    merged
+}
 
-                if !pass1_segments.is_empty() {
-                    let p_char = get_first_char(pass1_segments.last().unwrap());
-                    if !is_separator(p_char) {
-                        let prev = pass1_segments.pop().unwrap();
-                        pass1_segments.push(prev + seg);
-                    } else {
-                        pass1_segments.push(seg.clone());
-                    }
-                } else {
-                    pass1_segments.push(seg.clone());
+/// Respell `segments`, which concatenate to `text` with some codepoints
+/// replaced one for one, as in `text`. Left as they are if a stage dropped
+/// or added characters.
+fn restore_spelling(text: &str, segments: &mut [String]) {
+    let total: usize = segments.iter().map(|seg| seg.chars().count()).sum();
+    if total != text.chars().count() {
+        return;
+    }
+    let mut chars = text.chars();
+    for seg in segments {
+        let len = seg.chars().count();
+        seg.clear();
+        seg.extend(chars.by_ref().take(len));
+    }
+}
+
+/// Put the zero-width spaces of `text` back into `segments`, which
+/// concatenate to `text` without them: each ZWSP between two segments
+/// becomes a segment of its own, one inside a segment stays there.
+fn restore_zwsp(text: &str, segments: Vec<String>) -> Vec<String> {
+    let mut restored = Vec::with_capacity(segments.len());
+    let mut chars = text.chars().peekable();
+    for seg in segments {
+        while chars.next_if_eq(&'\u{200b}').is_some() {
+            restored.push("\u{200b}".to_string());
+        }
+        let mut with_zwsp = String::with_capacity(seg.len());
+        for (k, _) in seg.chars().enumerate() {
+            if k > 0 {
+                while chars.next_if_eq(&'\u{200b}').is_some() {
+                    with_zwsp.push('\u{200b}');
                 }
-            } else {
-                pass1_segments.push(seg.clone());
             }
+            with_zwsp.extend(chars.next());
         }
-
-        pass1_segments
+        restored.push(with_zwsp);
     }
+    restored.extend(chars.map(String::from));
+    restored
 }
 
-// ============================================================================
-// 1BRC: Fast inline helper functions (avoid .chars().collect())
-// ============================================================================
-
-/// Get first char without allocating Vec<char>
-#[inline]
-fn get_first_char(s: &str) -> char {
-    s.chars().next().unwrap_or(' ')
+/// Map segments (which concatenate to `text`, possibly minus ZWSP) back to
+/// byte spans in `text`.
+pub(crate) fn segments_to_spans(text: &str, segments: &[String]) -> Vec<Span> {
+    let mut spans = Vec::with_capacity(segments.len());
+    extend_spans(text, segments, &mut spans);
+    spans
 }
 
-/// Get first char and codepoint length without allocating Vec<char>
-#[inline]
-fn get_first_char_and_len(s: &str) -> (char, usize) {
-    let mut first = ' ';
-    let mut count = 0;
-    for c in s.chars() {
-        if count == 0 {
-            first = c;
+/// `segments_to_spans`, appending to `spans`.
+fn extend_spans(text: &str, segments: &[String], spans: &mut Vec<Span>) {
+    let mut chars = text.char_indices().peekable();
+
+    for seg in segments {
+        let mut start = None;
+        let mut end = 0;
+        for seg_char in seg.chars() {
+            if seg_char != '\u{200b}' {
+                while chars.next_if(|&(_, c)| c == '\u{200b}').is_some() {}
+            }
+            if let Some((i, c)) = chars.next() {
+                start.get_or_insert(i);
+                end = i + c.len_utf8();
+            }
         }
-        count += 1;
+        spans.push(Span::new(start.unwrap_or(end), end));
     }
-    (first, count)
 }
 
 // Helpers - Codepoint-based versions
 
+/// A number, a range of two numbers if `ranges`, and an attached unit.
 #[inline]
-fn get_khmer_cluster_length_cps(cps: &[char], start: usize) -> usize {
-    if start >= cps.len() {
+fn get_number_length_cps(cps: &[char], start: usize, format: NumberFormat, ranges: bool) -> usize {
+    let len = get_amount_length_cps(cps, start, format, ranges);
+    if len == 0 {
         return 0;
     }
+    len + get_unit_length_cps(cps, start + len)
+}
 
-    let first_char = cps[start];
-    let code = first_char as u32;
-
-    // Must start with Base Consonant (1780-17A2) or Indep Vowel (17A3-17B3)
-    if !((code >= 0x1780 && code <= 0x17B3)) {
-        return 1;
+/// A number with a currency symbol or code right before or after it, or
+/// separated by one space from a code.
+#[inline]
+fn get_currency_amount_length_cps<S: AsRef<str>>(
+    cps: &[char],
+    start: usize,
+    currencies: &[S],
+    format: NumberFormat,
+    ranges: bool,
+) -> usize {
+    let is_code = |from: usize, len: usize| cps[from..from + len].iter().all(|c| c.is_alphabetic());
+
+    let prefix = currency_length_cps(cps, start, currencies);
+    if prefix > 0 {
+        let mut j = start + prefix;
+        if is_code(start, prefix) && cps.get(j) == Some(&' ') {
+            j += 1;
+        }
+        return match get_amount_length_cps(cps, j, format, ranges) {
+            0 => 0,
+            amount => j + amount - start,
+        };
     }
 
-    let mut len = 1;
-    let mut i = start + 1;
+    let amount = get_amount_length_cps(cps, start, format, ranges);
+    if amount == 0 {
+        return 0;
+    }
+    let j = start + amount;
+    match currency_length_cps(cps, j, currencies) {
+        0 if cps.get(j) == Some(&' ') => match currency_length_cps(cps, j + 1, currencies) {
+            len if len > 0 && is_code(j + 1, len) => amount + 1 + len,
+            _ => 0,
+        },
+        0 => 0,
+        len => amount + len,
+    }
+}
 
-    while i < cps.len() {
-        let c = cps[i];
+/// A number, or a range of two numbers if `ranges`.
+#[inline]
+fn get_amount_length_cps(cps: &[char], start: usize, format: NumberFormat, ranges: bool) -> usize {
+    let mut len = get_digits_length_cps(cps, start, format);
+    if len == 0 {
+        return 0;
+    }
+    let dash = start + len;
+    if ranges && matches!(cps.get(dash), Some('-' | '–')) {
+        len += match get_digits_length_cps(cps, dash + 1, format) {
+            0 => 0,
+            second => 1 + second,
+        };
+    }
+    len
+}
 
-        // Coeng
-        if is_coeng(c) {
-            // Check if next is consonant
-            if i + 1 < cps.len() && is_consonant(cps[i + 1]) {
-                len += 2;
-                i += 2;
-                continue;
-            }
-            break;
-        }
+#[inline]
+fn get_digit_run_cps(cps: &[char], start: usize) -> usize {
+    cps.get(start..).map_or(0, |rest| rest.iter().take_while(|&&c| is_digit(c)).count())
+}
 
-        if is_dependent_vowel(c) || is_sign(c) {
-            len += 1;
-            i += 1;
-            continue;
+#[inline]
+fn get_digits_length_cps(cps: &[char], start: usize, format: NumberFormat) -> usize {
+    let (group, decimal) = match format {
+        NumberFormat::Any => return get_any_digits_length_cps(cps, start),
+        NumberFormat::DecimalPoint => (',', '.'),
+        NumberFormat::DecimalComma => ('.', ','),
+    };
+
+    let lead = get_digit_run_cps(cps, start);
+    if lead == 0 {
+        return 0;
+    }
+    let mut end = start + lead;
+    // Groups of exactly three digits after a leading group of one to three
+    if lead <= 3 {
+        while matches!(cps.get(end), Some(&c) if c == group || c == ' ') && get_digit_run_cps(cps, end + 1) == 3 {
+            end += 4;
         }
-
-        break;
     }
-
-    len
+    if cps.get(end) == Some(&decimal) {
+        end += match get_digit_run_cps(cps, end + 1) {
+            0 => 0,
+            decimals => 1 + decimals,
+        };
+    }
+    end - start
 }
 
 #[inline]
-fn get_number_length_cps(cps: &[char], start: usize) -> usize {
+fn get_any_digits_length_cps(cps: &[char], start: usize) -> usize {
     if start >= cps.len() {
         return 0;
     }
@@ -398,12 +1237,10 @@ fn get_number_length_cps(cps: &[char], start: usize) -> usize {
         }
 
         // Separators: , . space
-        if c == ',' || c == '.' || c == ' ' {
-            if i + 1 < cps.len() && is_digit(cps[i + 1]) {
-                last_valid_len = i - start + 2;
-                i += 2;
-                continue;
-            }
+        if (c == ',' || c == '.' || c == ' ') && i + 1 < cps.len() && is_digit(cps[i + 1]) {
+            last_valid_len = i - start + 2;
+            i += 2;
+            continue;
         }
         break;
     }
@@ -411,6 +1248,41 @@ fn get_number_length_cps(cps: &[char], start: usize) -> usize {
     last_valid_len
 }
 
+/// Units attached to a number with no space in between (`10kg`, `30%`,
+/// `៥គ.ម`), longest first where one is a prefix of another.
+const UNITS: &[&str] = &[
+    "%", "‰", "°C", "°F", "°", "km/h", "km²", "km", "kg", "cm", "mm", "mg", "ml", "m²", "m³", "m", "g", "l", "ha",
+    "គ.ម", "គ.ក", "ស.ម", "ម.ម", "ហ.ត",
+];
+
+#[inline]
+fn get_unit_length_cps(cps: &[char], start: usize) -> usize {
+    for unit in UNITS {
+        let len = unit.chars().count();
+        if !cps.get(start..start + len).is_some_and(|s| s.iter().copied().eq(unit.chars())) {
+            continue;
+        }
+        let next = cps.get(start + len).copied();
+        let last = cps[start + len - 1];
+        if is_latin_letter(last) {
+            // Not the start of a word (`5men`)
+            if next.is_some_and(|c| c.is_ascii_alphanumeric() || is_latin_letter(c)) {
+                continue;
+            }
+        } else if is_khmer_char(last) {
+            // Not the start of a syllable, with the abbreviation's final dot
+            if next.is_some_and(|c| is_dependent_vowel(c) || is_sign(c) || is_coeng(c)) {
+                continue;
+            }
+            if next == Some('.') {
+                return len + 1;
+            }
+        }
+        return len;
+    }
+    0
+}
+
 #[inline]
 fn get_acronym_length_cps(cps: &[char], start: usize) -> usize {
     let mut current = start;
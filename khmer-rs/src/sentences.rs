@@ -0,0 +1,137 @@
+//! Sentence splitting for Khmer and mixed Khmer/Latin text.
+//!
+//! Boundaries are placed after:
+//! - Khmer khan `។` (U+17D4) and bariyoosan `៕` (U+17D5)
+//! - Latin `!` and `?`
+//! - Latin `.` when followed by whitespace or end of text (so acronyms such
+//!   as `ស.ភ.ភ` and decimals such as `1.5` are left alone)
+//!
+//! Runs of terminators (`?!`, `។។`) stay together, and closing quotes or
+//! brackets right after a terminator belong to the sentence they close. A
+//! terminator followed by the repetition mark `ៗ` (U+17D7) is not a boundary.
+//! No boundary is placed inside `«…»`, `“…”` or `"…"`; if the text leaves a
+//! quote unbalanced, quotes are ignored so one stray `«` cannot swallow the
+//! rest of the text.
+
+use crate::algorithm::Segmenter;
+use crate::span::Span;
+
+#[inline]
+fn is_terminator(c: char) -> bool {
+    matches!(c, '\u{17D4}' | '\u{17D5}' | '!' | '?')
+}
+
+#[inline]
+fn is_closing(c: char) -> bool {
+    matches!(c, '»' | '”' | ')' | ']')
+}
+
+/// Split `text` into sentence spans, trimmed of surrounding whitespace.
+pub fn split_sentences(text: &str) -> Vec<Span> {
+    let (spans, balanced) = split_with_quotes(text, true);
+    if balanced {
+        spans
+    } else {
+        split_with_quotes(text, false).0
+    }
+}
+
+#[derive(Default)]
+struct QuoteState {
+    guillemet_depth: usize,
+    curly_depth: usize,
+    in_straight_quote: bool,
+}
+
+impl QuoteState {
+    fn update(&mut self, c: char) {
+        match c {
+            '«' => self.guillemet_depth += 1,
+            '»' => self.guillemet_depth = self.guillemet_depth.saturating_sub(1),
+            '“' => self.curly_depth += 1,
+            '”' => self.curly_depth = self.curly_depth.saturating_sub(1),
+            '"' => self.in_straight_quote = !self.in_straight_quote,
+            _ => {}
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.guillemet_depth > 0 || self.curly_depth > 0 || self.in_straight_quote
+    }
+}
+
+/// Returns the spans and whether every quote was closed.
+fn split_with_quotes(text: &str, balance_quotes: bool) -> (Vec<Span>, bool) {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let n = chars.len();
+    let mut spans = Vec::new();
+    let mut sentence_start = 0;
+    let mut quotes = QuoteState::default();
+    let mut i = 0;
+
+    while i < n {
+        let c = chars[i].1;
+        if balance_quotes {
+            quotes.update(c);
+        }
+
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        let is_end_mark = is_terminator(c)
+            || (c == '.' && next.is_none_or(|n| n.is_whitespace() || is_closing(n)));
+        if !is_end_mark || next == Some('\u{17D7}') {
+            i += 1;
+            continue;
+        }
+
+        // Absorb further terminators and closing quotes/brackets
+        let mut end = i + 1;
+        while end < n && (is_terminator(chars[end].1) || chars[end].1 == '.' || is_closing(chars[end].1)) {
+            if balance_quotes {
+                quotes.update(chars[end].1);
+            }
+            end += 1;
+        }
+
+        if !quotes.is_open() {
+            let end_byte = chars.get(end).map_or(text.len(), |&(b, _)| b);
+            push_trimmed(text, sentence_start, end_byte, &mut spans);
+            sentence_start = end_byte;
+        }
+        i = end;
+    }
+
+    push_trimmed(text, sentence_start, text.len(), &mut spans);
+
+    (spans, !quotes.is_open())
+}
+
+fn push_trimmed(text: &str, start: usize, end: usize, spans: &mut Vec<Span>) {
+    let slice = &text[start..end];
+    let trimmed_start = slice.len() - slice.trim_start().len();
+    let trimmed = slice.trim();
+    if !trimmed.is_empty() {
+        let s = start + trimmed_start;
+        spans.push(Span::new(s, s + trimmed.len()));
+    }
+}
+
+/// Segment `text` one sentence at a time, keeping the whitespace between
+/// sentences as its own segments. The DP never spans a sentence boundary and
+/// each call works on a shorter input.
+pub fn segment_by_sentence<S: Segmenter + ?Sized>(segmenter: &S, text: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut pos = 0;
+
+    for span in split_sentences(text) {
+        if span.start > pos {
+            segments.extend(segmenter.segment(&text[pos..span.start]));
+        }
+        segments.extend(segmenter.segment(span.as_str(text)));
+        pos = span.end;
+    }
+    if pos < text.len() {
+        segments.extend(segmenter.segment(&text[pos..]));
+    }
+
+    segments
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// Byte range `[start, end)` into the original input text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Slice of `text` covered by this span.
+    pub fn as_str<'a>(&self, text: &'a str) -> &'a str {
+        &text[self.start..self.end]
+    }
+}
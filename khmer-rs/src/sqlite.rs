@@ -0,0 +1,125 @@
+//! SQLite output (`--format sqlite`, `sqlite` feature): lines and their
+//! tokens in normalized tables, for querying results with SQL.
+//!
+//! ```sql
+//! -- The most frequent unknown tokens
+//! SELECT text, COUNT(*) AS n FROM tokens WHERE kind = 'unknown' GROUP BY text ORDER BY n DESC LIMIT 20;
+//! ```
+//!
+//! Rows are inserted in transactions of `batch_lines` lines; the index on
+//! `tokens.text` is built once at the end, which is faster than keeping it up
+//! to date on every insert.
+
+use std::io;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::algorithm::Segmenter;
+use crate::output::Record;
+use crate::schema::SCHEMA_VERSION;
+
+/// Tables of the database; `meta` holds `schema_version`.
+pub const SCHEMA: &str = "
+CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+CREATE TABLE lines (id INTEGER PRIMARY KEY, input TEXT NOT NULL);
+CREATE TABLE tokens (
+    line_id INTEGER NOT NULL REFERENCES lines (id),
+    position INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    kind TEXT,
+    cost REAL,
+    filtered INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (line_id, position)
+) WITHOUT ROWID;
+";
+
+/// Writes records into a new SQLite database.
+pub struct SqliteWriter {
+    conn: Connection,
+    batch_lines: usize,
+    /// Lines written in the open transaction
+    pending: usize,
+    lines: usize,
+}
+
+fn sql_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+impl SqliteWriter {
+    pub const DEFAULT_BATCH_LINES: usize = 10_000;
+
+    /// Create the database at `path`, replacing any file there.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let conn = Connection::open(path).map_err(sql_error)?;
+        conn.execute_batch("PRAGMA journal_mode = OFF; PRAGMA synchronous = OFF;").map_err(sql_error)?;
+        conn.execute_batch(SCHEMA).map_err(sql_error)?;
+        conn.execute("INSERT INTO meta (key, value) VALUES ('schema_version', ?1)", params![SCHEMA_VERSION.to_string()])
+            .map_err(sql_error)?;
+        Ok(SqliteWriter { conn, batch_lines: SqliteWriter::DEFAULT_BATCH_LINES, pending: 0, lines: 0 })
+    }
+
+    /// Commit every `lines` lines instead of `DEFAULT_BATCH_LINES`.
+    pub fn batch_lines(mut self, lines: usize) -> Self {
+        self.batch_lines = lines.max(1);
+        self
+    }
+
+    /// Insert one record, with the kind and dictionary cost `segmenter`
+    /// gives each token (`NULL` where it has none).
+    pub fn write_record<S: Segmenter + ?Sized>(&mut self, record: &Record<'_>, segmenter: &S) -> io::Result<()> {
+        if self.pending == 0 {
+            self.conn.execute_batch("BEGIN").map_err(sql_error)?;
+        }
+        self.conn
+            .prepare_cached("INSERT INTO lines (id, input) VALUES (?1, ?2)")
+            .and_then(|mut insert| insert.execute(params![record.id as i64, record.input]))
+            .map_err(sql_error)?;
+        let mut insert = self
+            .conn
+            .prepare_cached(
+                "INSERT INTO tokens (line_id, position, text, kind, cost, filtered) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .map_err(sql_error)?;
+        let filtered = record.filtered.as_deref().unwrap_or_default();
+        for (position, &text) in record.segments.iter().enumerate() {
+            let kind = segmenter.token_kind(text).map(|kind| kind.name());
+            let cost = segmenter.word_cost(text);
+            let is_filtered = filtered.contains(&position);
+            insert
+                .execute(params![record.id as i64, position as i64, text, kind, cost, is_filtered])
+                .map_err(sql_error)?;
+        }
+        drop(insert);
+        self.lines += 1;
+        self.pending += 1;
+        if self.pending >= self.batch_lines {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self) -> io::Result<()> {
+        if self.pending > 0 {
+            self.conn.execute_batch("COMMIT").map_err(sql_error)?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+
+    /// Lines written so far.
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// Commit the last lines and index the tokens by text.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.commit()?;
+        self.conn.execute_batch("CREATE INDEX tokens_text ON tokens (text);").map_err(sql_error)
+    }
+}
@@ -0,0 +1,76 @@
+//! Dictionary statistics for curation: how many entries come from the word
+//! list vs variant generation, how long they are, and what they cost in memory.
+//!
+//! Memory figures are estimates computed from container capacities and type
+//! sizes (hash table control bytes included), not allocator measurements.
+
+use std::mem::size_of;
+
+use fxhash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::dictionary::{Cost, Dictionary};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DictionaryStats {
+    /// Entries listed in the word file (after filtering)
+    pub base_words: usize,
+    /// Entries added by variant generation
+    pub variant_words: usize,
+    /// `length_histogram[n]` is the number of entries with `n` codepoints
+    pub length_histogram: Vec<usize>,
+    pub trie_nodes: usize,
+    /// Estimated heap bytes of the trie
+    pub trie_bytes: usize,
+    /// Estimated heap bytes of the word map, cost table and variant map
+    pub table_bytes: usize,
+}
+
+impl DictionaryStats {
+    pub fn total_words(&self) -> usize {
+        self.base_words + self.variant_words
+    }
+
+    pub fn estimated_bytes(&self) -> usize {
+        self.trie_bytes + self.table_bytes
+    }
+
+    /// Mean entry length in codepoints.
+    pub fn mean_length(&self) -> f64 {
+        let total = self.total_words();
+        if total == 0 {
+            return 0.0;
+        }
+        let sum: usize = self.length_histogram.iter().enumerate().map(|(len, &n)| len * n).sum();
+        sum as f64 / total as f64
+    }
+}
+
+impl Dictionary {
+    pub fn stats(&self) -> DictionaryStats {
+        let mut length_histogram = vec![0; self.max_word_length + 1];
+        for word in self.words.keys() {
+            length_histogram[word.chars().count()] += 1;
+        }
+
+        let (trie_nodes, trie_bytes) = (self.trie.len(), self.trie.heap_bytes());
+        let table_bytes = map_bytes(&self.words, |w, _| w.capacity())
+            + self.costs.capacity() * size_of::<Cost>()
+            + map_bytes(&self.variant_of, |v, c| v.capacity() + c.capacity());
+
+        DictionaryStats {
+            base_words: self.words.len() - self.variant_of.len(),
+            variant_words: self.variant_of.len(),
+            length_histogram,
+            trie_nodes,
+            trie_bytes,
+            table_bytes,
+        }
+    }
+}
+
+/// Table slots (entry + one control byte each) plus per-entry heap data.
+fn map_bytes<K, V>(map: &FxHashMap<K, V>, heap: impl Fn(&K, &V) -> usize) -> usize {
+    let slots = map.capacity() * (size_of::<(K, V)>() + 1);
+    slots + map.iter().map(|(k, v)| heap(k, v)).sum::<usize>()
+}
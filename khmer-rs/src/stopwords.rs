@@ -0,0 +1,163 @@
+//! Stop words and output token filters, for search indexing and keyword
+//! pipelines that want content words only (`--stop-words`,
+//! `--filter-separators`).
+//!
+//! A stop-word file has one word per line; blank lines and `#` comments are
+//! skipped. `stopwords/khmer.txt` is the built-in list.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use fxhash::FxHashSet;
+
+use crate::constants::is_separator;
+
+const KHMER: &str = include_str!("../stopwords/khmer.txt");
+
+/// A set of stop words, matched exactly.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StopWords {
+    words: FxHashSet<String>,
+}
+
+impl StopWords {
+    pub fn new() -> Self {
+        StopWords::default()
+    }
+
+    /// The built-in list of Khmer function words (`stopwords/khmer.txt`).
+    pub fn khmer() -> Self {
+        StopWords::from_reader(KHMER.as_bytes()).expect("built-in stop words are valid UTF-8")
+    }
+
+    pub fn open(path: &Path) -> io::Result<Self> {
+        StopWords::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut stop_words = StopWords::new();
+        for line in reader.lines() {
+            let line = line?;
+            let word = line.split('#').next().unwrap_or("").trim();
+            if !word.is_empty() {
+                stop_words.insert(word);
+            }
+        }
+        Ok(stop_words)
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        self.words.insert(word.to_string());
+    }
+
+    pub fn contains(&self, token: &str) -> bool {
+        self.words.contains(token)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for StopWords {
+    fn from_iter<I: IntoIterator<Item = S>>(words: I) -> Self {
+        let mut stop_words = StopWords::new();
+        for word in words {
+            stop_words.insert(word.as_ref());
+        }
+        stop_words
+    }
+}
+
+/// What a `TokenFilter` does with the tokens it matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FilterMode {
+    /// Leave them out of the output.
+    #[default]
+    Drop,
+    /// Keep them, and list their positions.
+    Tag,
+}
+
+impl FilterMode {
+    pub const ALL: [FilterMode; 2] = [FilterMode::Drop, FilterMode::Tag];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FilterMode::Drop => "drop",
+            FilterMode::Tag => "tag",
+        }
+    }
+}
+
+impl fmt::Display for FilterMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for FilterMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FilterMode::ALL.into_iter().find(|mode| mode.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = FilterMode::ALL.iter().map(|mode| mode.name()).collect();
+            format!("unknown filter mode {:?} (expected one of {})", s, names.join(", "))
+        })
+    }
+}
+
+/// Which output tokens to filter: stop words, separators (punctuation and
+/// whitespace), or both. Matches nothing by default.
+#[derive(Debug, Clone, Default)]
+pub struct TokenFilter {
+    stop_words: Option<StopWords>,
+    separators: bool,
+}
+
+impl TokenFilter {
+    pub fn new() -> Self {
+        TokenFilter::default()
+    }
+
+    /// Match the words of `stop_words`.
+    pub fn stop_words(mut self, stop_words: StopWords) -> Self {
+        self.stop_words = Some(stop_words);
+        self
+    }
+
+    /// Match tokens made only of separator characters or whitespace.
+    pub fn separators(mut self, separators: bool) -> Self {
+        self.separators = separators;
+        self
+    }
+
+    /// Whether the filter can match anything.
+    pub fn is_active(&self) -> bool {
+        self.stop_words.is_some() || self.separators
+    }
+
+    pub fn matches(&self, token: &str) -> bool {
+        if self.stop_words.as_ref().is_some_and(|words| words.contains(token)) {
+            return true;
+        }
+        self.separators && !token.is_empty() && token.chars().all(|c| is_separator(c) || c.is_whitespace())
+    }
+
+    /// The segments the filter does not match.
+    pub fn retain<'a, S: AsRef<str>>(&self, segments: &'a [S]) -> Vec<&'a str> {
+        segments.iter().map(AsRef::as_ref).filter(|segment| !self.matches(segment)).collect()
+    }
+
+    /// Indexes of the segments the filter matches.
+    pub fn positions<S: AsRef<str>>(&self, segments: &[S]) -> Vec<usize> {
+        segments.iter().enumerate().filter(|(_, segment)| self.matches(segment.as_ref())).map(|(i, _)| i).collect()
+    }
+}
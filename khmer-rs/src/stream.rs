@@ -0,0 +1,62 @@
+//! Lazy segmentation (`KhmerSegmenter::segment_iter`), for callers that
+//! only need the first tokens of a long text, such as a prefix display.
+//!
+//! The Viterbi pass needs a whole stretch of text before it can return its
+//! first token, so the text is cut at sentence boundaries
+//! (`sentences::split_sentences`) and each sentence is segmented when the
+//! iterator reaches it. The tokens are those of `segment_by_sentence`.
+
+use std::vec;
+
+use crate::segmenter::KhmerSegmenter;
+use crate::sentences::split_sentences;
+use crate::span::Span;
+use crate::token::Token;
+
+impl KhmerSegmenter {
+    /// Tokens of `text`, as `segment_tokens` gives them for each sentence,
+    /// segmenting one sentence at a time as they are consumed. Spans are
+    /// byte ranges into `text`.
+    pub fn segment_iter<'a>(&'a self, text: &'a str) -> SegmentIter<'a> {
+        // Sentences, and the whitespace between them as chunks of their own
+        let mut chunks = Vec::new();
+        let mut pos = 0;
+        for span in split_sentences(text) {
+            if span.start > pos {
+                chunks.push(Span::new(pos, span.start));
+            }
+            chunks.push(span);
+            pos = span.end;
+        }
+        if pos < text.len() {
+            chunks.push(Span::new(pos, text.len()));
+        }
+        SegmentIter { segmenter: self, text, chunks: chunks.into_iter(), tokens: Vec::new().into_iter() }
+    }
+}
+
+/// Iterator returned by `KhmerSegmenter::segment_iter`.
+pub struct SegmentIter<'a> {
+    segmenter: &'a KhmerSegmenter,
+    text: &'a str,
+    chunks: vec::IntoIter<Span>,
+    tokens: vec::IntoIter<Token>,
+}
+
+impl Iterator for SegmentIter<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        loop {
+            if let Some(token) = self.tokens.next() {
+                return Some(token);
+            }
+            let chunk = self.chunks.next()?;
+            let mut tokens = self.segmenter.segment_tokens(chunk.as_str(self.text));
+            for token in &mut tokens {
+                token.span = Span::new(token.span.start + chunk.start, token.span.end + chunk.start);
+            }
+            self.tokens = tokens.into_iter();
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Spelling suggestions, prefix completion and typo-tolerant lookup over the
+//! dictionary trie.
+//!
+//! For `suggest`, distances are Levenshtein distances counted in Khmer clusters (see
+//! `clusters::khmer_clusters`), so a wrong vowel on a consonant stack costs 1
+//! rather than the number of codepoints involved.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::clusters::khmer_clusters;
+use crate::constants::{is_coeng, is_consonant, is_dependent_vowel, is_sign};
+use crate::dictionary::{Cost, Dictionary, Trie, TrieNode};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub word: String,
+    /// Edit distance in clusters
+    pub distance: usize,
+    pub cost: Cost,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub word: String,
+    pub cost: Cost,
+}
+
+/// Max-heap entry ordered by cost, so the worst kept completion is on top.
+struct ByCost(Completion);
+
+impl PartialEq for ByCost {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ByCost {}
+
+impl PartialOrd for ByCost {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByCost {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cost.total_cmp(&other.0.cost).then_with(|| self.0.word.cmp(&other.0.word))
+    }
+}
+
+impl Dictionary {
+    /// Up to `limit` dictionary words starting with `prefix`, lowest cost
+    /// (most frequent) first. The prefix itself is included if it is a word.
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<Completion> {
+        if limit == 0 {
+            return Vec::new();
+        }
+
+        let mut node = self.trie.root();
+        for c in prefix.chars() {
+            match self.trie.child(node, c) {
+                Some(child) => node = child,
+                None => return Vec::new(),
+            }
+        }
+
+        let mut heap = BinaryHeap::with_capacity(limit + 1);
+        let mut path = prefix.to_string();
+        collect_completions(&self.trie, node, &mut path, limit, &mut heap);
+
+        heap.into_sorted_vec().into_iter().map(|c| c.0).collect()
+    }
+
+    /// Dictionary words starting at `cps[start]` that match the input with
+    /// exactly one dependent-vowel/sign edit (substitution, extra mark in the
+    /// input, or missing mark). Appends `(end, cost)` pairs to `out`; `end` is
+    /// the input position after the match, `cost` the word's own cost.
+    ///
+    /// Exact matches are not reported; use `common_prefixes` for those.
+    pub fn fuzzy_prefixes(&self, cps: &[char], start: usize, out: &mut Vec<(usize, Cost)>) {
+        fuzzy_walk(&self.trie, self.trie.root(), cps, start, start, false, out);
+    }
+
+    /// Dictionary words within `max_distance` cluster edits of `word`, closest
+    /// first, then by lowest cost.
+    ///
+    /// The trie is walked once; branches are pruned as soon as every alignment
+    /// exceeds `max_distance`, so small distances stay cheap.
+    pub fn suggest(&self, word: &str, max_distance: usize) -> Vec<Suggestion> {
+        let query: Vec<&str> = khmer_clusters(word).map(|s| s.as_str(word)).collect();
+        let first_row: Vec<usize> = (0..=query.len()).collect();
+
+        let mut walk = SuggestWalk { trie: &self.trie, query, max_distance, path: String::new(), results: Vec::new() };
+        walk.visit(self.trie.root(), 0, &first_row);
+
+        let mut results = walk.results;
+        results.sort_by(|a, b| {
+            a.distance
+                .cmp(&b.distance)
+                .then(a.cost.total_cmp(&b.cost))
+                .then_with(|| a.word.cmp(&b.word))
+        });
+        results
+    }
+}
+
+fn collect_completions(trie: &Trie, node: &TrieNode, path: &mut String, limit: usize, heap: &mut BinaryHeap<ByCost>) {
+    if node.is_word {
+        let worse_than_all = heap.len() == limit && heap.peek().is_some_and(|top| node.cost >= top.0.cost);
+        if !worse_than_all {
+            heap.push(ByCost(Completion { word: path.clone(), cost: node.cost }));
+            if heap.len() > limit {
+                heap.pop();
+            }
+        }
+    }
+
+    for (c, child) in trie.children(node) {
+        path.push(c);
+        collect_completions(trie, child, path, limit, heap);
+        path.pop();
+    }
+}
+
+#[inline]
+fn is_mark(c: char) -> bool {
+    is_dependent_vowel(c) || is_sign(c)
+}
+
+fn fuzzy_walk(
+    trie: &Trie,
+    node: &TrieNode,
+    cps: &[char],
+    start: usize,
+    pos: usize,
+    edited: bool,
+    out: &mut Vec<(usize, Cost)>,
+) {
+    if edited && node.is_word && pos > start {
+        out.push((pos, node.cost));
+    }
+
+    let input = cps.get(pos).copied();
+    if let Some(child) = input.and_then(|c| trie.child(node, c)) {
+        fuzzy_walk(trie, child, cps, start, pos + 1, edited, out);
+    }
+    if edited {
+        return;
+    }
+
+    // Extra mark in the input (never the first character of the word)
+    if let Some(c) = input {
+        if pos > start && is_mark(c) {
+            fuzzy_walk(trie, node, cps, start, pos + 1, true, out);
+        }
+    }
+
+    for (trie_char, child) in trie.children(node) {
+        if !is_mark(trie_char) || Some(trie_char) == input {
+            continue;
+        }
+        // Substituted mark
+        if input.is_some_and(is_mark) {
+            fuzzy_walk(trie, child, cps, start, pos + 1, true, out);
+        }
+        // Mark missing from the input
+        if pos > start {
+            fuzzy_walk(trie, child, cps, start, pos, true, out);
+        }
+    }
+}
+
+struct SuggestWalk<'a, 'q> {
+    trie: &'a Trie,
+    query: Vec<&'q str>,
+    max_distance: usize,
+    path: String,
+    results: Vec<Suggestion>,
+}
+
+impl<'a> SuggestWalk<'a, '_> {
+    /// `pending_start` is the byte offset in `path` of the cluster still being
+    /// built; `row` is the DP row after all clusters before it.
+    fn visit(&mut self, node: &'a TrieNode, pending_start: usize, row: &[usize]) {
+        if node.is_word {
+            let final_row = self.next_row(row, &self.path[pending_start..]);
+            let distance = final_row[self.query.len()];
+            if distance <= self.max_distance {
+                self.results.push(Suggestion { word: self.path.clone(), distance, cost: node.cost });
+            }
+        }
+
+        for (c, child) in self.trie.children(node) {
+            let pending = &self.path[pending_start..];
+            if pending.is_empty() || extends_cluster(pending, c) {
+                self.path.push(c);
+                self.visit(child, pending_start, row);
+                self.path.pop();
+            } else {
+                let next = self.next_row(row, pending);
+                if next.iter().min().is_some_and(|&m| m > self.max_distance) {
+                    continue;
+                }
+                let start = self.path.len();
+                self.path.push(c);
+                self.visit(child, start, &next);
+                self.path.pop();
+            }
+        }
+    }
+
+    /// Levenshtein row after appending `cluster` (no-op when empty).
+    fn next_row(&self, row: &[usize], cluster: &str) -> Vec<usize> {
+        if cluster.is_empty() {
+            return row.to_vec();
+        }
+        let mut next = Vec::with_capacity(row.len());
+        next.push(row[0] + 1);
+        for (j, q) in self.query.iter().enumerate() {
+            let substitution = row[j] + usize::from(*q != cluster);
+            next.push(substitution.min(row[j + 1] + 1).min(next[j] + 1));
+        }
+        next
+    }
+}
+
+/// Whether `c` continues the cluster `pending` (mirrors `get_khmer_cluster_length_cps`).
+fn extends_cluster(pending: &str, c: char) -> bool {
+    let first = pending.chars().next().unwrap_or(' ');
+    if !('\u{1780}'..='\u{17B3}').contains(&first) {
+        return false;
+    }
+    match pending.chars().next_back() {
+        Some(last) if is_coeng(last) => is_consonant(c),
+        _ => is_coeng(c) || is_dependent_vowel(c) || is_sign(c),
+    }
+}
@@ -0,0 +1,140 @@
+//! Segments tagged with what they are.
+
+use crate::constants::{is_currency_symbol, is_digit, is_separator};
+use crate::dictionary::Dictionary;
+use crate::patterns::{
+    is_date, is_email, is_emoji, is_hashtag, is_latin_word, is_mention, is_time, is_url, parse_roman_numeral,
+};
+use crate::script::Script;
+use crate::span::Span;
+use serde::{Deserialize, Serialize};
+
+/// Serialized under its `name`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenKind {
+    /// A dictionary word
+    Word,
+    /// A numeric date or Khmer date expression, only with
+    /// `SegmenterConfig::dates`; see `patterns::parse_date` for its fields
+    Date,
+    /// A phone number matching the segmenter's `SegmenterConfig::phone_patterns`.
+    /// Only set by `KhmerSegmenter::segment_tokens`; `classify` does not
+    /// know the patterns
+    Phone,
+    /// An amount with a currency symbol or code (`$100`, `100៛`, `USD 50`);
+    /// `KhmerSegmenter::parse_currency` splits it. Like `Phone`, only set by
+    /// `segment_tokens`
+    Currency,
+    /// A time of day (`8:30`); see `patterns::parse_time` for its fields
+    Time,
+    /// Digits, with grouping separators and an optional leading currency symbol
+    Number,
+    /// Punctuation the segmenter splits on
+    Separator,
+    Whitespace,
+    Url,
+    Email,
+    Hashtag,
+    Mention,
+    /// An emoji sequence: ZWJ sequences, skin tones, flags and keycaps included
+    Emoji,
+    /// An upper-case Roman numeral standing alone (`III`); see
+    /// `patterns::parse_roman_numeral` for its value
+    RomanNumeral,
+    /// A Latin-script word, possibly with internal apostrophes or hyphens
+    Latin,
+    /// Anything else: unknown clusters, symbols, other scripts
+    Unknown,
+}
+
+impl TokenKind {
+    pub const ALL: [TokenKind; 16] = [
+        TokenKind::Word,
+        TokenKind::Date,
+        TokenKind::Phone,
+        TokenKind::Currency,
+        TokenKind::Time,
+        TokenKind::Number,
+        TokenKind::Separator,
+        TokenKind::Whitespace,
+        TokenKind::Url,
+        TokenKind::Email,
+        TokenKind::Hashtag,
+        TokenKind::Mention,
+        TokenKind::Emoji,
+        TokenKind::RomanNumeral,
+        TokenKind::Latin,
+        TokenKind::Unknown,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenKind::Word => "word",
+            TokenKind::Date => "date",
+            TokenKind::Phone => "phone",
+            TokenKind::Currency => "currency",
+            TokenKind::Time => "time",
+            TokenKind::Number => "number",
+            TokenKind::Separator => "separator",
+            TokenKind::Whitespace => "whitespace",
+            TokenKind::Url => "url",
+            TokenKind::Email => "email",
+            TokenKind::Hashtag => "hashtag",
+            TokenKind::Mention => "mention",
+            TokenKind::Emoji => "emoji",
+            TokenKind::RomanNumeral => "roman-numeral",
+            TokenKind::Latin => "latin",
+            TokenKind::Unknown => "unknown",
+        }
+    }
+
+    /// Classify a segment produced with `dictionary`.
+    pub fn classify(token: &str, dictionary: &Dictionary) -> TokenKind {
+        let mut chars = token.chars();
+        let first = match chars.next() {
+            Some(c) => c,
+            None => return TokenKind::Unknown,
+        };
+
+        if token.chars().all(|c| c.is_whitespace() || c == '\u{200b}') {
+            TokenKind::Whitespace
+        } else if dictionary.contains(token) {
+            TokenKind::Word
+        } else if is_date(token) {
+            TokenKind::Date
+        } else if is_time(token) {
+            TokenKind::Time
+        } else if is_email(token) {
+            TokenKind::Email
+        } else if is_url(token) {
+            TokenKind::Url
+        } else if is_hashtag(token) {
+            TokenKind::Hashtag
+        } else if is_mention(token) {
+            TokenKind::Mention
+        } else if is_emoji(token) {
+            TokenKind::Emoji
+        } else if parse_roman_numeral(token).is_some() && dictionary.latin_word_cost(token).is_none() {
+            TokenKind::RomanNumeral
+        } else if is_latin_word(token) {
+            TokenKind::Latin
+        } else if is_digit(first) || (is_currency_symbol(first) && chars.next().is_some_and(is_digit)) {
+            TokenKind::Number
+        } else if token.chars().all(is_separator) {
+            TokenKind::Separator
+        } else {
+            TokenKind::Unknown
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Token {
+    pub text: String,
+    /// Byte range of the token in the input (see `KhmerSegmenter::segment_spans`)
+    pub span: Span,
+    pub kind: TokenKind,
+    /// See `Script::of_token`
+    pub script: Script,
+}
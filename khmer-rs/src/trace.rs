@@ -0,0 +1,139 @@
+//! Arc-level trace of the Viterbi pass (see `KhmerSegmenter::segment_trace`).
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dictionary::Cost;
+
+/// Which step of the DP proposed an arc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArcKind {
+    /// A whole Thai, Lao or Han run
+    Bypass,
+    /// One character after a coeng or a dependent vowel, with the repair penalty
+    Repair,
+    Number,
+    /// A date, time or phone number
+    Date,
+    Currency,
+    Separator,
+    Acronym,
+    /// An email address, URL or bare domain
+    Url,
+    /// A hashtag or @mention
+    Social,
+    Emoji,
+    RomanNumeral,
+    Latin,
+    /// A dictionary word, at its bigram cost when bigrams are loaded
+    Word,
+    /// A dictionary word one vowel or sign edit away
+    Fuzzy,
+    /// An unknown Khmer cluster, with the invalid-single penalty if it applies
+    UnknownCluster,
+    /// One character of anything else
+    Unknown,
+}
+
+impl ArcKind {
+    pub const ALL: [ArcKind; 16] = [
+        ArcKind::Bypass,
+        ArcKind::Repair,
+        ArcKind::Number,
+        ArcKind::Date,
+        ArcKind::Currency,
+        ArcKind::Separator,
+        ArcKind::Acronym,
+        ArcKind::Url,
+        ArcKind::Social,
+        ArcKind::Emoji,
+        ArcKind::RomanNumeral,
+        ArcKind::Latin,
+        ArcKind::Word,
+        ArcKind::Fuzzy,
+        ArcKind::UnknownCluster,
+        ArcKind::Unknown,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ArcKind::Bypass => "bypass",
+            ArcKind::Repair => "repair",
+            ArcKind::Number => "number",
+            ArcKind::Date => "date",
+            ArcKind::Currency => "currency",
+            ArcKind::Separator => "separator",
+            ArcKind::Acronym => "acronym",
+            ArcKind::Url => "url",
+            ArcKind::Social => "social",
+            ArcKind::Emoji => "emoji",
+            ArcKind::RomanNumeral => "roman-numeral",
+            ArcKind::Latin => "latin",
+            ArcKind::Word => "word",
+            ArcKind::Fuzzy => "fuzzy",
+            ArcKind::UnknownCluster => "unknown-cluster",
+            ArcKind::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for ArcKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// One candidate arc the DP relaxed. Positions are in characters of the
+/// text the DP ran over (`Trace::text`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceArc {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    pub kind: ArcKind,
+    /// Cost of the arc itself
+    pub cost: Cost,
+    /// Cost of the best path to `start` plus `cost`
+    pub total: Cost,
+    /// Whether `total` beat the best path to `end` found so far
+    pub improved: bool,
+    /// Whether this arc is the final best way to reach `end`
+    pub won: bool,
+}
+
+/// Every arc the Viterbi pass considered, in the order it relaxed them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trace {
+    /// The text the DP ran over: the input without zero-width spaces
+    pub text: String,
+    /// Grouped by start position, positions left to right
+    pub arcs: Vec<TraceArc>,
+    /// The segments on the best path, before post-processing
+    pub segments: Vec<String>,
+}
+
+impl Trace {
+    /// The arcs considered from `position`.
+    pub fn arcs_from(&self, position: usize) -> impl Iterator<Item = &TraceArc> {
+        self.arcs.iter().filter(move |arc| arc.start == position)
+    }
+
+    /// The arcs on the best path, left to right.
+    pub fn path(&self) -> Vec<&TraceArc> {
+        let mut path = Vec::new();
+        let mut end = self.text.chars().count();
+        while end > 0 {
+            match self.arcs.iter().find(|arc| arc.won && arc.end == end) {
+                Some(arc) => {
+                    path.push(arc);
+                    end = arc.start;
+                }
+                None => break,
+            }
+        }
+        path.reverse();
+        path
+    }
+}
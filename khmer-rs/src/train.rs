@@ -0,0 +1,242 @@
+//! Learning word frequencies from a raw corpus: segment it with the current
+//! model and count the resulting words. `reestimate` repeats this, rebuilding
+//! the dictionary from each round's counts (hard EM / Viterbi training).
+//!
+//! Only tokens containing a Khmer letter are counted, matching the reference
+//! frequency file (no numbers, punctuation or Latin words).
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use rayon::prelude::*;
+
+use crate::algorithm::Segmenter;
+use crate::constants::{is_consonant, is_independent_vowel};
+use crate::dictionary::{Cost, Dictionary};
+use crate::frequency::{Frequencies, FrequencyFormat};
+use crate::segmenter::KhmerSegmenter;
+
+#[inline]
+pub(crate) fn is_countable(token: &str) -> bool {
+    token.chars().any(|c| is_consonant(c) || is_independent_vowel(c))
+}
+
+/// Segment every line in parallel and count the Khmer words.
+pub fn count_tokens<T, S>(segmenter: &T, lines: &[S]) -> HashMap<String, u64>
+where
+    T: Segmenter + ?Sized,
+    S: AsRef<str> + Sync,
+{
+    lines
+        .par_iter()
+        .fold(HashMap::new, |mut counts: HashMap<String, u64>, line| {
+            for token in segmenter.segment(line.as_ref()) {
+                if is_countable(&token) {
+                    *counts.entry(token).or_insert(0) += 1;
+                }
+            }
+            counts
+        })
+        .reduce(HashMap::new, merge_counts)
+}
+
+/// Merge the smaller map into the larger one.
+pub(crate) fn merge_counts(a: HashMap<String, u64>, b: HashMap<String, u64>) -> HashMap<String, u64> {
+    let (mut big, small) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    for (word, n) in small {
+        *big.entry(word).or_insert(0) += n;
+    }
+    big
+}
+
+/// Word counts gathered from parallel workers as lines stream by, for
+/// corpora too large for `count_tokens`. Each worker counts into a map of
+/// its own, taken from a pool for the duration of a line, so workers never
+/// share a map; `finish` merges them.
+#[derive(Default)]
+pub struct TokenCounter {
+    pool: Mutex<Vec<HashMap<String, u64>>>,
+    /// Count every token but whitespace, not only Khmer words
+    all: bool,
+}
+
+impl TokenCounter {
+    /// Count Khmer words only, as `count_tokens` does, or with `all` every
+    /// token but whitespace (numbers, punctuation, Latin words, ...).
+    pub fn new(all: bool) -> Self {
+        TokenCounter { pool: Mutex::new(Vec::new()), all }
+    }
+
+    /// Count the segments of one line. Safe to call from many threads.
+    pub fn add<S: AsRef<str>>(&self, segments: &[S]) {
+        let mut counts = self.pool.lock().unwrap().pop().unwrap_or_default();
+        for token in segments {
+            let token = token.as_ref();
+            let counted = if self.all { !token.trim().is_empty() } else { is_countable(token) };
+            if !counted {
+                continue;
+            }
+            match counts.get_mut(token) {
+                Some(n) => *n += 1,
+                None => {
+                    counts.insert(token.to_string(), 1);
+                }
+            }
+        }
+        self.pool.lock().unwrap().push(counts);
+    }
+
+    /// The merged counts of every worker.
+    pub fn finish(self) -> HashMap<String, u64> {
+        self.pool.into_inner().unwrap().into_iter().fold(HashMap::new(), merge_counts)
+    }
+}
+
+/// How one re-estimation round changed things.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Iteration {
+    /// 1-based round number
+    pub iteration: usize,
+    /// Distinct words counted in the corpus this round
+    pub vocabulary: usize,
+    /// Dev lines segmented differently than before this round
+    pub dev_changed_lines: usize,
+    /// Word boundaries added or removed across the dev set
+    pub dev_boundary_changes: usize,
+}
+
+/// Run `iterations` rounds of segment -> count -> rebuild over `corpus`.
+///
+/// Each round counts words with the current dictionary, builds a new one from
+/// those counts with `rebuild`, and swaps it into `segmenter`. `dev` is
+/// segmented after every round and compared with the previous round's output;
+/// `on_iteration` receives the comparison. Returns the last round's counts.
+pub fn reestimate<S, F>(
+    segmenter: &KhmerSegmenter,
+    corpus: &[S],
+    dev: &[S],
+    iterations: usize,
+    mut rebuild: F,
+    mut on_iteration: impl FnMut(&Iteration),
+) -> io::Result<HashMap<String, u64>>
+where
+    S: AsRef<str> + Sync,
+    F: FnMut(Frequencies) -> io::Result<Dictionary>,
+{
+    let mut counts = HashMap::new();
+    let mut previous = segment_boundaries(segmenter, dev);
+
+    for iteration in 1..=iterations {
+        counts = count_tokens(segmenter, corpus);
+        // In the order `write_frequencies` writes them, so a rebuilt
+        // dictionary has the same costs as one loaded from the written file
+        let freq_data = by_frequency(&counts).into_iter().map(|(w, &n)| (w.clone(), n as Cost)).collect();
+        segmenter.swap_dictionary(Arc::new(rebuild(freq_data)?));
+
+        let current = segment_boundaries(segmenter, dev);
+        let mut report = Iteration { iteration, vocabulary: counts.len(), dev_changed_lines: 0, dev_boundary_changes: 0 };
+        for (before, after) in previous.iter().zip(&current) {
+            if before != after {
+                report.dev_changed_lines += 1;
+                report.dev_boundary_changes += symmetric_difference(before, after);
+            }
+        }
+        on_iteration(&report);
+        previous = current;
+    }
+
+    Ok(counts)
+}
+
+/// Byte offsets of the word boundaries inside each line.
+fn segment_boundaries<S: AsRef<str> + Sync>(segmenter: &KhmerSegmenter, lines: &[S]) -> Vec<Vec<usize>> {
+    lines
+        .par_iter()
+        .map(|line| {
+            let segments = segmenter.segment(line.as_ref());
+            let mut offset = 0;
+            let mut boundaries = Vec::with_capacity(segments.len());
+            for seg in &segments[..segments.len().saturating_sub(1)] {
+                offset += seg.len();
+                boundaries.push(offset);
+            }
+            boundaries
+        })
+        .collect()
+}
+
+/// Size of the symmetric difference of two sorted lists.
+fn symmetric_difference(a: &[usize], b: &[usize]) -> usize {
+    let (mut i, mut j, mut diff) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => {
+                diff += 1;
+                i += 1;
+            }
+            Ordering::Greater => {
+                diff += 1;
+                j += 1;
+            }
+            Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    diff + (a.len() - i) + (b.len() - j)
+}
+
+/// `counts` most frequent first, ties in word order.
+fn by_frequency(counts: &HashMap<String, u64>) -> Vec<(&String, &u64)> {
+    let mut entries: Vec<(&String, &u64)> = counts.iter().collect();
+    entries.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    entries
+}
+
+/// Write `counts` most frequent first (ties in word order) in any
+/// `FrequencyFormat`, readable by `frequency::read_frequencies`. Words the
+/// format cannot hold are left out: those containing whitespace
+/// (`012 345 678`, `100 USD`) in the Counts format, which separates the count
+/// with a space, and those containing a tab or starting or ending with
+/// whitespace in TSV, whose fields are trimmed. CSV quotes such words.
+/// Returns how many were left out.
+pub fn write_frequencies<W: Write>(
+    w: &mut W,
+    counts: &HashMap<String, u64>,
+    format: FrequencyFormat,
+) -> io::Result<usize> {
+    let mut entries = by_frequency(counts);
+    let total = entries.len();
+    match format {
+        FrequencyFormat::Counts => entries.retain(|(word, _)| !word.contains(char::is_whitespace)),
+        FrequencyFormat::Tsv => entries.retain(|(word, _)| !word.contains('\t') && word.trim() == word.as_str()),
+        _ => {}
+    }
+    let skipped = total - entries.len();
+
+    if format == FrequencyFormat::Json {
+        // Same layout as the reference file: one entry per line, 4-space indent
+        writeln!(w, "{{")?;
+        for (i, (word, n)) in entries.iter().enumerate() {
+            let sep = if i + 1 < entries.len() { "," } else { "" };
+            writeln!(w, "    {}: {}{}", serde_json::to_string(word)?, n, sep)?;
+        }
+        writeln!(w, "}}")?;
+        return Ok(skipped);
+    }
+
+    for (word, n) in entries {
+        match format {
+            FrequencyFormat::Tsv => writeln!(w, "{}\t{}", word, n)?,
+            FrequencyFormat::Csv if word.contains([',', '"']) || word.trim() != word.as_str() => {
+                writeln!(w, "\"{}\",{}", word.replace('"', "\"\""), n)?
+            }
+            FrequencyFormat::Csv => writeln!(w, "{},{}", word, n)?,
+            _ => writeln!(w, "{:>7} {}", n, word)?,
+        }
+    }
+    Ok(skipped)
+}
@@ -0,0 +1,135 @@
+//! Lexicon hygiene checks for dictionary word lists.
+//!
+//! `Dictionary` loading silently drops a few kinds of bad entries; the
+//! validator reports those and other malformed entries with line numbers, so
+//! they can be fixed in the source list instead.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::constants::{is_coeng, is_khmer_char, is_valid_single_word};
+use crate::orthography::{validate_syllables, SyllableIssueKind};
+use crate::variants::VariantGenerator;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IssueKind {
+    /// Starts with a coeng (U+17D2). Dropped on load.
+    LeadingCoeng,
+    /// Coeng at the end or not followed by a consonant or independent vowel
+    DanglingCoeng,
+    /// Dependent vowel or sign with no base consonant before it
+    DanglingMark,
+    /// Contains letters from a script other than Khmer
+    MixedScript,
+    /// Single character that is not a valid standalone word. Dropped on load.
+    InvalidSingleChar,
+    /// Contains the repetition mark `ៗ`. Dropped on load.
+    RepetitionMark,
+    /// Listed again after `first_line`
+    Duplicate { first_line: usize },
+    /// A generated spelling variant of the entry `of` on line `line`
+    DuplicateVariant { of: String, line: usize },
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssueKind::LeadingCoeng => write!(f, "starts with a coeng"),
+            IssueKind::DanglingCoeng => write!(f, "coeng without a subscript letter"),
+            IssueKind::DanglingMark => write!(f, "dependent vowel or sign without a base consonant"),
+            IssueKind::MixedScript => write!(f, "contains non-Khmer letters"),
+            IssueKind::InvalidSingleChar => write!(f, "single character that is not a standalone word"),
+            IssueKind::RepetitionMark => write!(f, "contains the repetition mark ៗ"),
+            IssueKind::Duplicate { first_line } => write!(f, "duplicate of line {}", first_line),
+            IssueKind::DuplicateVariant { of, line } => {
+                write!(f, "spelling variant of \"{}\" (line {}), generated automatically", of, line)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// 1-based line number in the word list
+    pub line: usize,
+    pub word: String,
+    pub kind: IssueKind,
+}
+
+/// Check every entry of a newline-separated word list. Issues are ordered by
+/// line; an entry may have several.
+pub fn validate_words<R: BufRead>(reader: R, generator: &VariantGenerator) -> io::Result<Vec<Issue>> {
+    let mut entries: Vec<(usize, String)> = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let word = line?.trim().to_string();
+        if !word.is_empty() {
+            entries.push((i + 1, word));
+        }
+    }
+
+    let mut issues = Vec::new();
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+    for (line, word) in &entries {
+        let mut report = |kind| issues.push(Issue { line: *line, word: word.clone(), kind });
+        for kind in check_word(word) {
+            report(kind);
+        }
+        match first_seen.get(word.as_str()) {
+            Some(&first_line) => report(IssueKind::Duplicate { first_line }),
+            None => {
+                first_seen.insert(word, *line);
+            }
+        }
+    }
+
+    // An entry the generator derives from an earlier entry is redundant
+    for (line, word) in &entries {
+        if first_seen[word.as_str()] != *line {
+            continue;
+        }
+        for variant in generator.generate(word) {
+            if let Some(&variant_line) = first_seen.get(variant.as_str()) {
+                if variant_line > *line {
+                    issues.push(Issue {
+                        line: variant_line,
+                        word: variant,
+                        kind: IssueKind::DuplicateVariant { of: word.clone(), line: *line },
+                    });
+                }
+            }
+        }
+    }
+
+    issues.sort_by_key(|issue| issue.line);
+    Ok(issues)
+}
+
+/// Issues that depend on the entry alone.
+pub fn check_word(word: &str) -> Vec<IssueKind> {
+    let mut kinds = Vec::new();
+    let chars: Vec<char> = word.chars().collect();
+
+    if chars.len() == 1 && !is_valid_single_word(chars[0]) {
+        kinds.push(IssueKind::InvalidSingleChar);
+    }
+    if chars.first().is_some_and(|&c| is_coeng(c)) {
+        kinds.push(IssueKind::LeadingCoeng);
+    }
+    let syllable_issues = validate_syllables(word);
+    let has = |kind| syllable_issues.iter().any(|issue| issue.kind == kind);
+    if has(SyllableIssueKind::DanglingCoeng) {
+        kinds.push(IssueKind::DanglingCoeng);
+    }
+    if has(SyllableIssueKind::DanglingVowel) || has(SyllableIssueKind::DanglingSign) {
+        kinds.push(IssueKind::DanglingMark);
+    }
+    if chars.iter().any(|&c| c.is_alphabetic() && !is_khmer_char(c)) {
+        kinds.push(IssueKind::MixedScript);
+    }
+    if chars.contains(&'ៗ') {
+        kinds.push(IssueKind::RepetitionMark);
+    }
+
+    kinds
+}
@@ -0,0 +1,147 @@
+//! Spelling variant generation shared by dictionary building and query expansion.
+
+use std::collections::HashSet;
+
+/// Generates alternative spellings of a word that render identically or are
+/// common misspellings. Each rule can be toggled independently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct VariantGenerator {
+    /// Swap coeng Ta (`្ត`) and coeng Da (`្ឍ`)
+    pub ta_da_swap: bool,
+    /// Swap the order of coeng Ro and an adjacent subscript (`្រ្ក` <-> `្ក្រ`)
+    pub coeng_ro_reorder: bool,
+}
+
+impl Default for VariantGenerator {
+    fn default() -> Self {
+        VariantGenerator { ta_da_swap: true, coeng_ro_reorder: true }
+    }
+}
+
+impl VariantGenerator {
+    /// All rules enabled; this is what `Dictionary::new` uses.
+    pub fn new() -> Self {
+        VariantGenerator::default()
+    }
+
+    /// All rules disabled: dictionary words are used exactly as listed.
+    pub fn none() -> Self {
+        VariantGenerator { ta_da_swap: false, coeng_ro_reorder: false }
+    }
+
+    pub fn ta_da_swap(mut self, enabled: bool) -> Self {
+        self.ta_da_swap = enabled;
+        self
+    }
+
+    pub fn coeng_ro_reorder(mut self, enabled: bool) -> Self {
+        self.coeng_ro_reorder = enabled;
+        self
+    }
+
+    /// `word` followed by its variants, for query expansion.
+    pub fn expand(&self, word: &str) -> Vec<String> {
+        let mut variants: Vec<String> = self.generate(word).into_iter().collect();
+        variants.sort();
+        variants.insert(0, word.to_string());
+        variants
+    }
+
+    /// Variants of `word`, not including `word` itself.
+    pub fn generate(&self, word: &str) -> HashSet<String> {
+        let mut variants = HashSet::new();
+        let coeng_ta = "\u{17D2}\u{178F}";
+        let coeng_da = "\u{17D2}\u{178D}";
+
+        // 1. Ta/Da Swapping
+        if self.ta_da_swap {
+            if word.contains(coeng_ta) {
+                variants.insert(word.replace(coeng_ta, coeng_da));
+            }
+            if word.contains(coeng_da) {
+                variants.insert(word.replace(coeng_da, coeng_ta));
+            }
+        }
+
+        if !self.coeng_ro_reorder {
+            return variants;
+        }
+
+        // 2. Coeng Ro Ordering
+        // Pattern: (Coeng Ro)(Other Coeng) <-> (Other Coeng)(Coeng Ro)
+        // Coeng Ro: \u17D2\u179A
+        // Other Coeng: \u17D2 followed by NOT \u179A
+
+        // Base set for Ro swapping includes original and Ta/Da variants
+        let mut base_set = variants.clone();
+        base_set.insert(word.to_string());
+
+        let coeng = '\u{17D2}';
+        let ro = '\u{179A}';
+
+        for w in base_set {
+            let chars: Vec<char> = w.chars().collect();
+            let n = chars.len();
+            if n < 4 {
+                 continue;
+            }
+
+            // Pass 1: Ro + Other -> Other + Ro
+            // Pattern: [Coeng, Ro, Coeng, NotRo]
+            let mut new_chars = chars.clone();
+            let mut modified = false;
+            let mut i = 0;
+            while i + 3 < new_chars.len() {
+                let c0 = new_chars[i];
+                let c1 = new_chars[i+1];
+                let c2 = new_chars[i+2];
+                let c3 = new_chars[i+3];
+
+                if c0 == coeng && c1 == ro && c2 == coeng && c3 != ro {
+                    // Swap (0,1) with (2,3)
+                    new_chars[i] = c2;
+                    new_chars[i+1] = c3;
+                    new_chars[i+2] = c0;
+                    new_chars[i+3] = c1;
+                    modified = true;
+                    i += 4;
+                } else {
+                    i += 1;
+                }
+            }
+            if modified {
+                variants.insert(new_chars.iter().collect());
+            }
+
+            // Pass 2: Other + Ro -> Ro + Other
+            // Pattern: [Coeng, NotRo, Coeng, Ro]
+            let mut new_chars_2 = chars.clone();
+            let mut modified_2 = false;
+            let mut i = 0;
+            while i + 3 < new_chars_2.len() {
+                let c0 = new_chars_2[i];
+                let c1 = new_chars_2[i+1];
+                let c2 = new_chars_2[i+2];
+                let c3 = new_chars_2[i+3];
+
+                if c0 == coeng && c1 != ro && c2 == coeng && c3 == ro {
+                    // Swap (0,1) with (2,3)
+                    new_chars_2[i] = c2;
+                    new_chars_2[i+1] = c3;
+                    new_chars_2[i+2] = c0;
+                    new_chars_2[i+3] = c1;
+                    modified_2 = true;
+                    i += 4;
+                } else {
+                    i += 1;
+                }
+            }
+            if modified_2 {
+                variants.insert(new_chars_2.iter().collect());
+            }
+        }
+
+        variants
+    }
+}
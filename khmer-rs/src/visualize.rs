@@ -0,0 +1,142 @@
+//! HTML rendering of segmented lines for review (`visualize`).
+//!
+//! Each token is a box colored by its `TokenKind`; hovering shows its kind,
+//! its dictionary cost and, when the token is an arc of the Viterbi path, the
+//! arc's cost and the path cost up to it (see `KhmerSegmenter::segment_trace`).
+//! Tokens that post-processing joined or split have no arc of their own.
+//! The page is self-contained: no scripts, no external files.
+
+use std::fmt::Write;
+
+use fxhash::FxHashMap;
+
+use crate::dictionary::Cost;
+use crate::segmenter::KhmerSegmenter;
+use crate::token::{Token, TokenKind};
+use crate::trace::TraceArc;
+
+/// Background color of each kind's boxes.
+fn color(kind: TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Word => "#e3f2fd",
+        TokenKind::Date | TokenKind::Time => "#e8f5e9",
+        TokenKind::Phone | TokenKind::Currency | TokenKind::Number | TokenKind::RomanNumeral => "#fff8e1",
+        TokenKind::Separator | TokenKind::Whitespace => "#f5f5f5",
+        TokenKind::Url | TokenKind::Email | TokenKind::Hashtag | TokenKind::Mention => "#ede7f6",
+        TokenKind::Emoji => "#fce4ec",
+        TokenKind::Latin => "#e0f7fa",
+        TokenKind::Unknown => "#ffcdd2",
+    }
+}
+
+/// Escape `text` for HTML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Render `lines`, each with its line number, as one HTML page titled `title`.
+pub fn render_html(segmenter: &KhmerSegmenter, lines: &[(usize, String)], title: &str) -> String {
+    let dictionary = segmenter.dictionary();
+    let mut counts: FxHashMap<TokenKind, usize> = FxHashMap::default();
+    let mut body = String::new();
+
+    for (id, text) in lines {
+        let trace = segmenter.segment_trace(text);
+        let path: FxHashMap<(usize, usize), &TraceArc> =
+            trace.path().into_iter().map(|arc| ((arc.start, arc.end), arc)).collect();
+
+        // Character position in the trace text (no zero-width spaces) of each byte offset
+        let mut positions = vec![0; text.len() + 1];
+        let mut position = 0;
+        for (offset, c) in text.char_indices() {
+            positions[offset] = position;
+            if c != '\u{200b}' {
+                position += 1;
+            }
+        }
+        positions[text.len()] = position;
+
+        let _ = write!(body, "<div class=\"line\"><span class=\"id\">{}</span>", id + 1);
+        for token in segmenter.segment_tokens(text) {
+            *counts.entry(token.kind).or_default() += 1;
+            let arc = path.get(&(positions[token.span.start], positions[token.span.end])).copied();
+            let word_cost = dictionary.contains(&token.text).then(|| dictionary.get_word_cost(&token.text));
+            // Spaces would collapse into the token boundaries: show them as dots
+            let shown = if token.kind == TokenKind::Whitespace {
+                "\u{b7}".repeat(token.text.chars().count())
+            } else {
+                token.text.clone()
+            };
+            let _ = write!(
+                body,
+                "<span class=\"tok {}\" title=\"{}\">{}</span>",
+                token.kind.name(),
+                escape(&tooltip(&token, arc, word_cost)),
+                escape(&shown)
+            );
+        }
+        body.push_str("</div>\n");
+    }
+
+    let tokens: usize = counts.values().sum();
+    let unknown = counts.get(&TokenKind::Unknown).copied().unwrap_or(0);
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"km\">\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(html, "<title>{}</title>", escape(title));
+    html.push_str("<style>\n");
+    html.push_str("body { font-family: \"Noto Sans Khmer\", \"Khmer OS\", sans-serif; margin: 2em; line-height: 2.4; }\n");
+    html.push_str(".line { margin-bottom: 0.6em; }\n");
+    html.push_str(".id { color: #999; font-size: 0.75em; display: inline-block; min-width: 4em; }\n");
+    html.push_str(".tok { border: 1px solid #90a4ae; border-radius: 3px; padding: 0 2px; margin: 0 1px; }\n");
+    html.push_str(".tok:hover { outline: 2px solid #37474f; }\n");
+    html.push_str(".whitespace { border-style: dotted; color: #bbb; }\n");
+    html.push_str(".unknown { border-color: #c62828; }\n");
+    for kind in TokenKind::ALL {
+        let _ = writeln!(html, ".{} {{ background: {}; }}", kind.name(), color(kind));
+    }
+    html.push_str("</style>\n</head>\n<body>\n");
+    let _ = writeln!(html, "<h1>{}</h1>", escape(title));
+    let _ = writeln!(
+        html,
+        "<p>{} lines, {} tokens, {} unknown ({:.2}%). Hover over a token for its costs.</p>",
+        lines.len(),
+        tokens,
+        unknown,
+        unknown as f64 * 100.0 / tokens.max(1) as f64
+    );
+    html.push_str("<p class=\"legend\">");
+    for kind in TokenKind::ALL.into_iter().filter(|kind| counts.contains_key(kind)) {
+        let _ = write!(html, "<span class=\"tok {}\">{} {}</span> ", kind.name(), kind.name(), counts[&kind]);
+    }
+    html.push_str("</p>\n");
+    html.push_str(&body);
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Hover text of `token`: its kind, its dictionary cost if listed, and the
+/// Viterbi arc it came from if any.
+fn tooltip(token: &Token, arc: Option<&TraceArc>, word_cost: Option<Cost>) -> String {
+    let mut tooltip = format!("{:?} ({})", token.text, token.kind.name());
+    if let Some(cost) = word_cost {
+        let _ = write!(tooltip, "\ndictionary cost {:.3}", cost);
+    }
+    match arc {
+        Some(arc) => {
+            let _ = write!(tooltip, "\nviterbi {} arc: cost {:.3}, path cost {:.3}", arc.kind, arc.cost, arc.total);
+        }
+        None => tooltip.push_str("\njoined or split after the Viterbi pass"),
+    }
+    tooltip
+}
@@ -0,0 +1,28 @@
+//! WebAssembly bindings (`--features wasm`).
+//!
+//! The browser has no file system, so the dictionary and frequency data are
+//! passed in as bytes (e.g. from `fetch(...).arrayBuffer()`).
+
+use wasm_bindgen::prelude::*;
+
+use crate::dictionary::Dictionary;
+use crate::segmenter::KhmerSegmenter;
+
+#[wasm_bindgen(js_name = KhmerSegmenter)]
+pub struct WasmSegmenter {
+    inner: KhmerSegmenter,
+}
+
+#[wasm_bindgen(js_class = KhmerSegmenter)]
+impl WasmSegmenter {
+    /// `dict` is the newline-separated word list, `freq` the optional JSON frequency map.
+    #[wasm_bindgen(constructor)]
+    pub fn new(dict: &[u8], freq: Option<Vec<u8>>) -> Result<WasmSegmenter, JsError> {
+        let dictionary = Dictionary::from_bytes(dict, freq.as_deref())?;
+        Ok(WasmSegmenter { inner: KhmerSegmenter::new(dictionary) })
+    }
+
+    pub fn segment(&self, text: &str) -> Vec<String> {
+        self.inner.segment(text)
+    }
+}
@@ -0,0 +1,49 @@
+use khmer_rs::algorithm::Segmenter;
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::eval::{evaluate, Reference};
+use khmer_rs::maxmatch::MaxMatchSegmenter;
+use khmer_rs::segmenter::KhmerSegmenter;
+use khmer_rs::sentences::segment_by_sentence;
+use std::sync::Arc;
+
+/// One token per character: a trivial third algorithm.
+struct CharSegmenter;
+
+impl Segmenter for CharSegmenter {
+    fn segment(&self, text: &str) -> Vec<String> {
+        text.chars().map(String::from).collect()
+    }
+}
+
+fn algorithms() -> Vec<(&'static str, Box<dyn Segmenter>)> {
+    let dictionary = Arc::new(Dictionary::from_bytes("សួស្តី\nបង\nប្អូន\nបងប្អូន\n".as_bytes(), None).unwrap());
+    vec![
+        ("viterbi", Box::new(KhmerSegmenter::builder(Arc::clone(&dictionary)).build())),
+        ("maxmatch", Box::new(MaxMatchSegmenter::new(dictionary))),
+        ("chars", Box::new(CharSegmenter)),
+    ]
+}
+
+#[test]
+fn test_algorithms_share_the_harness() {
+    let references = vec![
+        Reference { input: "សួស្តីបង".to_string(), segments: vec!["សួស្តី".to_string(), "បង".to_string()] },
+        Reference { input: "បងប្អូន".to_string(), segments: vec!["បងប្អូន".to_string()] },
+    ];
+
+    for (name, segmenter) in algorithms() {
+        let scores = evaluate(&segmenter, &references);
+        assert_eq!(scores.lines, 2, "{}", name);
+        let expected_exact = if name == "chars" { 0 } else { 2 };
+        assert_eq!(scores.exact_lines, expected_exact, "{}", name);
+    }
+}
+
+#[test]
+fn test_segment_by_sentence_with_any_algorithm() {
+    let text = "សួស្តីបង។ បងប្អូន។";
+    for (name, segmenter) in algorithms() {
+        let segments = segment_by_sentence(&segmenter, text);
+        assert_eq!(segments.concat(), text, "{}", name);
+    }
+}
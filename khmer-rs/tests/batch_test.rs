@@ -0,0 +1,216 @@
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use khmer_rs::batch::{shard_path, Checkpoint, Dedup, Interner, Pipeline, RecordWriter};
+
+#[test]
+fn test_dedup_maps_lines_to_first_occurrence() {
+    let lines = ["ក", "ខ", "ក", "គ", "ខ", "ក"];
+    let dedup = Dedup::new(&lines);
+    assert_eq!(dedup.unique, vec!["ក", "ខ", "គ"]);
+    assert_eq!(dedup.index, vec![0, 1, 0, 2, 1, 0]);
+    assert_eq!(dedup.duplicates(), 3);
+
+    // Fanning per-distinct results back out restores input order
+    let results: Vec<String> = dedup.unique.iter().map(|line| line.repeat(2)).collect();
+    let expanded: Vec<&str> = dedup.index.iter().map(|&i| results[i].as_str()).collect();
+    assert_eq!(expanded, lines.map(|line| line.repeat(2)));
+}
+
+#[test]
+fn test_dedup_without_duplicates() {
+    let lines = vec!["a".to_string(), "b".to_string()];
+    let dedup = Dedup::new(&lines);
+    assert_eq!(dedup.unique, vec!["a", "b"]);
+    assert_eq!(dedup.duplicates(), 0);
+    assert_eq!(Dedup::new::<String>(&[]).unique.len(), 0);
+}
+
+#[test]
+fn test_interner_shares_tokens() {
+    let interner = Interner::new();
+    assert!(interner.is_empty());
+    let first = interner.intern_all(vec!["ខ្ញុំ".to_string(), " ".to_string(), "។".to_string()]);
+    let second = interner.intern_all(vec!["។".to_string(), "ខ្ញុំ".to_string()]);
+    assert_eq!(first.iter().map(|t| &**t).collect::<Vec<_>>(), vec!["ខ្ញុំ", " ", "។"]);
+    assert!(Arc::ptr_eq(&first[0], &second[1]));
+    assert!(Arc::ptr_eq(&first[2], &second[0]));
+    assert_eq!(interner.len(), 3);
+}
+
+#[test]
+fn test_interner_across_threads() {
+    let interner = Interner::new();
+    let tokens: Vec<Vec<Arc<str>>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..4).map(|_| scope.spawn(|| interner.intern_all(vec!["ក".to_string(); 100]))).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    assert_eq!(interner.len(), 1);
+    assert!(tokens.iter().flatten().all(|token| Arc::ptr_eq(token, &tokens[0][0])));
+}
+
+#[test]
+fn test_pipeline_keeps_input_order() {
+    let lines: Vec<String> = (0..1000).map(|i| format!("line {}", i)).collect();
+    let mut written = Vec::new();
+    let count = Pipeline::new()
+        .workers(4)
+        .batch_lines(7)
+        .max_batches(3)
+        .run(
+            lines.iter().cloned().map(Ok),
+            |line| {
+                // Uneven work so batches finish out of order
+                if line.ends_with('3') {
+                    std::thread::sleep(std::time::Duration::from_micros(200));
+                }
+                line.len()
+            },
+            |id, line, len| {
+                written.push((id, line.to_string(), *len));
+                Ok(())
+            },
+        )
+        .unwrap();
+    assert_eq!(count, 1000);
+    let expected: Vec<_> = lines.iter().enumerate().map(|(id, line)| (id, line.clone(), line.len())).collect();
+    assert_eq!(written, expected);
+}
+
+#[test]
+fn test_pipeline_dedup_processes_each_line_once() {
+    let lines = ["ក", "ខ", "ក", "គ", "ខ", "ក"];
+    let calls = AtomicUsize::new(0);
+    let mut written = Vec::new();
+    Pipeline::new()
+        .workers(2)
+        .batch_lines(2)
+        .dedup(true)
+        .run(
+            lines.iter().map(|line| Ok(line.to_string())),
+            |line| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                line.repeat(2)
+            },
+            |id, line, doubled| {
+                written.push((id, line.to_string(), doubled.clone()));
+                Ok(())
+            },
+        )
+        .unwrap();
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+    let expected: Vec<_> = lines.iter().enumerate().map(|(id, line)| (id, line.to_string(), line.repeat(2))).collect();
+    assert_eq!(written, expected);
+}
+
+#[test]
+fn test_pipeline_errors() {
+    // Lines before a read error are written, then the error is returned
+    let lines = vec![Ok("a".to_string()), Ok("b".to_string()), Err(io::Error::other("bad read")), Ok("c".to_string())];
+    let mut written = Vec::new();
+    let err = Pipeline::new()
+        .batch_lines(1)
+        .run(lines, |line| line.to_string(), |_, line, _| {
+            written.push(line.to_string());
+            Ok(())
+        })
+        .unwrap_err();
+    assert_eq!(err.to_string(), "bad read");
+    assert_eq!(written, vec!["a", "b"]);
+
+    // A write error stops the run without waiting for the rest of the input
+    let err = Pipeline::new()
+        .workers(2)
+        .batch_lines(4)
+        .max_batches(2)
+        .run((0..).map(|i| Ok(i.to_string())), |line| line.len(), |id, _, _| {
+            if id == 100 {
+                Err(io::Error::other("disk full"))
+            } else {
+                Ok(())
+            }
+        })
+        .unwrap_err();
+    assert_eq!(err.to_string(), "disk full");
+}
+
+#[test]
+fn test_checkpoint_roundtrip() {
+    let dir = std::env::temp_dir().join(format!("khmer_checkpoint_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = Checkpoint::path_for(&dir.join("out.jsonl"));
+    assert!(path.ends_with("out.jsonl.checkpoint"));
+    assert_eq!(Checkpoint::load(&path).unwrap(), None);
+
+    Checkpoint { lines: 10, bytes: 1234 }.save(&path).unwrap();
+    Checkpoint { lines: 20, bytes: 2345 }.save(&path).unwrap();
+    assert_eq!(Checkpoint::load(&path).unwrap(), Some(Checkpoint { lines: 20, bytes: 2345 }));
+
+    std::fs::write(&path, "lines 20\n").unwrap();
+    assert_eq!(Checkpoint::load(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_shard_path() {
+    assert_eq!(shard_path(Path::new("out/run.jsonl"), 3), Path::new("out/run-00003.jsonl"));
+    assert_eq!(shard_path(Path::new("run"), 12), Path::new("run-00012"));
+}
+
+#[test]
+fn test_record_writer_shards_and_resumes() {
+    let dir = std::env::temp_dir().join(format!("khmer_shards_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("out.jsonl");
+    let read = |index| std::fs::read_to_string(shard_path(&path, index)).unwrap();
+
+    let mut writer = RecordWriter::create(&path, Some(2)).unwrap();
+    for record in ["a", "b", "c"] {
+        writer.write_record(record).unwrap();
+    }
+    let checkpoint = writer.checkpoint().unwrap();
+    assert_eq!(checkpoint, Checkpoint { lines: 3, bytes: 2 });
+    // Written after the checkpoint, then lost in a crash
+    writer.write_record("x").unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+    assert_eq!(read(1), "c\nx\n");
+
+    let mut writer = RecordWriter::resume(&path, Some(2), checkpoint).unwrap();
+    for record in ["d", "e"] {
+        writer.write_record(record).unwrap();
+    }
+    writer.flush().unwrap();
+    assert_eq!(writer.files(), 3);
+    assert_eq!(writer.lines(), 5);
+    assert_eq!((read(0), read(1), read(2)), ("a\nb\n".to_string(), "c\nd\n".to_string(), "e\n".to_string()));
+    assert!(!path.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pipeline_max_bytes_bounds_lines_in_flight() {
+    let read = AtomicUsize::new(0);
+    let mut written = 0;
+    let mut max_in_flight = 0;
+    let lines = (0..500).map(|i| {
+        read.fetch_add(10, Ordering::SeqCst);
+        Ok(format!("{:010}", i))
+    });
+    let count = Pipeline::new()
+        .workers(4)
+        .batch_lines(16)
+        .max_bytes(35)
+        .run(lines, |line| line.len(), |id, line, _| {
+            assert_eq!(line, format!("{:010}", id));
+            max_in_flight = max_in_flight.max(read.load(Ordering::SeqCst) - written);
+            written += line.len();
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(count, 500);
+    // Three lines in flight, and at most one more read and waiting for room
+    assert!(max_in_flight <= 40, "{} bytes in flight", max_in_flight);
+}
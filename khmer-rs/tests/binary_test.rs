@@ -0,0 +1,107 @@
+//! Binary dictionary cache: round trip, staleness and corrupt files.
+
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+use khmer_rs::dictionary::Dictionary;
+
+/// A cache directory of this test run, set as `XDG_CACHE_HOME` so the
+/// tests never touch the user's own cache. Set once, before any test loads.
+fn cache_home() -> PathBuf {
+    static SET: Once = Once::new();
+    let home = std::env::temp_dir().join(format!("khmer_binary_cache_home_{}", std::process::id()));
+    SET.call_once(|| std::env::set_var("XDG_CACHE_HOME", &home));
+    home
+}
+
+/// Remove `cache_home` once the last test has removed its cache from it.
+fn remove_cache_home(home: &Path) {
+    let _ = std::fs::remove_dir(home.join("khmer-rs")).and_then(|()| std::fs::remove_dir(home));
+}
+
+fn cache_path(dict: &Path, freq: &Path) -> PathBuf {
+    Dictionary::builder().cache_path(dict, freq)
+}
+
+fn load(dict: &Path, freq: &Path) -> Dictionary {
+    Dictionary::builder().binary_cache(true).load(dict, freq).unwrap()
+}
+
+fn assert_same(a: &Dictionary, b: &Dictionary) {
+    assert_eq!(a.words.len(), b.words.len());
+    assert_eq!(a.variant_of, b.variant_of);
+    assert_eq!((a.default_cost, a.unknown_cost, a.max_word_length), (b.default_cost, b.unknown_cost, b.max_word_length));
+    for word in a.words.keys() {
+        let cps: Vec<char> = word.chars().collect();
+        assert_eq!(a.get_word_cost(word), b.get_word_cost(word), "{}", word);
+        assert_eq!(b.lookup_codepoints(&cps, 0, cps.len()), Some(a.get_word_cost(word)), "{}", word);
+    }
+    assert_eq!(a.trie.len(), b.trie.len());
+}
+
+#[test]
+fn test_binary_cache_round_trip_and_staleness() {
+    let dir = std::env::temp_dir().join(format!("khmer_binary_cache_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (dict, freq) = (dir.join("words.txt"), dir.join("freq.json"));
+    std::fs::write(&dict, "សួស្តី\nបង\nកម្ពុជា\n").unwrap();
+    std::fs::write(&freq, r#"{"បង": 1000, "សួស្តី": 10}"#).unwrap();
+
+    let home = cache_home();
+    let cache = &cache_path(&dict, &freq);
+    assert!(cache.starts_with(&home), "{}", cache.display());
+    let built = load(&dict, &freq);
+    assert!(cache.exists());
+    // Nothing is written next to the word list
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+    let text = Dictionary::new(&dict, &freq).unwrap();
+    assert_same(&text, &built);
+    assert_same(&text, &load(&dict, &freq));
+
+    // Changed word list: rebuilt, not served stale
+    std::fs::write(&dict, "សួស្តី\nបង\nកម្ពុជា\nខ្មែរ\n").unwrap();
+    let changed = load(&dict, &freq);
+    assert!(changed.contains("ខ្មែរ"));
+    assert_same(&Dictionary::new(&dict, &freq).unwrap(), &changed);
+
+    // Other options: their own costs, in a cache file of their own
+    let fixed_builder = || Dictionary::builder().binary_cache(true).default_cost(7.0);
+    let fixed_cache = fixed_builder().cache_path(&dict, &freq);
+    assert_ne!(&fixed_cache, cache);
+    let fixed = fixed_builder().load(&dict, &freq).unwrap();
+    assert_eq!(fixed.get_word_cost("កម្ពុជា"), 7.0);
+    assert_ne!(load(&dict, &freq).get_word_cost("កម្ពុជា"), 7.0);
+    assert!(cache.exists() && fixed_cache.exists());
+    std::fs::remove_file(fixed_cache).unwrap();
+
+    // A corrupt cache is rebuilt
+    let bytes = std::fs::read(cache).unwrap();
+    std::fs::write(cache, &bytes[..bytes.len() / 2]).unwrap();
+    assert_same(&changed, &load(&dict, &freq));
+    std::fs::write(cache, b"garbage").unwrap();
+    assert_same(&changed, &load(&dict, &freq));
+
+    std::fs::remove_file(cache).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    remove_cache_home(&home);
+}
+
+#[test]
+fn test_binary_cache_without_frequency_file() {
+    let dir = std::env::temp_dir().join(format!("khmer_binary_cache_nofreq_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (dict, freq) = (dir.join("words.txt"), dir.join("missing.json"));
+    std::fs::write(&dict, "សួស្តី\nបង\n").unwrap();
+    let home = cache_home();
+    assert!(cache_path(&dict, &freq).starts_with(&home));
+
+    let first = load(&dict, &freq);
+    assert_same(&first, &load(&dict, &freq));
+    // The frequency file appearing makes the cache stale
+    std::fs::write(&freq, r#"{"បង": 1000, "សួស្តី": 10}"#).unwrap();
+    assert_same(&Dictionary::new(&dict, &freq).unwrap(), &load(&dict, &freq));
+
+    std::fs::remove_file(cache_path(&dict, &freq)).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    remove_cache_home(&home);
+}
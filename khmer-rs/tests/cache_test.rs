@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use khmer_rs::algorithm::Segmenter;
+use khmer_rs::cache::SegmentCache;
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::segmenter::KhmerSegmenter;
+
+fn segments(words: &[&str]) -> Vec<String> {
+    words.iter().map(|w| w.to_string()).collect()
+}
+
+#[test]
+fn test_cache_evicts_least_recently_used() {
+    let cache = SegmentCache::new(2);
+    cache.insert("a", segments(&["a"]));
+    cache.insert("b", segments(&["b"]));
+    // Touch "a" so "b" is the least recently used
+    assert_eq!(cache.get("a"), Some(segments(&["a"])));
+    cache.insert("c", segments(&["c"]));
+
+    assert_eq!(cache.get("b"), None);
+    assert_eq!(cache.get("a"), Some(segments(&["a"])));
+    assert_eq!(cache.get("c"), Some(segments(&["c"])));
+
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses), (3, 1));
+    assert_eq!((stats.entries, stats.capacity), (2, 2));
+    assert_eq!(stats.hit_rate(), 0.75);
+
+    // Updating an entry does not grow the cache
+    cache.insert("c", segments(&["c", "c"]));
+    assert_eq!(cache.get("c"), Some(segments(&["c", "c"])));
+    assert_eq!(cache.stats().entries, 2);
+
+    cache.clear();
+    assert_eq!(cache.stats().entries, 0);
+    assert_eq!(cache.get("a"), None);
+}
+
+#[test]
+fn test_insert_after_clear_is_skipped() {
+    let cache = SegmentCache::new(10);
+    let generation = cache.generation();
+    cache.insert_if_current("a", segments(&["a"]), generation);
+    assert_eq!(cache.get("a"), Some(segments(&["a"])));
+
+    // Computed before a clear, inserted after it: stale, so dropped
+    let stale = cache.generation();
+    cache.clear();
+    cache.insert_if_current("b", segments(&["b"]), stale);
+    assert_eq!(cache.get("b"), None);
+    cache.insert_if_current("b", segments(&["b"]), cache.generation());
+    assert_eq!(cache.get("b"), Some(segments(&["b"])));
+}
+
+#[test]
+fn test_large_cache_stays_bounded() {
+    let cache = SegmentCache::new(5000);
+    for i in 0..20000 {
+        cache.insert(&i.to_string(), Vec::new());
+    }
+    let stats = cache.stats();
+    assert!(stats.capacity >= 5000);
+    assert!(stats.entries <= stats.capacity);
+    assert!(cache.get("19999").is_some());
+    assert!(cache.get("0").is_none());
+}
+
+#[test]
+fn test_segmenter_cache() {
+    let dictionary = Arc::new(Dictionary::from_bytes("ក\nខ\n".as_bytes(), None).unwrap());
+    let segmenter = KhmerSegmenter::builder(Arc::clone(&dictionary)).cache(100).build();
+    assert_eq!(KhmerSegmenter::builder(dictionary).build().cache_stats(), None);
+
+    assert_eq!(segmenter.segment("កខ"), vec!["ក", "ខ"]);
+    assert_eq!(segmenter.segment("កខ"), vec!["ក", "ខ"]);
+    let stats = Segmenter::cache_stats(&Box::new(&segmenter)).unwrap();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+
+    // A new dictionary invalidates the cached lines
+    segmenter.swap_dictionary(Arc::new(Dictionary::from_bytes("កខ\n".as_bytes(), None).unwrap()));
+    assert_eq!(segmenter.segment("កខ"), vec!["កខ"]);
+    assert_eq!(segmenter.cache_stats().unwrap().misses, 2);
+}
@@ -0,0 +1,68 @@
+//! Tests for dictionary-independent cluster and syllable APIs.
+
+use khmer_rs::clusters::{get_khmer_cluster_length_cps, khmer_clusters, segment_syllables};
+
+#[test]
+fn test_segment_syllables() {
+    assert_eq!(segment_syllables("កម្ពុជា"), vec!["ក", "ម្ពុ", "ជា"]);
+    assert_eq!(segment_syllables("ស្ត្រី"), vec!["ស្ត្រី"]);
+}
+
+#[test]
+fn test_segment_syllables_mixed_script() {
+    assert_eq!(segment_syllables("ខ្មែរ ok"), vec!["ខ្មែ", "រ", " ", "o", "k"]);
+}
+
+#[test]
+fn test_segment_syllables_drops_zwsp() {
+    assert_eq!(segment_syllables("ក\u{200b}ខ"), vec!["ក", "ខ"]);
+    assert!(segment_syllables("").is_empty());
+}
+
+fn clusters(text: &str) -> Vec<&str> {
+    khmer_clusters(text).map(|s| s.as_str(text)).collect()
+}
+
+#[test]
+fn test_clusters_cover_input() {
+    let text = "ស្ត្រី\u{200b}ទៅ abc។";
+    let spans: Vec<_> = khmer_clusters(text).collect();
+    assert_eq!(spans.first().unwrap().start, 0);
+    assert_eq!(spans.last().unwrap().end, text.len());
+    assert!(spans.windows(2).all(|w| w[0].end == w[1].start));
+}
+
+#[test]
+fn test_clusters_leading_coeng() {
+    assert_eq!(clusters("\u{17D2}កា"), vec!["\u{17D2}", "កា"]);
+}
+
+#[test]
+fn test_clusters_dangling_vowel() {
+    assert_eq!(clusters("ាក"), vec!["ា", "ក"]);
+    assert_eq!(clusters(" ុំ"), vec![" ", "ុ", "ំ"]);
+}
+
+#[test]
+fn test_clusters_coeng_without_consonant() {
+    // Trailing coeng and coeng before a vowel both end the cluster
+    assert_eq!(clusters("ក\u{17D2}"), vec!["ក", "\u{17D2}"]);
+    assert_eq!(clusters("ក\u{17D2}ា"), vec!["ក", "\u{17D2}", "ា"]);
+    // Doubled coeng
+    assert_eq!(clusters("ក\u{17D2}\u{17D2}ក"), vec!["ក", "\u{17D2}", "\u{17D2}", "ក"]);
+}
+
+#[test]
+fn test_clusters_match_codepoint_helper() {
+    let text = "ខ្ញុំស្រឡាញ់\u{17D2}ភាសា ាខ្មែរ១២ ok";
+    let cps: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    let mut expected = Vec::new();
+    while i < cps.len() {
+        let len = get_khmer_cluster_length_cps(&cps, i);
+        expected.push(cps[i..i + len].iter().collect::<String>());
+        i += len;
+    }
+    assert_eq!(clusters(text), expected);
+    assert_eq!(get_khmer_cluster_length_cps(&cps, cps.len()), 0);
+}
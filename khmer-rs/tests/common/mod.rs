@@ -0,0 +1,20 @@
+//! Fixtures shared by the integration tests.
+
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use khmer_rs::dictionary::Dictionary;
+
+/// The bundled dictionary, loaded once per test binary.
+pub fn dictionary() -> Arc<Dictionary> {
+    static DICTIONARY: OnceLock<Arc<Dictionary>> = OnceLock::new();
+    Arc::clone(DICTIONARY.get_or_init(|| {
+        Arc::new(
+            Dictionary::new(
+                Path::new("../data/khmer_dictionary_words.txt"),
+                Path::new("../data/khmer_word_frequencies.json"),
+            )
+            .expect("Failed to load dictionary"),
+        )
+    }))
+}
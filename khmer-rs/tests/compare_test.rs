@@ -0,0 +1,52 @@
+use khmer_rs::algorithm::Segmenter;
+use khmer_rs::compare::{compare, Disagreement};
+
+/// Splits on spaces, and optionally also after every `ក`.
+struct Splitter {
+    after_ka: bool,
+}
+
+impl Segmenter for Splitter {
+    fn segment(&self, text: &str) -> Vec<String> {
+        let mut segments = Vec::new();
+        let mut current = String::new();
+        for c in text.chars() {
+            if c == ' ' {
+                segments.extend((!current.is_empty()).then(|| std::mem::take(&mut current)));
+                segments.push(" ".to_string());
+                continue;
+            }
+            current.push(c);
+            if self.after_ka && c == 'ក' {
+                segments.push(std::mem::take(&mut current));
+            }
+        }
+        segments.extend((!current.is_empty()).then_some(current));
+        segments
+    }
+}
+
+#[test]
+fn test_compare_reports_agreement_and_samples() {
+    let lines = ["ខគ ឃ", "កខ គ", "ងក កច"];
+    let comparison = compare(&Splitter { after_ka: false }, &Splitter { after_ka: true }, &lines, 1);
+
+    assert_eq!(comparison.scores.lines, 3);
+    assert_eq!(comparison.scores.exact_lines, 1);
+    assert_eq!((comparison.tokens_a(), comparison.tokens_b()), (6, 8));
+    assert_eq!(comparison.token_delta(), 2);
+    // Only the first disagreement is kept
+    assert_eq!(comparison.samples.len(), 1);
+    assert_eq!(comparison.samples[0].line, 1);
+    assert_eq!(comparison.samples[0].b, vec!["ក", "ខ", " ", "គ"]);
+}
+
+#[test]
+fn test_disagreement_hunks() {
+    let tokens = |s: &str| s.split('|').map(String::from).collect::<Vec<_>>();
+    let sample = Disagreement { line: 0, a: tokens("ក|ខគ| |ឃង|ច"), b: tokens("ក|ខ|គ| |ឃង|ច") };
+    assert_eq!(sample.hunks(), vec![(1..2, 1..3)]);
+
+    let sample = Disagreement { line: 0, a: tokens("កខ|គ|ឃង"), b: tokens("ក|ខគ|ឃ|ង") };
+    assert_eq!(sample.hunks(), vec![(0..2, 0..2), (2..3, 2..4)]);
+}
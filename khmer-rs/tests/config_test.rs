@@ -0,0 +1,102 @@
+//! Tests for the configuration file and the cost overrides it feeds.
+
+use std::sync::Arc;
+
+use khmer_rs::config::{ConfigFile, DictionaryConfig, Pass, DEFAULT_REPAIR_PENALTY};
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::segmenter::KhmerSegmenter;
+
+#[test]
+fn test_config_file_from_toml() {
+    let file = ConfigFile::from_toml(
+        "[dictionary]\nunknown_cost = 12.5\n\n[segmenter]\nrepair_penalty = 40.0\ncanonicalize_variants = true\n",
+    )
+    .expect("Failed to parse config");
+    assert_eq!(file.dictionary, DictionaryConfig { default_cost: None, unknown_cost: Some(12.5) });
+    assert_eq!(file.segmenter.repair_penalty, Some(40.0));
+    assert!(file.segmenter.canonicalize_variants);
+    assert_eq!(file.segmenter.invalid_single_penalty, None);
+
+    assert!(ConfigFile::from_toml("").is_ok());
+    assert!(ConfigFile::from_toml("[segmenter]\nrepair_penalt = 1.0\n").is_err());
+}
+
+#[test]
+fn test_disabled_passes() {
+    let file = ConfigFile::from_toml("[segmenter]\ndisabled_passes = [\"sign-merge\"]\n").expect("Failed to parse config");
+    assert_eq!(file.segmenter.disabled_passes, vec![Pass::SignMerge]);
+    assert!(!file.segmenter.is_enabled(Pass::SignMerge));
+    assert!(file.segmenter.is_enabled(Pass::UnknownMerge));
+
+    for pass in Pass::ALL {
+        assert_eq!(pass.name().parse::<Pass>(), Ok(pass));
+    }
+    assert!("sign_merge".parse::<Pass>().is_err());
+}
+
+#[test]
+fn test_config_file_set() {
+    let mut file = ConfigFile::default();
+    for key in ConfigFile::NUMERIC_KEYS {
+        file.set(key, 1.5).expect("Listed key rejected");
+    }
+    assert_eq!(file.dictionary.default_cost, Some(1.5));
+    assert_eq!(file.segmenter.beam_margin, Some(1.5));
+    assert!(file.set("canonicalize_variants", 1.0).is_err());
+}
+
+#[test]
+fn test_dictionary_cost_overrides() {
+    let words = "សួស្តី\nបង\n".as_bytes();
+    let derived = Dictionary::from_bytes(words, None).expect("Failed to build dictionary");
+    let fixed = Dictionary::builder()
+        .config(DictionaryConfig { default_cost: Some(3.0), unknown_cost: None })
+        .unknown_cost(30.0)
+        .load_bytes(words, None)
+        .expect("Failed to build dictionary");
+
+    assert_ne!(derived.default_cost, fixed.default_cost);
+    assert_eq!(fixed.default_cost, 3.0);
+    assert_eq!(fixed.unknown_cost, 30.0);
+    assert_eq!(fixed.get_word_cost("បង"), 3.0);
+}
+
+#[test]
+fn test_segmenter_penalties() {
+    let dictionary =
+        Arc::new(Dictionary::from_bytes("សួស្តី\nបង\n".as_bytes(), None).expect("Failed to build dictionary"));
+    let default = KhmerSegmenter::builder(Arc::clone(&dictionary)).build();
+    let explicit = KhmerSegmenter::builder(dictionary)
+        .repair_penalty(DEFAULT_REPAIR_PENALTY)
+        .invalid_single_penalty(10.0)
+        .build();
+
+    assert_eq!(explicit.config().repair_penalty, Some(DEFAULT_REPAIR_PENALTY));
+    for text in ["សួស្តីបង", "ក្សួស្តី", "កសួស្តីបង"] {
+        assert_eq!(explicit.segment(text), default.segment(text));
+    }
+}
+
+#[test]
+fn test_config_file_round_trip() {
+    use khmer_rs::config::{NumberFormat, WhitespacePolicy};
+
+    let mut file = ConfigFile::default();
+    file.dictionary.unknown_cost = Some(12.5);
+    file.segmenter.repair_penalty = Some(40.0);
+    file.segmenter.disabled_passes = vec![Pass::UnknownMerge];
+    file.segmenter.number_format = NumberFormat::DecimalComma;
+    file.segmenter.whitespace = WhitespacePolicy::Collapse;
+    file.segmenter.extra_separators = vec!['|', '…'];
+    file.segmenter.currencies = Some(vec!["USD".to_string()]);
+
+    let text = file.to_toml().unwrap();
+    assert!(text.contains("disabled_passes = [\"unknown-merge\"]"), "{}", text);
+    assert!(!text.contains("default_cost"), "{}", text);
+    let back = ConfigFile::from_toml(&text).unwrap();
+    assert_eq!(format!("{:?}", back), format!("{:?}", file));
+
+    let json = serde_json::to_string(&file).unwrap();
+    let back: ConfigFile = serde_json::from_str(&json).unwrap();
+    assert_eq!(format!("{:?}", back), format!("{:?}", file));
+}
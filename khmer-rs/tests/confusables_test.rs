@@ -0,0 +1,69 @@
+//! Folding of lookalike codepoints before dictionary lookup.
+
+mod common;
+
+use std::io::Cursor;
+use std::sync::Arc;
+
+use khmer_rs::config::SegmenterConfig;
+use khmer_rs::confusables::Confusables;
+use khmer_rs::segmenter::KhmerSegmenter;
+use khmer_rs::token::TokenKind;
+
+use common::dictionary;
+
+/// `អ្នកចេះ` spelled with the deprecated U+17A3
+const DEPRECATED: &str = "\u{17A3}្នកចេះ";
+
+#[test]
+fn test_mapping_file() {
+    let file = "# lookalikes\n\u{17A3}\tអ\nU+FF11\t1\n\nU+00A0\tU+0020\r\n";
+    let confusables = Confusables::from_reader(Cursor::new(file)).unwrap();
+    assert_eq!(confusables.len(), 3);
+    assert_eq!(confusables.fold('\u{17A3}'), 'អ');
+    assert_eq!(confusables.fold('\u{A0}'), ' ');
+    assert_eq!(confusables.fold('ក'), 'ក');
+    assert_eq!(confusables.fold_str("１\u{A0}\u{17A3}"), "1 អ");
+
+    for bad in ["ab\tc\n", "a\n", "a\tb\tc\n", "U+ZZZZ\ta\n"] {
+        let err = Confusables::from_reader(Cursor::new(bad)).unwrap_err();
+        assert!(err.to_string().contains("line 1"), "{:?}: {}", bad, err);
+    }
+}
+
+#[test]
+fn test_builtin_mapping() {
+    let confusables = Confusables::builtin();
+    assert_eq!(confusables.fold_str(DEPRECATED), "អ្នកចេះ");
+    assert_eq!(confusables.fold_str("０１２−３"), "012-3");
+    assert!(matches!(confusables.fold_str("អ្នក 12"), std::borrow::Cow::Borrowed(_)));
+}
+
+#[test]
+fn test_segments_keep_the_input_spelling() {
+    let dictionary = dictionary();
+    let plain = KhmerSegmenter::builder(Arc::clone(&dictionary)).build();
+    let folding = KhmerSegmenter::builder(Arc::clone(&dictionary)).confusables(Confusables::builtin()).build();
+
+    assert_eq!(plain.segment("អ្នកចេះ"), vec!["អ្នកចេះ"]);
+    assert_ne!(plain.segment(DEPRECATED), vec![DEPRECATED]);
+    assert_eq!(folding.segment(DEPRECATED), vec![DEPRECATED]);
+
+    let text = format!("{} និង អ្នក", DEPRECATED);
+    let tokens = folding.segment_tokens(&text);
+    assert_eq!(tokens[0].text, DEPRECATED);
+    assert_eq!(tokens[0].kind, TokenKind::Word);
+    assert_eq!(tokens[0].span.as_str(&text), DEPRECATED);
+    assert_eq!(folding.word_cost(DEPRECATED), plain.word_cost("អ្នកចេះ"));
+    assert_eq!(folding.segment_lossless(&text).concat(), text);
+
+    // Text without confusables segments as without the mapping
+    assert_eq!(folding.segment("ខ្ញុំស្រឡាញ់ភាសាខ្មែរ"), plain.segment("ខ្ញុំស្រឡាញ់ភាសាខ្មែរ"));
+}
+
+#[test]
+fn test_canonicalize_variants_keeps_the_folded_spelling() {
+    let config = SegmenterConfig { canonicalize_variants: true, ..Default::default() };
+    let segmenter = KhmerSegmenter::builder(dictionary()).config(config).confusables(Confusables::builtin()).build();
+    assert_eq!(segmenter.segment(DEPRECATED), vec!["អ្នកចេះ"]);
+}
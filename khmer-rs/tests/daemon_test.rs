@@ -0,0 +1,79 @@
+#![cfg(unix)]
+//! Round-trip tests for the Unix socket daemon protocol.
+
+mod common;
+
+use std::io;
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use khmer_rs::daemon::{self, Client};
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::segmenter::KhmerSegmenter;
+
+fn connect_with_retry(path: &Path) -> Client {
+    for _ in 0..100 {
+        if let Ok(client) = Client::connect(path) {
+            return client;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    panic!("daemon did not start listening on {}", path.display());
+}
+
+#[test]
+fn test_daemon_round_trip() {
+    let segmenter = Arc::new(KhmerSegmenter::builder(common::dictionary()).build());
+
+    let socket_path = std::env::temp_dir().join(format!("khmer-rs-test-{}.sock", std::process::id()));
+    {
+        let segmenter = Arc::clone(&segmenter);
+        let socket_path = socket_path.clone();
+        thread::spawn(move || daemon::serve(segmenter, &socket_path));
+    }
+
+    let mut client = connect_with_retry(&socket_path);
+
+    // Multiple requests over one connection, including an empty one
+    for text in ["សួស្តី បង", "", "ខ្ញុំស្រលាញ់កម្ពុជា"] {
+        let remote = client.segment(text).expect("request failed");
+        assert_eq!(remote, segmenter.segment(text));
+    }
+
+    // A second daemon does not take the socket over
+    let err = daemon::serve(Arc::clone(&segmenter), &socket_path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+    assert_eq!(client.segment("បង").unwrap(), vec!["បង"]);
+
+    let _ = std::fs::remove_file(&socket_path);
+}
+
+#[test]
+fn test_daemon_socket_path_checks() {
+    let segmenter = Arc::new(KhmerSegmenter::new(Dictionary::from_bytes("បង\n".as_bytes(), None).unwrap()));
+    let dir = std::env::temp_dir().join(format!("khmer-rs-test-paths-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // A regular file in the way is not deleted
+    let file_path = dir.join("not-a-socket");
+    std::fs::write(&file_path, "keep me").unwrap();
+    let err = daemon::serve(Arc::clone(&segmenter), &file_path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "keep me");
+
+    // A socket nobody listens on is stale and replaced
+    let stale_path = dir.join("stale.sock");
+    drop(UnixListener::bind(&stale_path).unwrap());
+    {
+        let segmenter = Arc::clone(&segmenter);
+        let stale_path = stale_path.clone();
+        thread::spawn(move || daemon::serve(segmenter, &stale_path));
+    }
+    let mut client = connect_with_retry(&stale_path);
+    assert_eq!(client.segment("បង").unwrap(), vec!["បង"]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
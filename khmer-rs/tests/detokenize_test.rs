@@ -0,0 +1,48 @@
+use khmer_rs::config::{SegmenterConfig, WhitespacePolicy};
+use khmer_rs::detokenize::detokenize;
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::segmenter::KhmerSegmenter;
+
+#[test]
+fn test_detokenize_khmer_words() {
+    assert_eq!(detokenize(&["ខ្ញុំ", "ស្រឡាញ់", "កម្ពុជា"]), "ខ្ញុំស្រឡាញ់កម្ពុជា");
+    assert_eq!(detokenize::<&str>(&[]), "");
+    // Khmer digits are written like Khmer text
+    assert_eq!(detokenize(&["ថ្ងៃទី", "១៥", "ខែ", "មករា"]), "ថ្ងៃទី១៥ខែមករា");
+    assert_eq!(detokenize(&["ចំនួន", "៩៣ ៩៧០", "នាក់"]), "ចំនួន៩៣ ៩៧០នាក់");
+}
+
+#[test]
+fn test_detokenize_spaces_latin_and_numbers() {
+    assert_eq!(detokenize(&["ខ្ញុំ", "ប្រើ", "iPhone", "ថ្មី"]), "ខ្ញុំប្រើ iPhone ថ្មី");
+    assert_eq!(detokenize(&["តម្លៃ", "100", "ដុល្លារ"]), "តម្លៃ 100 ដុល្លារ");
+    assert_eq!(detokenize(&["New", "York"]), "New York");
+}
+
+#[test]
+fn test_detokenize_punctuation() {
+    assert_eq!(detokenize(&["ខ្ញុំ", "ទៅ", "។", "គាត់", "មក", "៕"]), "ខ្ញុំទៅ។ គាត់មក៕");
+    assert_eq!(detokenize(&["Hello", ",", "world", "!"]), "Hello, world!");
+    assert_eq!(detokenize(&["ពាក្យ", "(", "word", ")", "នេះ"]), "ពាក្យ (word) នេះ");
+    assert_eq!(detokenize(&["«", "សួស្តី", "»"]), "«សួស្តី»");
+    assert_eq!(detokenize(&["$", "100", "និង", "50", "%"]), "$100 និង 50%");
+    assert_eq!(detokenize(&["10", "-", "20", "km/h"]), "10-20 km/h");
+    assert_eq!(detokenize(&["ផ្សេង", "ៗ", "ទៀត"]), "ផ្សេងៗទៀត");
+    assert_eq!(detokenize(&["he", "said", "\"", "hi", "\"", "."]), "he said \"hi\".");
+}
+
+#[test]
+fn test_detokenize_keeps_whitespace_tokens() {
+    assert_eq!(detokenize(&["Hello", " ", "world"]), "Hello world");
+    assert_eq!(detokenize(&["ខ្ញុំ", "  ", "Apple", "\u{200b}", "ទៅ"]), "ខ្ញុំ  Apple\u{200b}ទៅ");
+}
+
+#[test]
+fn test_detokenize_inverts_segmentation() {
+    let dictionary = Dictionary::from_bytes("សួស្តី\nបង\nប្អូន\n".as_bytes(), None).unwrap();
+    let config = SegmenterConfig { whitespace: WhitespacePolicy::Keep, ..Default::default() };
+    let segmenter = KhmerSegmenter::builder(dictionary).config(config).build();
+    for line in ["សួស្តីបង ប្អូន", "សួស្តី Apple 2024។"] {
+        assert_eq!(detokenize(&segmenter.segment(line)), line);
+    }
+}
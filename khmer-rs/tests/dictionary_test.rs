@@ -0,0 +1,242 @@
+//! Tests for dictionary construction and lookup APIs.
+
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::segmenter::KhmerSegmenter;
+use khmer_rs::variants::VariantGenerator;
+
+#[test]
+fn test_from_bytes_without_frequencies() {
+    let dictionary = Dictionary::from_bytes("សួស្តី\nបង\n".as_bytes(), None).expect("Failed to build dictionary");
+    assert!(dictionary.contains("សួស្តី"));
+    // Coeng Ta/Da variant is generated as with file loading
+    assert!(dictionary.contains("សួស\u{17D2}\u{178D}ី"));
+    assert_eq!(dictionary.get_word_cost("បង"), dictionary.default_cost);
+
+    let segmenter = KhmerSegmenter::new(dictionary);
+    assert_eq!(segmenter.segment("សួស្តីបង"), vec!["សួស្តី", "បង"]);
+}
+
+#[test]
+fn test_from_bytes_with_frequencies() {
+    let freq = r#"{"បង": 1000, "សួស្តី": 10}"#;
+    let dictionary = Dictionary::from_bytes("សួស្តី\nបង\n".as_bytes(), Some(freq.as_bytes()))
+        .expect("Failed to build dictionary");
+    assert!(dictionary.get_word_cost("បង") < dictionary.get_word_cost("សួស្តី"));
+    assert!(dictionary.unknown_cost > dictionary.default_cost);
+}
+
+#[test]
+fn test_canonical_form_of_variants() {
+    let variant = "សួស\u{17D2}\u{178D}ី";
+    let dictionary = Dictionary::from_bytes("សួស្តី\nបង\n".as_bytes(), None).unwrap();
+    assert_eq!(dictionary.canonical_form(variant), "សួស្តី");
+    assert_eq!(dictionary.canonical_form("សួស្តី"), "សួស្តី");
+    assert_eq!(dictionary.canonical_form("xyz"), "xyz");
+
+    // A variant that is also listed in the file stays as written
+    let dictionary = Dictionary::from_bytes(format!("សួស្តី\n{}\n", variant).as_bytes(), None).unwrap();
+    assert_eq!(dictionary.canonical_form(variant), variant);
+}
+
+#[test]
+fn test_segmenter_canonicalize_variants() {
+    let variant = "សួស\u{17D2}\u{178D}ី";
+    let text = format!("{}បង", variant);
+
+    let dictionary = Dictionary::from_bytes("សួស្តី\nបង\n".as_bytes(), None).unwrap();
+    let segmenter = KhmerSegmenter::new(dictionary);
+    assert_eq!(segmenter.segment(&text), vec![variant, "បង"]);
+
+    let dictionary = Dictionary::from_bytes("សួស្តី\nបង\n".as_bytes(), None).unwrap();
+    let segmenter = KhmerSegmenter::builder(dictionary).canonicalize_variants(true).build();
+    assert_eq!(segmenter.segment(&text), vec!["សួស្តី", "បង"]);
+}
+
+#[test]
+fn test_variant_generator_rules() {
+    let coeng_da = "ស\u{17D2}\u{178D}ី";
+    // Coeng Ro + Coeng Ka <-> Coeng Ka + Coeng Ro
+    let ro_first = "ស\u{17D2}\u{179A}\u{17D2}\u{1780}";
+    let ro_last = "ស\u{17D2}\u{1780}\u{17D2}\u{179A}";
+
+    let all = VariantGenerator::new();
+    assert_eq!(all.expand("ស្តី"), vec!["ស្តី".to_string(), coeng_da.to_string()]);
+    assert!(all.generate(ro_first).contains(ro_last));
+
+    let ta_da_only = VariantGenerator::new().coeng_ro_reorder(false);
+    assert!(ta_da_only.generate(ro_first).is_empty());
+    assert_eq!(ta_da_only.generate("ស្តី").len(), 1);
+
+    assert!(VariantGenerator::none().generate("ស្តី").is_empty());
+}
+
+#[test]
+fn test_dictionary_builder_without_variants() {
+    let dictionary = Dictionary::builder()
+        .variants(VariantGenerator::none())
+        .load_bytes("សួស្តី\n".as_bytes(), None)
+        .unwrap();
+    assert!(dictionary.contains("សួស្តី"));
+    assert!(!dictionary.contains("សួស\u{17D2}\u{178D}ី"));
+}
+
+#[test]
+fn test_suggest_within_cluster_distance() {
+    let dictionary = Dictionary::from_bytes("កម្ពុជា\nកម្មករ\nខ្មែរ\nសាលា\n".as_bytes(), None).unwrap();
+
+    // Exact match has distance 0
+    let exact = dictionary.suggest("ខ្មែរ", 0);
+    assert_eq!(exact.len(), 1);
+    assert_eq!(exact[0].word, "ខ្មែរ");
+
+    // Wrong vowel on a stacked cluster is a single cluster edit: ម្ពុ -> ម្ពូ
+    let suggestions = dictionary.suggest("កម្ពូជា", 1);
+    assert_eq!(suggestions[0].word, "កម្ពុជា");
+    assert_eq!(suggestions[0].distance, 1);
+
+    // Missing cluster
+    let suggestions = dictionary.suggest("សា", 1);
+    assert_eq!(suggestions.iter().map(|s| s.word.as_str()).collect::<Vec<_>>(), vec!["សាលា"]);
+
+    assert!(dictionary.suggest("អអអអ", 1).is_empty());
+}
+
+#[test]
+fn test_complete_prefix_by_cost() {
+    let freq = r#"{"កម្ពុជា": 500, "កម្មករ": 50, "កម្ម": 5000}"#;
+    let dictionary = Dictionary::from_bytes("កម្ពុជា\nកម្មករ\nកម្ម\nខ្មែរ\n".as_bytes(), Some(freq.as_bytes())).unwrap();
+
+    let words: Vec<String> = dictionary.complete("កម្", 10).into_iter().map(|c| c.word).collect();
+    assert_eq!(words, vec!["កម្ម", "កម្ពុជា", "កម្មករ"]);
+
+    let top: Vec<String> = dictionary.complete("កម្", 2).into_iter().map(|c| c.word).collect();
+    assert_eq!(top, vec!["កម្ម", "កម្ពុជា"]);
+
+    assert!(dictionary.complete("គ", 5).is_empty());
+    assert!(dictionary.complete("ក", 0).is_empty());
+}
+
+#[test]
+fn test_common_prefixes_matches_lookup() {
+    let dictionary = Dictionary::from_bytes("កម្ម\nកម្មករ\nក\nករ\n".as_bytes(), None).unwrap();
+    let cps: Vec<char> = "xកម្មករ".chars().collect();
+
+    let matches: Vec<usize> = dictionary.common_prefixes(&cps, 1).map(|(end, _)| end).collect();
+    let expected: Vec<usize> = (2..=cps.len())
+        .filter(|&end| dictionary.lookup_codepoints(&cps, 1, end).is_some())
+        .collect();
+    assert_eq!(matches, expected);
+    assert_eq!(matches, vec![2, 5, 7]);
+
+    assert_eq!(dictionary.common_prefixes(&cps, 0).count(), 0);
+    assert_eq!(dictionary.common_prefixes(&cps, cps.len()).count(), 0);
+}
+
+#[test]
+fn test_trie_lookup_whatever_the_insertion_order() {
+    // Siblings arrive out of order; children are kept sorted for the search
+    let words = ["ឃក", "កខ", "គង", "កក", "ខក", "កគ", "គក"];
+    let dictionary = Dictionary::from_bytes(words.join("\n").as_bytes(), None).unwrap();
+    for word in words {
+        let cps: Vec<char> = word.chars().collect();
+        assert!(dictionary.lookup_codepoints(&cps, 0, cps.len()).is_some(), "{}", word);
+    }
+    let cps: Vec<char> = "កឃគខង".chars().collect();
+    assert_eq!(dictionary.lookup_codepoints(&cps, 0, 1), None);
+    assert_eq!(dictionary.lookup_codepoints(&cps, 0, 2), None);
+    assert_eq!(dictionary.lookup_codepoints(&cps, 3, 5), None);
+    // Root, four first characters, seven words
+    assert_eq!(dictionary.trie.len(), 12);
+}
+
+#[test]
+fn test_fuzzy_prefixes_single_mark_edit() {
+    let dictionary = Dictionary::from_bytes("កុំ\nសាលា\n".as_bytes(), None).unwrap();
+    let fuzzy = |text: &str| {
+        let cps: Vec<char> = text.chars().collect();
+        let mut out = Vec::new();
+        dictionary.fuzzy_prefixes(&cps, 0, &mut out);
+        out.into_iter().map(|(end, _)| end).collect::<Vec<_>>()
+    };
+
+    // Substituted vowel: កូំ -> កុំ
+    assert_eq!(fuzzy("កូំ"), vec![3]);
+    // Missing sign: កុ -> កុំ
+    assert_eq!(fuzzy("កុ"), vec![2]);
+    // Extra mark in the input: សាលាា -> សាលា (+ ា)
+    assert!(fuzzy("សាលាា").contains(&5));
+    // Exact matches and consonant differences are not reported
+    assert!(fuzzy("កុំ").is_empty());
+    assert!(fuzzy("ខុំ").is_empty());
+}
+
+#[test]
+fn test_segmenter_fuzzy_match() {
+    let text = "សាលូកុំ";
+    let dictionary = Dictionary::from_bytes("កុំ\nសាលា\n".as_bytes(), None).unwrap();
+    let segmenter = KhmerSegmenter::builder(dictionary).fuzzy_match(1.0).build();
+    assert_eq!(segmenter.segment(text), vec!["សាលូ", "កុំ"]);
+}
+
+#[test]
+fn test_stats() {
+    // ត/ដ swap generates one variant of the first entry
+    let dictionary = Dictionary::from_bytes("ស្តី\nបង\nក\n".as_bytes(), None).unwrap();
+    let stats = dictionary.stats();
+
+    assert_eq!(stats.base_words, 3);
+    assert_eq!(stats.variant_words, 1);
+    assert_eq!(stats.total_words(), dictionary.words.len());
+    assert_eq!(stats.length_histogram, vec![0, 1, 1, 0, 2]);
+    // root + ស ្ ត ី + ដ ី + ប ង + ក
+    assert_eq!(stats.trie_nodes, 10);
+    assert!(stats.trie_bytes > 0 && stats.table_bytes > 0);
+    assert!((stats.mean_length() - 11.0 / 4.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_overlay() {
+    use khmer_rs::overlay::Overlay;
+
+    let base = "បង\nស្តី\n".as_bytes();
+    let medical = Overlay::from_reader("ថ្នាំ\nបង\n".as_bytes()).unwrap().cost(3.0);
+    let overrides = Overlay::from_reader("ថ្នាំ\t1.5\nពេទ្យ\n".as_bytes()).unwrap();
+    let dictionary = Dictionary::builder()
+        .overlay(medical)
+        .overlay(overrides)
+        .load_bytes(base, None)
+        .unwrap();
+
+    // Overlay cost overrides the base cost; later overlays win per entry
+    assert_eq!(dictionary.get_word_cost("បង"), 3.0);
+    assert_eq!(dictionary.get_word_cost("ថ្នាំ"), 1.5);
+    // New word without any cost gets the default cost
+    assert_eq!(dictionary.get_word_cost("ពេទ្យ"), dictionary.default_cost);
+    let cps: Vec<char> = "ពេទ្យ".chars().collect();
+    assert_eq!(dictionary.lookup_codepoints(&cps, 0, cps.len()), Some(dictionary.default_cost));
+    assert_eq!(dictionary.max_word_length, 5);
+    // Untouched base entries and their variants are unchanged
+    assert_eq!(dictionary.get_word_cost("ស\u{17D2}\u{178D}ី"), dictionary.default_cost);
+
+    assert!(Overlay::from_reader("ថ្នាំ\tcheap\n".as_bytes()).is_err());
+}
+
+#[test]
+fn test_latin_words() {
+    use khmer_rs::overlay::Overlay;
+
+    let english = Overlay::from_reader("the\t2.0\nDon't\nFacebook\t3.5\n".as_bytes()).unwrap();
+    let dictionary = Dictionary::builder().latin_words(english).load_bytes("បង\n".as_bytes(), None).unwrap();
+
+    // Case-insensitive, with the typographic apostrophe folded
+    assert_eq!(dictionary.latin_word_cost("The"), Some(2.0));
+    assert_eq!(dictionary.latin_word_cost("FACEBOOK"), Some(3.5));
+    assert_eq!(dictionary.latin_word_cost("don’t"), Some(dictionary.default_cost));
+    assert_eq!(dictionary.latin_word_cost("twitter"), None);
+    // Kept apart from the Khmer word list
+    assert!(!dictionary.contains("the"));
+    assert_eq!(dictionary.words.len(), 1);
+
+    let plain = Dictionary::builder().load_bytes("បង\n".as_bytes(), None).unwrap();
+    assert_eq!(plain.latin_word_cost("the"), None);
+}
@@ -0,0 +1,26 @@
+#![cfg(feature = "embedded")]
+//! Compiled-in dictionary (`cargo test --features embedded`).
+
+mod common;
+
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::embedded;
+use khmer_rs::segmenter::KhmerSegmenter;
+
+#[test]
+fn test_embedded_matches_the_data_files() {
+    let embedded = Dictionary::embedded().unwrap();
+    let files = common::dictionary();
+    assert_eq!(embedded.words.len(), files.words.len());
+    assert_eq!(embedded.default_cost, files.default_cost);
+    assert_eq!(embedded.get_word_cost("កម្ពុជា"), files.get_word_cost("កម្ពុជា"));
+
+    let segmenter = KhmerSegmenter::new(embedded);
+    assert_eq!(segmenter.segment("ខ្ញុំស្រលាញ់កម្ពុជា"), vec!["ខ្ញុំ", "ស្រលាញ់", "កម្ពុជា"]);
+}
+
+#[test]
+fn test_embedded_data_is_compressed() {
+    let raw = embedded::dictionary().unwrap().len() + embedded::frequencies().unwrap().len();
+    assert!(embedded::compressed_len() < raw / 2);
+}
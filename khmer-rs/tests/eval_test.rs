@@ -0,0 +1,141 @@
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::eval::{cross_validate, evaluate, k_folds, read_reference_jsonl, read_references, Reference, ReferenceFormat, Scores};
+use khmer_rs::segmenter::KhmerSegmenter;
+
+#[test]
+fn test_scores() {
+    let mut scores = Scores::default();
+    // Spaces and ZWSP do not shift offsets
+    scores.add(&["សួស្តី", " ", "បង"], &["សួស្តី", "\u{200B}", "បង"]);
+    assert_eq!(scores.exact_lines, 1);
+
+    // Reference: ក|ខគ|ឃ ; predicted: ក|ខ|គឃ
+    scores.add(&["ក", "ខគ", "ឃ"], &["ក", "ខ", "គឃ"]);
+    assert_eq!(scores.lines, 2);
+    assert_eq!(scores.exact_lines, 1);
+    assert_eq!((scores.words.reference, scores.words.predicted, scores.words.matched), (5, 5, 3));
+    // Boundaries: reference {1, 3}, predicted {1, 2}, plus 1 from the first line
+    assert_eq!((scores.boundaries.reference, scores.boundaries.predicted, scores.boundaries.matched), (3, 3, 2));
+    assert!((scores.words.f1() - 0.6).abs() < 1e-9);
+    assert!((scores.exact_rate() - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_evaluate_reference_jsonl() {
+    let jsonl = r#"{"id": 0, "input": "សួស្តីបង", "segments": ["សួស្តី", "បង"]}
+
+{"id": 1, "input": "បងប្អូន", "segments": ["បងប្អូន"]}
+"#;
+    let references = read_reference_jsonl(jsonl.as_bytes()).unwrap();
+    assert_eq!(references.len(), 2);
+
+    let dictionary = Dictionary::from_bytes("សួស្តី\nបង\nប្អូន\n".as_bytes(), None).unwrap();
+    let scores = evaluate(&KhmerSegmenter::new(dictionary), &references);
+    assert_eq!(scores.exact_lines, 1);
+    assert_eq!(scores.words.matched, 2);
+    assert_eq!(scores.boundaries.recall(), 1.0);
+    assert_eq!(scores.boundaries.precision(), 0.5);
+}
+
+#[test]
+fn test_read_khpos_references() {
+    let khpos = "សួស្តី/n បង/n ។/PU\n\n1/2/NUM ក/n\n";
+    let references = read_references(khpos.as_bytes(), ReferenceFormat::KhPos).unwrap();
+    assert_eq!(references.len(), 2);
+    assert_eq!(references[0].input, "សួស្តីបង។");
+    assert_eq!(references[0].segments, ["សួស្តី", "បង", "។"]);
+    // Only the last `/TAG` is a tag
+    assert_eq!(references[1].segments, ["1/2", "ក"]);
+}
+
+#[test]
+fn test_read_alt_references() {
+    let alt = "SNT.80188.1\tសួស្តី បង ប្អូន\nSNT.80188.2\tបង/n ប្អូន/n\n";
+    let references = read_references(alt.as_bytes(), ReferenceFormat::Alt).unwrap();
+    assert_eq!(references.len(), 2);
+    assert_eq!(references[0].input, "សួស្តីបងប្អូន");
+    assert_eq!(references[0].segments, ["សួស្តី", "បង", "ប្អូន"]);
+    assert_eq!(references[1].segments, ["បង", "ប្អូន"]);
+
+    let dictionary = Dictionary::from_bytes("សួស្តី\nបង\nប្អូន\n".as_bytes(), None).unwrap();
+    let scores = evaluate(&KhmerSegmenter::new(dictionary), &references);
+    assert_eq!(scores.lines, 2);
+    assert_eq!(scores.words.reference, 5);
+}
+
+#[test]
+fn test_reference_format_names() {
+    for format in ReferenceFormat::ALL {
+        assert_eq!(format.name().parse::<ReferenceFormat>(), Ok(format));
+    }
+    assert!("conll".parse::<ReferenceFormat>().is_err());
+}
+
+#[test]
+fn test_read_space_and_zwsp_references() {
+    let space = "សួស្តី បង\n   \nបង ប្អូន/n\n";
+    let references = read_references(space.as_bytes(), ReferenceFormat::Space).unwrap();
+    assert_eq!(references.len(), 2);
+    assert_eq!(references[0].input, "សួស្តីបង");
+    // No tags in this format
+    assert_eq!(references[1].segments, ["បង", "ប្អូន/n"]);
+
+    let zwsp = "សួស្តី\u{200b}បង\u{200b} \u{200b}ប្អូន\n\n";
+    let references = read_references(zwsp.as_bytes(), ReferenceFormat::Zwsp).unwrap();
+    assert_eq!(references.len(), 1);
+    // Spaces are text, kept in the input
+    assert_eq!(references[0].input, "សួស្តីបង ប្អូន");
+    assert_eq!(references[0].segments, ["សួស្តី", "បង", " ", "ប្អូន"]);
+}
+
+fn reference(word: &str) -> Reference {
+    Reference { input: word.to_string(), segments: vec![word.to_string()] }
+}
+
+#[test]
+fn test_k_folds() {
+    let references: Vec<Reference> = (0..10).map(|i| reference(&i.to_string())).collect();
+    let folds = k_folds(&references, 3, 7);
+    assert_eq!(folds.iter().map(Vec::len).collect::<Vec<_>>(), [4, 3, 3]);
+
+    // Every line lands in exactly one fold, in input order within it
+    let mut inputs: Vec<usize> = folds.iter().flatten().map(|r| r.input.parse().unwrap()).collect();
+    for fold in &folds {
+        assert!(fold.windows(2).all(|w| w[0].input.parse::<usize>().unwrap() < w[1].input.parse().unwrap()));
+    }
+    inputs.sort_unstable();
+    assert_eq!(inputs, (0..10).collect::<Vec<_>>());
+
+    // The seed decides the folds
+    assert_eq!(k_folds(&references, 3, 7), folds);
+    assert_ne!(k_folds(&references, 3, 8), folds);
+}
+
+#[test]
+fn test_cross_validate() {
+    let scores = |matched: &[usize]| -> Vec<Scores> {
+        matched
+            .iter()
+            .map(|&m| {
+                let mut scores = Scores::default();
+                for i in 0..4 {
+                    let predicted: &[&str] = if i < m { &["កខ"] } else { &["ក", "ខ"] };
+                    scores.add(&["កខ"], predicted);
+                }
+                scores
+            })
+            .collect()
+    };
+    // Candidate 0 is better on folds 0 and 1, candidate 1 on fold 2
+    let candidates = vec![scores(&[4, 4, 0]), scores(&[3, 3, 4])];
+    let folds = cross_validate(&candidates);
+    assert_eq!(folds.len(), 3);
+    // Holding out fold 0: 0 scores 4/8 on the rest, 1 scores 7/8
+    assert_eq!(folds[0].best, 1);
+    assert_eq!(folds[0].test.exact_lines, 3);
+    assert_eq!(folds[1].best, 1);
+    // Holding out fold 2: 0 scores 8/8, 1 scores 6/8
+    assert_eq!(folds[2].best, 0);
+    assert_eq!(folds[2].train.exact_lines, 8);
+    assert_eq!(folds[2].test.exact_lines, 0);
+}
@@ -0,0 +1,71 @@
+#![cfg(all(feature = "external", unix))]
+//! External tokenizer adapter (`cargo test --features external`). Standard
+//! Unix tools stand in for the tokenizers, which are not installed here.
+
+use khmer_rs::algorithm::Segmenter;
+use khmer_rs::external::{ExternalSegmenter, ExternalTool};
+
+#[test]
+fn test_splits_each_answer_on_the_delimiter() {
+    // `cat` answers every line with itself
+    let cat = ExternalSegmenter::spawn("cat", &[] as &[&str], "|").unwrap();
+    assert_eq!(cat.segment("ខ្ញុំ|ស្រលាញ់|កម្ពុជា"), vec!["ខ្ញុំ", "ស្រលាញ់", "កម្ពុជា"]);
+    assert_eq!(cat.segment("ក||ខ"), vec!["ក", "ខ"]);
+    assert_eq!(cat.segment(""), Vec::<String>::new());
+}
+
+#[test]
+fn test_line_breaks_stay_one_request() {
+    let cat = ExternalSegmenter::spawn("cat", &[] as &[&str], " ").unwrap();
+    assert_eq!(cat.segment("ក\nខ"), vec!["ក", "ខ"]);
+    // The next answer is not shifted by the line break above
+    assert_eq!(cat.segment("គ"), vec!["គ"]);
+}
+
+#[test]
+fn test_tool_errors() {
+    assert!(ExternalSegmenter::spawn("khmer-rs-no-such-tokenizer", &[] as &[&str], " ").is_err());
+    assert!(ExternalSegmenter::spawn("cat", &[] as &[&str], "").is_err());
+
+    let exits = ExternalSegmenter::spawn("true", &[] as &[&str], " ").unwrap();
+    assert!(exits.try_segment("ក").is_err());
+}
+
+#[test]
+fn test_shared_across_threads() {
+    let cat = ExternalSegmenter::spawn("cat", &[] as &[&str], " ").unwrap();
+    std::thread::scope(|scope| {
+        for i in 0..4 {
+            let cat = &cat;
+            scope.spawn(move || {
+                for j in 0..50 {
+                    let line = format!("ក{} ខ{}", i, j);
+                    assert_eq!(cat.segment(&line), vec![format!("ក{}", i), format!("ខ{}", j)]);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn test_drop_lets_the_tool_exit() {
+    // The tool finishes its work after its input ends, unless it is killed
+    let marker = std::env::temp_dir().join(format!("khmer_external_exit_{}", std::process::id()));
+    let script = format!("cat; echo done > '{}'", marker.display());
+    drop(ExternalSegmenter::spawn("sh", &["-c", &script], " ").unwrap());
+    assert_eq!(std::fs::read_to_string(&marker).unwrap(), "done\n");
+    std::fs::remove_file(&marker).unwrap();
+
+    // One that ignores the end of its input is killed
+    let start = std::time::Instant::now();
+    drop(ExternalSegmenter::spawn("sh", &["-c", "cat; exec sleep 30"], " ").unwrap());
+    assert!(start.elapsed() < std::time::Duration::from_secs(10));
+}
+
+#[test]
+fn test_tool_names_round_trip() {
+    for tool in ExternalTool::ALL {
+        assert_eq!(tool.name().parse::<ExternalTool>(), Ok(tool));
+    }
+    assert!("jieba".parse::<ExternalTool>().unwrap_err().contains("khmercut"));
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "cdylib")]
+//! Exercises the C ABI from Rust (`cargo test --features cdylib`).
+
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+
+use khmer_rs::ffi::*;
+
+#[test]
+fn test_ffi_segment_round_trip() {
+    let dict = CString::new("../data/khmer_dictionary_words.txt").unwrap();
+    let freq = CString::new("../data/khmer_word_frequencies.json").unwrap();
+
+    unsafe {
+        let handle = khmer_segmenter_new(dict.as_ptr(), freq.as_ptr());
+        assert!(!handle.is_null());
+
+        let text = "សួស្តី បង";
+        let mut out = MaybeUninit::<KhmerSegments>::uninit();
+        assert_eq!(khmer_segment(handle, text.as_ptr(), text.len(), out.as_mut_ptr()), KHMER_OK);
+        let mut out = out.assume_init();
+
+        let segments: Vec<&str> = std::slice::from_raw_parts(out.segments, out.count)
+            .iter()
+            .map(|s| std::str::from_utf8(std::slice::from_raw_parts(s.ptr, s.len)).unwrap())
+            .collect();
+        assert_eq!(segments, vec!["សួស្តី", " ", "បង"]);
+        khmer_segments_free(&mut out);
+        assert_eq!(out.count, 0);
+
+        let invalid = [0xffu8, 0xfe];
+        let mut out = MaybeUninit::<KhmerSegments>::uninit();
+        assert_eq!(khmer_segment(handle, invalid.as_ptr(), invalid.len(), out.as_mut_ptr()), KHMER_ERR_INVALID_UTF8);
+
+        khmer_segmenter_free(handle);
+    }
+}
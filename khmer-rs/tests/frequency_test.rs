@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::frequency::{read_frequencies, FrequencyFormat};
+
+#[test]
+fn test_format_from_path() {
+    assert_eq!(FrequencyFormat::from_path(Path::new("freq.json")), FrequencyFormat::Json);
+    assert_eq!(FrequencyFormat::from_path(Path::new("freq.TSV")), FrequencyFormat::Tsv);
+    assert_eq!(FrequencyFormat::from_path(Path::new("freq.csv")), FrequencyFormat::Csv);
+    assert_eq!(FrequencyFormat::from_path(Path::new("freq.txt")), FrequencyFormat::Counts);
+    assert_eq!(FrequencyFormat::from_path(Path::new("freq")), FrequencyFormat::Json);
+}
+
+#[test]
+fn test_read_line_formats() {
+    let tsv = "word\tcount\nបង\t10\tNOUN\nសួស្តី\t5\nបង\t2\n";
+    let counts = read_frequencies(tsv.as_bytes(), FrequencyFormat::Tsv).unwrap();
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts["បង"], 12.0);
+
+    let csv = "\"ក,ខ\",3\n\"a \"\"b\"\"\",4,x\nបង,7\n";
+    let counts = read_frequencies(csv.as_bytes(), FrequencyFormat::Csv).unwrap();
+    assert_eq!(counts["ក,ខ"], 3.0);
+    assert_eq!(counts["a \"b\""], 4.0);
+    assert_eq!(counts["បង"], 7.0);
+
+    let uniq = "     42 បង\n      7 សួស្តី\nកម្ពុជា 3\n";
+    let counts = read_frequencies(uniq.as_bytes(), FrequencyFormat::Counts).unwrap();
+    assert_eq!(counts["បង"], 42.0);
+    assert_eq!(counts["សួស្តី"], 7.0);
+    assert_eq!(counts["កម្ពុជា"], 3.0);
+
+    let bad = "បង\t1\nសួស្តី\tmany\n";
+    assert!(read_frequencies(bad.as_bytes(), FrequencyFormat::Tsv).is_err());
+}
+
+#[test]
+fn test_read_keeps_file_order() {
+    // Costs depend on the order counts are summed in, so it is the file's
+    let json = r#"{"សួស្តី": 5, "បង": 10, "កម្ពុជា": 1, "បង": 12}"#;
+    let counts = read_frequencies(json.as_bytes(), FrequencyFormat::Json).unwrap();
+    let words: Vec<&str> = counts.iter().map(|(word, _)| word).collect();
+    assert_eq!(words, ["សួស្តី", "បង", "កម្ពុជា"]);
+    // A repeated JSON key takes the last value, as in Python
+    assert_eq!(counts["បង"], 12.0);
+    assert_eq!(serde_json::to_string(&counts).unwrap(), r#"{"សួស្តី":5.0,"បង":12.0,"កម្ពុជា":1.0}"#);
+
+    let tsv = "កម្ពុជា\t1\nបង\t2\nកម្ពុជា\t3\n";
+    let counts = read_frequencies(tsv.as_bytes(), FrequencyFormat::Tsv).unwrap();
+    assert_eq!(counts.iter().collect::<Vec<_>>(), [("កម្ពុជា", 4.0), ("បង", 2.0)]);
+}
+
+#[test]
+fn test_formats_give_same_costs() {
+    let words = "បង\nសួស្តី\n".as_bytes();
+    let from_json = Dictionary::from_bytes(words, Some(r#"{"បង": 30, "សួស្តី": 10}"#.as_bytes())).unwrap();
+    let from_tsv = Dictionary::builder()
+        .frequency_format(FrequencyFormat::Tsv)
+        .load_bytes(words, Some("បង\t30\nសួស្តី\t10\n".as_bytes()))
+        .unwrap();
+
+    for word in ["បង", "សួស្តី"] {
+        assert_eq!(from_json.get_word_cost(word), from_tsv.get_word_cost(word));
+    }
+    assert_eq!(from_json.default_cost, from_tsv.default_cost);
+}
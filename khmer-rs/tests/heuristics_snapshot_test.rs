@@ -0,0 +1,102 @@
+//! Snapshot tests for each built-in post-processing pass on its own.
+//!
+//! `tests/snapshots/<pass>.jsonl` holds one case per line: a description, the
+//! segments fed to the pass and the segments it returned when the snapshot
+//! was taken. To add a case, append a line without `output`; to accept a
+//! change in a pass, rerun with `UPDATE_SNAPSHOTS=1` and review the diff of
+//! the snapshot file.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use khmer_rs::config::Pass;
+use khmer_rs::postprocess::{PostProcess, SignMerge, SnapInvalidSingles, UnknownMerge};
+use serde::{Deserialize, Serialize};
+
+use common::dictionary;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Case {
+    description: String,
+    input: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output: Option<Vec<String>>,
+}
+
+fn stage(pass: Pass) -> Box<dyn PostProcess> {
+    match pass {
+        Pass::SnapInvalidSingles => Box::new(SnapInvalidSingles),
+        Pass::SignMerge => Box::new(SignMerge),
+        Pass::UnknownMerge => Box::new(UnknownMerge),
+    }
+}
+
+fn snapshot_path(pass: Pass) -> PathBuf {
+    Path::new("tests/snapshots").join(format!("{}.jsonl", pass.name()))
+}
+
+/// Run every case of `pass`'s snapshot file through the pass alone, and
+/// fail listing each case whose output changed (or rewrite the file with
+/// `UPDATE_SNAPSHOTS=1`).
+fn check_snapshots(pass: Pass) {
+    let path = snapshot_path(pass);
+    let text = std::fs::read_to_string(&path).expect("Failed to read snapshot file");
+    let mut cases: Vec<Case> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).expect("Failed to parse snapshot case"))
+        .collect();
+    assert!(!cases.is_empty(), "{} has no cases", path.display());
+
+    let dictionary = dictionary();
+    let stage = stage(pass);
+    let mut changed = Vec::new();
+    for case in &mut cases {
+        let actual = stage.process(case.input.clone(), &dictionary);
+        assert_eq!(actual.concat(), case.input.concat(), "{}: {}: text changed", pass, case.description);
+        if case.output.as_ref() != Some(&actual) {
+            changed.push(format!(
+                "{}\n    input:    {:?}\n    expected: {:?}\n    actual:   {:?}",
+                case.description, case.input, case.output, actual
+            ));
+            case.output = Some(actual);
+        }
+    }
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        let lines: Vec<String> = cases.iter().map(|case| serde_json::to_string(case).unwrap()).collect();
+        std::fs::write(&path, lines.join("\n") + "\n").expect("Failed to write snapshot file");
+        return;
+    }
+    assert!(
+        changed.is_empty(),
+        "{} of {} {} snapshots changed (rerun with UPDATE_SNAPSHOTS=1 to accept):\n  {}",
+        changed.len(),
+        cases.len(),
+        pass,
+        changed.join("\n  ")
+    );
+}
+
+#[test]
+fn test_snap_invalid_singles_snapshots() {
+    check_snapshots(Pass::SnapInvalidSingles);
+}
+
+#[test]
+fn test_sign_merge_snapshots() {
+    check_snapshots(Pass::SignMerge);
+}
+
+#[test]
+fn test_unknown_merge_snapshots() {
+    check_snapshots(Pass::UnknownMerge);
+}
+
+#[test]
+fn test_every_pass_has_snapshots() {
+    for pass in Pass::ALL {
+        assert!(snapshot_path(pass).exists(), "no snapshot file for {}", pass);
+    }
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "hf-tokenizers")]
+//! HuggingFace pre-tokenizer offsets (`cargo test --features hf-tokenizers`).
+
+mod common;
+
+use std::sync::Arc;
+
+use khmer_rs::hf::{pre_tokenize_str, KhmerPreTokenizer};
+use khmer_rs::segmenter::KhmerSegmenter;
+
+#[test]
+fn test_pre_tokenizer_offsets() {
+    let segmenter = Arc::new(KhmerSegmenter::builder(common::dictionary()).build());
+
+    let text = "សួស្តី បង\u{200b}ខ្ញុំ";
+    let tokens = pre_tokenize_str(&KhmerPreTokenizer::new(segmenter.clone()), text).unwrap();
+    let words: Vec<&str> = tokens.iter().map(|(t, _)| t.as_str()).collect();
+    assert_eq!(words, vec!["សួស្តី", "បង", "ខ្ញុំ"]);
+    for (token, (start, end)) in &tokens {
+        assert_eq!(&text[*start..*end], token);
+    }
+
+    let with_ws = pre_tokenize_str(&KhmerPreTokenizer::new(segmenter).keep_whitespace(true), text).unwrap();
+    assert_eq!(with_ws.len(), 4);
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "icu")]
+//! ICU4X comparison backend (`cargo test --features icu`).
+
+use khmer_rs::algorithm::Segmenter;
+use khmer_rs::eval::{evaluate, Reference};
+use khmer_rs::icu::{IcuModel, IcuSegmenter};
+
+#[test]
+fn test_icu_segments_cover_input() {
+    let text = "សួស្តីបង ខ្ញុំទៅផ្សារ។";
+    for model in [IcuModel::Dictionary, IcuModel::Lstm] {
+        let segments = IcuSegmenter::new(model).segment(text);
+        assert!(segments.len() > 3, "{:?}: {:?}", model, segments);
+        assert_eq!(segments.concat(), text, "{:?}", model);
+        assert!(segments.contains(&" ".to_string()), "{:?}", model);
+    }
+}
+
+#[test]
+fn test_icu_runs_through_eval() {
+    let references = vec![Reference {
+        input: "សួស្តីបង".to_string(),
+        segments: vec!["សួស្តី".to_string(), "បង".to_string()],
+    }];
+    let segmenter: Box<dyn Segmenter> = Box::new(IcuSegmenter::new(IcuModel::Dictionary));
+    let scores = evaluate(&segmenter, &references);
+    assert_eq!(scores.lines, 1);
+    assert!(scores.boundaries.recall() > 0.0);
+}
@@ -0,0 +1,72 @@
+//! Decoding of input lines.
+
+use std::io::{Cursor, ErrorKind};
+
+use khmer_rs::input::{decode_lossy, InvalidUtf8, LineDecoder, LineEndings, Utf8Policy};
+use khmer_rs::sampling::LineSelection;
+
+#[test]
+fn test_decode_lossy_counts_sequences() {
+    assert_eq!(decode_lossy("សួស្តី".as_bytes()), ("សួស្តី".to_string(), 0));
+    // A lone continuation byte, a truncated 3-byte sequence and an invalid byte
+    let bytes = b"a\x80b\xe1\x9ec\xff";
+    assert_eq!(decode_lossy(bytes), ("a\u{fffd}b\u{fffd}c\u{fffd}".to_string(), 3));
+    assert_eq!(decode_lossy(bytes).0, String::from_utf8_lossy(bytes));
+}
+
+#[test]
+fn test_strict_decoder_fails_on_the_line() {
+    let input = Cursor::new(b"ok\nstill ok\nbad \xff\nnever read\n".to_vec());
+    let lines: Vec<_> = LineSelection::default().lines(input).collect();
+    assert_eq!(lines.len(), 3);
+    let err = lines[2].as_ref().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.to_string().contains("line 3"), "{}", err);
+}
+
+#[test]
+fn test_lossy_decoder_reports_lines() {
+    let decoder = LineDecoder::new().utf8(Utf8Policy::Lossy);
+    let input = Cursor::new(b"ok\n\nbad \xff\r\nfine\n\x80x\x80\n".to_vec());
+    let lines: Vec<String> =
+        LineSelection::default().decoded_lines(input, decoder.clone()).collect::<std::io::Result<_>>().unwrap();
+    assert_eq!(lines, vec!["ok", "bad \u{fffd}", "fine", "\u{fffd}x\u{fffd}"]);
+    // Line numbers count every line, empty ones included
+    assert_eq!(decoder.invalid_utf8(), InvalidUtf8 { sequences: 3, lines: 2, line_numbers: vec![3, 5] });
+}
+
+fn decode_all(decoder: LineDecoder, bytes: &[u8]) -> Vec<String> {
+    LineSelection::default().decoded_lines(Cursor::new(bytes.to_vec()), decoder).collect::<std::io::Result<_>>().unwrap()
+}
+
+#[test]
+fn test_bom_and_crlf_are_dropped() {
+    let bytes = "\u{feff}សួស្តី\r\nបង \r\n\u{feff}ok\r\nlast\r".as_bytes();
+    // Only the byte order mark at the start of the input goes
+    assert_eq!(decode_all(LineDecoder::new(), bytes), vec!["សួស្តី", "បង ", "\u{feff}ok", "last"]);
+    assert_eq!(decode_all(LineDecoder::new().trim(true), bytes), vec!["សួស្តី", "បង", "\u{feff}ok", "last"]);
+    assert_eq!(
+        decode_all(LineDecoder::new().keep_bom(true).line_endings(LineEndings::Lf), bytes),
+        vec!["\u{feff}សួស្តី\r", "បង \r", "\u{feff}ok\r", "last\r"]
+    );
+    // A line holding only the byte order mark is empty once it is dropped
+    assert_eq!(decode_all(LineDecoder::new(), b"\xef\xbb\xbf\nx\n"), vec!["x"]);
+}
+
+#[test]
+fn test_utf8_policy_names() {
+    for policy in Utf8Policy::ALL {
+        assert_eq!(policy.name().parse::<Utf8Policy>(), Ok(policy));
+    }
+    assert_eq!(Utf8Policy::default(), Utf8Policy::Strict);
+    assert!("latin1".parse::<Utf8Policy>().unwrap_err().contains("lossy"));
+}
+
+#[test]
+fn test_line_endings_names() {
+    for endings in LineEndings::ALL {
+        assert_eq!(endings.name().parse::<LineEndings>(), Ok(endings));
+    }
+    assert_eq!(LineEndings::default(), LineEndings::Crlf);
+    assert!("cr".parse::<LineEndings>().unwrap_err().contains("crlf, lf"));
+}
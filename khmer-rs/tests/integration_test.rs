@@ -2,7 +2,6 @@ use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 use serde::Deserialize;
-use serde_json::Value;
 
 use khmer_rs::dictionary::Dictionary;
 use khmer_rs::segmenter::KhmerSegmenter;
@@ -0,0 +1,45 @@
+use khmer_rs::keywords::TfIdf;
+
+#[test]
+fn test_tfidf_document_frequency() {
+    let mut tfidf = TfIdf::new();
+    assert_eq!(tfidf.add_document(["ក", "ខ", "ក", " ", "123", "!"]), 0);
+    assert_eq!(tfidf.add_document(["ក", "គ"]), 1);
+
+    assert_eq!(tfidf.documents(), 2);
+    // Only Khmer words are terms
+    assert_eq!(tfidf.vocabulary(), 3);
+    assert_eq!(tfidf.document_frequency("ក"), 2);
+    assert_eq!(tfidf.document_frequency("ខ"), 1);
+    assert_eq!(tfidf.document_frequency("123"), 0);
+    assert!((tfidf.idf("ក") - 1.0).abs() < 1e-9);
+    assert!((tfidf.idf("ខ") - (1.5f64.ln() + 1.0)).abs() < 1e-9);
+}
+
+#[test]
+fn test_tfidf_keywords() {
+    let mut tfidf = TfIdf::new();
+    tfidf.add_document(["ក", "ខ", "ខ", "គ"]);
+    tfidf.add_document(["ក", "គ"]);
+    tfidf.add_document(["ក", "ឃ"]);
+
+    let keywords = tfidf.keywords(0, 10, 1.0);
+    let words: Vec<&str> = keywords.iter().map(|k| k.word).collect();
+    // Frequent here and rare elsewhere first; common everywhere last
+    assert_eq!(words, ["ខ", "គ", "ក"]);
+    assert_eq!(keywords[0].count, 2);
+    let idf = (4.0f64 / 2.0).ln() + 1.0;
+    assert!((keywords[0].score - 0.5 * idf).abs() < 1e-9);
+
+    assert_eq!(tfidf.keywords(0, 1, 1.0).len(), 1);
+    // "ក" is in every document, "គ" in two of three
+    let words: Vec<&str> = tfidf.keywords(0, 10, 0.5).iter().map(|k| k.word).collect();
+    assert_eq!(words, ["ខ"]);
+}
+
+#[test]
+fn test_tfidf_empty_document() {
+    let mut tfidf = TfIdf::new();
+    tfidf.add_document(["123", " "]);
+    assert!(tfidf.keywords(0, 10, 1.0).is_empty());
+}
@@ -0,0 +1,76 @@
+//! Tests for `KhmerSegmenter::segment_lossless` and `SegmenterConfig::lossless`.
+
+mod common;
+
+use khmer_rs::config::WhitespacePolicy;
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::segmenter::KhmerSegmenter;
+use proptest::prelude::*;
+
+use common::dictionary;
+
+/// Khmer letters and signs, with the whitespace, ZWSP and punctuation the
+/// segmenter treats specially.
+fn text() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![
+            4 => prop::char::range('\u{1780}', '\u{17F9}'),
+            1 => prop::sample::select(vec![' ', ' ', '\t', '\u{200b}', '។', '.', '-', '$', 'a', 'Z', '1', '😀']),
+        ],
+        0..40,
+    )
+    .prop_map(|chars| chars.into_iter().collect())
+}
+
+#[test]
+fn test_lossless_overrides() {
+    let text = "\u{200b} ខ្ញុំ  ស្រលាញ់\u{200b}កម្ពុជា\t";
+    let segmenter = KhmerSegmenter::builder(dictionary())
+        .whitespace(WhitespacePolicy::Drop)
+        .canonicalize_variants(true)
+        .build();
+    assert_eq!(segmenter.segment(text), vec!["ខ្ញុំ", "ស្រលាញ់", "កម្ពុជា"]);
+    assert_eq!(segmenter.segment_lossless(text).concat(), text);
+
+    let lossless = KhmerSegmenter::builder(dictionary()).lossless(true).build();
+    let segments = lossless.segment(text);
+    assert_eq!(segments, vec!["\u{200b}", " ", "ខ្ញុំ", " ", " ", "ស្រលាញ់", "\u{200b}", "កម្ពុជា\t"]);
+    let spans: Vec<&str> = lossless.segment_spans(text).into_iter().map(|span| span.as_str(text)).collect();
+    assert_eq!(spans, segments);
+    let tokens: Vec<String> = lossless.segment_tokens(text).into_iter().map(|token| token.text).collect();
+    assert_eq!(tokens, segments);
+}
+
+#[test]
+fn test_lossless_despite_lossy_stage() {
+    let segmenter = KhmerSegmenter::builder(dictionary())
+        .add_post_processor(|mut segments: Vec<String>, _: &Dictionary| {
+            segments.retain(|s| s != " ");
+            segments
+        })
+        .build();
+    let text = "ខ្ញុំ ស្រលាញ់ កម្ពុជា";
+    assert_eq!(segmenter.segment_lossless(text).concat(), text);
+}
+
+proptest! {
+    #[test]
+    fn prop_segment_lossless_concatenates_to_input(text in text()) {
+        let segmenter = KhmerSegmenter::builder(dictionary()).canonicalize_variants(true).build();
+        let segments = segmenter.segment_lossless(&text);
+        prop_assert_eq!(segments.concat(), text);
+        prop_assert!(segments.iter().all(|seg| !seg.is_empty()));
+    }
+
+    #[test]
+    fn prop_lossless_mode_concatenates_to_input(text in text(), boundaries in any::<bool>()) {
+        let segmenter = KhmerSegmenter::builder(dictionary())
+            .lossless(true)
+            .zwsp_boundaries(boundaries)
+            .whitespace(WhitespacePolicy::Collapse)
+            .build();
+        prop_assert_eq!(segmenter.segment(&text).concat(), text.clone());
+        let spans: String = segmenter.segment_spans(&text).into_iter().map(|span| span.as_str(&text)).collect();
+        prop_assert_eq!(spans, text);
+    }
+}
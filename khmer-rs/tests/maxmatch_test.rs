@@ -0,0 +1,49 @@
+//! Tests for the greedy longest-match baseline.
+
+mod common;
+
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::frequency::Frequencies;
+use khmer_rs::maxmatch::MaxMatchSegmenter;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_DICT: AtomicUsize = AtomicUsize::new(0);
+
+fn dictionary(words: &[&str]) -> Dictionary {
+    let dir = std::env::temp_dir().join(format!("khmer_maxmatch_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let dict_path = dir.join(format!("dict_{}.txt", NEXT_DICT.fetch_add(1, Ordering::Relaxed)));
+    let mut file = std::fs::File::create(&dict_path).unwrap();
+    for word in words {
+        writeln!(file, "{}", word).unwrap();
+    }
+    Dictionary::builder().load_with_frequencies(&dict_path, Frequencies::new()).unwrap()
+}
+
+#[test]
+fn test_longest_match_wins() {
+    // Viterbi could prefer កខ + គឃ; greedy takes the longest word first
+    let segmenter = MaxMatchSegmenter::new(dictionary(&["កខ", "គឃ", "កខគ"]));
+    assert_eq!(segmenter.segment("កខគឃ"), vec!["កខគ", "ឃ"]);
+}
+
+#[test]
+fn test_unknown_text_falls_back_to_clusters_and_runs() {
+    let segmenter = MaxMatchSegmenter::new(dictionary(&["កខ"]));
+    assert_eq!(segmenter.segment("កខស្រ abc12!"), vec!["កខ", "ស្រ", " ", "abc12", "!"]);
+}
+
+#[test]
+fn test_zero_width_spaces_are_dropped() {
+    let segmenter = MaxMatchSegmenter::new(dictionary(&["កខ"]));
+    assert_eq!(segmenter.segment("ក\u{200b}ខ"), vec!["កខ"]);
+    assert!(segmenter.segment("").is_empty());
+}
+
+#[test]
+fn test_output_covers_input() {
+    let segmenter = MaxMatchSegmenter::new(common::dictionary());
+    let text = "ក្រុមហ៊ុនបានប្រកាសថា ១០០ ដុល្លារ";
+    assert_eq!(segmenter.segment(text).concat(), text);
+}
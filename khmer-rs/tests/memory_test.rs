@@ -0,0 +1,14 @@
+use khmer_rs::memory::parse_bytes;
+
+#[test]
+fn test_parse_bytes() {
+    assert_eq!(parse_bytes("1000000"), Some(1_000_000));
+    assert_eq!(parse_bytes("64k"), Some(64 * 1024));
+    assert_eq!(parse_bytes("512M"), Some(512 << 20));
+    assert_eq!(parse_bytes("512MB"), Some(512 << 20));
+    assert_eq!(parse_bytes("1.5G"), Some(3 << 29));
+    assert_eq!(parse_bytes(" 2 g "), Some(2 << 30));
+    assert_eq!(parse_bytes("12X"), None);
+    assert_eq!(parse_bytes("-1M"), None);
+    assert_eq!(parse_bytes(""), None);
+}
@@ -0,0 +1,117 @@
+use std::thread;
+
+use khmer_rs::bigram::BigramTable;
+use khmer_rs::dictionary::Cost;
+use khmer_rs::ngrams::NgramCounter;
+
+fn separator(token: &str) -> bool {
+    token == "។"
+}
+
+#[test]
+fn test_ngrams_counts_orders() {
+    let counter = NgramCounter::new(&[3, 2], false);
+    counter.add(&["ក", " ", "ខ", "គ", "។", "ក", "ខ"], separator);
+    let ngrams = counter.finish();
+
+    // Whitespace is skipped, separators are tokens like any other
+    assert_eq!(ngrams.get(&["ក", "ខ"]), 2);
+    assert_eq!(ngrams.get(&["គ", "។"]), 1);
+    assert_eq!(ngrams.get(&["ក", "ខ", "គ"]), 1);
+    assert_eq!(ngrams.get(&["ក", " "]), 0);
+    assert_eq!(ngrams.get(&["ក"]), 0);
+    assert_eq!(ngrams.distinct(2), 4);
+    assert_eq!(ngrams.distinct(3), 4);
+}
+
+#[test]
+fn test_ngrams_skip_separators() {
+    let counter = NgramCounter::new(&[2], true);
+    counter.add(&["ក", "ខ", "។", "គ", "ឃ"], separator);
+    let ngrams = counter.finish();
+
+    assert_eq!(ngrams.get(&["ក", "ខ"]), 1);
+    assert_eq!(ngrams.get(&["គ", "ឃ"]), 1);
+    // No n-gram spans the separator
+    assert_eq!(ngrams.get(&["ខ", "គ"]), 0);
+    assert_eq!(ngrams.get(&["ខ", "។"]), 0);
+    assert_eq!(ngrams.distinct(2), 2);
+}
+
+#[test]
+fn test_ngrams_ranked_and_tsv() {
+    let counter = NgramCounter::new(&[2, 3], false);
+    counter.add(&["ក", "ខ", "ក", "ខ", "គ"], separator);
+    let ngrams = counter.finish();
+
+    let ranked = ngrams.ranked(2);
+    assert_eq!(ranked, vec![(vec!["ក", "ខ"], 2)]);
+
+    let mut out = Vec::new();
+    ngrams.write_tsv(&mut out, 1).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "ក\tខ\t2");
+    // Bigrams before trigrams, then by count and token order
+    assert_eq!(lines[1..3], ["ខ\tក\t1", "ខ\tគ\t1"]);
+    assert_eq!(lines[3..], ["ក\tខ\tក\t1", "ក\tខ\tគ\t1", "ខ\tក\tខ\t1"]);
+}
+
+#[test]
+fn test_ngrams_skip_tab_tokens() {
+    // The segmenter keeps `ខ្ញុំ\t` and `😀\t😀` whole
+    let counter = NgramCounter::new(&[2, 3], false);
+    counter.add(&["ក", "ខ្ញុំ\t", "ទៅ", "គ", "😀\t😀", "ឃ"], separator);
+    let ngrams = counter.finish();
+
+    assert_eq!(ngrams.get(&["ទៅ", "គ"]), 1);
+    // No n-gram holds or spans a tab-containing token
+    assert_eq!(ngrams.distinct(2), 1);
+    assert_eq!(ngrams.distinct(3), 0);
+    for (tokens, _) in ngrams.ranked(1) {
+        assert_eq!(tokens.len(), 2, "{:?}", tokens);
+    }
+    let mut out = Vec::new();
+    ngrams.write_bigram_costs(&mut out, 1).unwrap();
+    let table = BigramTable::from_reader(out.as_slice()).unwrap();
+    assert_eq!(table.len(), 1);
+    assert!(table.get("ទៅ", "គ").is_some());
+}
+
+#[test]
+fn test_ngrams_merges_workers() {
+    let counter = NgramCounter::new(&[2], false);
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..25 {
+                    counter.add(&["ក", "ខ"], separator);
+                }
+            });
+        }
+    });
+    assert_eq!(counter.finish().get(&["ក", "ខ"]), 100);
+}
+
+#[test]
+fn test_bigram_costs_round_trip() {
+    let counter = NgramCounter::new(&[2], false);
+    for _ in 0..3 {
+        counter.add(&["ក", "ខ"], separator);
+    }
+    counter.add(&["ក", "គ"], separator);
+    let ngrams = counter.finish();
+
+    let mut out = Vec::new();
+    ngrams.write_bigram_costs(&mut out, 1).unwrap();
+    let table = BigramTable::from_reader(out.as_slice()).unwrap();
+    assert_eq!(table.len(), 2);
+    let (often, rarely): (Cost, Cost) = (0.75, 0.25);
+    assert!((table.get("ក", "ខ").unwrap() + often.log10()).abs() < 1e-3);
+    assert!((table.get("ក", "គ").unwrap() + rarely.log10()).abs() < 1e-3);
+
+    // Rare pairs are dropped but still count towards P(word | previous)
+    let costs = ngrams.bigram_costs(2);
+    assert_eq!(costs.len(), 1);
+    assert_eq!((costs[0].0, costs[0].1), ("ក", "ខ"));
+}
@@ -0,0 +1,75 @@
+//! Async segmentation, driven by a minimal `block_on` (the crate has no
+//! async runtime).
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use khmer_rs::algorithm::Segmenter;
+use khmer_rs::nonblocking::AsyncSegmenter;
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+/// Splits on spaces; panics on `!`.
+struct Words;
+
+impl Segmenter for Words {
+    fn segment(&self, text: &str) -> Vec<String> {
+        assert!(!text.contains('!'), "bad input");
+        text.split(' ').map(str::to_string).collect()
+    }
+}
+
+#[test]
+fn test_segment_async() {
+    let segmenter = AsyncSegmenter::new(Arc::new(Words));
+    assert_eq!(block_on(segmenter.segment_async("ក ខ")), vec!["ក", "ខ"]);
+
+    let pending: Vec<_> = (0..100).map(|i| segmenter.segment_async(format!("ក {}", i))).collect();
+    for (i, pending) in pending.into_iter().enumerate() {
+        assert_eq!(block_on(pending), vec!["ក".to_string(), i.to_string()]);
+    }
+}
+
+#[test]
+fn test_segment_batch_async_on_a_dedicated_pool() {
+    let pool = Arc::new(rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap());
+    let segmenter = AsyncSegmenter::new(Arc::new(Words) as Arc<dyn Segmenter>).pool(pool);
+    let lines: Vec<String> = (0..500).map(|i| format!("{} ក", i)).collect();
+    let segments = block_on(segmenter.segment_batch_async(lines.clone()));
+    assert_eq!(segments.len(), lines.len());
+    for (i, segments) in segments.iter().enumerate() {
+        assert_eq!(segments, &vec![i.to_string(), "ក".to_string()]);
+    }
+    assert_eq!(block_on(segmenter.segment_batch_async(Vec::new())), Vec::<Vec<String>>::new());
+}
+
+#[test]
+fn test_panic_is_raised_where_awaited() {
+    let segmenter = AsyncSegmenter::new(Arc::new(Words));
+    let pending = segmenter.segment_async("!");
+    let caught = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| block_on(pending)));
+    assert!(caught.is_err());
+    // The pool survives
+    assert_eq!(block_on(segmenter.segment_async("ក")), vec!["ក"]);
+}
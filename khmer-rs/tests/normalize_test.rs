@@ -0,0 +1,58 @@
+//! Tests for the normalization pre-pass.
+
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::normalize::normalize;
+use khmer_rs::segmenter::KhmerSegmenter;
+
+#[test]
+fn test_normalize_is_identity_on_clean_text() {
+    for text in ["ខ្ញុំស្រលាញ់កម្ពុជា", "សួស្តី បង។", "hello 123"] {
+        assert_eq!(normalize(text), text);
+    }
+}
+
+#[test]
+fn test_reorder_vowel_before_subscript() {
+    // ក + ា + ្រ -> ក្រា
+    assert_eq!(normalize("ក\u{17B6}\u{17D2}\u{179A}"), "ក\u{17D2}\u{179A}\u{17B6}");
+}
+
+#[test]
+fn test_reorder_sign_before_vowel() {
+    // ក + ំ + ុ -> កុំ
+    assert_eq!(normalize("ក\u{17C6}\u{17BB}"), "កុំ");
+}
+
+#[test]
+fn test_collapse_duplicate_coeng() {
+    assert_eq!(normalize("ស\u{17D2}\u{17D2}ត"), "ស្ត");
+}
+
+#[test]
+fn test_deprecated_characters() {
+    assert_eq!(normalize("\u{17A3}"), "\u{17A2}");
+    assert_eq!(normalize("\u{17A4}"), "\u{17A2}\u{17B6}");
+    assert_eq!(normalize("ក\u{17B4}ខ"), "កខ");
+    assert_eq!(normalize("\u{17D8}"), "។ល។");
+}
+
+#[test]
+fn test_invisible_joiners() {
+    assert_eq!(normalize("ក\u{200C}ខ\u{200D}គ"), "កខគ");
+    // ZWJ holds emoji sequences together
+    let family = "👨\u{200D}👩\u{200D}👧";
+    assert_eq!(normalize(family), family);
+    assert_eq!(normalize(&format!("សួស្តី{}", family)), format!("សួស្តី{}", family));
+    let rainbow_flag = "🏳\u{FE0F}\u{200D}🌈";
+    assert_eq!(normalize(rainbow_flag), rainbow_flag);
+    assert_eq!(normalize("ក\u{200b}\u{200b}ខ"), "ក\u{200b}ខ");
+    assert_eq!(normalize("ក\u{FEFF}ខ"), "ក\u{200b}ខ");
+    assert_eq!(normalize("\u{200b}ក\u{200b} ខ\u{200b}"), "ក ខ");
+}
+
+#[test]
+fn test_normalized_emoji_stays_one_token() {
+    let segmenter = KhmerSegmenter::new(Dictionary::from_bytes("សួស្តី\n".as_bytes(), None).unwrap());
+    let family = "👨\u{200D}👩\u{200D}👧";
+    assert_eq!(segmenter.segment(&normalize(&format!("សួស្តី{}", family))), vec!["សួស្តី", family]);
+}
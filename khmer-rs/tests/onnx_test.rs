@@ -0,0 +1,39 @@
+#![cfg(feature = "onnx")]
+//! ONNX backend decoding (`cargo test --features onnx`). Inference itself
+//! needs ONNX Runtime and a model, so only vocabulary and tag decoding are
+//! covered here.
+
+use khmer_rs::onnx::{segments_from_scores, Vocabulary};
+
+#[test]
+fn test_vocabulary_ids() {
+    let vocab = Vocabulary::from_reader("<pad>\n<unk>\n \nក\nខ\n".as_bytes()).unwrap();
+    assert_eq!(vocab.len(), 3);
+    assert_eq!(vocab.id(' '), 2);
+    assert_eq!(vocab.id('ក'), 3);
+    assert_eq!(vocab.id('ខ'), 4);
+    assert_eq!(vocab.id('គ'), 1);
+}
+
+#[test]
+fn test_segments_from_bies_scores() {
+    let cps: Vec<char> = "កខគ ឃ".chars().collect();
+    // Best tags: B, E, S, (space: I, overridden), S
+    #[rustfmt::skip]
+    let scores = [
+        0.9, 0.1, 0.0, 0.0,
+        0.0, 0.1, 0.9, 0.0,
+        0.0, 0.0, 0.1, 0.9,
+        0.0, 0.9, 0.0, 0.1,
+        0.1, 0.0, 0.0, 0.9,
+    ];
+    assert_eq!(segments_from_scores(&cps, &scores, 4), vec!["កខ", "គ", " ", "ឃ"]);
+}
+
+#[test]
+fn test_segments_from_bi_scores() {
+    let cps: Vec<char> = "កខគ".chars().collect();
+    let scores = [0.2, 0.8, 0.1, 0.9, 0.7, 0.3];
+    // The first character always starts a word
+    assert_eq!(segments_from_scores(&cps, &scores, 2), vec!["កខ", "គ"]);
+}
@@ -0,0 +1,68 @@
+use khmer_rs::orthography::{attaches_to_previous, validate_syllables, SyllableIssue, SyllableIssueKind};
+
+/// `(offset, kind)` of each issue.
+fn issues(text: &str) -> Vec<(usize, SyllableIssueKind)> {
+    validate_syllables(text).into_iter().map(|issue| (issue.offset, issue.kind)).collect()
+}
+
+#[test]
+fn test_valid_syllables() {
+    assert!(issues("សួស្តី").is_empty());
+    assert!(issues("ស្ត្រី").is_empty());
+    assert!(issues("វ្ឫក្ស").is_empty());
+    // Register shifters come before the vowel, signs after it
+    assert!(issues("ប៊ីចាំ").is_empty());
+    // Joiners stay inside the syllable
+    assert!(issues("ក\u{200C}ា").is_empty());
+    assert!(issues("ឆ្នាំ 2024, ok").is_empty());
+}
+
+#[test]
+fn test_coeng_issues() {
+    assert_eq!(issues("ក្"), vec![(3, SyllableIssueKind::DanglingCoeng)]);
+    assert_eq!(issues("្ក"), vec![(0, SyllableIssueKind::CoengWithoutBase)]);
+    assert_eq!(
+        issues("ក្ា"),
+        vec![(3, SyllableIssueKind::DanglingCoeng), (6, SyllableIssueKind::DanglingVowel)]
+    );
+    assert_eq!(issues("ក្ក្ក្ក"), vec![(15, SyllableIssueKind::TooManySubscripts)]);
+    assert_eq!(issues("កា្ក"), vec![(6, SyllableIssueKind::SubscriptAfterVowel)]);
+}
+
+#[test]
+fn test_mark_issues() {
+    assert_eq!(issues("ាក"), vec![(0, SyllableIssueKind::DanglingVowel)]);
+    assert_eq!(issues("កាា"), vec![(6, SyllableIssueKind::DoubleVowel)]);
+    assert_eq!(issues("កំំ"), vec![(6, SyllableIssueKind::RepeatedSign)]);
+    // Other characters end the syllable
+    assert_eq!(issues("ក ំ"), vec![(4, SyllableIssueKind::DanglingSign)]);
+    // Each syllable has its own vowel
+    assert!(issues("កាកា").is_empty());
+}
+
+#[test]
+fn test_issue_display() {
+    let issue = validate_syllables("a ា")[0];
+    assert_eq!(issue, SyllableIssue { offset: 2, character: 'ា', kind: SyllableIssueKind::DanglingVowel });
+    assert_eq!(issue.to_string(), "dangling-vowel (U+17B6) at offset 2");
+    assert_eq!(
+        serde_json::to_string(&issue).unwrap(),
+        r#"{"offset":2,"character":"ា","kind":"dangling-vowel"}"#
+    );
+}
+
+#[test]
+fn test_kind_names() {
+    for kind in SyllableIssueKind::ALL {
+        assert_eq!(kind.name().parse::<SyllableIssueKind>(), Ok(kind));
+    }
+    let err = "double".parse::<SyllableIssueKind>().unwrap_err();
+    assert!(err.starts_with("unknown syllable issue \"double\""), "{}", err);
+}
+
+#[test]
+fn test_attaches_to_previous() {
+    let cps: Vec<char> = "ក្កា".chars().collect();
+    let attached: Vec<bool> = (0..cps.len()).map(|i| attaches_to_previous(&cps, i)).collect();
+    assert_eq!(attached, vec![false, false, true, true]);
+}
@@ -0,0 +1,162 @@
+//! Output record formats.
+
+use khmer_rs::output::{Jsonl, OutputFormat, OutputFormatter, Record};
+use khmer_rs::schema::SCHEMA_VERSION;
+
+fn records() -> Vec<Record<'static>> {
+    vec![
+        Record::new(0, "សួស្តីបង", &["សួស្តី", "បង"]),
+        Record::new(7, "a \"quoted\"\tline\u{1}\\", &["a", " ", "\"quoted\"", "\t", "line\u{1}\\"]),
+        Record::new(12345, "", &[] as &[&str]).filtered(vec![]),
+        Record::new(3, "ខ្ញុំ និង អ្នក", &["ខ្ញុំ", " ", "និង", " ", "អ្នក"]).filtered(vec![1, 2, 3]),
+    ]
+}
+
+/// The record as serde_json would write it.
+fn expected(record: &Record) -> serde_json::Value {
+    serde_json::to_value(record).unwrap()
+}
+
+#[test]
+fn test_jsonl_matches_serde_json() {
+    let mut out = Vec::new();
+    for record in records() {
+        out.clear();
+        Jsonl.encode(&record, &mut out).unwrap();
+        assert_eq!(out.pop(), Some(b'\n'));
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value, expected(&record), "{}", String::from_utf8_lossy(&out));
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+    }
+    // Records without a filter list have no "filtered" field
+    assert!(expected(&records()[0]).get("filtered").is_none());
+}
+
+#[test]
+fn test_output_format_names() {
+    for &format in OutputFormat::ALL {
+        assert_eq!(format.name().parse::<OutputFormat>(), Ok(format));
+    }
+    assert_eq!(OutputFormat::default(), OutputFormat::Jsonl);
+    assert!("xml".parse::<OutputFormat>().unwrap_err().contains("jsonl"));
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_msgpack_records() {
+    let records = records();
+    let mut out = Vec::new();
+    for record in &records {
+        OutputFormat::Msgpack.formatter().unwrap().encode(record, &mut out).unwrap();
+    }
+    // Records follow each other with no separator
+    let mut reader = out.as_slice();
+    for record in &records {
+        let value: serde_json::Value = rmp_serde::from_read(&mut reader).unwrap();
+        assert_eq!(value, expected(record));
+    }
+    assert!(reader.is_empty());
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor_records() {
+    let records = records();
+    let mut out = Vec::new();
+    for record in &records {
+        OutputFormat::Cbor.formatter().unwrap().encode(record, &mut out).unwrap();
+    }
+    let mut reader = out.as_slice();
+    for record in &records {
+        let value: serde_json::Value = ciborium::from_reader(&mut reader).unwrap();
+        assert_eq!(value, expected(record));
+    }
+    assert!(reader.is_empty());
+}
+
+/// Reads a varint off the front of `bytes`.
+#[cfg(feature = "protobuf")]
+fn read_varint(bytes: &mut &[u8]) -> u64 {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first().unwrap();
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    value
+}
+
+/// Decodes one `SegmentationRecord` message into the JSON of its record.
+#[cfg(feature = "protobuf")]
+fn decode_protobuf(mut message: &[u8]) -> serde_json::Value {
+    let (mut version, mut id, mut input) = (0, 0, String::new());
+    let (mut segments, mut filtered) = (Vec::new(), None);
+    while !message.is_empty() {
+        let key = read_varint(&mut message);
+        if key & 7 == 0 {
+            let value = read_varint(&mut message);
+            match key >> 3 {
+                1 => version = value,
+                2 => id = value,
+                field => panic!("unexpected varint field {}", field),
+            }
+            continue;
+        }
+        assert_eq!(key & 7, 2, "length-delimited field");
+        let len = read_varint(&mut message) as usize;
+        let (mut bytes, rest) = message.split_at(len);
+        message = rest;
+        match key >> 3 {
+            3 => input = String::from_utf8(bytes.to_vec()).unwrap(),
+            4 => segments.push(String::from_utf8(bytes.to_vec()).unwrap()),
+            5 => {
+                let indexes: &mut Vec<u64> = filtered.get_or_insert_with(Vec::new);
+                while !bytes.is_empty() {
+                    indexes.push(read_varint(&mut bytes));
+                }
+            }
+            field => panic!("unexpected field {}", field),
+        }
+    }
+    let mut value = serde_json::json!({"schema_version": version, "id": id, "input": input, "segments": segments});
+    if let Some(filtered) = filtered {
+        value["filtered"] = serde_json::json!(filtered);
+    }
+    value
+}
+
+#[cfg(feature = "protobuf")]
+#[test]
+fn test_protobuf_records() {
+    let records = records();
+    let mut out = Vec::new();
+    for record in &records {
+        OutputFormat::Protobuf.formatter().unwrap().encode(record, &mut out).unwrap();
+    }
+    let mut reader = out.as_slice();
+    for record in &records {
+        let len = read_varint(&mut reader) as usize;
+        let (message, rest) = reader.split_at(len);
+        reader = rest;
+        let mut expected = expected(record);
+        // An empty repeated field is not written, so it reads back as absent
+        if record.filtered.as_ref().is_some_and(Vec::is_empty) {
+            expected.as_object_mut().unwrap().remove("filtered");
+        }
+        assert_eq!(decode_protobuf(message), expected);
+    }
+    assert!(reader.is_empty());
+
+    // Wire bytes of a small record, as protoc-generated code writes them
+    out.clear();
+    let record = Record::new(1, "ab", &["a", "b"]).filtered(vec![1, 300]);
+    OutputFormat::Protobuf.formatter().unwrap().encode(&record, &mut out).unwrap();
+    let message = [
+        0x08, 0x01, 0x10, 0x01, 0x1a, 0x02, b'a', b'b', 0x22, 0x01, b'a', 0x22, 0x01, b'b', 0x2a, 0x03, 0x01, 0xac, 0x02,
+    ];
+    assert_eq!(out[0] as usize, message.len());
+    assert_eq!(&out[1..], &message);
+}
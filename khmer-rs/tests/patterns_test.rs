@@ -0,0 +1,420 @@
+//! Tests for the recognizers of non-dictionary tokens.
+
+mod common;
+
+
+use khmer_rs::config::{DEFAULT_CURRENCIES, DEFAULT_PHONE_PATTERNS};
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::patterns::{
+    currency_length_cps, email_length_cps, emoji_length_cps, hashtag_length_cps, is_email, is_emoji, is_hashtag,
+    is_latin_word, is_mention, is_numeral_context, is_phone, is_url, khmer_date_length_cps, latin_word_length_cps, mention_length_cps,
+    numeric_date_length_cps, parse_currency, parse_date, parse_roman_numeral, parse_time, phone_length_cps,
+    roman_numeral_length_cps, time_length_cps, url_length_cps, CurrencyAmount, Date, Time,
+};
+use khmer_rs::segmenter::KhmerSegmenter;
+use khmer_rs::token::TokenKind;
+
+use common::dictionary;
+
+fn setup() -> KhmerSegmenter {
+    KhmerSegmenter::builder(dictionary()).build()
+}
+
+fn url_prefix(text: &str) -> String {
+    let cps: Vec<char> = text.chars().collect();
+    cps[..url_length_cps(&cps, 0)].iter().collect()
+}
+
+#[test]
+fn test_url_length() {
+    assert_eq!(url_prefix("https://example.com/a/b?q=1#top rest"), "https://example.com/a/b?q=1#top");
+    assert_eq!(url_prefix("HTTP://localhost:8080/"), "HTTP://localhost:8080/");
+    assert_eq!(url_prefix("www.rfa.org/khmer។"), "www.rfa.org/khmer");
+    assert_eq!(url_prefix("example.com.kh, and"), "example.com.kh");
+    assert_eq!(url_prefix("https://x.org/a_(b)))"), "https://x.org/a_(b)");
+    assert_eq!(url_prefix("https://x.org."), "https://x.org");
+
+    // Unknown top-level domains need a scheme or www.
+    assert_eq!(url_prefix("file.txt"), "");
+    assert_eq!(url_prefix("U.S.A"), "");
+    assert_eq!(url_prefix("http://"), "");
+
+    // Never from the middle of a word or address
+    let cps: Vec<char> = "someone@example.com".chars().collect();
+    assert_eq!(url_length_cps(&cps, 8), 0);
+
+    assert!(is_url("https://rfa.org"));
+    assert!(!is_url("https://rfa.org ok"));
+}
+
+fn email_prefix(text: &str) -> String {
+    let cps: Vec<char> = text.chars().collect();
+    cps[..email_length_cps(&cps, 0)].iter().collect()
+}
+
+#[test]
+fn test_email_length() {
+    assert_eq!(email_prefix("someone@example.com។"), "someone@example.com");
+    assert_eq!(email_prefix("first.last+tag@mail.gov.kh."), "first.last+tag@mail.gov.kh");
+    assert_eq!(email_prefix("user@localhost"), "");
+    assert_eq!(email_prefix("user.@example.com"), "");
+    assert_eq!(email_prefix("@example.com"), "");
+    assert_eq!(email_prefix("user@example.c0m"), "");
+
+    assert!(is_email("a@b.co"));
+    assert!(!is_email("a@b.co ok"));
+}
+
+#[test]
+fn test_segment_keeps_emails() {
+    let segmenter = setup();
+    let text = "ផ្ញើមក someone@example.com ឬ info@gov.kh។";
+    let tokens = segmenter.segment_tokens(text);
+    let emails: Vec<&str> = tokens.iter().filter(|t| t.kind == TokenKind::Email).map(|t| t.text.as_str()).collect();
+    assert_eq!(emails, vec!["someone@example.com", "info@gov.kh"]);
+    assert!(!tokens.iter().any(|t| t.kind == TokenKind::Unknown), "{:?}", tokens);
+}
+
+#[test]
+fn test_segment_keeps_urls() {
+    let segmenter = setup();
+    let text = "សូមចូលមើល https://www.example.com/news?id=42 ឬ rfa.org។";
+    let segments = segmenter.segment(text);
+    assert!(segments.contains(&"https://www.example.com/news?id=42".to_string()), "{:?}", segments);
+    assert!(segments.contains(&"rfa.org".to_string()), "{:?}", segments);
+    assert_eq!(segments.concat(), text);
+
+    let tokens = segmenter.segment_tokens(text);
+    let urls: Vec<&str> = tokens.iter().filter(|t| t.kind == TokenKind::Url).map(|t| t.span.as_str(text)).collect();
+    assert_eq!(urls, vec!["https://www.example.com/news?id=42", "rfa.org"]);
+    assert_eq!(tokens[0].kind, TokenKind::Word);
+    assert_eq!(tokens.last().unwrap().kind, TokenKind::Separator);
+}
+
+fn prefix(text: &str, recognizer: fn(&[char], usize) -> usize) -> String {
+    let cps: Vec<char> = text.chars().collect();
+    cps[..recognizer(&cps, 0)].iter().collect()
+}
+
+#[test]
+fn test_hashtag_and_mention_length() {
+    assert_eq!(prefix("#ខ្មែរ ស្រឡាញ់", hashtag_length_cps), "#ខ្មែរ");
+    assert_eq!(prefix("#Cambodia2024។", hashtag_length_cps), "#Cambodia2024");
+    assert_eq!(prefix("# tag", hashtag_length_cps), "");
+    assert_eq!(prefix("@khmer.news.", mention_length_cps), "@khmer.news");
+    assert_eq!(prefix("@ok_123 ", mention_length_cps), "@ok_123");
+    assert_eq!(prefix("@", mention_length_cps), "");
+
+    // Not in the middle of a word
+    let cps: Vec<char> = "C#".chars().collect();
+    assert_eq!(hashtag_length_cps(&cps, 1), 0);
+
+    assert!(is_hashtag("#ខ្មែរ"));
+    assert!(is_mention("@handle"));
+    assert!(!is_mention("#handle"));
+}
+
+#[test]
+fn test_social_media_mode() {
+    let text = "ស្រឡាញ់ #ខ្មែរ ណាស់ @sok_dara #news";
+    let plain = setup();
+    assert!(!plain.segment(text).contains(&"#ខ្មែរ".to_string()));
+
+    let social = KhmerSegmenter::builder(plain.dictionary()).social_media(true).build();
+    let tokens = social.segment_tokens(text);
+    let tagged: Vec<(&str, TokenKind)> = tokens
+        .iter()
+        .filter(|t| matches!(t.kind, TokenKind::Hashtag | TokenKind::Mention))
+        .map(|t| (t.text.as_str(), t.kind))
+        .collect();
+    assert_eq!(
+        tagged,
+        vec![("#ខ្មែរ", TokenKind::Hashtag), ("@sok_dara", TokenKind::Mention), ("#news", TokenKind::Hashtag)]
+    );
+}
+
+#[test]
+fn test_emoji_length() {
+    // Family (ZWJ sequence), skin tone, flag, keycap, subdivision flag
+    let sequences = [
+        "👨\u{200D}👩\u{200D}👧",
+        "👍🏽",
+        "🇰🇭",
+        "1\u{FE0F}\u{20E3}",
+        "❤\u{FE0F}",
+        "🏴\u{E0067}\u{E0062}\u{E0065}\u{E006E}\u{E0067}\u{E007F}",
+    ];
+    for emoji in sequences {
+        assert_eq!(prefix(&format!("{}ok", emoji), emoji_length_cps), emoji);
+        assert!(is_emoji(emoji), "{}", emoji);
+    }
+    assert_eq!(prefix("😀😀", emoji_length_cps), "😀");
+    assert_eq!(prefix("👍\u{200D}", emoji_length_cps), "👍");
+    assert_eq!(prefix("1 ", emoji_length_cps), "");
+    assert!(!is_emoji("ក"));
+}
+
+#[test]
+fn test_segment_keeps_emoji_sequences() {
+    let segmenter = setup();
+    let text = "សួស្តី👋🏾 គ្រួសារ👨\u{200D}👩\u{200D}👧🇰🇭";
+    let tokens = segmenter.segment_tokens(text);
+    let emoji: Vec<&str> = tokens.iter().filter(|t| t.kind == TokenKind::Emoji).map(|t| t.text.as_str()).collect();
+    assert_eq!(emoji, vec!["👋🏾", "👨\u{200D}👩\u{200D}👧", "🇰🇭"]);
+    assert_eq!(tokens.iter().map(|t| t.text.as_str()).collect::<String>(), text);
+}
+
+#[test]
+fn test_latin_word_length() {
+    assert_eq!(prefix("don't stop", latin_word_length_cps), "don't");
+    assert_eq!(prefix("Phnom-Penh,", latin_word_length_cps), "Phnom-Penh");
+    assert_eq!(prefix("Cœdès។", latin_word_length_cps), "Cœdès");
+    assert_eq!(prefix("abc-", latin_word_length_cps), "abc");
+    assert_eq!(prefix("XXXV.", latin_word_length_cps), "XXXV");
+    assert_eq!(prefix("1abc", latin_word_length_cps), "");
+
+    assert!(is_latin_word("d'union"));
+    assert!(!is_latin_word("abc1"));
+}
+
+#[test]
+fn test_segment_groups_latin_words() {
+    let segmenter = setup();
+    let text = "ខ្ញុំរៀន English និង d'union-PHAI-LIN។";
+    let segments = segmenter.segment(text);
+    assert!(segments.contains(&"English".to_string()), "{:?}", segments);
+    assert!(segments.contains(&"d'union-PHAI-LIN".to_string()), "{:?}", segments);
+
+    let tokens = segmenter.segment_tokens("Tuk-C'a ក្រុង");
+    assert_eq!(tokens[0].text, "Tuk-C'a");
+    assert_eq!(tokens[0].kind, TokenKind::Latin);
+}
+
+fn date(day: u32, month: u32, year: Option<u32>) -> Option<Date> {
+    Some(Date { day: Some(day), month: Some(month), year })
+}
+
+#[test]
+fn test_numeric_date() {
+    assert_eq!(prefix("15/01/2024 ok", numeric_date_length_cps), "15/01/2024");
+    assert_eq!(prefix("2024-01-15។", numeric_date_length_cps), "2024-01-15");
+    assert_eq!(prefix("១-៦-៦៦ ", numeric_date_length_cps), "១-៦-៦៦");
+    assert_eq!(prefix("15/01-2024", numeric_date_length_cps), "");
+    assert_eq!(prefix("32/01/2024", numeric_date_length_cps), "");
+    assert_eq!(prefix("1.2.3.4", numeric_date_length_cps), "");
+    assert_eq!(prefix("3/4", numeric_date_length_cps), "");
+    // Version numbers and digits of π
+    assert_eq!(prefix("1.2.24", numeric_date_length_cps), "");
+    assert_eq!(prefix("3.14.15", numeric_date_length_cps), "");
+    assert_eq!(prefix("15.01.2024", numeric_date_length_cps), "15.01.2024");
+    // Days past the end of the month
+    assert_eq!(prefix("30/02/2024", numeric_date_length_cps), "");
+    assert_eq!(prefix("31-04-2024", numeric_date_length_cps), "");
+    assert_eq!(prefix("29/02/2023", numeric_date_length_cps), "");
+    assert_eq!(prefix("1900-02-29", numeric_date_length_cps), "");
+    assert_eq!(parse_date("29/02/2024"), date(29, 2, Some(2024)));
+    assert_eq!(parse_date("2000-02-29"), date(29, 2, Some(2000)));
+    assert_eq!(parse_date("29/02/24"), date(29, 2, Some(24)));
+
+    assert_eq!(parse_date("15/01/2024"), date(15, 1, Some(2024)));
+    // Day first unless only month first is valid
+    assert_eq!(parse_date("01/02/2024"), date(1, 2, Some(2024)));
+    assert_eq!(parse_date("12/31/2024"), date(31, 12, Some(2024)));
+    assert_eq!(parse_date("២៩-១១-៥៩"), date(29, 11, Some(59)));
+}
+
+#[test]
+fn test_khmer_date() {
+    let full = "ថ្ងៃទី១៥ ខែមករា ឆ្នាំ២០២៤";
+    assert_eq!(prefix(&format!("{} នេះ", full), khmer_date_length_cps), full);
+    assert_eq!(parse_date(full), date(15, 1, Some(2024)));
+    assert_eq!(parse_date("ថ្ងៃទី ២២ ខែវិច្ឆិកា"), date(22, 11, None));
+    assert_eq!(parse_date("ខែ មីនា ឆ្នាំ ១៩៤៦"), Some(Date { day: None, month: Some(3), year: Some(1946) }));
+
+    // One part alone is not a date
+    assert_eq!(prefix("ថ្ងៃទី៤ ឬទី៥", khmer_date_length_cps), "");
+    assert_eq!(prefix("ខែមករា នេះ", khmer_date_length_cps), "");
+    assert_eq!(prefix("ថ្ងៃទី៤០ ខែមករា", khmer_date_length_cps), "");
+}
+
+#[test]
+fn test_segment_dates() {
+    let plain = setup();
+    let text = "កើតនៅថ្ងៃទី១៥ ខែមករា ឆ្នាំ២០២៤ ឬ 15/01/2024។";
+    let segments = plain.segment(text);
+    assert!(!segments.contains(&"15/01/2024".to_string()), "{:?}", segments);
+    assert!(!segments.iter().any(|s| s.contains("ខែមករា ")), "{:?}", segments);
+    assert!(!plain.segment_tokens("v1.2.24 15.01.2024").iter().any(|t| t.kind == TokenKind::Date));
+
+    let segmenter = KhmerSegmenter::builder(plain.dictionary()).dates(true).build();
+    let tokens = segmenter.segment_tokens(text);
+    let dates: Vec<&str> = tokens.iter().filter(|t| t.kind == TokenKind::Date).map(|t| t.text.as_str()).collect();
+    assert_eq!(dates, vec!["ថ្ងៃទី១៥ ខែមករា ឆ្នាំ២០២៤", "15/01/2024"]);
+    let tokens = segmenter.segment_tokens("1.2.24 3.14.15 30/02/2024");
+    assert!(!tokens.iter().any(|t| t.kind == TokenKind::Date), "{:?}", tokens);
+}
+
+#[test]
+fn test_time_length() {
+    assert_eq!(prefix("8:30 ព្រឹក", time_length_cps), "8:30");
+    assert_eq!(prefix("២៣:៥៩:០៥។", time_length_cps), "២៣:៥៩:០៥");
+    assert_eq!(prefix("10:30:", time_length_cps), "10:30");
+    assert_eq!(prefix("25:00", time_length_cps), "");
+    assert_eq!(prefix("24:00", time_length_cps), "24:00");
+    assert_eq!(prefix("24:30", time_length_cps), "");
+    assert_eq!(prefix("24:00:01", time_length_cps), "");
+    assert_eq!(prefix("8:3", time_length_cps), "");
+    assert_eq!(prefix("8:61", time_length_cps), "");
+    assert_eq!(prefix("1:02:03:04", time_length_cps), "");
+    assert_eq!(prefix("8", time_length_cps), "");
+
+    assert_eq!(parse_time("៨:៣០"), Some(Time { hour: 8, minute: 30, second: None }));
+    assert_eq!(parse_time("23:59:05"), Some(Time { hour: 23, minute: 59, second: Some(5) }));
+    assert_eq!(parse_time("8:30 "), None);
+}
+
+#[test]
+fn test_segment_times() {
+    let segmenter = setup();
+    let tokens = segmenter.segment_tokens("ម៉ោង៨:៣០ព្រឹក ដល់ 17:00");
+    let times: Vec<&str> = tokens.iter().filter(|t| t.kind == TokenKind::Time).map(|t| t.text.as_str()).collect();
+    assert_eq!(times, vec!["៨:៣០", "17:00"]);
+    assert_eq!(tokens[0].text, "ម៉ោង");
+}
+
+fn phone_prefix(text: &str, patterns: &[&str]) -> String {
+    let cps: Vec<char> = text.chars().collect();
+    cps[..phone_length_cps(&cps, 0, patterns)].iter().collect()
+}
+
+#[test]
+fn test_phone_length() {
+    let defaults = DEFAULT_PHONE_PATTERNS;
+    assert_eq!(phone_prefix("012 345 678 ok", defaults), "012 345 678");
+    assert_eq!(phone_prefix("097-123-4567។", defaults), "097-123-4567");
+    assert_eq!(phone_prefix("០១២ ៣៤៥ ៦៧៨", defaults), "០១២ ៣៤៥ ៦៧៨");
+    assert_eq!(phone_prefix("+855 12 345 678", defaults), "+855 12 345 678");
+    assert_eq!(phone_prefix("012-345-67", defaults), "");
+    assert_eq!(phone_prefix("012 345 6789 0", defaults), "012 345 6789");
+    assert_eq!(phone_prefix("012-345-67890", defaults), "");
+
+    // Custom patterns; literal digits match Khmer digits too
+    let custom = ["(0##) ###-###", "1##"];
+    assert_eq!(phone_prefix("(023) 123-456", &custom), "(023) 123-456");
+    assert_eq!(phone_prefix("១១៧", &custom), "១១៧");
+    assert_eq!(phone_prefix("012 345 678", &custom), "");
+
+    assert!(is_phone("012345678", defaults));
+    assert!(!is_phone("12345678", defaults));
+}
+
+#[test]
+fn test_segment_phones() {
+    let plain = setup();
+    let text = "ទូរស័ព្ទ 012-345-678 ឬ +855 97 123 4567។";
+    let tokens = plain.segment_tokens(text);
+    let phones: Vec<&str> = tokens.iter().filter(|t| t.kind == TokenKind::Phone).map(|t| t.text.as_str()).collect();
+    assert_eq!(phones, vec!["012-345-678", "+855 97 123 4567"]);
+
+    let custom = KhmerSegmenter::builder(plain.dictionary()).phone_patterns(vec!["0##/###/###".to_string()]).build();
+    let tokens = custom.segment_tokens("លេខ 012/345/678 ឬ 012-345-678");
+    let phones: Vec<&str> = tokens.iter().filter(|t| t.kind == TokenKind::Phone).map(|t| t.text.as_str()).collect();
+    assert_eq!(phones, vec!["012/345/678"]);
+}
+
+#[test]
+fn test_roman_numerals() {
+    assert_eq!(prefix("III ok", roman_numeral_length_cps), "III");
+    assert_eq!(prefix("XXXV.", roman_numeral_length_cps), "XXXV");
+    assert_eq!(prefix("MCMXCIV។", roman_numeral_length_cps), "MCMXCIV");
+    assert_eq!(prefix("IIII", roman_numeral_length_cps), "");
+    assert_eq!(prefix("IXX", roman_numeral_length_cps), "");
+    assert_eq!(prefix("CIVIC", roman_numeral_length_cps), "");
+    assert_eq!(prefix("XIth", roman_numeral_length_cps), "");
+    assert_eq!(prefix("iii", roman_numeral_length_cps), "");
+    // The pronoun, not one
+    assert_eq!(prefix("I love", roman_numeral_length_cps), "");
+    let cps: Vec<char> = "AXI".chars().collect();
+    assert_eq!(roman_numeral_length_cps(&cps, 1), 0);
+
+    assert_eq!(parse_roman_numeral("XIV"), Some(14));
+    assert_eq!(parse_roman_numeral("MMXXIV"), Some(2024));
+    assert_eq!(parse_roman_numeral("XIV "), None);
+}
+
+#[test]
+fn test_segment_roman_numerals() {
+    let segmenter = setup();
+    let tokens = segmenter.segment_tokens("សម័យកាល III និង ជំពូក XIV។");
+    let numerals: Vec<&str> =
+        tokens.iter().filter(|t| t.kind == TokenKind::RomanNumeral).map(|t| t.text.as_str()).collect();
+    assert_eq!(numerals, vec!["III", "XIV"]);
+    assert_eq!(segmenter.segment_tokens("MIXED")[0].kind, TokenKind::Latin);
+
+    // English words spelled with numeral letters, outside numeral context
+    for text in ["I love Cambodia", "MIX", "a CD and a DIV", "LIVID"] {
+        let tokens = segmenter.segment_tokens(text);
+        assert!(tokens.iter().all(|t| t.kind != TokenKind::RomanNumeral), "{:?}: {:?}", text, tokens);
+    }
+    let tokens = segmenter.segment_tokens("Chapter XIV, Vol. II");
+    assert_eq!(tokens.iter().filter(|t| t.kind == TokenKind::RomanNumeral).count(), 2, "{:?}", tokens);
+}
+
+#[test]
+fn test_listed_latin_words_are_not_numerals() {
+    use khmer_rs::overlay::Overlay;
+
+    let english = Overlay::from_reader("mix\n".as_bytes()).unwrap();
+    let dictionary = Dictionary::builder().latin_words(english).load_bytes("ជំពូក\n".as_bytes(), None).unwrap();
+    let segmenter = KhmerSegmenter::new(dictionary);
+    let tokens = segmenter.segment_tokens("ជំពូក MIX");
+    assert_eq!(tokens.last().unwrap().text, "MIX");
+    assert_eq!(tokens.last().unwrap().kind, TokenKind::Latin);
+    assert_eq!(segmenter.segment_tokens("ជំពូក XIV").last().unwrap().kind, TokenKind::RomanNumeral);
+}
+
+#[test]
+fn test_numeral_context() {
+    let context = |before: &str| is_numeral_context(before.chars().rev());
+    assert!(context("សម័យកាល "));
+    assert!(context("ជំពូក\u{200b}"));
+    assert!(context("Chapter "));
+    assert!(context("see vol. "));
+    assert!(!context(""));
+    assert!(!context("I love "));
+    assert!(!context("2024 "));
+}
+
+#[test]
+fn test_currency_length() {
+    let cps: Vec<char> = "USD50 USDT XUSD ៛".chars().collect();
+    assert_eq!(currency_length_cps(&cps, 0, DEFAULT_CURRENCIES), 3);
+    assert_eq!(currency_length_cps(&cps, 6, DEFAULT_CURRENCIES), 0);
+    assert_eq!(currency_length_cps(&cps, 12, DEFAULT_CURRENCIES), 0);
+    assert_eq!(currency_length_cps(&cps, 16, DEFAULT_CURRENCIES), 1);
+
+    let amount = |currency, amount| Some(CurrencyAmount { currency, amount });
+    assert_eq!(parse_currency("$1,000.50", DEFAULT_CURRENCIES), amount("$", "1,000.50"));
+    assert_eq!(parse_currency("១០០៛", DEFAULT_CURRENCIES), amount("៛", "១០០"));
+    assert_eq!(parse_currency("USD 50", DEFAULT_CURRENCIES), amount("USD", "50"));
+    assert_eq!(parse_currency("$ 50", DEFAULT_CURRENCIES), None);
+    assert_eq!(parse_currency("$", DEFAULT_CURRENCIES), None);
+}
+
+#[test]
+fn test_segment_currency_amounts() {
+    let plain = setup();
+    let text = "តម្លៃ $100 ឬ ៤០០០០៛ ឬ USD 25។";
+    let tokens = plain.segment_tokens(text);
+    let amounts: Vec<&str> = tokens.iter().filter(|t| t.kind == TokenKind::Currency).map(|t| t.text.as_str()).collect();
+    assert_eq!(amounts, vec!["$100", "៤០០០០៛", "USD 25"]);
+    let parsed = plain.parse_currency("៤០០០០៛").unwrap();
+    assert_eq!((parsed.currency, parsed.amount), ("៛", "៤០០០០"));
+
+    // A custom set replaces the defaults
+    let custom = KhmerSegmenter::builder(plain.dictionary()).currencies(vec!["រៀល".to_string()]).build();
+    let tokens = custom.segment_tokens("៥០០ រៀល ឬ $5");
+    assert_eq!(tokens[0].text, "៥០០ រៀល");
+    assert_eq!(tokens[0].kind, TokenKind::Currency);
+    assert!(!tokens.iter().any(|t| t.text == "$5"), "{:?}", tokens);
+}
@@ -0,0 +1,80 @@
+//! Tests for pluggable post-processing stages.
+
+mod common;
+
+use std::sync::Arc;
+
+use khmer_rs::config::Pass;
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::postprocess::{PostProcess, SignMerge, SnapInvalidSingles, UnknownMerge};
+use khmer_rs::segmenter::KhmerSegmenter;
+
+use common::dictionary;
+
+/// Joins segments of the listed words into one token.
+struct JoinCompounds(Vec<(&'static str, &'static str)>);
+
+impl PostProcess for JoinCompounds {
+    fn process(&self, segments: Vec<String>, _dictionary: &Dictionary) -> Vec<String> {
+        let mut joined: Vec<String> = Vec::with_capacity(segments.len());
+        for seg in segments {
+            match joined.last_mut() {
+                Some(prev) if self.0.iter().any(|&(a, b)| prev == a && seg == b) => prev.push_str(&seg),
+                _ => joined.push(seg),
+            }
+        }
+        joined
+    }
+}
+
+#[test]
+fn test_builtin_stages_match_default() {
+    let dictionary = dictionary();
+    let default = KhmerSegmenter::builder(Arc::clone(&dictionary)).build();
+    let explicit = KhmerSegmenter::builder(Arc::clone(&dictionary))
+        .post_processors(vec![Box::new(SnapInvalidSingles), Box::new(SignMerge), Box::new(UnknownMerge)])
+        .build();
+    let none = KhmerSegmenter::builder(Arc::clone(&dictionary)).post_processors(Vec::new()).build();
+    let all_disabled = Pass::ALL
+        .into_iter()
+        .fold(KhmerSegmenter::builder(dictionary), |builder, pass| builder.disable_pass(pass))
+        .build();
+
+    let corpus = std::fs::read_to_string("../data/khmer_folktales_extracted.txt").expect("Failed to read corpus");
+    for line in corpus.lines().take(300) {
+        assert_eq!(explicit.segment(line), default.segment(line));
+        assert_eq!(none.segment(line), all_disabled.segment(line));
+    }
+}
+
+#[test]
+fn test_custom_stage_runs_last() {
+    let dictionary = dictionary();
+    let plain = KhmerSegmenter::builder(Arc::clone(&dictionary)).build();
+    assert_eq!(plain.segment("ខ្ញុំស្រលាញ់កម្ពុជា"), vec!["ខ្ញុំ", "ស្រលាញ់", "កម្ពុជា"]);
+
+    let custom = KhmerSegmenter::builder(dictionary)
+        .add_post_processor(JoinCompounds(vec![("ស្រលាញ់", "កម្ពុជា")]))
+        .add_post_processor(|mut segments: Vec<String>, _: &Dictionary| {
+            segments.retain(|s| s != " ");
+            segments
+        })
+        .build();
+    assert_eq!(custom.segment("ខ្ញុំស្រលាញ់កម្ពុជា"), vec!["ខ្ញុំ", "ស្រលាញ់កម្ពុជា"]);
+    assert_eq!(custom.segment("ខ្ញុំ ស្រលាញ់"), vec!["ខ្ញុំ", "ស្រលាញ់"]);
+}
+
+#[test]
+fn test_explain_names_each_stage() {
+    let dictionary = dictionary();
+    let segmenter = KhmerSegmenter::builder(dictionary)
+        .add_post_processor(JoinCompounds(vec![("ស្រលាញ់", "កម្ពុជា")]))
+        .build();
+
+    let stages = segmenter.explain("ស្រលាញ់ឃកម្ពុជា");
+    let names: Vec<&str> = stages.iter().map(|stage| stage.name.as_str()).collect();
+    assert_eq!(names, vec!["viterbi", "snap-invalid-singles", "sign-merge", "unknown-merge", "custom", "output"]);
+    assert_eq!(stages[0].segments, vec!["ស្រលាញ់", "ឃ", "កម្ពុជា"]);
+    assert_eq!(stages[1].segments, vec!["ស្រលាញ់ឃ", "កម្ពុជា"]);
+    assert_eq!(stages.last().unwrap().segments, segmenter.segment("ស្រលាញ់ឃកម្ពុជា"));
+}
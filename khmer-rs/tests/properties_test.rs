@@ -0,0 +1,155 @@
+//! Property-based tests: invariants that must hold on any input, checked on
+//! random Khmer and mixed-script strings. Set `PROPTEST_CASES` to run more.
+
+mod common;
+
+use std::sync::OnceLock;
+
+use khmer_rs::clusters::{khmer_clusters, segment_syllables};
+use khmer_rs::config::{NumberFormat, WhitespacePolicy};
+use khmer_rs::dictionary::Cost;
+use khmer_rs::maxmatch::MaxMatchSegmenter;
+use khmer_rs::normalize::normalize;
+use khmer_rs::segmenter::KhmerSegmenter;
+use proptest::prelude::*;
+
+use common::dictionary;
+
+const ZWSP: char = '\u{200b}';
+
+fn segmenter() -> &'static KhmerSegmenter {
+    static SEGMENTER: OnceLock<KhmerSegmenter> = OnceLock::new();
+    SEGMENTER.get_or_init(|| KhmerSegmenter::builder(dictionary()).build())
+}
+
+/// A well-formed syllable: consonant, optional subscript, optional
+/// dependent vowel and optional sign.
+fn syllable() -> impl Strategy<Value = String> {
+    (
+        prop::char::range('\u{1780}', '\u{17A2}'),
+        prop::option::of(prop::char::range('\u{1780}', '\u{17A2}')),
+        prop::option::of(prop::char::range('\u{17B6}', '\u{17C5}')),
+        prop::option::of(prop::char::range('\u{17C6}', '\u{17D1}')),
+    )
+        .prop_map(|(base, subscript, vowel, sign)| {
+            let mut syllable = String::from(base);
+            if let Some(subscript) = subscript {
+                syllable.push('\u{17D2}');
+                syllable.push(subscript);
+            }
+            syllable.extend(vowel);
+            syllable.extend(sign);
+            syllable
+        })
+}
+
+/// Any character of the Khmer block alone, so lone coengs, vowels without
+/// a base and stray signs come up often.
+fn khmer_char() -> impl Strategy<Value = String> {
+    prop::char::range('\u{1780}', '\u{17FF}').prop_map(String::from)
+}
+
+/// Text the segmenter has recognizers or special cases for.
+fn other() -> impl Strategy<Value = String> {
+    prop::sample::select(vec![
+        " ", "  ", "\t", "\u{200b}", "\u{200c}", "\u{FEFF}", "។", "៕", ".", ",", ":", "-", "/", "$", "៛", "%", "«", "»",
+        "1", "១២", "1,000", "8:30", "15/01/2024", "012 345 678", "USD", "kg", "III", "hello", "don't", "rfa.org",
+        "a@b.com", "#tag", "@user", "😀", "👨\u{200d}👩\u{200d}👧", "🇰🇭", "สวัสดี", "中文", "ថ្ងៃទី", "ខែមករា",
+    ])
+    .prop_map(String::from)
+}
+
+fn text() -> impl Strategy<Value = String> {
+    prop::collection::vec(prop_oneof![6 => syllable(), 2 => khmer_char(), 2 => other()], 0..30)
+        .prop_map(|pieces| pieces.concat())
+}
+
+proptest! {
+    #[test]
+    fn prop_segments_concatenate_to_input(text in text()) {
+        let segments = segmenter().segment(&text);
+        prop_assert_eq!(segments.concat(), text.replace(ZWSP, ""));
+        prop_assert!(segments.iter().all(|seg| !seg.is_empty()));
+    }
+
+    #[test]
+    fn prop_segments_concatenate_to_normalized_input(text in text()) {
+        let normalized = normalize(&text);
+        prop_assert_eq!(normalize(&normalized), normalized.clone());
+        let segments = segmenter().segment(&normalized);
+        prop_assert_eq!(segments.concat(), normalized.replace(ZWSP, ""));
+    }
+
+    #[test]
+    fn prop_spans_are_monotonic_and_contiguous(text in text()) {
+        let spans = segmenter().segment_spans(&text);
+        let mut end = 0;
+        for span in &spans {
+            prop_assert!(!span.is_empty());
+            // Only zero-width spaces may lie between two spans
+            prop_assert!(text[end..span.start].chars().all(|c| c == ZWSP));
+            end = span.end;
+        }
+        prop_assert!(text[end..].chars().all(|c| c == ZWSP));
+
+        let tokens = segmenter().segment_tokens(&text);
+        prop_assert_eq!(tokens.iter().map(|token| token.span).collect::<Vec<_>>(), spans);
+    }
+
+    #[test]
+    fn prop_trace_path_is_viterbi_output(text in text()) {
+        let trace = segmenter().segment_trace(&text);
+        let viterbi = segmenter().explain(&text).into_iter().find(|stage| stage.name == "viterbi");
+        prop_assert_eq!(&trace.segments, &viterbi.map(|stage| stage.segments).unwrap_or_default());
+        prop_assert_eq!(trace.segments.concat(), trace.text);
+    }
+
+    #[test]
+    fn prop_options_do_not_panic(
+        text in text(),
+        social_media in any::<bool>(),
+        dates in any::<bool>(),
+        number_ranges in any::<bool>(),
+        zwsp_boundaries in any::<bool>(),
+        keep_zwsp in any::<bool>(),
+        whitespace in prop::sample::select(WhitespacePolicy::ALL.to_vec()),
+        number_format in prop::sample::select(NumberFormat::ALL.to_vec()),
+        fuzzy in prop::option::of(0.0..10.0 as Cost),
+        beam in prop::option::of(0.0..10.0 as Cost),
+    ) {
+        let mut builder = KhmerSegmenter::builder(dictionary())
+            .social_media(social_media)
+            .dates(dates)
+            .number_ranges(number_ranges)
+            .zwsp_boundaries(zwsp_boundaries)
+            .keep_zwsp(keep_zwsp)
+            .whitespace(whitespace)
+            .number_format(number_format)
+            .canonicalize_variants(true);
+        if let Some(cost) = fuzzy {
+            builder = builder.fuzzy_match(cost);
+        }
+        if let Some(margin) = beam {
+            builder = builder.beam(margin);
+        }
+        let segmenter = builder.build();
+        let segments = segmenter.segment(&text);
+        prop_assert!(segments.iter().all(|seg| !seg.is_empty()));
+        prop_assert_eq!(segmenter.segment_spans(&text).len(), segments.len());
+        prop_assert_eq!(segmenter.segment_lossless(&text).concat(), text);
+    }
+
+    #[test]
+    fn prop_clusters_and_baselines_cover_input(text in text()) {
+        let mut end = 0;
+        for span in khmer_clusters(&text) {
+            prop_assert_eq!(span.start, end);
+            prop_assert!(!span.is_empty());
+            end = span.end;
+        }
+        prop_assert_eq!(end, text.len());
+        prop_assert_eq!(segment_syllables(&text).concat(), text.replace(ZWSP, ""));
+        let maxmatch = MaxMatchSegmenter::new(dictionary());
+        prop_assert_eq!(maxmatch.segment(&text).concat(), text.replace(ZWSP, ""));
+    }
+}
@@ -0,0 +1,75 @@
+use khmer_rs::algorithm::Segmenter;
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::report::{CoverageReport, Counts, UnknownReport};
+use khmer_rs::segmenter::KhmerSegmenter;
+use khmer_rs::token::TokenKind;
+
+fn segmenter() -> KhmerSegmenter {
+    KhmerSegmenter::new(Dictionary::from_bytes("ខ្ញុំ\nស្រលាញ់\nភាសា\nខ្មែរ\n".as_bytes(), None).unwrap())
+}
+
+fn add_line(report: &mut UnknownReport, segmenter: &KhmerSegmenter, line: &str) {
+    let segments = segmenter.segment(line);
+    let kinds: Vec<TokenKind> = segments.iter().map(|s| Segmenter::token_kind(segmenter, s).unwrap()).collect();
+    report.add(&segments, &kinds);
+}
+
+#[test]
+fn test_unknown_report_ranks_and_counts() {
+    let segmenter = segmenter();
+    let mut report = UnknownReport::new(2);
+    add_line(&mut report, &segmenter, "ខ្ញុំ ស្រលាញ់ ឲ្យ ភាសា ឲ្យ");
+    add_line(&mut report, &segmenter, "ខ្មែរ ឲ្យ ១២៣ ប៉ូ");
+    add_line(&mut report, &segmenter, "ឲ្យ");
+
+    assert_eq!(report.unknown(), 5);
+    assert_eq!(report.tokens(), 10);
+    assert_eq!(report.distinct(), 2);
+    let ranked = report.ranked();
+    assert_eq!(ranked[0].0, "ឲ្យ");
+    assert_eq!((ranked[0].1.count, ranked[0].1.lines), (4, 3));
+    assert_eq!(ranked[0].1.examples, vec!["ខ្ញុំ | ស្រលាញ់ | [ឲ្យ] | ភាសា | ឲ្យ", "ឲ្យ | ភាសា | [ឲ្យ]"]);
+    assert_eq!(ranked[1].0, "ប៉ូ");
+
+    let mut tsv = Vec::new();
+    report.write_tsv(&mut tsv).unwrap();
+    let tsv = String::from_utf8(tsv).unwrap();
+    let lines: Vec<&str> = tsv.lines().collect();
+    assert_eq!(lines[0], "rank\tcount\tlines\tshare\tcumulative\ttoken\texamples");
+    assert!(lines[1].starts_with("1\t4\t3\t80.00\t80.00\tឲ្យ\tខ្ញុំ | ស្រលាញ់ | [ឲ្យ]"));
+    assert!(lines[2].starts_with("2\t1\t1\t20.00\t100.00\tប៉ូ\tឲ្យ | ១២៣ | [ប៉ូ]"));
+}
+
+#[test]
+fn test_coverage_report() {
+    let segments = ["ខ្ញុំ", " ", "ឲ្យ", " ", "១២៣", "។", "ស្រលាញ់ភាសាខ្មែរ"];
+    let kinds = [
+        TokenKind::Word,
+        TokenKind::Whitespace,
+        TokenKind::Unknown,
+        TokenKind::Whitespace,
+        TokenKind::Number,
+        TokenKind::Separator,
+        TokenKind::Word,
+    ];
+    let mut report = CoverageReport::new();
+    report.add(&segments, &kinds);
+    report.add(&segments[..3], &kinds[..3]);
+
+    assert_eq!(report.lines, 2);
+    assert_eq!(report.overall.word, Counts { tokens: 3, chars: 5 + 5 + 16 });
+    assert_eq!(report.overall.unknown, Counts { tokens: 2, chars: 6 });
+    assert_eq!(report.overall.other, Counts { tokens: 2, chars: 4 });
+    assert_eq!(report.overall.total(), Counts { tokens: 7, chars: 36 });
+    let (tokens, chars) = report.overall.coverage();
+    assert!((tokens - 0.6).abs() < 1e-9);
+    assert!((chars - 26.0 / 32.0).abs() < 1e-9);
+
+    // ខ្ញុំ has 5 codepoints, ឲ្យ 3; the long word goes to the 10+ row
+    assert_eq!(report.by_length[4].word.tokens, 2);
+    assert_eq!(report.by_length[2].unknown.tokens, 2);
+    assert_eq!(report.by_length[2].other.tokens, 1);
+    assert_eq!(report.by_length[0].other.tokens, 1);
+    assert_eq!(report.by_length[9].word, Counts { tokens: 1, chars: 16 });
+    assert_eq!(report.by_length.len(), 10);
+}
@@ -0,0 +1,67 @@
+//! Tests for sign-merge rule files.
+
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::rules::{Action, RuleSet};
+use khmer_rs::segmenter::KhmerSegmenter;
+
+fn strings(segments: &[&str]) -> Vec<String> {
+    segments.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_parse_rules() {
+    let rules = RuleSet::from_toml(
+        "[[rule]]\nname = \"bantoc\"\npattern = [\"consonant\", \"U+17CB|់\"]\naction = \"merge-previous\"\n\n\
+         [[rule]]\npattern = [\"any\"]\naction = \"merge-next\"\n",
+    )
+    .expect("Failed to parse rules");
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules.rules()[0].name, "bantoc");
+    assert_eq!(rules.rules()[1].name, "rule 2");
+    assert_eq!(rules.rules()[1].action, Action::MergeNext);
+
+    assert!(rules.rules()[0].matches("ក់"));
+    assert!(!rules.rules()[0].matches("ក"));
+    assert!(!rules.rules()[0].matches("ក់ក"));
+    assert!(!rules.rules()[0].matches("អ៉"));
+
+    assert!(RuleSet::from_toml("").expect("Empty file").is_empty());
+    assert!(RuleSet::from_toml("[[rule]]\npattern = [\"vowel\"]\naction = \"merge-next\"\n").is_err());
+    assert!(RuleSet::from_toml("[[rule]]\npattern = []\naction = \"merge-next\"\n").is_err());
+    assert!(RuleSet::from_toml("[[rule]]\npattern = [\"any\"]\naction = \"split\"\n").is_err());
+}
+
+#[test]
+fn test_apply_rules() {
+    let dictionary = Dictionary::from_bytes("គ់\n".as_bytes(), None).expect("Failed to build dictionary");
+    let rules = RuleSet::from_toml(
+        "[[rule]]\npattern = [\"consonant\", \"U+17CB\"]\naction = \"merge-previous\"\n\n\
+         [[rule]]\npattern = [\"consonant\", \"U+17D0\"]\naction = \"merge-next\"\n",
+    )
+    .expect("Failed to parse rules");
+
+    assert_eq!(rules.apply(strings(&["ក", "ខ់", "គ"]), &dictionary), strings(&["កខ់", "គ"]));
+    // Nothing to merge with at the line edges
+    assert_eq!(rules.apply(strings(&["ខ់", "ក", "ខ័"]), &dictionary), strings(&["ខ់", "ក", "ខ័"]));
+    assert_eq!(rules.apply(strings(&["ខ័", "ក", "គ"]), &dictionary), strings(&["ខ័ក", "គ"]));
+    // Dictionary words are left alone
+    assert_eq!(rules.apply(strings(&["ក", "គ់"]), &dictionary), strings(&["ក", "គ់"]));
+}
+
+#[test]
+fn test_bundled_rules_match_builtin() {
+    let dictionary = common::dictionary();
+    let rules = RuleSet::open(Path::new("rules/sign_merge.toml")).expect("Failed to load rules");
+    let builtin = KhmerSegmenter::builder(Arc::clone(&dictionary)).build();
+    let from_file = KhmerSegmenter::builder(dictionary).rules(rules).build();
+
+    let corpus = std::fs::read_to_string("../data/khmer_folktales_extracted.txt").expect("Failed to read corpus");
+    for line in corpus.lines().take(500) {
+        assert_eq!(from_file.segment(line), builtin.segment(line), "{}", line);
+    }
+}
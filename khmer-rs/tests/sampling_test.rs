@@ -0,0 +1,77 @@
+use std::io::Cursor;
+
+use khmer_rs::sampling::{LineSelection, Shuffle, ShuffleStats, SplitMix64};
+
+fn select(selection: LineSelection, text: &str) -> Vec<String> {
+    selection.lines(Cursor::new(text)).collect::<std::io::Result<_>>().unwrap()
+}
+
+#[test]
+fn test_skip_limit_and_head_bytes() {
+    let text = "a\n\nb\r\nc\n  \nd\ne";
+    let all = LineSelection::default();
+    assert_eq!(select(all, text), vec!["a", "b", "c", "d", "e"]);
+    assert_eq!(select(LineSelection { skip: 1, limit: Some(2), ..all }, text), vec!["b", "c"]);
+    // "a\n\nb\r\n" is 6 bytes: "c\n" does not end within 7
+    assert_eq!(select(LineSelection { head_bytes: Some(7), ..all }, text), vec!["a", "b"]);
+    assert_eq!(select(LineSelection { head_bytes: Some(8), ..all }, text), vec!["a", "b", "c"]);
+    assert!(select(LineSelection { skip: 10, ..all }, text).is_empty());
+}
+
+#[test]
+fn test_sample_is_seeded_and_stable() {
+    let text: String = (0..10_000).map(|i| format!("line {}\n", i)).collect();
+    let sample = LineSelection { sample: Some(0.1), seed: 42, ..Default::default() };
+    let first = select(sample, &text);
+    assert_eq!(first, select(sample, &text));
+    assert!((800..1200).contains(&first.len()), "{} lines", first.len());
+    assert_ne!(first, select(LineSelection { seed: 43, ..sample }, &text));
+    // A limit takes a prefix of the same sample
+    assert_eq!(select(LineSelection { limit: Some(10), ..sample }, &text), first[..10]);
+}
+
+#[test]
+fn test_splitmix64() {
+    let mut rng = SplitMix64::new(0);
+    // Reference values of SplitMix64 seeded with 0
+    assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+    assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+    let mut rng = SplitMix64::new(1);
+    assert!((0..1000).all(|_| rng.below(7) < 7 && (0.0..1.0).contains(&rng.next_f64())));
+}
+
+#[test]
+fn test_shuffle_in_memory_and_through_buckets() {
+    let dir = std::env::temp_dir().join(format!("khmer_shuffle_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("corpus.txt");
+    let mut text: String = (0..2000).map(|i| format!("line {}\n", i)).collect();
+    text.push_str("\nlast");
+    std::fs::write(&input, &text).unwrap();
+    let mut expected: Vec<&str> = text.lines().collect();
+    expected.sort();
+
+    let shuffled = |shuffle: Shuffle, name: &str| {
+        let output = dir.join(name);
+        let stats = shuffle.run(&input, &output).unwrap();
+        (stats, std::fs::read_to_string(output).unwrap())
+    };
+    let (stats, in_memory) = shuffled(Shuffle::new(7), "a.txt");
+    assert_eq!(stats, ShuffleStats { lines: 2002, buckets: 1 });
+    let (stats, bucketed) = shuffled(Shuffle::new(7).max_memory(4096), "b.txt");
+    assert_eq!(stats.lines, 2002);
+    assert!(stats.buckets > 1);
+
+    for output in [&in_memory, &bucketed] {
+        assert!(output.ends_with('\n'));
+        assert_ne!(output.lines().take(100).collect::<Vec<_>>(), expected[..100]);
+        let mut lines: Vec<&str> = output.lines().collect();
+        lines.sort();
+        assert_eq!(lines, expected);
+    }
+    // Same seed and budget, same order; the bucket files are gone
+    assert_eq!(shuffled(Shuffle::new(7).max_memory(4096), "c.txt").1, bucketed);
+    assert_ne!(shuffled(Shuffle::new(8), "d.txt").1, in_memory);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 5);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,47 @@
+//! Control and invisible character removal.
+
+use std::borrow::Cow;
+
+use khmer_rs::sanitize::{InvisibleClass, SanitizeAction, SanitizeCounts, Sanitizer};
+
+#[test]
+fn test_classes() {
+    assert_eq!(InvisibleClass::of('\u{0}'), Some(InvisibleClass::Control));
+    assert_eq!(InvisibleClass::of('\u{85}'), Some(InvisibleClass::Control));
+    assert_eq!(InvisibleClass::of('\u{7F}'), Some(InvisibleClass::Control));
+    assert_eq!(InvisibleClass::of('\u{FEFF}'), Some(InvisibleClass::Bom));
+    assert_eq!(InvisibleClass::of('\u{200F}'), Some(InvisibleClass::Bidi));
+    assert_eq!(InvisibleClass::of('\u{2067}'), Some(InvisibleClass::Bidi));
+    assert_eq!(InvisibleClass::of('\u{AD}'), Some(InvisibleClass::Invisible));
+    assert_eq!(InvisibleClass::of('\u{2060}'), Some(InvisibleClass::Invisible));
+    // Whitespace, zero-width spaces and joiners, and Khmer stay
+    for c in ['\t', '\n', '\r', ' ', '\u{200B}', '\u{200C}', '\u{200D}', 'ក', '្', '\u{17B4}'] {
+        assert_eq!(InvisibleClass::of(c), None, "{:?}", c);
+    }
+}
+
+#[test]
+fn test_strip_and_count() {
+    let sanitizer = Sanitizer::new(SanitizeAction::Strip);
+    assert!(matches!(sanitizer.sanitize("សួស្តី\tបង"), Cow::Borrowed("សួស្តី\tបង")));
+    assert_eq!(sanitizer.sanitize("\u{200E}សួស្តី\u{200F}\u{AD}បង\u{FEFF}\u{1}\u{9C}"), "សួស្តីបង");
+    assert_eq!(sanitizer.sanitize("a\u{202B}b\u{202C}"), "ab");
+    assert_eq!(sanitizer.counts(), SanitizeCounts { control: 2, bom: 1, bidi: 4, invisible: 1 });
+    assert_eq!(sanitizer.counts().total(), 8);
+    assert_eq!(sanitizer.counts().get(InvisibleClass::Bidi), 4);
+}
+
+#[test]
+fn test_space_replaces_controls_only() {
+    let sanitizer = Sanitizer::new(SanitizeAction::Space);
+    assert_eq!(sanitizer.sanitize("ខ្ញុំ\u{B}អ្នក\u{85}និង\u{200E}គេ"), "ខ្ញុំ អ្នក និងគេ");
+    assert_eq!(sanitizer.counts(), SanitizeCounts { control: 2, bidi: 1, ..Default::default() });
+}
+
+#[test]
+fn test_sanitize_action_names() {
+    for action in SanitizeAction::ALL {
+        assert_eq!(action.name().parse::<SanitizeAction>(), Ok(action));
+    }
+    assert!("drop".parse::<SanitizeAction>().unwrap_err().contains("strip, space"));
+}
@@ -0,0 +1,18 @@
+//! The JSON Schema of the output records.
+
+use khmer_rs::schema::{json_schema, SCHEMA_VERSION};
+
+#[test]
+fn test_schema_describes_the_record() {
+    let schema = json_schema();
+    let properties = schema["properties"].as_object().unwrap();
+    assert_eq!(properties["schema_version"]["const"], SCHEMA_VERSION);
+    for field in ["schema_version", "id", "input", "segments", "filtered"] {
+        assert!(properties.contains_key(field), "{}", field);
+    }
+    // Every required field is described, and the optional ones are not required
+    let required: Vec<&str> = schema["required"].as_array().unwrap().iter().map(|f| f.as_str().unwrap()).collect();
+    assert!(required.iter().all(|field| properties.contains_key(*field)));
+    assert!(!required.contains(&"filtered"));
+    assert!(schema["$id"].as_str().unwrap().ends_with(&format!("v{}", SCHEMA_VERSION)));
+}
@@ -0,0 +1,67 @@
+//! Tests for script-run classification.
+
+mod common;
+
+
+use khmer_rs::script::{script_runs, Script};
+use khmer_rs::segmenter::KhmerSegmenter;
+
+use common::dictionary;
+
+fn setup() -> KhmerSegmenter {
+    KhmerSegmenter::builder(dictionary()).build()
+}
+
+#[test]
+fn test_script_of() {
+    assert_eq!(Script::of('ក'), Script::Khmer);
+    assert_eq!(Script::of('é'), Script::Latin);
+    assert_eq!(Script::of('ก'), Script::Thai);
+    assert_eq!(Script::of('ລ'), Script::Lao);
+    assert_eq!(Script::of('中'), Script::Han);
+    assert_eq!(Script::of('៣'), Script::Digit);
+    assert_eq!(Script::of('7'), Script::Digit);
+    assert_eq!(Script::of(' '), Script::Other);
+    assert_eq!(Script::of('Ж'), Script::Other);
+
+    assert_eq!(Script::of_token("https://rfa.org"), Script::Latin);
+    assert_eq!(Script::of_token("$100"), Script::Digit);
+    assert_eq!(Script::of_token("?!"), Script::Other);
+}
+
+#[test]
+fn test_script_runs() {
+    let text = "ខ្មែរ ไทย中文12";
+    let runs: Vec<(&str, Script)> = script_runs(text).into_iter().map(|(span, s)| (span.as_str(text), s)).collect();
+    assert_eq!(
+        runs,
+        vec![
+            ("ខ្មែរ", Script::Khmer),
+            (" ", Script::Other),
+            ("ไทย", Script::Thai),
+            ("中文", Script::Han),
+            ("12", Script::Digit),
+        ]
+    );
+    assert!(script_runs("").is_empty());
+}
+
+#[test]
+fn test_foreign_runs_bypass_dp() {
+    let segmenter = setup();
+    // Thai and Han runs stay whole and are not merged with neighbouring unknowns
+    let text = "ខ្ញុំនិយាយภาษาไทยនិង中文ផង";
+    let tokens = segmenter.segment_tokens(text);
+    let foreign: Vec<(&str, Script)> = tokens
+        .iter()
+        .filter(|t| t.script.bypasses_dp())
+        .map(|t| (t.text.as_str(), t.script))
+        .collect();
+    assert_eq!(foreign, vec![("ภาษาไทย", Script::Thai), ("中文", Script::Han)]);
+    assert_eq!(tokens.iter().map(|t| t.text.as_str()).collect::<String>(), text);
+    assert_eq!(tokens[0].script, Script::Khmer);
+
+    let tokens = segmenter.segment_tokens("Hello ລາວ");
+    let scripts: Vec<Script> = tokens.iter().map(|t| t.script).collect();
+    assert_eq!(scripts, vec![Script::Latin, Script::Other, Script::Lao]);
+}
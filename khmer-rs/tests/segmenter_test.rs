@@ -5,6 +5,7 @@ use khmer_rs::dictionary::Dictionary;
 use khmer_rs::segmenter::KhmerSegmenter;
 use serde::Deserialize;
 use std::path::Path;
+use std::sync::Arc;
 
 #[derive(Debug, Deserialize)]
 struct TestCase {
@@ -89,6 +90,88 @@ fn test_numbers() {
     assert_eq!(result, vec!["១២៣៤៥"]);
 }
 
+#[test]
+fn test_numbers_with_units() {
+    let (segmenter, _) = setup();
+    assert_eq!(segmenter.segment("10kg"), vec!["10kg"]);
+    assert_eq!(segmenter.segment("៣០%។"), vec!["៣០%", "។"]);
+    assert_eq!(segmenter.segment("ចម្ងាយ៥គ.ម."), vec!["ចម្ងាយ", "៥គ.ម."]);
+    assert_eq!(segmenter.segment("1.5km/h"), vec!["1.5km/h"]);
+    // Not a unit when a word goes on, or when separated by a space
+    assert_eq!(segmenter.segment("5min"), vec!["5", "min"]);
+    assert_eq!(segmenter.segment("៥ម៉ោង"), vec!["៥", "ម៉ោង"]);
+    assert_eq!(segmenter.segment("10 kg"), vec!["10", " ", "kg"]);
+}
+
+#[test]
+fn test_number_formats_and_ranges() {
+    use khmer_rs::config::NumberFormat;
+
+    let (plain, _) = setup();
+    // Any separator between digits by default
+    assert_eq!(plain.segment("1.234,5"), vec!["1.234,5"]);
+    assert_eq!(plain.segment("10-20"), vec!["10", "-", "20"]);
+
+    let comma = KhmerSegmenter::builder(plain.dictionary()).number_format(NumberFormat::DecimalComma).build();
+    assert_eq!(comma.segment("1.234.567,89"), vec!["1.234.567,89"]);
+    assert_eq!(comma.segment("1,234.5"), vec!["1,234", ".", "5"]);
+    assert_eq!(comma.segment("12.34"), vec!["12", ".", "34"]);
+
+    let point = KhmerSegmenter::builder(plain.dictionary()).number_format(NumberFormat::DecimalPoint).build();
+    assert_eq!(point.segment("1,234,567.89"), vec!["1,234,567.89"]);
+    assert_eq!(point.segment("១ ២៣៤,៥"), vec!["១ ២៣៤", ",", "៥"]);
+
+    let ranges = KhmerSegmenter::builder(plain.dictionary()).number_ranges(true).build();
+    assert_eq!(ranges.segment("10-20kg"), vec!["10-20kg"]);
+    assert_eq!(ranges.segment("អាយុ២០–៣០ឆ្នាំ"), vec!["អាយុ", "២០–៣០", "ឆ្នាំ"]);
+    assert_eq!(ranges.segment("10 - 20"), vec!["10", " ", "-", " ", "20"]);
+}
+
+#[test]
+fn test_custom_separators() {
+    use khmer_rs::token::TokenKind;
+
+    let (plain, _) = setup();
+    assert_eq!(plain.segment("ខ្ញុំ|ស្រលាញ់…កម្ពុជា"), vec!["ខ្ញុំ|ស្រលាញ់…", "កម្ពុជា"]);
+
+    let extra = KhmerSegmenter::builder(plain.dictionary()).extra_separators(vec!['|', '…']).build();
+    assert_eq!(extra.segment("ខ្ញុំ|ស្រលាញ់…កម្ពុជា"), vec!["ខ្ញុំ", "|", "ស្រលាញ់", "…", "កម្ពុជា"]);
+    let kinds: Vec<TokenKind> = extra.segment_tokens("ខ្ញុំ|ស្រលាញ់").into_iter().map(|t| t.kind).collect();
+    assert_eq!(kinds, vec![TokenKind::Word, TokenKind::Separator, TokenKind::Word]);
+
+    // Replacing the set drops the built-in separators not listed
+    let replaced = KhmerSegmenter::builder(plain.dictionary()).separators(vec!['|', ' ']).build();
+    assert_eq!(replaced.segment("ខ្ញុំ|ស្រលាញ់ $"), vec!["ខ្ញុំ", "|", "ស្រលាញ់", " ", "$"]);
+    let kinds: Vec<TokenKind> = replaced.segment_tokens("ស្រលាញ់ $").into_iter().map(|t| t.kind).collect();
+    assert_eq!(kinds, vec![TokenKind::Word, TokenKind::Whitespace, TokenKind::Unknown]);
+}
+
+#[test]
+fn test_whitespace_policies() {
+    use khmer_rs::config::WhitespacePolicy;
+
+    let text = "ខ្ញុំ   ស្រលាញ់\t \tកម្ពុជា 012 345 678";
+    let (plain, _) = setup();
+    assert_eq!(
+        plain.segment(text),
+        vec!["ខ្ញុំ", " ", " ", " ", "ស្រលាញ់\t", " ", "\t", "កម្ពុជា", " ", "012 345 678"]
+    );
+
+    let with = |policy| KhmerSegmenter::builder(plain.dictionary()).whitespace(policy).build();
+    let keep = with(WhitespacePolicy::Keep);
+    assert_eq!(keep.segment(text), vec!["ខ្ញុំ", "   ", "ស្រលាញ់", "\t \t", "កម្ពុជា", " ", "012 345 678"]);
+    let collapse = with(WhitespacePolicy::Collapse);
+    assert_eq!(collapse.segment(text), vec!["ខ្ញុំ", " ", "ស្រលាញ់", " ", "កម្ពុជា", " ", "012 345 678"]);
+    let drop = with(WhitespacePolicy::Drop);
+    assert_eq!(drop.segment(text), vec!["ខ្ញុំ", "ស្រលាញ់", "កម្ពុជា", "012 345 678"]);
+
+    // Spans still point into the input
+    let spans: Vec<&str> = collapse.segment_spans(text).into_iter().map(|span| span.as_str(text)).collect();
+    assert_eq!(spans, keep.segment(text));
+    let tokens: Vec<&str> = drop.segment_spans(text).into_iter().map(|span| span.as_str(text)).collect();
+    assert_eq!(tokens, drop.segment(text));
+}
+
 #[test]
 fn test_empty_string() {
     let (segmenter, _) = setup();
@@ -110,3 +193,245 @@ fn test_punctuation() {
     let result = segmenter.segment("សួស្តី។");
     assert_eq!(result, vec!["សួស្តី", "។"]);
 }
+
+#[test]
+fn test_segment_spans() {
+    let (segmenter, _) = setup();
+    let text = "សួស្តី\u{200b}បង។";
+    let spans = segmenter.segment_spans(text);
+    let words: Vec<&str> = spans.iter().map(|s| s.as_str(text)).collect();
+    assert_eq!(words, vec!["សួស្តី", "បង", "។"]);
+}
+
+#[test]
+fn test_keep_zwsp() {
+    use khmer_rs::token::TokenKind;
+
+    let (plain, _) = setup();
+    let segmenter = KhmerSegmenter::builder(plain.dictionary()).keep_zwsp(true).build();
+    let text = "\u{200b}សួស្តី\u{200b}បង\u{200b}\u{200b}។";
+    let segments = segmenter.segment(text);
+    assert_eq!(segments, vec!["\u{200b}", "សួស្តី", "\u{200b}", "បង", "\u{200b}", "\u{200b}", "។"]);
+    assert_eq!(segments.concat(), text);
+
+    let spans: Vec<&str> = segmenter.segment_spans(text).into_iter().map(|span| span.as_str(text)).collect();
+    assert_eq!(spans, segments);
+    assert_eq!(segmenter.segment_tokens(text)[2].kind, TokenKind::Whitespace);
+
+    // A ZWSP the segmenter joins across stays inside the segment
+    let text = "ស្រ\u{200b}លាញ់";
+    assert_eq!(segmenter.segment(text), vec![text]);
+    assert_eq!(segmenter.segment_spans(text)[0].as_str(text), text);
+}
+
+#[test]
+fn test_zwsp_boundaries() {
+    let (plain, _) = setup();
+    let text = "ដាក់\u{200b}អន្ទាក់\u{200b}នៅ\u{200b}ចុង\u{200b}ឈើ";
+    assert_eq!(plain.segment(text), vec!["ដាក់អន្ទាក់", "នៅ", "ចុងឈើ"]);
+
+    let hinted = KhmerSegmenter::builder(plain.dictionary()).zwsp_boundaries(true).build();
+    assert_eq!(hinted.segment(text), vec!["ដាក់", "អន្ទាក់", "នៅ", "ចុង", "ឈើ"]);
+    let spans: Vec<&str> = hinted.segment_spans(text).into_iter().map(|span| span.as_str(text)).collect();
+    assert_eq!(spans, hinted.segment(text));
+
+    let kept = KhmerSegmenter::builder(plain.dictionary()).zwsp_boundaries(true).keep_zwsp(true).build();
+    assert_eq!(kept.segment(text).concat(), text);
+    assert_eq!(kept.segment(text).len(), 9);
+}
+
+#[test]
+fn test_segment_trace() {
+    use khmer_rs::trace::ArcKind;
+
+    let (segmenter, _) = setup();
+    let text = "ខ្ញុំស្រលាញ់ភាសាខ្មែរ";
+    let trace = segmenter.segment_trace(text);
+    assert_eq!(trace.text, text);
+    assert_eq!(trace.segments, vec!["ខ្ញុំ", "ស្រលាញ់", "ភាសាខ្មែរ"]);
+    assert_eq!(trace.segments, segmenter.segment(text));
+
+    // At the start both the word and the unknown cluster reach position 5;
+    // the word is cheaper and wins
+    let from_start: Vec<_> = trace.arcs_from(0).filter(|arc| arc.end == 5).collect();
+    assert_eq!(from_start.len(), 2);
+    assert_eq!((from_start[0].kind, from_start[0].won), (ArcKind::Word, true));
+    assert_eq!((from_start[1].kind, from_start[1].won), (ArcKind::UnknownCluster, false));
+    assert!(from_start[1].total > from_start[0].total);
+
+    for arc in &trace.arcs {
+        assert_eq!(arc.text.chars().count(), arc.end - arc.start);
+        assert!(!arc.won || arc.improved);
+    }
+    for end in 1..=text.chars().count() {
+        assert!(trace.arcs.iter().filter(|arc| arc.end == end && arc.won).count() <= 1);
+    }
+
+    let json = serde_json::to_value(&trace).unwrap();
+    assert_eq!(json["arcs"][0]["kind"], "word");
+    assert_eq!(json["segments"][2], "ភាសាខ្មែរ");
+}
+
+#[test]
+fn test_segment_trace_zwsp_boundaries() {
+    let (plain, _) = setup();
+    let text = "ដាក់\u{200b}អន្ទាក់\u{200b}នៅ";
+    let hinted = KhmerSegmenter::builder(plain.dictionary()).zwsp_boundaries(true).build();
+    let trace = hinted.segment_trace(text);
+    assert_eq!(trace.text, "ដាក់អន្ទាក់នៅ");
+    assert_eq!(trace.segments, vec!["ដាក់", "អន្ទាក់", "នៅ"]);
+    // No arc crosses a boundary
+    assert!(trace.arcs.iter().all(|arc| arc.start >= 4 || arc.end <= 4));
+    assert!(trace.arcs.iter().all(|arc| arc.start >= 11 || arc.end <= 11));
+}
+
+#[test]
+fn test_swap_dictionary() {
+    use std::sync::Arc;
+
+    let segmenter = KhmerSegmenter::new(Dictionary::from_bytes("ក\nខ\n".as_bytes(), None).unwrap());
+    assert_eq!(segmenter.segment("កខ"), vec!["ក", "ខ"]);
+
+    let old = segmenter.swap_dictionary(Arc::new(Dictionary::from_bytes("កខ\n".as_bytes(), None).unwrap()));
+    assert!(old.contains("ក"));
+    assert!(segmenter.dictionary().contains("កខ"));
+    assert_eq!(segmenter.segment("កខ"), vec!["កខ"]);
+}
+
+#[test]
+fn test_bigram_costs() {
+    use khmer_rs::bigram::BigramTable;
+
+    // Without frequencies both "ក|ខគ" and "កខ|គ" cost two default words
+    let words = "ក\nខគ\nកខ\nគ\n";
+    let with_bigram = |prev: &str, word: &str| {
+        let mut table = BigramTable::new();
+        table.insert(prev, word, 0.1);
+        let dictionary = Dictionary::from_bytes(words.as_bytes(), None).unwrap();
+        KhmerSegmenter::builder(dictionary).bigrams(table).build()
+    };
+
+    assert_eq!(with_bigram("ក", "ខគ").segment("កខគ"), vec!["ក", "ខគ"]);
+    assert_eq!(with_bigram("កខ", "គ").segment("កខគ"), vec!["កខ", "គ"]);
+
+    let table = BigramTable::from_reader("previous\tword\tcost\nក\tខគ\t0.5\n".as_bytes()).unwrap();
+    assert_eq!(table.len(), 1);
+    assert_eq!(table.get("ក", "ខគ"), Some(0.5));
+    assert_eq!(table.get("ខគ", "ក"), None);
+    assert!(BigramTable::from_reader("ក\tខគ\t0.5\nក\tខ\n".as_bytes()).is_err());
+}
+
+#[test]
+fn test_length_normalization() {
+    // Without frequencies one long word beats two short ones; a positive
+    // exponent makes the 4-cluster word cost more than two 2-cluster words
+    let dictionary = || Dictionary::from_bytes("កខគឃ\nកខ\nគឃ\n".as_bytes(), None).unwrap();
+    assert_eq!(KhmerSegmenter::new(dictionary()).segment("កខគឃ"), vec!["កខគឃ"]);
+
+    let normalized = KhmerSegmenter::builder(dictionary()).length_normalization(1.5).build();
+    assert_eq!(normalized.segment("កខគឃ"), vec!["កខ", "គឃ"]);
+}
+
+#[test]
+fn test_beam_pruning() {
+    let (exact, cases) = setup();
+    let dictionary = Dictionary::new(
+        Path::new("../data/khmer_dictionary_words.txt"),
+        Path::new("../data/khmer_word_frequencies.json"),
+    )
+    .unwrap();
+    let pruned = KhmerSegmenter::builder(dictionary).beam(0.5).build();
+
+    let long_line: String = cases.iter().map(|c| c.input.as_str()).collect::<Vec<_>>().join("");
+    for text in cases.iter().map(|c| c.input.as_str()).chain([long_line.as_str()]) {
+        // Pruning may change the segmentation, but never loses text
+        assert_eq!(pruned.segment(text).concat(), exact.segment(text).concat());
+    }
+    // Common words are far ahead of any pruning margin
+    assert_eq!(pruned.segment("ខ្ញុំស្រលាញ់កម្ពុជា"), exact.segment("ខ្ញុំស្រលាញ់កម្ពុជា"));
+}
+
+#[test]
+fn test_equal_cost_paths_keep_the_first() {
+    use khmer_rs::dictionary::Cost;
+    use khmer_rs::overlay::Overlay;
+
+    // ក + ខ and កខ both cost 2.0; កខ is relaxed first (from position 0) and
+    // the later, equally cheap path through ខ does not replace it
+    let costs = Overlay::from_reader("ក\t1.0\nខ\t1.0\nកខ\t2.0\n".as_bytes()).unwrap();
+    let dictionary = Dictionary::builder().overlay(costs).load_bytes("ក\nខ\nកខ\n".as_bytes(), None).unwrap();
+    assert_eq!(dictionary.get_word_cost("កខ"), 2.0 as Cost);
+    let segmenter = KhmerSegmenter::new(dictionary);
+    assert_eq!(segmenter.segment("កខ"), vec!["កខ"]);
+}
+
+#[test]
+fn test_disable_pass() {
+    use khmer_rs::config::Pass;
+
+    let (segmenter, _) = setup();
+    let dictionary = segmenter.dictionary();
+    let raw = Pass::ALL
+        .into_iter()
+        .fold(KhmerSegmenter::builder(Arc::clone(&dictionary)), |builder, pass| builder.disable_pass(pass))
+        .build();
+    let no_merge = KhmerSegmenter::builder(dictionary).disable_pass(Pass::UnknownMerge).build();
+
+    // Unknown clusters stay apart without the unknown-merge pass
+    assert_eq!(segmenter.segment("បង្កៀ"), vec!["បង្កៀ"]);
+    assert_eq!(no_merge.segment("បង្កៀ"), vec!["ប", "ង្កៀ"]);
+    assert_eq!(no_merge.config().disabled_passes, vec![Pass::UnknownMerge]);
+
+    for text in ["ខ្ញុំស្រលាញ់កម្ពុជា", "បង្កៀ", "នេហ៍"] {
+        assert_eq!(raw.segment(text).concat(), text);
+    }
+}
+
+#[test]
+fn test_segment_into_reuses_the_buffer() {
+    use khmer_rs::config::WhitespacePolicy;
+
+    let (segmenter, test_cases) = setup();
+    let dictionary = segmenter.dictionary();
+    let segmenters = [
+        segmenter,
+        KhmerSegmenter::builder(Arc::clone(&dictionary)).whitespace(WhitespacePolicy::Drop).build(),
+        KhmerSegmenter::builder(Arc::clone(&dictionary)).whitespace(WhitespacePolicy::Collapse).build(),
+        KhmerSegmenter::builder(Arc::clone(&dictionary)).keep_zwsp(true).build(),
+        KhmerSegmenter::builder(Arc::clone(&dictionary)).lossless(true).build(),
+        KhmerSegmenter::builder(dictionary).cache(16).build(),
+    ];
+    let texts = test_cases
+        .iter()
+        .map(|case| case.input.as_str())
+        .chain(["", "សួស្តី  \t បង", "ខ្ញុំ\u{200b}ស្រលាញ់\u{200b}កម្ពុជា"]);
+
+    for segmenter in &segmenters {
+        // Left over from an earlier call: cleared, not appended to
+        let mut segments = vec!["stale".to_string()];
+        let mut spans = Vec::new();
+        for text in texts.clone() {
+            segmenter.segment_into(text, &mut segments);
+            assert_eq!(segments, segmenter.segment(text), "{:?}", text);
+            segmenter.segment_spans_into(text, &mut spans);
+            assert_eq!(spans, segmenter.segment_spans(text), "{:?}", text);
+        }
+    }
+}
+
+#[test]
+fn test_tokens_serde_round_trip() {
+    use khmer_rs::token::Token;
+
+    let (segmenter, _) = setup();
+    let tokens = segmenter.segment_tokens("សម័យកាល XIV ថ្ងៃទី 15/01/2024 https://rfa.org");
+    let json = serde_json::to_string(&tokens).unwrap();
+    assert!(json.contains(r#""kind":"roman-numeral""#), "{}", json);
+    assert!(json.contains(r#""span":{"start":0,"#), "{}", json);
+    let back: Vec<Token> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, tokens);
+
+    let stages = segmenter.explain("ខ្ញុំស្រលាញ់កម្ពុជា");
+    let json = serde_json::to_string(&stages).unwrap();
+    assert_eq!(serde_json::from_str::<Vec<khmer_rs::segmenter::Stage>>(&json).unwrap(), stages);
+}
@@ -0,0 +1,52 @@
+//! Tests for sentence splitting.
+
+use khmer_rs::sentences::split_sentences;
+
+fn sentences(text: &str) -> Vec<&str> {
+    split_sentences(text).iter().map(|s| s.as_str(text)).collect()
+}
+
+#[test]
+fn test_khmer_terminators() {
+    assert_eq!(
+        sentences("ខ្ញុំទៅសាលា។ គាត់នៅផ្ទះ៕"),
+        vec!["ខ្ញុំទៅសាលា។", "គាត់នៅផ្ទះ៕"]
+    );
+}
+
+#[test]
+fn test_latin_punctuation_and_runs() {
+    assert_eq!(sentences("Really?! Yes. Done"), vec!["Really?!", "Yes.", "Done"]);
+}
+
+#[test]
+fn test_dot_inside_acronym_and_number() {
+    assert_eq!(sentences("ស.ភ.ភ ចំណាយ 1.5 លាន។"), vec!["ស.ភ.ភ ចំណាយ 1.5 លាន។"]);
+}
+
+#[test]
+fn test_no_split_inside_quotes() {
+    let text = "គេថា «អ្នកអើយ! មកនេះ» ។ បុរសនោះទៅ។";
+    assert_eq!(sentences(text), vec!["គេថា «អ្នកអើយ! មកនេះ» ។", "បុរសនោះទៅ។"]);
+}
+
+#[test]
+fn test_closing_quote_attaches_to_sentence() {
+    assert_eq!(sentences("«មកនេះ។» ទៅ។"), vec!["«មកនេះ។»", "ទៅ។"]);
+}
+
+#[test]
+fn test_unbalanced_quote_is_ignored() {
+    assert_eq!(sentences("«មកនេះ។ ទៅ។"), vec!["«មកនេះ។", "ទៅ។"]);
+}
+
+#[test]
+fn test_repetition_mark_is_not_a_boundary() {
+    assert_eq!(sentences("ក្មេង។ៗ ទៅ"), vec!["ក្មេង។ៗ ទៅ"]);
+}
+
+#[test]
+fn test_empty_and_whitespace() {
+    assert!(split_sentences("").is_empty());
+    assert!(split_sentences("   ").is_empty());
+}
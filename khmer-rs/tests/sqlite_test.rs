@@ -0,0 +1,66 @@
+//! SQLite output: tables, token kinds and costs, batched commits.
+#![cfg(feature = "sqlite")]
+
+use khmer_rs::dictionary::{Cost, Dictionary};
+use khmer_rs::output::Record;
+use khmer_rs::segmenter::KhmerSegmenter;
+use khmer_rs::sqlite::SqliteWriter;
+use rusqlite::Connection;
+
+type TokenRow = (i64, String, Option<String>, Option<Cost>, bool);
+
+#[test]
+fn test_sqlite_tables() {
+    let dictionary = Dictionary::builder().load_bytes("សួស្តី\nបង\n".as_bytes(), None).unwrap();
+    let cost = dictionary.get_word_cost("បង");
+    let segmenter = KhmerSegmenter::new(dictionary);
+    let path = std::env::temp_dir().join(format!("khmer_sqlite_{}.db", std::process::id()));
+    std::fs::write(&path, "not a database").unwrap();
+
+    // Batches of 2 lines: the third line is committed by finish
+    let mut writer = SqliteWriter::create(&path).unwrap().batch_lines(2);
+    let lines = ["សួស្តីបង", "បង ១០", "សួស្តី"];
+    for (id, line) in lines.iter().enumerate() {
+        let segments = segmenter.segment(line);
+        let mut record = Record::new(id, line, &segments);
+        if id == 1 {
+            record = record.filtered(vec![1]);
+        }
+        writer.write_record(&record, &segmenter).unwrap();
+    }
+    assert_eq!(writer.lines(), 3);
+    writer.finish().unwrap();
+
+    let conn = Connection::open(&path).unwrap();
+    let version: String = conn.query_row("SELECT value FROM meta WHERE key = 'schema_version'", [], |r| r.get(0)).unwrap();
+    assert_eq!(version, "1");
+    let inputs: Vec<String> = conn
+        .prepare("SELECT input FROM lines ORDER BY id")
+        .unwrap()
+        .query_map([], |r| r.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(inputs, lines);
+
+    let rows: Vec<TokenRow> = conn
+        .prepare("SELECT position, text, kind, cost, filtered FROM tokens WHERE line_id = 1 ORDER BY position")
+        .unwrap()
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            (0, "បង".to_string(), Some("word".to_string()), Some(cost), false),
+            (1, " ".to_string(), Some("whitespace".to_string()), None, true),
+            (2, "១០".to_string(), Some("number".to_string()), None, false),
+        ]
+    );
+    let tokens: i64 = conn.query_row("SELECT COUNT(*) FROM tokens", [], |r| r.get(0)).unwrap();
+    assert_eq!(tokens, 6);
+
+    drop(conn);
+    std::fs::remove_file(&path).unwrap();
+}
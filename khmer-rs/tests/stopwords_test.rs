@@ -0,0 +1,46 @@
+use khmer_rs::stopwords::{FilterMode, StopWords, TokenFilter};
+
+#[test]
+fn test_stop_words_from_reader() {
+    let stop_words = StopWords::from_reader("# comment\nនិង\n\n  ដែល  # relative\n".as_bytes()).unwrap();
+    assert_eq!(stop_words.len(), 2);
+    assert!(stop_words.contains("និង"));
+    assert!(stop_words.contains("ដែល"));
+    assert!(!stop_words.contains("# comment"));
+}
+
+#[test]
+fn test_builtin_stop_words() {
+    let stop_words = StopWords::khmer();
+    assert!(stop_words.len() > 50);
+    for word in ["និង", "ដែល", "បាន", "នៅ", "នេះ"] {
+        assert!(stop_words.contains(word), "{}", word);
+    }
+    assert!(!stop_words.contains("កម្ពុជា"));
+}
+
+#[test]
+fn test_token_filter() {
+    let segments = ["ខ្ញុំ", " ", "ស្រឡាញ់", "កម្ពុជា", "។", "!?"];
+    assert!(!TokenFilter::new().is_active());
+    assert_eq!(TokenFilter::new().retain(&segments), segments);
+
+    let stop_words: StopWords = ["ខ្ញុំ"].into_iter().collect();
+    let filter = TokenFilter::new().stop_words(stop_words.clone());
+    assert_eq!(filter.retain(&segments), [" ", "ស្រឡាញ់", "កម្ពុជា", "។", "!?"]);
+
+    let filter = TokenFilter::new().separators(true);
+    assert_eq!(filter.retain(&segments), ["ខ្ញុំ", "ស្រឡាញ់", "កម្ពុជា"]);
+
+    let filter = TokenFilter::new().stop_words(stop_words).separators(true);
+    assert_eq!(filter.retain(&segments), ["ស្រឡាញ់", "កម្ពុជា"]);
+    assert_eq!(filter.positions(&segments), [0, 1, 4, 5]);
+}
+
+#[test]
+fn test_filter_mode_names() {
+    for mode in FilterMode::ALL {
+        assert_eq!(mode.name().parse::<FilterMode>(), Ok(mode));
+    }
+    assert!("keep".parse::<FilterMode>().is_err());
+}
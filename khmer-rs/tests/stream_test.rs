@@ -0,0 +1,47 @@
+//! Lazy segmentation with `segment_iter`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::segmenter::KhmerSegmenter;
+use khmer_rs::sentences::segment_by_sentence;
+use khmer_rs::token::TokenKind;
+
+const WORDS: &str = "ខ្ញុំ\nទៅ\nសាលា\nគាត់\nនៅ\nផ្ទះ\n";
+const TEXT: &str = "ខ្ញុំទៅសាលា។  គាត់នៅផ្ទះ៕ ខ្ញុំនៅផ្ទះ";
+
+#[test]
+fn test_segment_iter_matches_segment_by_sentence() {
+    let segmenter = KhmerSegmenter::new(Dictionary::from_bytes(WORDS.as_bytes(), None).unwrap());
+    let tokens: Vec<_> = segmenter.segment_iter(TEXT).collect();
+    let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+    assert_eq!(texts, segment_by_sentence(&segmenter, TEXT));
+    assert_eq!(&texts[..4], ["ខ្ញុំ", "ទៅ", "សាលា", "។"]);
+    // Spans index the whole text, not the sentence
+    for token in &tokens {
+        assert_eq!(token.span.as_str(TEXT), token.text);
+    }
+    assert_eq!(tokens[4].kind, TokenKind::Whitespace);
+    assert_eq!(segmenter.segment_iter("").count(), 0);
+}
+
+#[test]
+fn test_segment_iter_is_lazy() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    let segmenter = KhmerSegmenter::builder(Dictionary::from_bytes(WORDS.as_bytes(), None).unwrap())
+        .add_post_processor(move |segments: Vec<String>, _: &Dictionary| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            segments
+        })
+        .build();
+
+    let first: Vec<_> = segmenter.segment_iter(TEXT).take(2).map(|token| token.text).collect();
+    assert_eq!(first, ["ខ្ញុំ", "ទៅ"]);
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+    segmenter.segment_iter(TEXT).for_each(drop);
+    // Three sentences and the two gaps between them
+    assert_eq!(calls.load(Ordering::Relaxed), 1 + 5);
+}
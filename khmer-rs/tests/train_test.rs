@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use khmer_rs::dictionary::{Cost, Dictionary};
+use khmer_rs::frequency::{read_frequencies, FrequencyFormat};
+use khmer_rs::segmenter::KhmerSegmenter;
+use khmer_rs::train::{count_tokens, reestimate, write_frequencies, TokenCounter};
+
+fn small_segmenter() -> KhmerSegmenter {
+    KhmerSegmenter::new(Dictionary::from_bytes("សួស្តី\nបង\nប្អូន\n".as_bytes(), None).unwrap())
+}
+
+#[test]
+fn test_count_tokens() {
+    let lines = ["សួស្តីបង", "បង ប្អូន 123!", "បងប្អូន"];
+    let counts = count_tokens(&small_segmenter(), &lines);
+
+    assert_eq!(counts["បង"], 3);
+    assert_eq!(counts["សួស្តី"], 1);
+    assert_eq!(counts["ប្អូន"], 2);
+    // Spaces, digits and punctuation are not counted
+    assert_eq!(counts.len(), 3);
+}
+
+#[test]
+fn test_token_counter_merges_workers() {
+    let segmenter = small_segmenter();
+    let lines = ["សួស្តីបង", "បង ប្អូន 123!", "បងប្អូន"];
+    let counter = TokenCounter::new(false);
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| lines.iter().for_each(|line| counter.add(&segmenter.segment(line))));
+        }
+    });
+    let counts = counter.finish();
+    let single = count_tokens(&segmenter, &lines);
+    assert_eq!(counts.len(), single.len());
+    assert!(single.iter().all(|(word, n)| counts[word] == 4 * n));
+
+    // With `all`, everything but whitespace
+    let counter = TokenCounter::new(true);
+    counter.add(&segmenter.segment("បង ប្អូន 123!"));
+    let counts = counter.finish();
+    assert_eq!(counts.len(), 4);
+    assert_eq!(counts["123"], 1);
+    assert_eq!(counts["!"], 1);
+}
+
+#[test]
+fn test_write_frequencies_round_trip() {
+    let lines = ["សួស្តីបង", "បងប្អូន", "បង"];
+    let counts = count_tokens(&small_segmenter(), &lines);
+
+    for format in [FrequencyFormat::Json, FrequencyFormat::Tsv, FrequencyFormat::Csv, FrequencyFormat::Counts] {
+        let mut out = Vec::new();
+        write_frequencies(&mut out, &counts, format).unwrap();
+        let read = read_frequencies(out.as_slice(), format).unwrap();
+        assert_eq!(read.len(), counts.len(), "{:?}", format);
+        for (word, &n) in &counts {
+            assert_eq!(read[word.as_str()], n as Cost, "{:?} {}", format, word);
+        }
+    }
+
+    let mut out = Vec::new();
+    write_frequencies(&mut out, &counts, FrequencyFormat::Tsv).unwrap();
+    assert!(String::from_utf8(out).unwrap().starts_with("បង\t3\n"));
+}
+
+#[test]
+fn test_write_frequencies_with_spaces() {
+    // `count --all` keeps phone numbers, currency amounts and tabs after
+    // a word (`ខ្ញុំ\t`) whole
+    let counts: HashMap<String, u64> = [("បង", 3), ("012 345 678", 2), ("100 USD", 1), ("ខ្ញុំ\t", 1)]
+        .into_iter()
+        .map(|(w, n)| (w.to_string(), n))
+        .collect();
+
+    for format in [FrequencyFormat::Json, FrequencyFormat::Csv] {
+        let mut out = Vec::new();
+        assert_eq!(write_frequencies(&mut out, &counts, format).unwrap(), 0);
+        let read = read_frequencies(out.as_slice(), format).unwrap();
+        assert_eq!(read.len(), 4, "{:?}", format);
+        assert_eq!(read["012 345 678"], 2.0, "{:?}", format);
+        assert_eq!(read["ខ្ញុំ\t"], 1.0, "{:?}", format);
+    }
+
+    let mut out = Vec::new();
+    assert_eq!(write_frequencies(&mut out, &counts, FrequencyFormat::Tsv).unwrap(), 1);
+    let read = read_frequencies(out.as_slice(), FrequencyFormat::Tsv).unwrap();
+    assert_eq!(read.len(), 3);
+    assert_eq!(read["012 345 678"], 2.0);
+    assert_eq!(read.get("ខ្ញុំ"), None);
+
+    let mut out = Vec::new();
+    assert_eq!(write_frequencies(&mut out, &counts, FrequencyFormat::Counts).unwrap(), 3);
+    let read = read_frequencies(out.as_slice(), FrequencyFormat::Counts).unwrap();
+    assert_eq!(read.iter().collect::<Vec<_>>(), vec![("បង", 3.0)]);
+}
+
+#[test]
+fn test_reestimate() {
+    // Without frequencies "ក ខ" and "កខ" cost the same per word, so the
+    // longer word wins; counts from the corpus then settle on it
+    let words = "ក\nខ\nកខ\nគ\n";
+    let segmenter = KhmerSegmenter::new(Dictionary::from_bytes(words.as_bytes(), None).unwrap());
+    let corpus = ["កខគ"; 6];
+    let dev = ["កខ", "គគ"];
+
+    let mut reports = Vec::new();
+    let counts = reestimate(
+        &segmenter,
+        &corpus,
+        &dev,
+        2,
+        |freq| {
+            let json = serde_json::to_vec(&freq).unwrap();
+            Dictionary::from_bytes(words.as_bytes(), Some(&json))
+        },
+        |it| reports.push(it.clone()),
+    )
+    .unwrap();
+
+    assert_eq!(counts["កខ"], 6);
+    assert_eq!(counts["គ"], 6);
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].iteration, 1);
+    assert_eq!(reports[0].vocabulary, 2);
+    // Stable segmentation: the dev set does not change
+    assert!(reports.iter().all(|r| r.dev_changed_lines == 0 && r.dev_boundary_changes == 0));
+    assert!(segmenter.dictionary().get_word_cost("កខ") < segmenter.dictionary().get_word_cost("ក"));
+}
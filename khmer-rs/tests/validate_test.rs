@@ -0,0 +1,35 @@
+use khmer_rs::validate::{check_word, validate_words, IssueKind};
+use khmer_rs::variants::VariantGenerator;
+
+#[test]
+fn test_check_word() {
+    assert!(check_word("សួស្តី").is_empty());
+    assert!(check_word("ក").is_empty());
+
+    assert_eq!(check_word("្កា"), vec![IssueKind::LeadingCoeng]);
+    assert!(check_word("វ្ឫក្ស").is_empty());
+    assert_eq!(check_word("ក្"), vec![IssueKind::DanglingCoeng]);
+    assert_eq!(check_word("ក្ា"), vec![IssueKind::DanglingCoeng, IssueKind::DanglingMark]);
+    assert_eq!(check_word("ាក"), vec![IssueKind::DanglingMark]);
+    assert_eq!(check_word("ឃ"), vec![IssueKind::InvalidSingleChar]);
+    assert_eq!(check_word("ផ្សារabc"), vec![IssueKind::MixedScript]);
+    assert_eq!(check_word("ផ្សេងៗ"), vec![IssueKind::RepetitionMark]);
+    // Digits and punctuation are not letters
+    assert!(check_word("ឆ្នាំ2024").is_empty());
+}
+
+#[test]
+fn test_validate_words_duplicates() {
+    let list = "សួស្តី\nបង\n\nបង\nសួស\u{17D2}\u{178D}ី\n";
+    let issues = validate_words(list.as_bytes(), &VariantGenerator::new()).unwrap();
+
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].line, 4);
+    assert_eq!(issues[0].kind, IssueKind::Duplicate { first_line: 2 });
+    assert_eq!(issues[1].line, 5);
+    assert_eq!(issues[1].kind, IssueKind::DuplicateVariant { of: "សួស្តី".to_string(), line: 1 });
+
+    // Without variant generation the Ta/Da spelling is a separate word
+    let issues = validate_words(list.as_bytes(), &VariantGenerator::none()).unwrap();
+    assert_eq!(issues.len(), 1);
+}
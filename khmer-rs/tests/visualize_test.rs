@@ -0,0 +1,40 @@
+use khmer_rs::dictionary::Dictionary;
+use khmer_rs::segmenter::KhmerSegmenter;
+use khmer_rs::visualize::render_html;
+
+fn segmenter() -> KhmerSegmenter {
+    KhmerSegmenter::new(Dictionary::from_bytes("ខ្ញុំ\nស្រលាញ់\nភាសា\n".as_bytes(), None).unwrap())
+}
+
+#[test]
+fn test_render_html_boxes_and_tooltips() {
+    let lines = vec![(0, "ខ្ញុំស្រលាញ់ ភាសា".to_string()), (41, "<b>".to_string())];
+    let html = render_html(&segmenter(), &lines, "Sample & more");
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>Sample &amp; more</title>"));
+    assert!(html.contains("<span class=\"id\">1</span>"));
+    assert!(html.contains("<span class=\"id\">42</span>"));
+    assert_eq!(html.matches("<div class=\"line\">").count(), 2);
+
+    // Dictionary words carry their cost and their Viterbi arc
+    let start = html.find("<span class=\"tok word\" title=\"&quot;ភាសា&quot; (word)").expect("word box");
+    let tooltip = &html[start..html[start..].find("\">ភាសា</span>").unwrap() + start];
+    assert!(tooltip.contains("dictionary cost"));
+    assert!(tooltip.contains("viterbi word arc: cost"));
+    assert!(html.contains("<span class=\"tok whitespace\""));
+
+    // Input text is escaped
+    assert!(!html.contains("<b>"));
+    assert!(html.contains("&lt;"));
+    assert!(html.contains("class=\"tok unknown\""));
+}
+
+#[test]
+fn test_render_html_merged_tokens_have_no_arc() {
+    // The DP splits off the invalid single ឃ; post-processing snaps it back
+    let lines = vec![(0, "ស្រលាញ់ឃ".to_string())];
+    let html = render_html(&segmenter(), &lines, "Merged");
+    assert!(html.contains(">ស្រលាញ់ឃ</span>"));
+    assert!(html.contains("joined or split after the Viterbi pass"));
+}